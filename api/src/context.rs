@@ -123,9 +123,13 @@ impl Context {
         limit: u16,
         ledger_version: u64,
     ) -> Result<Vec<TransactionOnChainData>> {
-        let data = self
-            .db
-            .get_transactions(start_version, limit as u64, ledger_version, true)?;
+        let data = self.db.get_transactions(
+            start_version,
+            Order::Ascending,
+            limit as u64,
+            ledger_version,
+            true,
+        )?;
 
         let txn_start_version = data
             .first_transaction_version
@@ -171,6 +175,7 @@ impl Context {
         let txns = self.db.get_account_transactions(
             address,
             start_seq_number,
+            Order::Ascending,
             limit as u64,
             true,
             ledger_version,
@@ -53,7 +53,13 @@ impl AptosValidatorInterface for DBDebuggerInterface {
     fn get_committed_transactions(&self, start: Version, limit: u64) -> Result<Vec<Transaction>> {
         Ok(self
             .0
-            .get_transactions(start, limit, self.get_latest_version()?, false)?
+            .get_transactions(
+                start,
+                Order::Ascending,
+                limit,
+                self.get_latest_version()?,
+                false,
+            )?
             .transactions)
     }
 
@@ -4,18 +4,80 @@
 use crate::utils;
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::BTreeMap,
     net::{IpAddr, Ipv4Addr, SocketAddr},
     path::PathBuf,
 };
 
+/// RocksDB compaction styles, see https://github.com/facebook/rocksdb/wiki/Compaction
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompactionStyle {
+    /// Leveled compaction, the RocksDB default. Suits CFs with updates and deletes and frequent
+    /// point reads, e.g. the jellyfish merkle node CF.
+    Level,
+    /// Universal compaction. Lower write amplification at the cost of space amplification, suits
+    /// append-heavy CFs like the transaction and event CFs.
+    Universal,
+    /// FIFO compaction. Drops the oldest files once the CF exceeds its size limit, so only suits
+    /// append-only CFs whose old data can be lost or is pruned anyway.
+    Fifo,
+}
+
+/// RocksDB compression types, see https://github.com/facebook/rocksdb/wiki/Compression
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompressionType {
+    None,
+    Snappy,
+    /// The default for all column families.
+    Lz4,
+    /// Better compression ratio than LZ4 at a higher CPU cost, suits rarely read CFs.
+    Zstd,
+}
+
+/// Table options of a single column family. Options left unset keep the defaults.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ColumnFamilyTuning {
+    /// Size in bytes of the LRU block cache dedicated to the column family. Defaults to the
+    /// shared block cache if configured, otherwise to the 8MB RocksDB creates for each column
+    /// family.
+    pub block_cache_size: Option<usize>,
+    /// Bits per key of the bloom filter, which saves reading blocks for point lookups of keys
+    /// that don't exist. No bloom filter by default.
+    pub bloom_filter_bits_per_key: Option<i32>,
+    pub compression: Option<CompressionType>,
+}
+
 /// Port selected RocksDB options for tuning underlying rocksdb instance of AptosDB.
 /// see https://github.com/facebook/rocksdb/blob/master/include/rocksdb/options.h
 /// for detailed explanations.
-#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct RocksdbConfig {
     pub max_open_files: i32,
     pub max_total_wal_size: u64,
+    /// Compaction style per column family name. Column families not listed here use the default
+    /// level compaction.
+    pub cf_compaction_styles: BTreeMap<String, CompactionStyle>,
+    /// Table options per column family name, e.g. to give the jellyfish merkle node CF a larger
+    /// block cache than the write set CF.
+    pub cf_tuning: BTreeMap<String, ColumnFamilyTuning>,
+    /// Size in bytes of a single LRU block cache shared by all column families without a block
+    /// cache of their own in `cf_tuning`, bounding the memory used for caching blocks. None gives
+    /// each column family a separate default cache.
+    pub shared_block_cache_size: Option<usize>,
+    /// Maximum number of items a single read request to AptosDB can ask for, e.g. the `limit` of
+    /// `get_transactions`, `get_events` and `get_account_transactions`. Defaults to 5000.
+    pub max_request_limit: Option<u64>,
+    /// Maximum number of epoch ending ledger infos a state proof served by AptosDB carries. A
+    /// client further behind gets a truncated proof with `more` set and has to ask again from the
+    /// last epoch in it. Must be positive, defaults to 100.
+    pub max_epochs_per_state_proof: Option<usize>,
+    /// Number of account states, keyed by address and version, AptosDB keeps in an in-memory LRU
+    /// cache for reads. None or 0 disables the cache.
+    pub account_state_cache_capacity: Option<usize>,
 }
 
 impl Default for RocksdbConfig {
@@ -28,6 +90,12 @@ impl Default for RocksdbConfig {
             // families are updated at non-uniform frequencies.
             #[allow(clippy::integer_arithmetic)] // TODO: remove once clippy lint fixed
             max_total_wal_size: 1u64 << 30,
+            cf_compaction_styles: BTreeMap::new(),
+            cf_tuning: BTreeMap::new(),
+            shared_block_cache_size: None,
+            max_request_limit: None,
+            max_epochs_per_state_proof: None,
+            account_state_cache_capacity: None,
         }
     }
 }
@@ -52,6 +120,7 @@ pub const NO_OP_STORAGE_PRUNER_CONFIG: StoragePrunerConfig = StoragePrunerConfig
     state_store_prune_window: None,
     default_prune_window: None,
     max_version_to_prune_per_batch: Some(100),
+    ledger_max_version_to_prune_per_batch: None,
 };
 
 #[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -68,6 +137,10 @@ pub struct StoragePrunerConfig {
     /// Maximum version to prune per batch, should not be too large to avoid spike in disk IO caused
     /// by large batches in the pruner.
     pub max_version_to_prune_per_batch: Option<u64>,
+    /// Like `max_version_to_prune_per_batch`, but for the stores pruned according to
+    /// `default_prune_window`, which are pruned by a separate background worker. Falls back to
+    /// `max_version_to_prune_per_batch` if not specified.
+    pub ledger_max_version_to_prune_per_batch: Option<u64>,
 }
 
 impl StoragePrunerConfig {
@@ -80,6 +153,7 @@ impl StoragePrunerConfig {
             state_store_prune_window,
             default_prune_window: default_store_prune_window,
             max_version_to_prune_per_batch,
+            ledger_max_version_to_prune_per_batch: None,
         }
     }
 }
@@ -101,6 +175,7 @@ impl Default for StorageConfig {
                 state_store_prune_window: Some(1_000_000),
                 default_prune_window: Some(10_000_000),
                 max_version_to_prune_per_batch: Some(100),
+                ledger_max_version_to_prune_per_batch: None,
             },
             data_dir: PathBuf::from("/opt/aptos/data"),
             // Default read/write/connection timeout, in milliseconds
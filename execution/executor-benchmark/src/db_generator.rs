@@ -2,7 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    transaction_executor::TransactionExecutor, transaction_generator::TransactionGenerator,
+    transaction_executor::TransactionExecutor,
+    transaction_generator::{GasParameters, TransactionGenerator, Workload},
     TransactionCommitter,
 };
 use aptos_config::{
@@ -35,6 +36,9 @@ pub fn run(
     block_size: usize,
     db_dir: impl AsRef<Path>,
     storage_pruner_config: StoragePrunerConfig,
+    workload: Workload,
+    seed: [u8; 32],
+    gas_parameters: GasParameters,
 ) {
     println!("Initializing...");
 
@@ -71,8 +75,14 @@ pub fn run(
     let gen_thread = std::thread::Builder::new()
         .name("txn_generator".to_string())
         .spawn(move || {
-            let mut generator =
-                TransactionGenerator::new_with_sender(genesis_key, num_accounts, block_sender);
+            let mut generator = TransactionGenerator::new_with_sender(
+                genesis_key,
+                num_accounts,
+                block_sender,
+                seed,
+            )
+            .with_workload(workload)
+            .with_gas_parameters(gas_parameters);
             generator.run_mint(init_account_balance, block_size);
             generator
         })
@@ -7,12 +7,17 @@ pub mod transaction_executor;
 pub mod transaction_generator;
 
 use crate::{
-    transaction_committer::TransactionCommitter, transaction_executor::TransactionExecutor,
-    transaction_generator::TransactionGenerator,
+    transaction_committer::TransactionCommitter,
+    transaction_executor::TransactionExecutor,
+    transaction_generator::{AccountSelection, BlockSizeDistribution, TransactionGenerator},
+};
+use aptos_config::config::{
+    NodeConfig, RocksdbConfig, StoragePrunerConfig, NO_OP_STORAGE_PRUNER_CONFIG,
 };
-use aptos_config::config::{NodeConfig, RocksdbConfig, NO_OP_STORAGE_PRUNER_CONFIG};
 use aptos_logger::prelude::*;
 
+use aptos_crypto::HashValue;
+use aptos_types::transaction::{Transaction, Version};
 use aptos_vm::AptosVM;
 use aptosdb::AptosDB;
 use executor::block_executor::BlockExecutor;
@@ -21,9 +26,14 @@ use std::{
     fs,
     path::Path,
     sync::{mpsc, Arc},
+    thread::JoinHandle,
+    time::{Duration, Instant},
 };
 use storage_interface::{DbReader, DbReaderWriter};
 
+/// How long `run_pruner_benchmark` waits for the pruner to make progress before giving up.
+const PRUNER_STALL_TIMEOUT: Duration = Duration::from_secs(60);
+
 pub fn init_db_and_executor(config: &NodeConfig) -> (Arc<dyn DbReader>, BlockExecutor<AptosVM>) {
     let (db, dbrw) = DbReaderWriter::wrap(
         AptosDB::open(
@@ -40,19 +50,12 @@ pub fn init_db_and_executor(config: &NodeConfig) -> (Arc<dyn DbReader>, BlockExe
     (db, executor)
 }
 
-/// Runs the benchmark with given parameters.
-pub fn run_benchmark(
-    block_size: usize,
-    num_transfer_blocks: usize,
-    source_dir: impl AsRef<Path>,
-    checkpoint_dir: impl AsRef<Path>,
-    verify: bool,
-) {
+fn create_checkpoint(source_dir: &Path, checkpoint_dir: &Path) {
     // Create rocksdb checkpoint.
-    if checkpoint_dir.as_ref().exists() {
-        fs::remove_dir_all(checkpoint_dir.as_ref().join("aptosdb")).unwrap_or(());
+    if checkpoint_dir.exists() {
+        fs::remove_dir_all(checkpoint_dir.join("aptosdb")).unwrap_or(());
     }
-    std::fs::create_dir_all(checkpoint_dir.as_ref()).unwrap();
+    std::fs::create_dir_all(checkpoint_dir).unwrap();
 
     AptosDB::open(
         &source_dir,
@@ -61,37 +64,142 @@ pub fn run_benchmark(
         RocksdbConfig::default(),
     )
     .expect("db open failure.")
-    .create_checkpoint(checkpoint_dir.as_ref().join("aptosdb"))
+    .create_checkpoint(checkpoint_dir.join("aptosdb"))
     .expect("db checkpoint creation fails.");
+}
+
+/// Runs the benchmark with given parameters, generating blocks of the workload recorded in the
+/// metadata of the DB in `source_dir`.
+pub fn run_benchmark(
+    block_size_distribution: BlockSizeDistribution,
+    num_blocks: usize,
+    account_selection: AccountSelection,
+    source_dir: impl AsRef<Path>,
+    checkpoint_dir: impl AsRef<Path>,
+    verify: bool,
+) {
+    create_checkpoint(source_dir.as_ref(), checkpoint_dir.as_ref());
 
     let (mut config, genesis_key) = aptos_genesis_tool::test_config();
     config.storage.dir = checkpoint_dir.as_ref().to_path_buf();
 
     let (db, executor) = init_db_and_executor(&config);
     let parent_block_id = executor.committed_block_id();
-    let executor_1 = Arc::new(executor);
-    let executor_2 = executor_1.clone();
 
     let (block_sender, block_receiver) = mpsc::sync_channel(50 /* bound */);
-    let (commit_sender, commit_receiver) = mpsc::sync_channel(3 /* bound */);
 
     let mut generator =
-        TransactionGenerator::new_with_metafile(genesis_key, block_sender, source_dir);
+        TransactionGenerator::new_with_metafile(genesis_key, block_sender, source_dir)
+            .with_account_selection(account_selection);
     let start_version = generator.version();
 
-    // Spawn two threads to run transaction generator and executor separately.
+    // Spawn threads to run transaction generator, executor and committer separately.
     let gen_thread = std::thread::Builder::new()
         .name("txn_generator".to_string())
         .spawn(move || {
-            generator.run_transfer(block_size, num_transfer_blocks);
+            generator.run_workload(block_size_distribution, num_blocks);
             generator
         })
         .expect("Failed to spawn transaction generator thread.");
+    let (exe_thread, commit_thread) = spawn_executor_and_committer(
+        Arc::new(executor),
+        parent_block_id,
+        start_version,
+        block_receiver,
+    );
+
+    // Wait for generator to finish.
+    let mut generator = gen_thread.join().unwrap();
+    generator.drop_sender();
+    // Wait until all transactions are committed.
+    exe_thread.join().unwrap();
+    commit_thread.join().unwrap();
+
+    // Do a sanity check on the sequence number to make sure all transactions are committed.
+    if verify {
+        generator.verify_sequence_number(db.as_ref());
+    }
+}
+
+/// Generates `num_blocks` blocks of the workload recorded in the metadata of the DB in
+/// `source_dir` and writes them to `txns_file`, for `run_replay_benchmark` to execute against the
+/// same DB without the cost of generating and signing them.
+pub fn generate_transactions(
+    block_size_distribution: BlockSizeDistribution,
+    num_blocks: usize,
+    account_selection: AccountSelection,
+    source_dir: impl AsRef<Path>,
+    txns_file: impl AsRef<Path>,
+) {
+    let (_config, genesis_key) = aptos_genesis_tool::test_config();
+    let (block_sender, block_receiver) = mpsc::sync_channel(50 /* bound */);
+    let mut generator =
+        TransactionGenerator::new_with_metafile(genesis_key, block_sender, source_dir)
+            .with_account_selection(account_selection);
+
+    let gen_thread = std::thread::Builder::new()
+        .name("txn_generator".to_string())
+        .spawn(move || {
+            generator.run_workload(block_size_distribution, num_blocks);
+            generator.drop_sender();
+        })
+        .expect("Failed to spawn transaction generator thread.");
+    let num_txns = TransactionGenerator::dump_to_file(txns_file, block_receiver);
+    gen_thread.join().unwrap();
+    info!(num_txns = num_txns, "Transactions generated.");
+}
+
+/// Runs the benchmark with the blocks in `txns_file`, written by `generate_transactions` for the
+/// DB in `source_dir`.
+pub fn run_replay_benchmark(
+    txns_file: impl AsRef<Path>,
+    source_dir: impl AsRef<Path>,
+    checkpoint_dir: impl AsRef<Path>,
+) {
+    create_checkpoint(source_dir.as_ref(), checkpoint_dir.as_ref());
+
+    let (mut config, _genesis_key) = aptos_genesis_tool::test_config();
+    config.storage.dir = checkpoint_dir.as_ref().to_path_buf();
+
+    let (db, executor) = init_db_and_executor(&config);
+    let parent_block_id = executor.committed_block_id();
+    let start_version = db.get_latest_version().expect("DB should be bootstrapped.");
+
+    let (block_sender, block_receiver) = mpsc::sync_channel(50 /* bound */);
+    let (exe_thread, commit_thread) = spawn_executor_and_committer(
+        Arc::new(executor),
+        parent_block_id,
+        start_version,
+        block_receiver,
+    );
+    let num_txns = TransactionGenerator::replay_from_file(txns_file, block_sender);
+    // Wait until all transactions are committed.
+    exe_thread.join().unwrap();
+    commit_thread.join().unwrap();
+
+    assert_eq!(
+        db.get_latest_version().unwrap(),
+        start_version + num_txns,
+        "All transactions should be committed."
+    );
+}
+
+/// Spawns threads to execute the blocks received from `block_receiver` and commit them, until
+/// the sending end of the channel is dropped.
+fn spawn_executor_and_committer(
+    executor: Arc<BlockExecutor<AptosVM>>,
+    parent_block_id: HashValue,
+    start_version: Version,
+    block_receiver: mpsc::Receiver<Vec<Transaction>>,
+) -> (JoinHandle<()>, JoinHandle<()>) {
+    let executor_2 = executor.clone();
+    let (commit_sender, commit_receiver) = mpsc::sync_channel(3 /* bound */);
+
     let exe_thread = std::thread::Builder::new()
         .name("txn_executor".to_string())
         .spawn(move || {
             let mut exe = TransactionExecutor::new(
-                executor_1,
+                executor,
                 parent_block_id,
                 start_version,
                 Some(commit_sender),
@@ -111,23 +219,84 @@ pub fn run_benchmark(
         })
         .expect("Failed to spawn transaction committer thread.");
 
-    // Wait for generator to finish.
-    let mut generator = gen_thread.join().unwrap();
-    generator.drop_sender();
-    // Wait until all transactions are committed.
-    exe_thread.join().unwrap();
-    commit_thread.join().unwrap();
+    (exe_thread, commit_thread)
+}
 
-    // Do a sanity check on the sequence number to make sure all transactions are committed.
-    if verify {
-        generator.verify_sequence_number(db.as_ref());
+/// Benchmarks the pruner in isolation: the DB in `source_dir`, expected to be created with pruning
+/// disabled, is checkpointed to `checkpoint_dir` and opened with `storage_pruner_config`, then
+/// the pruner deletes the backlog without any new commits. Reports the versions pruned per second
+/// and returns the resulting prune floor of the opened DB. Panics if the pruner stops making
+/// progress for `PRUNER_STALL_TIMEOUT`.
+pub fn run_pruner_benchmark(
+    source_dir: impl AsRef<Path>,
+    checkpoint_dir: impl AsRef<Path>,
+    storage_pruner_config: StoragePrunerConfig,
+) -> Version {
+    create_checkpoint(source_dir.as_ref(), checkpoint_dir.as_ref());
+
+    let db = AptosDB::open(
+        checkpoint_dir.as_ref(),
+        false, /* readonly */
+        storage_pruner_config,
+        RocksdbConfig::default(),
+    )
+    .expect("DB should open.");
+    let latest_version = db.get_latest_version().expect("DB should be bootstrapped.");
+    let health_check = || db.health_check().expect("Health check should work.");
+
+    let start_floor = health_check().first_viable_version;
+    let start = Instant::now();
+    db.wake_pruner(latest_version);
+    let (mut prune_floor, mut last_progress) = (start_floor, Instant::now());
+    loop {
+        let health_status = health_check();
+        if health_status.first_viable_version > prune_floor {
+            prune_floor = health_status.first_viable_version;
+            last_progress = Instant::now();
+        }
+        if health_status
+            .pruner_backlog
+            .expect("Pruner should be enabled.")
+            == 0
+        {
+            break;
+        }
+        assert!(
+            last_progress.elapsed() < PRUNER_STALL_TIMEOUT,
+            "Pruner made no progress in {:?}, stuck at version {}.",
+            PRUNER_STALL_TIMEOUT,
+            prune_floor,
+        );
+        std::thread::sleep(Duration::from_millis(10));
     }
+    let elapsed = start.elapsed();
+
+    info!(
+        latest_version = latest_version,
+        prune_floor = prune_floor,
+        elapsed_ms = elapsed.as_millis() as u64,
+        versions_per_sec = (prune_floor - start_floor) as f64 / elapsed.as_secs_f64(),
+        "Pruner benchmark finished."
+    );
+    prune_floor
 }
 
 #[cfg(test)]
 mod tests {
-    use aptos_config::config::NO_OP_STORAGE_PRUNER_CONFIG;
+    use crate::{
+        transaction_committer::gen_li_with_sigs,
+        transaction_generator::{
+            AccountSelection, BlockSizeDistribution, GasParameters, TransactionGenerator, Workload,
+            WorkloadWeights, DEFAULT_SEED,
+        },
+    };
+    use aptos_config::config::{RocksdbConfig, StoragePrunerConfig, NO_OP_STORAGE_PRUNER_CONFIG};
+    use aptos_crypto::HashValue;
     use aptos_temppath::TempPath;
+    use aptosdb::AptosDB;
+    use executor_types::BlockExecutorTrait;
+    use std::sync::mpsc;
+    use storage_interface::DbReader;
 
     #[test]
     fn test_benchmark() {
@@ -142,14 +311,182 @@ mod tests {
             5,     /* block_size */
             storage_dir.as_ref(),
             NO_OP_STORAGE_PRUNER_CONFIG, /* prune_window */
+            Workload::P2p,
+            DEFAULT_SEED,
+            GasParameters::default(),
         );
 
         super::run_benchmark(
-            5, /* block_size */
-            5, /* num_transfer_blocks */
+            BlockSizeDistribution::Uniform { min: 1, max: 5 },
+            5, /* num_blocks */
+            AccountSelection::Uniform,
             storage_dir.as_ref(),
             checkpoint_dir,
             false,
         );
     }
+
+    #[test]
+    fn test_mixed_benchmark() {
+        let storage_dir = TempPath::new();
+        let checkpoint_dir = TempPath::new();
+        storage_dir.create_as_dir().unwrap();
+        checkpoint_dir.create_as_dir().unwrap();
+
+        crate::db_generator::run(
+            25,    /* num_accounts */
+            10000, /* init_account_balance */
+            5,     /* block_size */
+            storage_dir.as_ref(),
+            NO_OP_STORAGE_PRUNER_CONFIG, /* prune_window */
+            Workload::Mixed(WorkloadWeights {
+                p2p: 2,
+                account_creation: 1,
+                module_publish: 1,
+            }),
+            DEFAULT_SEED,
+            GasParameters::default(),
+        );
+
+        super::run_benchmark(
+            BlockSizeDistribution::Fixed(8),
+            5, /* num_blocks */
+            AccountSelection::Uniform,
+            storage_dir.as_ref(),
+            checkpoint_dir,
+            true, /* verify */
+        );
+    }
+
+    #[test]
+    fn test_replay_benchmark() {
+        let storage_dir = TempPath::new();
+        let checkpoint_dir = TempPath::new();
+        let txns_file = TempPath::new();
+        storage_dir.create_as_dir().unwrap();
+        checkpoint_dir.create_as_dir().unwrap();
+
+        crate::db_generator::run(
+            25,    /* num_accounts */
+            10000, /* init_account_balance */
+            5,     /* block_size */
+            storage_dir.as_ref(),
+            NO_OP_STORAGE_PRUNER_CONFIG, /* prune_window */
+            Workload::P2p,
+            DEFAULT_SEED,
+            GasParameters::default(),
+        );
+
+        super::generate_transactions(
+            BlockSizeDistribution::Uniform { min: 1, max: 5 },
+            5, /* num_blocks */
+            AccountSelection::Uniform,
+            storage_dir.as_ref(),
+            txns_file.as_ref(),
+        );
+        super::run_replay_benchmark(txns_file.as_ref(), storage_dir.as_ref(), checkpoint_dir);
+    }
+
+    #[test]
+    fn test_multi_agent_transfers() {
+        let storage_dir = TempPath::new();
+        storage_dir.create_as_dir().unwrap();
+
+        crate::db_generator::run(
+            25,    /* num_accounts */
+            10000, /* init_account_balance */
+            5,     /* block_size */
+            storage_dir.as_ref(),
+            NO_OP_STORAGE_PRUNER_CONFIG, /* prune_window */
+            Workload::P2p,
+            DEFAULT_SEED,
+            GasParameters::default(),
+        );
+
+        let (mut config, genesis_key) = aptos_genesis_tool::test_config();
+        config.storage.dir = storage_dir.as_ref().to_path_buf();
+        let (db, executor) = super::init_db_and_executor(&config);
+        let start_version = db.get_latest_version().unwrap();
+
+        let (block_sender, block_receiver) = mpsc::sync_channel(50 /* bound */);
+        let mut generator = TransactionGenerator::new_with_metafile(
+            genesis_key,
+            block_sender,
+            storage_dir.as_ref(),
+        );
+        generator.gen_multi_agent_transfers(5 /* block_size */, 1 /* num_blocks */, 2);
+        generator.gen_transfer_transactions(5 /* block_size */, 1 /* num_blocks */);
+        generator.drop_sender();
+        let multi_agent_block = block_receiver.recv().unwrap();
+        let transfer_block = block_receiver.recv().unwrap();
+
+        // The multi-agent transfers are discarded, and the transfers generated afterwards expect
+        // the sequence numbers they left untouched.
+        let multi_agent_block_id = HashValue::random();
+        let output = executor
+            .execute_block(
+                (multi_agent_block_id, multi_agent_block),
+                executor.committed_block_id(),
+            )
+            .unwrap();
+        assert!(output
+            .compute_status()
+            .iter()
+            .all(|status| status.is_discarded()));
+        let transfer_block_id = HashValue::random();
+        let output = executor
+            .execute_block((transfer_block_id, transfer_block), multi_agent_block_id)
+            .unwrap();
+        assert!(output
+            .compute_status()
+            .iter()
+            .all(|status| !status.is_discarded()));
+
+        let version = start_version + 5;
+        assert_eq!(generator.version(), version);
+        executor
+            .commit_blocks(
+                vec![multi_agent_block_id, transfer_block_id],
+                gen_li_with_sigs(transfer_block_id, output.root_hash(), version),
+            )
+            .unwrap();
+        assert_eq!(db.get_latest_version().unwrap(), version);
+        generator.verify_sequence_number(db.as_ref());
+    }
+
+    #[test]
+    fn test_pruner_benchmark() {
+        let storage_dir = TempPath::new();
+        let checkpoint_dir = TempPath::new();
+        storage_dir.create_as_dir().unwrap();
+        checkpoint_dir.create_as_dir().unwrap();
+
+        crate::db_generator::run(
+            25,    /* num_accounts */
+            10000, /* init_account_balance */
+            5,     /* block_size */
+            storage_dir.as_ref(),
+            NO_OP_STORAGE_PRUNER_CONFIG, /* prune_window */
+            Workload::P2p,
+            DEFAULT_SEED,
+            GasParameters::default(),
+        );
+        let latest_version = AptosDB::open(
+            storage_dir.as_ref(),
+            true,                        /* readonly */
+            NO_OP_STORAGE_PRUNER_CONFIG, /* pruner */
+            RocksdbConfig::default(),
+        )
+        .unwrap()
+        .get_latest_version()
+        .unwrap();
+
+        let prune_window = 5;
+        let prune_floor = super::run_pruner_benchmark(
+            storage_dir.as_ref(),
+            checkpoint_dir,
+            StoragePrunerConfig::new(Some(prune_window), Some(prune_window), Some(2)),
+        );
+        assert_eq!(prune_floor, latest_version - prune_window);
+    }
 }
@@ -3,6 +3,9 @@
 
 use aptos_config::config::StoragePrunerConfig;
 use aptos_secure_push_metrics::MetricsPusher;
+use executor_benchmark::transaction_generator::{
+    seed_from_name, AccountSelection, BlockSizeDistribution, GasParameters, Workload, DEFAULT_SEED,
+};
 use std::path::PathBuf;
 use structopt::StructOpt;
 
@@ -38,13 +41,32 @@ enum Command {
 
         #[structopt(long)]
         max_version_to_prune_per_batch: Option<u64>,
-    },
-    RunExecutor {
+
         #[structopt(
             long,
-            default_value = "1000",
-            about = "number of transfer blocks to run"
+            default_value = "p2p",
+            about = "Workload run against the DB: p2p, account-creation, module-publish, or \
+                     mixed:<p2p weight>:<account creation weight>:<module publish weight>"
         )]
+        workload: Workload,
+
+        #[structopt(
+            long,
+            about = "Name of the scenario to derive the seed of the accounts and transactions from"
+        )]
+        seed: Option<String>,
+
+        #[structopt(long, default_value = "1")]
+        gas_unit_price: u64,
+
+        #[structopt(long, default_value = "1000")]
+        max_gas_amount: u64,
+
+        #[structopt(long, default_value = "300")]
+        txn_expiration_time_secs: u64,
+    },
+    RunExecutor {
+        #[structopt(long, default_value = "1000", about = "number of blocks to run")]
         blocks: usize,
 
         #[structopt(long, parse(from_os_str))]
@@ -53,12 +75,103 @@ enum Command {
         #[structopt(long, parse(from_os_str))]
         checkpoint_dir: PathBuf,
 
+        #[structopt(
+            long,
+            about = "When set, transfer block sizes are drawn uniformly from [block_size, max_block_size]"
+        )]
+        max_block_size: Option<usize>,
+
+        #[structopt(
+            long,
+            parse(try_from_str = parse_zipf_exponent),
+            about = "When set, transfer accounts follow a Zipfian distribution with this exponent"
+        )]
+        zipf_exponent: Option<f64>,
+
         #[structopt(
             long,
             about = "Verify sequence number of all the accounts after execution finishes"
         )]
         verify: bool,
     },
+    GenerateTxns {
+        #[structopt(long, default_value = "1000", about = "number of blocks to generate")]
+        blocks: usize,
+
+        #[structopt(long, parse(from_os_str))]
+        data_dir: PathBuf,
+
+        #[structopt(long, parse(from_os_str), about = "File to write the blocks to")]
+        txns_file: PathBuf,
+
+        #[structopt(
+            long,
+            about = "When set, block sizes are drawn uniformly from [block_size, max_block_size]"
+        )]
+        max_block_size: Option<usize>,
+
+        #[structopt(
+            long,
+            parse(try_from_str = parse_zipf_exponent),
+            about = "When set, transfer accounts follow a Zipfian distribution with this exponent"
+        )]
+        zipf_exponent: Option<f64>,
+    },
+    ReplayExecutor {
+        #[structopt(long, parse(from_os_str), about = "File written by generate-txns")]
+        txns_file: PathBuf,
+
+        #[structopt(long, parse(from_os_str), about = "DB the blocks were generated for")]
+        data_dir: PathBuf,
+
+        #[structopt(long, parse(from_os_str))]
+        checkpoint_dir: PathBuf,
+    },
+    RunPruner {
+        #[structopt(long, parse(from_os_str), about = "DB created with pruning disabled")]
+        data_dir: PathBuf,
+
+        #[structopt(long, parse(from_os_str))]
+        checkpoint_dir: PathBuf,
+
+        #[structopt(long, default_value = "0")]
+        prune_window: u64,
+
+        #[structopt(long, default_value = "100")]
+        max_version_to_prune_per_batch: u64,
+    },
+}
+
+fn block_size_distribution(
+    block_size: usize,
+    max_block_size: Option<usize>,
+) -> BlockSizeDistribution {
+    match max_block_size {
+        Some(max) => BlockSizeDistribution::Uniform {
+            min: block_size,
+            max,
+        },
+        None => BlockSizeDistribution::Fixed(block_size),
+    }
+}
+
+/// Parses the exponent of the Zipfian distribution, which must be positive and finite.
+fn parse_zipf_exponent(s: &str) -> Result<f64, String> {
+    let exponent: f64 = s
+        .parse()
+        .map_err(|e| format!("Bad Zipfian exponent {}: {}", s, e))?;
+    if exponent.is_finite() && exponent > 0.0 {
+        Ok(exponent)
+    } else {
+        Err(format!(
+            "Zipfian exponent must be positive and finite, got {}",
+            s
+        ))
+    }
+}
+
+fn account_selection(zipf_exponent: Option<f64>) -> AccountSelection {
+    zipf_exponent.map_or(AccountSelection::Uniform, AccountSelection::Zipfian)
 }
 
 fn main() {
@@ -78,6 +191,11 @@ fn main() {
             state_store_prune_window,
             default_store_prune_window,
             max_version_to_prune_per_batch,
+            workload,
+            seed,
+            gas_unit_price,
+            max_gas_amount,
+            txn_expiration_time_secs,
         } => {
             executor_benchmark::db_generator::run(
                 num_accounts,
@@ -89,22 +207,73 @@ fn main() {
                     Some(default_store_prune_window.unwrap_or(10_000_000)),
                     Some(max_version_to_prune_per_batch.unwrap_or(100)),
                 ),
+                workload,
+                seed.as_deref().map_or(DEFAULT_SEED, seed_from_name),
+                GasParameters {
+                    gas_unit_price,
+                    max_gas_amount,
+                    txn_expiration_time_secs,
+                },
             );
         }
         Command::RunExecutor {
             blocks,
             data_dir,
             checkpoint_dir,
+            max_block_size,
+            zipf_exponent,
             verify,
         } => {
             aptos_logger::Logger::new().init();
             executor_benchmark::run_benchmark(
-                opt.block_size,
+                block_size_distribution(opt.block_size, max_block_size),
                 blocks,
+                account_selection(zipf_exponent),
                 data_dir,
                 checkpoint_dir,
                 verify,
             );
         }
+        Command::GenerateTxns {
+            blocks,
+            data_dir,
+            txns_file,
+            max_block_size,
+            zipf_exponent,
+        } => {
+            aptos_logger::Logger::new().init();
+            executor_benchmark::generate_transactions(
+                block_size_distribution(opt.block_size, max_block_size),
+                blocks,
+                account_selection(zipf_exponent),
+                data_dir,
+                txns_file,
+            );
+        }
+        Command::ReplayExecutor {
+            txns_file,
+            data_dir,
+            checkpoint_dir,
+        } => {
+            aptos_logger::Logger::new().init();
+            executor_benchmark::run_replay_benchmark(txns_file, data_dir, checkpoint_dir);
+        }
+        Command::RunPruner {
+            data_dir,
+            checkpoint_dir,
+            prune_window,
+            max_version_to_prune_per_batch,
+        } => {
+            aptos_logger::Logger::new().init();
+            executor_benchmark::run_pruner_benchmark(
+                data_dir,
+                checkpoint_dir,
+                StoragePrunerConfig::new(
+                    Some(prune_window),
+                    Some(prune_window),
+                    Some(max_version_to_prune_per_batch),
+                ),
+            );
+        }
     }
 }
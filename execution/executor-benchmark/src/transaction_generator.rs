@@ -3,32 +3,54 @@
 
 use aptos_crypto::{
     ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
-    PrivateKey, SigningKey, Uniform,
+    HashValue, PrivateKey, SigningKey, Uniform,
 };
 use aptos_logger::info;
 use aptos_sdk::transaction_builder::TransactionFactory;
 use aptos_types::{
     account_address::AccountAddress,
-    account_config::{aptos_root_address, AccountResource},
+    account_config::aptos_root_address,
     chain_id::ChainId,
     transaction::{RawTransaction, SignedTransaction, Transaction, Version},
 };
 use chrono::Local;
 use indicatif::{ProgressBar, ProgressStyle};
-use rand::{rngs::StdRng, SeedableRng};
+use move_binary_format::file_format::empty_module;
+use move_core_types::identifier::Identifier;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::{
-    convert::TryFrom,
     fs::File,
-    io::{Read, Write},
+    io::{BufReader, BufWriter, ErrorKind, Read, Write},
     path::Path,
-    sync::mpsc,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc,
+    },
     time::Instant,
 };
 use storage_interface::DbReader;
 
 const META_FILENAME: &str = "metadata.toml";
 const MAX_ACCOUNTS_INVOLVED_IN_P2P: usize = 1_000_000;
+/// Accounts are generated in partitions of this size in parallel, each with its own seed. Fixed
+/// regardless of the number of threads, so that the accounts generated are reproducible.
+const ACCOUNTS_PER_PARTITION: usize = 10_000;
+/// The seed of the accounts and transactions generated, unless configured otherwise.
+pub const DEFAULT_SEED: [u8; 32] = [1u8; 32];
+
+/// Derives a seed from `name`, to reproduce a scenario by name rather than by its seed.
+pub fn seed_from_name(name: &str) -> [u8; 32] {
+    let hash = HashValue::sha3_256_of(name.as_bytes());
+    let seed: &[u8; 32] = hash.as_ref();
+    *seed
+}
+
+fn default_seed() -> [u8; 32] {
+    DEFAULT_SEED
+}
 
 fn get_progress_bar(num_accounts: usize) -> ProgressBar {
     let bar = ProgressBar::new(num_accounts as u64);
@@ -49,11 +71,274 @@ macro_rules! now_fmt {
 #[serde(tag = "type", content = "args")]
 enum TestCase {
     P2p(P2pTestCase),
+    AccountCreation(AccountCreationTestCase),
+    ModulePublish(ModulePublishTestCase),
+    Mixed(MixedTestCase),
 }
 
 #[derive(Serialize, Deserialize)]
 struct P2pTestCase {
     num_accounts: usize,
+    #[serde(default = "default_seed")]
+    seed: [u8; 32],
+    #[serde(default)]
+    gas_parameters: GasParameters,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AccountCreationTestCase {
+    num_accounts: usize,
+    #[serde(default = "default_seed")]
+    seed: [u8; 32],
+    #[serde(default)]
+    gas_parameters: GasParameters,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ModulePublishTestCase {
+    num_accounts: usize,
+    #[serde(default = "default_seed")]
+    seed: [u8; 32],
+    #[serde(default)]
+    gas_parameters: GasParameters,
+}
+
+#[derive(Serialize, Deserialize)]
+struct MixedTestCase {
+    num_accounts: usize,
+    weights: WorkloadWeights,
+    #[serde(default = "default_seed")]
+    seed: [u8; 32],
+    #[serde(default)]
+    gas_parameters: GasParameters,
+}
+
+impl TestCase {
+    fn new(
+        num_accounts: usize,
+        workload: Workload,
+        seed: [u8; 32],
+        gas_parameters: GasParameters,
+    ) -> Self {
+        match workload {
+            Workload::P2p => Self::P2p(P2pTestCase {
+                num_accounts,
+                seed,
+                gas_parameters,
+            }),
+            Workload::AccountCreation => Self::AccountCreation(AccountCreationTestCase {
+                num_accounts,
+                seed,
+                gas_parameters,
+            }),
+            Workload::ModulePublish => Self::ModulePublish(ModulePublishTestCase {
+                num_accounts,
+                seed,
+                gas_parameters,
+            }),
+            Workload::Mixed(weights) => Self::Mixed(MixedTestCase {
+                num_accounts,
+                weights,
+                seed,
+                gas_parameters,
+            }),
+        }
+    }
+
+    /// Returns the number of accounts in the DB, the workload to run against it, the seed the
+    /// accounts were generated with and the gas parameters of the transactions.
+    fn into_parts(self) -> (usize, Workload, [u8; 32], GasParameters) {
+        match self {
+            Self::P2p(P2pTestCase {
+                num_accounts,
+                seed,
+                gas_parameters,
+            }) => (num_accounts, Workload::P2p, seed, gas_parameters),
+            Self::AccountCreation(AccountCreationTestCase {
+                num_accounts,
+                seed,
+                gas_parameters,
+            }) => (
+                num_accounts,
+                Workload::AccountCreation,
+                seed,
+                gas_parameters,
+            ),
+            Self::ModulePublish(ModulePublishTestCase {
+                num_accounts,
+                seed,
+                gas_parameters,
+            }) => (num_accounts, Workload::ModulePublish, seed, gas_parameters),
+            Self::Mixed(MixedTestCase {
+                num_accounts,
+                weights,
+                seed,
+                gas_parameters,
+            }) => (num_accounts, Workload::Mixed(weights), seed, gas_parameters),
+        }
+    }
+}
+
+/// The shape of the transactions generated by [`TransactionGenerator::run_workload`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Workload {
+    /// Transfers between random pairs of existing accounts.
+    P2p,
+    /// Creations of new accounts by the root account.
+    AccountCreation,
+    /// Publishing of new modules by random existing accounts.
+    ModulePublish,
+    /// All of the above, interleaved according to the weights.
+    Mixed(WorkloadWeights),
+}
+
+impl FromStr for Workload {
+    type Err = String;
+
+    /// Parses `p2p`, `account-creation`, `module-publish` or `mixed:<p2p>:<account
+    /// creation>:<module publish>`, the latter with the weights of the transaction kinds, e.g.
+    /// `mixed:8:1:1`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "p2p" => Ok(Self::P2p),
+            "account-creation" => Ok(Self::AccountCreation),
+            "module-publish" => Ok(Self::ModulePublish),
+            _ => {
+                let weights = s
+                    .strip_prefix("mixed:")
+                    .map(|weights| {
+                        weights
+                            .split(':')
+                            .map(u32::from_str)
+                            .collect::<Result<Vec<_>, _>>()
+                    })
+                    .ok_or_else(|| format!("Unknown workload: {}", s))?
+                    .map_err(|e| format!("Bad workload weight in {}: {}", s, e))?;
+                match weights[..] {
+                    [p2p, account_creation, module_publish] => {
+                        let weights = WorkloadWeights {
+                            p2p,
+                            account_creation,
+                            module_publish,
+                        };
+                        weights
+                            .total()
+                            .map_err(|e| format!("Bad workload weights in {}: {}", s, e))?;
+                        Ok(Self::Mixed(weights))
+                    }
+                    _ => Err(format!("Expecting 3 workload weights in {}", s)),
+                }
+            }
+        }
+    }
+}
+
+/// The relative frequencies of the kinds of transactions in a mixed workload, e.g. with weights
+/// 8, 1 and 1, 80% of the transactions are transfers.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct WorkloadWeights {
+    pub p2p: u32,
+    pub account_creation: u32,
+    pub module_publish: u32,
+}
+
+impl WorkloadWeights {
+    /// Returns the sum of the weights. Errors if they are all zero or the sum overflows.
+    fn total(&self) -> Result<u32, String> {
+        let total = self
+            .p2p
+            .checked_add(self.account_creation)
+            .and_then(|total| total.checked_add(self.module_publish))
+            .ok_or_else(|| "The sum of the workload weights overflows.".to_string())?;
+        if total == 0 {
+            return Err("All workload weights are zero.".to_string());
+        }
+        Ok(total)
+    }
+
+    fn sample(&self, rng: &mut StdRng) -> TransactionKind {
+        let total = self.total().unwrap_or_else(|e| panic!("{}", e));
+        let n = rng.gen_range(0..total);
+        if n < self.p2p {
+            TransactionKind::P2p
+        } else if n < self.p2p + self.account_creation {
+            TransactionKind::AccountCreation
+        } else {
+            TransactionKind::ModulePublish
+        }
+    }
+}
+
+enum TransactionKind {
+    P2p,
+    AccountCreation,
+    ModulePublish,
+}
+
+/// The gas parameters of the generated transactions.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct GasParameters {
+    pub gas_unit_price: u64,
+    pub max_gas_amount: u64,
+    /// Number of seconds a transaction expires after it's generated.
+    pub txn_expiration_time_secs: u64,
+}
+
+impl Default for GasParameters {
+    fn default() -> Self {
+        Self {
+            gas_unit_price: 1,
+            max_gas_amount: 1000,
+            txn_expiration_time_secs: 300,
+        }
+    }
+}
+
+/// Describes how the number of transactions in each generated transfer block is chosen.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BlockSizeDistribution {
+    /// Every block has exactly this many transactions.
+    Fixed(usize),
+    /// Block sizes are drawn uniformly from `[min, max]`.
+    Uniform { min: usize, max: usize },
+}
+
+impl BlockSizeDistribution {
+    fn sample(&self, rng: &mut StdRng) -> usize {
+        match *self {
+            Self::Fixed(block_size) => block_size,
+            Self::Uniform { min, max } => {
+                assert!(min <= max, "Bad block size range [{}, {}]", min, max);
+                rng.gen_range(min..=max)
+            }
+        }
+    }
+}
+
+/// Describes how the sender and receiver of each generated transfer are chosen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AccountSelection {
+    /// Every account is equally likely to be chosen.
+    Uniform,
+    /// The `i`-th account is chosen with probability proportional to `1 / (i + 1)^s`, so that
+    /// transfers concentrate on a few hot accounts, the more so the larger the exponent `s`.
+    Zipfian(f64),
+}
+
+/// Returns the cumulative distribution of the Zipfian distribution over `num_accounts` accounts
+/// with exponent `s`, normalized so that the last entry is 1.
+fn zipfian_cdf(num_accounts: usize, s: f64) -> Vec<f64> {
+    assert!(s > 0.0, "Bad Zipfian exponent {}", s);
+    let mut cdf: Vec<f64> = (1..=num_accounts)
+        .scan(0.0, |sum, rank| {
+            *sum += 1.0 / (rank as f64).powf(s);
+            Some(*sum)
+        })
+        .collect();
+    if let Some(&total) = cdf.last() {
+        cdf.iter_mut().for_each(|p| *p /= total);
+    }
+    cdf
 }
 
 // TODO: use LocalAccount instead
@@ -76,12 +361,28 @@ pub struct TransactionGenerator {
     /// Used to mint accounts.
     genesis_key: Ed25519PrivateKey,
 
+    /// The sequence number of the next transaction sent by the root account.
+    root_sequence_number: u64,
+
+    /// The workload `run_workload` generates, recorded in the metadata of the DB.
+    workload: Workload,
+
     /// Record the number of txns generated.
     version: Version,
 
+    /// Seeds `rng` and the generation of the accounts, recorded in the metadata of the DB.
+    seed: [u8; 32],
+
+    /// Used by `transaction_factory`, recorded in the metadata of the DB.
+    gas_parameters: GasParameters,
+
     /// For deterministic transaction generation.
     rng: StdRng,
 
+    /// The cumulative distribution over `accounts_cache` to choose the accounts involved in
+    /// transfers from, or `None` to choose them uniformly.
+    account_cdf: Option<Vec<f64>>,
+
     /// Each generated block of transactions are sent to this channel. Using `SyncSender` to make
     /// sure if execution is slow to consume the transactions, we do not run out of memory.
     block_sender: Option<mpsc::SyncSender<Vec<Transaction>>>,
@@ -89,55 +390,96 @@ pub struct TransactionGenerator {
 
 impl TransactionGenerator {
     pub fn new(genesis_key: Ed25519PrivateKey, num_accounts: usize) -> Self {
-        Self::new_impl(genesis_key, num_accounts, None)
+        Self::new_impl(genesis_key, num_accounts, None, DEFAULT_SEED)
     }
 
     pub fn new_with_sender(
         genesis_key: Ed25519PrivateKey,
         num_accounts: usize,
         block_sender: mpsc::SyncSender<Vec<Transaction>>,
+        seed: [u8; 32],
     ) -> Self {
-        Self::new_impl(genesis_key, num_accounts, Some(block_sender))
+        Self::new_impl(genesis_key, num_accounts, Some(block_sender), seed)
     }
 
     fn new_impl(
         genesis_key: Ed25519PrivateKey,
         num_accounts: usize,
         block_sender: Option<mpsc::SyncSender<Vec<Transaction>>>,
+        seed: [u8; 32],
     ) -> Self {
-        let seed = [1u8; 32];
         let rng = StdRng::from_seed(seed);
         Self {
-            accounts_cache: Self::gen_account_cache(num_accounts),
+            accounts_cache: Self::gen_account_cache(num_accounts, seed),
             num_accounts,
             genesis_key,
+            root_sequence_number: 0,
+            workload: Workload::P2p,
             version: 0,
+            seed,
+            gas_parameters: GasParameters::default(),
             rng,
+            account_cdf: None,
             block_sender,
         }
     }
 
-    fn gen_account_cache(num_accounts: usize) -> Vec<AccountData> {
+    pub fn with_workload(mut self, workload: Workload) -> Self {
+        self.workload = workload;
+        self
+    }
+
+    pub fn with_gas_parameters(mut self, gas_parameters: GasParameters) -> Self {
+        assert!(
+            gas_parameters.max_gas_amount > 0,
+            "max_gas_amount must be positive."
+        );
+        self.gas_parameters = gas_parameters;
+        self
+    }
+
+    pub fn with_account_selection(mut self, account_selection: AccountSelection) -> Self {
+        self.account_cdf = match account_selection {
+            AccountSelection::Uniform => None,
+            AccountSelection::Zipfian(s) => Some(zipfian_cdf(self.accounts_cache.len(), s)),
+        };
+        self
+    }
+
+    /// Generates `num_accounts` accounts in parallel. The first `n` accounts generated are the
+    /// same for any `num_accounts >= n`, so a DB can be used with a subset of its accounts.
+    fn gen_account_cache(num_accounts: usize, seed: [u8; 32]) -> Vec<AccountData> {
         let start = Instant::now();
-        let seed = [1u8; 32];
-        let mut rng = StdRng::from_seed(seed);
 
-        let mut accounts = Vec::with_capacity(num_accounts);
         println!("[{}] Generating {} accounts.", now_fmt!(), num_accounts);
         let bar = get_progress_bar(num_accounts);
-        for _i in 0..num_accounts {
-            let private_key = Ed25519PrivateKey::generate(&mut rng);
-            let public_key = private_key.public_key();
-            let address = aptos_types::account_address::from_public_key(&public_key);
-            let account = AccountData {
-                private_key,
-                public_key,
-                address,
-                sequence_number: 0,
-            };
-            accounts.push(account);
-            bar.inc(1);
-        }
+        let num_generated = AtomicU64::new(0);
+        let num_partitions = (num_accounts + ACCOUNTS_PER_PARTITION - 1) / ACCOUNTS_PER_PARTITION;
+        let accounts = (0..num_partitions)
+            .into_par_iter()
+            .flat_map_iter(|partition| {
+                let mut rng = StdRng::from_seed(partition_seed(seed, partition));
+                let partition_size = std::cmp::min(
+                    ACCOUNTS_PER_PARTITION,
+                    num_accounts - partition * ACCOUNTS_PER_PARTITION,
+                );
+                let num_generated = &num_generated;
+                let bar = &bar;
+                (0..partition_size).map(move |_i| {
+                    let private_key = Ed25519PrivateKey::generate(&mut rng);
+                    let public_key = private_key.public_key();
+                    let address = aptos_types::account_address::from_public_key(&public_key);
+                    bar.set_position(num_generated.fetch_add(1, Ordering::Relaxed) + 1);
+                    AccountData {
+                        private_key,
+                        public_key,
+                        address,
+                        sequence_number: 0,
+                    }
+                })
+            })
+            // Collecting a parallel iterator preserves the order of the items.
+            .collect::<Vec<_>>();
         bar.finish();
         println!("[{}] done.", now_fmt!());
 
@@ -159,30 +501,36 @@ impl TransactionGenerator {
         let mut contents = vec![];
         file.read_to_end(&mut contents).unwrap();
         let test_case: TestCase = toml::from_slice(&contents).expect("Must exist.");
-        let num_accounts = match test_case {
-            TestCase::P2p(P2pTestCase { num_accounts }) => num_accounts,
-        };
+        let (num_accounts, workload, seed, gas_parameters) = test_case.into_parts();
 
-        let seed = [1u8; 32];
         let rng = StdRng::from_seed(seed);
         Self {
-            accounts_cache: Self::gen_account_cache(std::cmp::min(
-                num_accounts,
-                MAX_ACCOUNTS_INVOLVED_IN_P2P,
-            )),
+            accounts_cache: Self::gen_account_cache(
+                std::cmp::min(num_accounts, MAX_ACCOUNTS_INVOLVED_IN_P2P),
+                seed,
+            ),
             num_accounts,
             genesis_key,
+            // The root account created and minted to every account.
+            root_sequence_number: 2 * num_accounts as u64,
+            workload,
             version: 2 * num_accounts as Version,
+            seed,
+            gas_parameters,
             rng,
+            account_cdf: None,
             block_sender: Some(block_sender),
         }
     }
 
     // Write metadata
     pub fn write_meta<P: AsRef<Path>>(self, path: &P) {
-        let metadata = TestCase::P2p(P2pTestCase {
-            num_accounts: self.num_accounts,
-        });
+        let metadata = TestCase::new(
+            self.num_accounts,
+            self.workload,
+            self.seed,
+            self.gas_parameters,
+        );
         let serialized = toml::to_vec(&metadata).unwrap();
         let meta_file = path.as_ref().join(META_FILENAME);
         let mut file = File::create(meta_file).unwrap();
@@ -193,22 +541,65 @@ impl TransactionGenerator {
         self.version
     }
 
+    pub fn workload(&self) -> Workload {
+        self.workload
+    }
+
     pub fn run_mint(&mut self, init_account_balance: u64, block_size: usize) {
         assert!(self.block_sender.is_some());
         self.gen_account_creations(block_size);
         self.gen_mint_transactions(init_account_balance, block_size);
     }
 
-    pub fn run_transfer(&mut self, block_size: usize, num_transfer_blocks: usize) {
+    pub fn run_transfer(
+        &mut self,
+        block_size_distribution: BlockSizeDistribution,
+        num_transfer_blocks: usize,
+    ) {
         assert!(self.block_sender.is_some());
-        self.gen_transfer_transactions(block_size, num_transfer_blocks);
+        self.gen_transfer_transactions_with_distribution(
+            block_size_distribution,
+            num_transfer_blocks,
+        );
     }
 
-    pub fn transaction_factory() -> TransactionFactory {
+    /// Runs `num_blocks` blocks of the workload of the generator.
+    pub fn run_workload(
+        &mut self,
+        block_size_distribution: BlockSizeDistribution,
+        num_blocks: usize,
+    ) {
+        assert!(self.block_sender.is_some());
+        let weights = match self.workload {
+            Workload::P2p => {
+                self.run_transfer(block_size_distribution, num_blocks);
+                return;
+            }
+            Workload::AccountCreation => WorkloadWeights {
+                p2p: 0,
+                account_creation: 1,
+                module_publish: 0,
+            },
+            Workload::ModulePublish => WorkloadWeights {
+                p2p: 0,
+                account_creation: 0,
+                module_publish: 1,
+            },
+            Workload::Mixed(weights) => weights,
+        };
+        self.gen_mixed_transactions_with_distribution(weights, block_size_distribution, num_blocks);
+    }
+
+    pub fn run_mixed(&mut self, weights: WorkloadWeights, block_size: usize, num_blocks: usize) {
+        assert!(self.block_sender.is_some());
+        self.gen_mixed_transactions(weights, block_size, num_blocks);
+    }
+
+    pub fn transaction_factory(&self) -> TransactionFactory {
         TransactionFactory::new(ChainId::test())
-            .with_transaction_expiration_time(300)
-            .with_gas_unit_price(1)
-            .with_max_gas_amount(1000)
+            .with_transaction_expiration_time(self.gas_parameters.txn_expiration_time_secs)
+            .with_gas_unit_price(self.gas_parameters.gas_unit_price)
+            .with_max_gas_amount(self.gas_parameters.max_gas_amount)
     }
 
     pub fn gen_account_creations(&mut self, block_size: usize) -> Vec<Vec<Transaction>> {
@@ -221,19 +612,20 @@ impl TransactionGenerator {
             self.accounts_cache.len(),
         );
         let bar = get_progress_bar(self.accounts_cache.len());
-        for (i, block) in self.accounts_cache.chunks(block_size).enumerate() {
+        for block in self.accounts_cache.chunks(block_size) {
             let mut transactions = Vec::with_capacity(block_size);
-            for (j, account) in block.iter().enumerate() {
+            for account in block {
                 let txn = create_transaction(
                     &self.genesis_key,
                     self.genesis_key.public_key(),
-                    Self::transaction_factory()
+                    self.transaction_factory()
                         .create_user_account(&account.public_key)
                         .sender(root_address)
-                        .sequence_number((i * block_size + j) as u64)
+                        .sequence_number(self.root_sequence_number)
                         .build(),
                 );
                 transactions.push(txn);
+                self.root_sequence_number += 1;
             }
             self.version += transactions.len() as Version;
             if let Some(sender) = &self.block_sender {
@@ -260,19 +652,20 @@ impl TransactionGenerator {
         let total_accounts = self.accounts_cache.len();
         println!("[{}] Generating {} mint txns.", now_fmt!(), total_accounts,);
         let bar = get_progress_bar(total_accounts);
-        for (i, block) in self.accounts_cache.chunks(block_size).enumerate() {
+        for block in self.accounts_cache.chunks(block_size) {
             let mut transactions = Vec::with_capacity(block_size);
-            for (j, account) in block.iter().enumerate() {
+            for account in block {
                 let txn = create_transaction(
                     &self.genesis_key,
                     self.genesis_key.public_key(),
-                    Self::transaction_factory()
+                    self.transaction_factory()
                         .mint(account.address, init_account_balance)
                         .sender(root_address)
-                        .sequence_number((total_accounts + i * block_size + j) as u64)
+                        .sequence_number(self.root_sequence_number)
                         .build(),
                 );
                 transactions.push(txn);
+                self.root_sequence_number += 1;
             }
             self.version += transactions.len() as Version;
 
@@ -293,29 +686,137 @@ impl TransactionGenerator {
         &mut self,
         block_size: usize,
         num_blocks: usize,
+    ) -> Vec<Vec<Transaction>> {
+        self.gen_transfer_transactions_with_distribution(
+            BlockSizeDistribution::Fixed(block_size),
+            num_blocks,
+        )
+    }
+
+    /// Generates transactions for random pairs of accounts, with the size of each block drawn
+    /// from `block_size_distribution`.
+    pub fn gen_transfer_transactions_with_distribution(
+        &mut self,
+        block_size_distribution: BlockSizeDistribution,
+        num_blocks: usize,
     ) -> Vec<Vec<Transaction>> {
         let mut txn_block = vec![];
 
         for _i in 0..num_blocks {
+            let block_size = block_size_distribution.sample(&mut self.rng);
             let mut transactions = Vec::with_capacity(block_size);
             for _j in 0..block_size {
-                let indices = rand::seq::index::sample(&mut self.rng, self.accounts_cache.len(), 2);
+                transactions.push(self.gen_transfer_transaction());
+            }
+            self.version += transactions.len() as Version;
+
+            if let Some(sender) = &self.block_sender {
+                sender.send(transactions).unwrap();
+            } else {
+                txn_block.push(transactions);
+            }
+        }
+        txn_block
+    }
+
+    /// Generates multi-agent transfers between random pairs of accounts, each also signed by
+    /// `num_secondary_signers` other random accounts.
+    ///
+    /// Note the Aptos framework currently rejects multi-agent transactions in the prologue, so
+    /// these are discarded after their signatures are verified. Hence they neither consume the
+    /// sequence numbers of the senders nor take up versions, and other transactions generated
+    /// afterwards can be executed after them.
+    pub fn gen_multi_agent_transfers(
+        &mut self,
+        block_size: usize,
+        num_blocks: usize,
+        num_secondary_signers: usize,
+    ) -> Vec<Vec<Transaction>> {
+        assert!(
+            num_secondary_signers + 2 <= self.accounts_cache.len(),
+            "Not enough accounts for {} secondary signers.",
+            num_secondary_signers,
+        );
+        let mut txn_block = vec![];
+
+        for _i in 0..num_blocks {
+            let mut transactions = Vec::with_capacity(block_size);
+            for _j in 0..block_size {
+                let indices = rand::seq::index::sample(
+                    &mut self.rng,
+                    self.accounts_cache.len(),
+                    num_secondary_signers + 2,
+                );
                 let sender_idx = indices.index(0);
                 let receiver_idx = indices.index(1);
 
                 let sender = &self.accounts_cache[sender_idx];
                 let receiver = &self.accounts_cache[receiver_idx];
-                let txn = create_transaction(
-                    &sender.private_key,
-                    sender.public_key.clone(),
-                    Self::transaction_factory()
-                        .transfer(receiver.address, 1)
-                        .sender(sender.address)
-                        .sequence_number(sender.sequence_number)
-                        .build(),
-                );
+                let secondary_signers: Vec<_> = indices
+                    .iter()
+                    .skip(2)
+                    .map(|idx| &self.accounts_cache[idx])
+                    .collect();
+                let txn = self
+                    .transaction_factory()
+                    .transfer(receiver.address, 1)
+                    .sender(sender.address)
+                    .sequence_number(sender.sequence_number)
+                    .build()
+                    .sign_multi_agent(
+                        &sender.private_key,
+                        secondary_signers.iter().map(|a| a.address).collect(),
+                        secondary_signers.iter().map(|a| &a.private_key).collect(),
+                    )
+                    .expect("Signing multi-agent transaction must succeed.")
+                    .into_inner();
+                transactions.push(Transaction::UserTransaction(txn));
+            }
+
+            if let Some(sender) = &self.block_sender {
+                sender.send(transactions).unwrap();
+            } else {
+                txn_block.push(transactions);
+            }
+        }
+        txn_block
+    }
+
+    /// Generates transactions of the kinds in `weights`, interleaved at random according to them.
+    /// Accounts created are new accounts, not funded and not involved in any further transactions.
+    pub fn gen_mixed_transactions(
+        &mut self,
+        weights: WorkloadWeights,
+        block_size: usize,
+        num_blocks: usize,
+    ) -> Vec<Vec<Transaction>> {
+        self.gen_mixed_transactions_with_distribution(
+            weights,
+            BlockSizeDistribution::Fixed(block_size),
+            num_blocks,
+        )
+    }
+
+    /// Like [`gen_mixed_transactions`](Self::gen_mixed_transactions), with the size of each block
+    /// drawn from `block_size_distribution`.
+    pub fn gen_mixed_transactions_with_distribution(
+        &mut self,
+        weights: WorkloadWeights,
+        block_size_distribution: BlockSizeDistribution,
+        num_blocks: usize,
+    ) -> Vec<Vec<Transaction>> {
+        let mut txn_block = vec![];
+
+        for _i in 0..num_blocks {
+            let block_size = block_size_distribution.sample(&mut self.rng);
+            let mut transactions = Vec::with_capacity(block_size);
+            for _j in 0..block_size {
+                let txn = match weights.sample(&mut self.rng) {
+                    TransactionKind::P2p => self.gen_transfer_transaction(),
+                    TransactionKind::AccountCreation => self.gen_account_creation_transaction(),
+                    TransactionKind::ModulePublish => self.gen_module_publish_transaction(),
+                };
                 transactions.push(txn);
-                self.accounts_cache[sender_idx].sequence_number += 1;
             }
             self.version += transactions.len() as Version;
 
@@ -328,6 +829,99 @@ impl TransactionGenerator {
         txn_block
     }
 
+    /// Generates a transfer between a random pair of accounts.
+    fn gen_transfer_transaction(&mut self) -> Transaction {
+        let (sender_idx, receiver_idx) = self.sample_account_pair();
+
+        let sender = &self.accounts_cache[sender_idx];
+        let receiver = &self.accounts_cache[receiver_idx];
+        let txn = create_transaction(
+            &sender.private_key,
+            sender.public_key.clone(),
+            self.transaction_factory()
+                .transfer(receiver.address, 1)
+                .sender(sender.address)
+                .sequence_number(sender.sequence_number)
+                .build(),
+        );
+        self.accounts_cache[sender_idx].sequence_number += 1;
+        txn
+    }
+
+    /// Chooses the indices of two distinct accounts according to the account selection.
+    fn sample_account_pair(&mut self) -> (usize, usize) {
+        match &self.account_cdf {
+            None => {
+                let indices = rand::seq::index::sample(&mut self.rng, self.accounts_cache.len(), 2);
+                (indices.index(0), indices.index(1))
+            }
+            Some(cdf) => {
+                assert!(cdf.len() >= 2, "Not enough accounts for transfers.");
+                let last_idx = cdf.len() - 1;
+                let p: f64 = self.rng.gen();
+                // Rounding may leave the last entry slightly below 1.
+                let sender_idx = std::cmp::min(cdf.partition_point(|&q| q <= p), last_idx);
+
+                // Sample the receiver from the distribution with the mass of the sender removed,
+                // rather than rejecting the sender, which could take forever with a hot sender.
+                let lower = if sender_idx == 0 {
+                    0.0
+                } else {
+                    cdf[sender_idx - 1]
+                };
+                let mass = cdf[sender_idx] - lower;
+                let p = self.rng.gen::<f64>() * (1.0 - mass);
+                let receiver_idx = if p < lower || sender_idx == last_idx {
+                    std::cmp::min(
+                        cdf[..sender_idx].partition_point(|&q| q <= p),
+                        sender_idx - 1,
+                    )
+                } else {
+                    std::cmp::min(
+                        sender_idx + 1 + cdf[sender_idx + 1..].partition_point(|&q| q <= p + mass),
+                        last_idx,
+                    )
+                };
+                (sender_idx, receiver_idx)
+            }
+        }
+    }
+
+    /// Generates the creation of a new account by the root account.
+    fn gen_account_creation_transaction(&mut self) -> Transaction {
+        let public_key = Ed25519PrivateKey::generate(&mut self.rng).public_key();
+        let txn = create_transaction(
+            &self.genesis_key,
+            self.genesis_key.public_key(),
+            self.transaction_factory()
+                .create_user_account(&public_key)
+                .sender(aptos_root_address())
+                .sequence_number(self.root_sequence_number)
+                .build(),
+        );
+        self.root_sequence_number += 1;
+        txn
+    }
+
+    /// Generates the publishing of a new, empty module by a random account.
+    fn gen_module_publish_transaction(&mut self) -> Transaction {
+        let sender_idx = self.rng.gen_range(0..self.accounts_cache.len());
+        let sender = &self.accounts_cache[sender_idx];
+        // Named after the sequence number so every module published by an account is new.
+        let name = format!("BenchmarkModule{}", sender.sequence_number);
+        let txn = create_transaction(
+            &sender.private_key,
+            sender.public_key.clone(),
+            self.transaction_factory()
+                .module(empty_module_bytes(sender.address, &name))
+                .sender(sender.address)
+                .sequence_number(sender.sequence_number)
+                .build(),
+        );
+        self.accounts_cache[sender_idx].sequence_number += 1;
+        txn
+    }
+
     /// Verifies the sequence numbers in storage match what we have locally.
     pub fn verify_sequence_number(&self, db: &dyn DbReader) {
         println!(
@@ -338,24 +932,78 @@ impl TransactionGenerator {
         let bar = get_progress_bar(self.accounts_cache.len());
         for account in &self.accounts_cache {
             let address = account.address;
-            let blob = db
-                .get_latest_account_state(address)
+            let sequence_number = db
+                .get_account_sequence_number(address, db.get_latest_version().unwrap())
                 .expect("Failed to query storage.")
                 .expect("Account must exist.");
-            let account_resource = AccountResource::try_from(&blob).unwrap();
-            assert_eq!(account_resource.sequence_number(), account.sequence_number);
+            assert_eq!(sequence_number, account.sequence_number);
             bar.inc(1);
         }
         bar.finish();
         println!("[{}] done.", now_fmt!());
     }
 
+    /// Writes `blocks` to the file `path`, for [`replay_from_file`](Self::replay_from_file) to
+    /// send them again without generating and signing the transactions. Each block is written BCS
+    /// serialized, prefixed by its length as a little endian `u64`. Returns the number of
+    /// transactions written.
+    pub fn dump_to_file<P: AsRef<Path>, I: IntoIterator<Item = Vec<Transaction>>>(
+        path: P,
+        blocks: I,
+    ) -> Version {
+        let mut file = BufWriter::new(File::create(path).unwrap());
+        let mut num_txns = 0;
+        for block in blocks {
+            let bytes = bcs::to_bytes(&block).unwrap();
+            file.write_all(&(bytes.len() as u64).to_le_bytes()).unwrap();
+            file.write_all(&bytes).unwrap();
+            num_txns += block.len() as Version;
+        }
+        file.flush().unwrap();
+        num_txns
+    }
+
+    /// Sends the blocks in the file `path` written by [`dump_to_file`](Self::dump_to_file) to
+    /// `block_sender` one by one, then drops it to notify the receiving end of the channel.
+    /// Returns the number of transactions sent.
+    pub fn replay_from_file<P: AsRef<Path>>(
+        path: P,
+        block_sender: mpsc::SyncSender<Vec<Transaction>>,
+    ) -> Version {
+        let mut file = BufReader::new(File::open(path).unwrap());
+        let mut num_txns = 0;
+        let mut len_bytes = [0u8; 8];
+        loop {
+            match file.read_exact(&mut len_bytes) {
+                Ok(()) => (),
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+                Err(e) => panic!("Failed to read transactions file: {}", e),
+            }
+            let mut bytes = vec![0u8; u64::from_le_bytes(len_bytes) as usize];
+            file.read_exact(&mut bytes)
+                .expect("Transactions file must not be truncated.");
+            let block: Vec<Transaction> =
+                bcs::from_bytes(&bytes).expect("Transactions file must be valid.");
+            num_txns += block.len() as Version;
+            block_sender.send(block).unwrap();
+        }
+        num_txns
+    }
+
     /// Drops the sender to notify the receiving end of the channel.
     pub fn drop_sender(&mut self) {
         self.block_sender.take().unwrap();
     }
 }
 
+/// Derives the seed of an account generation partition from the base `seed`.
+fn partition_seed(mut seed: [u8; 32], partition: usize) -> [u8; 32] {
+    for (byte, partition_byte) in seed.iter_mut().zip(partition.to_le_bytes().iter()) {
+        *byte = byte.wrapping_add(*partition_byte);
+    }
+    seed
+}
+
 fn create_transaction(
     private_key: &Ed25519PrivateKey,
     public_key: Ed25519PublicKey,
@@ -365,3 +1013,412 @@ fn create_transaction(
     let signed_txn = SignedTransaction::new(raw_txn, public_key, signature);
     Transaction::UserTransaction(signed_txn)
 }
+
+/// Returns the serialized module `address::name` with no structs or functions.
+fn empty_module_bytes(address: AccountAddress, name: &str) -> Vec<u8> {
+    let mut module = empty_module();
+    module.address_identifiers[0] = address;
+    module.identifiers[0] = Identifier::new(name).expect("Module name must be valid.");
+    let mut bytes = vec![];
+    module
+        .serialize(&mut bytes)
+        .expect("Module serialization must succeed.");
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_temppath::TempPath;
+    use aptos_types::transaction::TransactionPayload;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_block_size_distribution() {
+        const NUM_BLOCKS: usize = 200;
+        let (min, max) = (2, 8);
+        let distribution = BlockSizeDistribution::Uniform { min, max };
+
+        let mut generator =
+            TransactionGenerator::new(Ed25519PrivateKey::generate_for_testing(), 10);
+        let blocks =
+            generator.gen_transfer_transactions_with_distribution(distribution, NUM_BLOCKS);
+        assert_eq!(blocks.len(), NUM_BLOCKS);
+        assert!(blocks.iter().all(|b| (min..=max).contains(&b.len())));
+
+        let num_txns: usize = blocks.iter().map(Vec::len).sum();
+        let mean = num_txns as f64 / NUM_BLOCKS as f64;
+        assert!((mean - (min + max) as f64 / 2.0).abs() < 0.5);
+        assert_eq!(generator.version(), num_txns as Version);
+
+        // Sequence numbers must stay contiguous per sender across blocks of varying size.
+        let mut next_seq_nums = HashMap::new();
+        for txn in blocks.iter().flatten() {
+            let signed_txn = txn.as_signed_user_txn().unwrap();
+            let next_seq_num = next_seq_nums.entry(signed_txn.sender()).or_insert(0);
+            assert_eq!(signed_txn.sequence_number(), *next_seq_num);
+            *next_seq_num += 1;
+        }
+        for account in &generator.accounts_cache {
+            assert_eq!(
+                next_seq_nums.get(&account.address).cloned().unwrap_or(0),
+                account.sequence_number
+            );
+        }
+    }
+
+    #[test]
+    fn test_multi_agent_transfers() {
+        const NUM_BLOCKS: usize = 5;
+        const BLOCK_SIZE: usize = 10;
+        const NUM_SECONDARY_SIGNERS: usize = 3;
+
+        let mut generator =
+            TransactionGenerator::new(Ed25519PrivateKey::generate_for_testing(), 10);
+        let blocks =
+            generator.gen_multi_agent_transfers(BLOCK_SIZE, NUM_BLOCKS, NUM_SECONDARY_SIGNERS);
+        assert_eq!(blocks.len(), NUM_BLOCKS);
+        assert_eq!(generator.version(), 0);
+
+        for txn in blocks.iter().flatten() {
+            let signed_txn = txn.as_signed_user_txn().unwrap();
+            assert!(signed_txn.is_multi_agent());
+            signed_txn.clone().check_signature().unwrap();
+
+            let secondary_signers = signed_txn.authenticator().secondary_signer_addreses();
+            assert_eq!(secondary_signers.len(), NUM_SECONDARY_SIGNERS);
+            assert!(!secondary_signers.contains(&signed_txn.sender()));
+            assert!(!signed_txn.contains_duplicate_signers());
+
+            // The transactions are discarded, so no sequence number is consumed.
+            assert_eq!(signed_txn.sequence_number(), 0);
+        }
+        assert!(generator
+            .accounts_cache
+            .iter()
+            .all(|account| account.sequence_number == 0));
+    }
+
+    #[test]
+    fn test_dump_and_replay() {
+        let txns_file = TempPath::new();
+        let mut generator =
+            TransactionGenerator::new(Ed25519PrivateKey::generate_for_testing(), 10);
+        let blocks = generator.gen_transfer_transactions_with_distribution(
+            BlockSizeDistribution::Uniform { min: 0, max: 5 },
+            20, /* num_blocks */
+        );
+        let num_txns = blocks.iter().map(Vec::len).sum::<usize>() as Version;
+        assert_eq!(
+            TransactionGenerator::dump_to_file(&txns_file, blocks.clone()),
+            num_txns
+        );
+
+        let (block_sender, block_receiver) = mpsc::sync_channel(1);
+        let replay_thread = std::thread::spawn(move || {
+            TransactionGenerator::replay_from_file(&txns_file, block_sender)
+        });
+        // The blocks are sent again with the same boundaries.
+        assert_eq!(block_receiver.iter().collect::<Vec<_>>(), blocks);
+        assert_eq!(replay_thread.join().unwrap(), num_txns);
+    }
+
+    #[test]
+    fn test_gen_account_cache() {
+        let addresses = |accounts: Vec<AccountData>| -> Vec<AccountAddress> {
+            accounts
+                .into_iter()
+                .map(|account| account.address)
+                .collect()
+        };
+
+        let accounts = addresses(TransactionGenerator::gen_account_cache(
+            ACCOUNTS_PER_PARTITION + 10,
+            DEFAULT_SEED,
+        ));
+        assert_eq!(accounts.len(), ACCOUNTS_PER_PARTITION + 10);
+        let unique_accounts: std::collections::HashSet<_> = accounts.iter().collect();
+        assert_eq!(unique_accounts.len(), accounts.len());
+
+        // Reproducible, and any smaller cache is a prefix of a larger one.
+        assert_eq!(
+            addresses(TransactionGenerator::gen_account_cache(
+                ACCOUNTS_PER_PARTITION + 10,
+                DEFAULT_SEED,
+            )),
+            accounts
+        );
+        assert_eq!(
+            addresses(TransactionGenerator::gen_account_cache(
+                ACCOUNTS_PER_PARTITION + 5,
+                DEFAULT_SEED,
+            )),
+            accounts[..ACCOUNTS_PER_PARTITION + 5]
+        );
+        assert_eq!(
+            addresses(TransactionGenerator::gen_account_cache(5, DEFAULT_SEED)),
+            accounts[..5]
+        );
+    }
+
+    #[test]
+    fn test_zipfian_transfers() {
+        const NUM_ACCOUNTS: usize = 100;
+        const NUM_BLOCKS: usize = 100;
+        const BLOCK_SIZE: usize = 10;
+
+        let cdf = zipfian_cdf(NUM_ACCOUNTS, 1.0);
+        assert_eq!(cdf.len(), NUM_ACCOUNTS);
+        assert!(cdf.windows(2).all(|w| w[0] < w[1]));
+        assert!((cdf[NUM_ACCOUNTS - 1] - 1.0).abs() < 1e-9);
+
+        let mut generator =
+            TransactionGenerator::new(Ed25519PrivateKey::generate_for_testing(), NUM_ACCOUNTS)
+                .with_account_selection(AccountSelection::Zipfian(1.0));
+        let blocks = generator.gen_transfer_transactions(BLOCK_SIZE, NUM_BLOCKS);
+
+        let index_by_address: HashMap<_, _> = generator
+            .accounts_cache
+            .iter()
+            .enumerate()
+            .map(|(idx, account)| (account.address, idx))
+            .collect();
+        let mut num_sent = vec![0usize; NUM_ACCOUNTS];
+        let mut next_seq_nums = HashMap::new();
+        for txn in blocks.iter().flatten() {
+            let signed_txn = txn.as_signed_user_txn().unwrap();
+            let receiver = match signed_txn.payload() {
+                TransactionPayload::ScriptFunction(script_fn) => {
+                    bcs::from_bytes::<AccountAddress>(&script_fn.args()[0]).unwrap()
+                }
+                _ => panic!("Expecting a transfer."),
+            };
+            assert_ne!(signed_txn.sender(), receiver);
+            num_sent[index_by_address[&signed_txn.sender()]] += 1;
+
+            let next_seq_num = next_seq_nums.entry(signed_txn.sender()).or_insert(0);
+            assert_eq!(signed_txn.sequence_number(), *next_seq_num);
+            *next_seq_num += 1;
+        }
+        // With exponent 1, the hottest account sends about 19% of the transfers, while the last
+        // 25 accounts together send about 6%.
+        let num_txns = (NUM_BLOCKS * BLOCK_SIZE) as f64;
+        assert!(num_sent[0] as f64 > 0.12 * num_txns);
+        assert!(num_sent[0] > 2 * num_sent[NUM_ACCOUNTS - 25..].iter().sum::<usize>());
+    }
+
+    #[test]
+    fn test_zipfian_account_pair_with_hot_sender() {
+        // Nearly all the mass is on the first account, which is nearly always the sender.
+        let mut generator = TransactionGenerator::new(Ed25519PrivateKey::generate_for_testing(), 3)
+            .with_account_selection(AccountSelection::Zipfian(50.0));
+        let mut num_received = [0usize; 3];
+        for _ in 0..1000 {
+            let (sender_idx, receiver_idx) = generator.sample_account_pair();
+            assert_ne!(sender_idx, receiver_idx);
+            num_received[receiver_idx] += 1;
+        }
+        // With the sender's mass removed, the second account dominates among the receivers.
+        assert!(num_received[1] > 900);
+    }
+
+    #[test]
+    fn test_mixed_transactions() {
+        const NUM_BLOCKS: usize = 50;
+        const BLOCK_SIZE: usize = 10;
+        let weights = WorkloadWeights {
+            p2p: 2,
+            account_creation: 1,
+            module_publish: 1,
+        };
+
+        let mut generator =
+            TransactionGenerator::new(Ed25519PrivateKey::generate_for_testing(), 10);
+        let blocks = generator.gen_mixed_transactions(weights, BLOCK_SIZE, NUM_BLOCKS);
+        assert_eq!(blocks.len(), NUM_BLOCKS);
+        assert!(blocks.iter().all(|b| b.len() == BLOCK_SIZE));
+        assert_eq!(generator.version(), (NUM_BLOCKS * BLOCK_SIZE) as Version);
+
+        let mut counts = HashMap::new();
+        let mut next_seq_nums = HashMap::new();
+        for txn in blocks.iter().flatten() {
+            let signed_txn = txn.as_signed_user_txn().unwrap();
+            let kind = match signed_txn.payload() {
+                TransactionPayload::ModuleBundle(_) => "module_publish",
+                _ if signed_txn.sender() == aptos_root_address() => "account_creation",
+                _ => "p2p",
+            };
+            *counts.entry(kind).or_insert(0) += 1;
+
+            let next_seq_num = next_seq_nums.entry(signed_txn.sender()).or_insert(0);
+            assert_eq!(signed_txn.sequence_number(), *next_seq_num);
+            *next_seq_num += 1;
+        }
+        // Roughly half of the transactions are transfers, a quarter of each of the others.
+        assert!((200..300).contains(&counts["p2p"]));
+        assert!((75..175).contains(&counts["account_creation"]));
+        assert!((75..175).contains(&counts["module_publish"]));
+        assert_eq!(
+            generator.root_sequence_number,
+            counts["account_creation"] as u64
+        );
+        for account in &generator.accounts_cache {
+            assert_eq!(
+                next_seq_nums.get(&account.address).cloned().unwrap_or(0),
+                account.sequence_number
+            );
+        }
+    }
+
+    #[test]
+    fn test_workload_in_metafile() {
+        let db_dir = TempPath::new();
+        db_dir.create_as_dir().unwrap();
+        let workload = Workload::Mixed(WorkloadWeights {
+            p2p: 8,
+            account_creation: 1,
+            module_publish: 1,
+        });
+        let seed = seed_from_name("test_workload_in_metafile");
+        let gas_parameters = GasParameters {
+            gas_unit_price: 2,
+            max_gas_amount: 500,
+            txn_expiration_time_secs: 60,
+        };
+        let source = TransactionGenerator::new_impl(
+            Ed25519PrivateKey::generate_for_testing(),
+            10,
+            None,
+            seed,
+        )
+        .with_workload(workload)
+        .with_gas_parameters(gas_parameters);
+        let addresses: Vec<_> = source.accounts_cache.iter().map(|a| a.address).collect();
+        source.write_meta(&db_dir);
+
+        let (block_sender, _block_receiver) = mpsc::sync_channel(1);
+        let generator = TransactionGenerator::new_with_metafile(
+            Ed25519PrivateKey::generate_for_testing(),
+            block_sender,
+            &db_dir,
+        );
+        assert_eq!(generator.workload(), workload);
+        assert_eq!(generator.num_accounts, 10);
+        assert_eq!(generator.root_sequence_number, 20);
+        assert_eq!(generator.seed, seed);
+        assert_eq!(generator.gas_parameters, gas_parameters);
+        assert!(generator
+            .accounts_cache
+            .iter()
+            .map(|a| a.address)
+            .eq(addresses.into_iter()));
+    }
+
+    #[test]
+    fn test_metafile_without_seed() {
+        let db_dir = TempPath::new();
+        db_dir.create_as_dir().unwrap();
+        // Written before the seed was configurable.
+        std::fs::write(
+            db_dir.path().join(META_FILENAME),
+            "type = \"P2p\"\n\n[args]\nnum_accounts = 10\n",
+        )
+        .unwrap();
+
+        let (block_sender, _block_receiver) = mpsc::sync_channel(1);
+        let generator = TransactionGenerator::new_with_metafile(
+            Ed25519PrivateKey::generate_for_testing(),
+            block_sender,
+            &db_dir,
+        );
+        assert_eq!(generator.workload(), Workload::P2p);
+        assert_eq!(generator.seed, DEFAULT_SEED);
+        assert_eq!(generator.gas_parameters, GasParameters::default());
+    }
+
+    #[test]
+    fn test_gas_parameters() {
+        let gas_parameters = GasParameters {
+            gas_unit_price: 3,
+            max_gas_amount: 2000,
+            txn_expiration_time_secs: 100,
+        };
+        let mut generator =
+            TransactionGenerator::new(Ed25519PrivateKey::generate_for_testing(), 10)
+                .with_gas_parameters(gas_parameters);
+        let blocks = generator.gen_mixed_transactions(
+            WorkloadWeights {
+                p2p: 1,
+                account_creation: 1,
+                module_publish: 1,
+            },
+            10, /* block_size */
+            2,  /* num_blocks */
+        );
+        for txn in blocks.iter().flatten() {
+            let signed_txn = txn.as_signed_user_txn().unwrap();
+            assert_eq!(signed_txn.gas_unit_price(), 3);
+            assert_eq!(signed_txn.max_gas_amount(), 2000);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "max_gas_amount must be positive.")]
+    fn test_zero_max_gas_amount() {
+        let _generator = TransactionGenerator::new(Ed25519PrivateKey::generate_for_testing(), 2)
+            .with_gas_parameters(GasParameters {
+                max_gas_amount: 0,
+                ..Default::default()
+            });
+    }
+
+    #[test]
+    fn test_seed() {
+        let gen_transfers = |seed| {
+            let mut generator = TransactionGenerator::new_impl(
+                Ed25519PrivateKey::generate_for_testing(),
+                10,
+                None,
+                seed,
+            );
+            generator.gen_transfer_transactions(5 /* block_size */, 2 /* num_blocks */)
+        };
+        assert_eq!(seed_from_name("hot-keys"), seed_from_name("hot-keys"));
+        assert_ne!(seed_from_name("hot-keys"), seed_from_name("cold-keys"));
+        assert_eq!(gen_transfers(DEFAULT_SEED), gen_transfers(DEFAULT_SEED));
+        assert_ne!(
+            gen_transfers(DEFAULT_SEED),
+            gen_transfers(seed_from_name("hot-keys"))
+        );
+    }
+
+    #[test]
+    fn test_parse_workload() {
+        assert_eq!("p2p".parse(), Ok(Workload::P2p));
+        assert_eq!("account-creation".parse(), Ok(Workload::AccountCreation));
+        assert_eq!("module-publish".parse(), Ok(Workload::ModulePublish));
+        assert_eq!(
+            "mixed:8:1:0".parse(),
+            Ok(Workload::Mixed(WorkloadWeights {
+                p2p: 8,
+                account_creation: 1,
+                module_publish: 0,
+            }))
+        );
+        assert!("mixed:8:1".parse::<Workload>().is_err());
+        assert!("mixed:0:0:0".parse::<Workload>().is_err());
+        assert!(format!("mixed:{}:1:0", u32::MAX)
+            .parse::<Workload>()
+            .is_err());
+        assert_eq!(
+            format!("mixed:{}:0:0", u32::MAX).parse(),
+            Ok(Workload::Mixed(WorkloadWeights {
+                p2p: u32::MAX,
+                account_creation: 0,
+                module_publish: 0,
+            }))
+        );
+        assert!("mixed:8:x:1".parse::<Workload>().is_err());
+        assert!("transfer".parse::<Workload>().is_err());
+    }
+}
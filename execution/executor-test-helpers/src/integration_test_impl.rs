@@ -316,7 +316,7 @@ pub fn test_execution_with_storage_impl() -> Arc<AptosDB> {
 
     let transaction_list_with_proof = db
         .reader
-        .get_transactions(3, 10, current_version, false)
+        .get_transactions(3, Order::Ascending, 10, current_version, false)
         .unwrap();
     verify_transactions(&transaction_list_with_proof, &block1[2..]).unwrap();
 
@@ -455,7 +455,7 @@ pub fn test_execution_with_storage_impl() -> Arc<AptosDB> {
 
     let transaction_list_with_proof = db
         .reader
-        .get_transactions(10, 17, current_version, false)
+        .get_transactions(10, Order::Ascending, 17, current_version, false)
         .unwrap();
     verify_transactions(&transaction_list_with_proof, &block2[..]).unwrap();
 
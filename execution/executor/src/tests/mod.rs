@@ -24,7 +24,7 @@ use aptosdb::AptosDB;
 use executor_types::{BlockExecutorTrait, ChunkExecutorTrait, ExecutedTrees, TransactionReplayer};
 use proptest::prelude::*;
 use std::collections::BTreeMap;
-use storage_interface::DbReaderWriter;
+use storage_interface::{DbReaderWriter, Order};
 
 mod chunk_executor_tests;
 
@@ -295,6 +295,7 @@ fn create_transaction_chunks(
                 .reader
                 .get_transactions(
                     range.start,
+                    Order::Ascending,
                     range.end - range.start,
                     ledger_version,
                     false, /* fetch_events */
@@ -474,7 +475,7 @@ proptest! {
             // get txn_infos from db
             let db = executor.db.reader.clone();
             prop_assert_eq!(db.get_latest_version().unwrap(), num_txns as Version);
-            let txn_list = db.get_transactions(1 /* start version */, num_txns as u64, num_txns as Version /* ledger version */, false /* fetch events */).unwrap();
+            let txn_list = db.get_transactions(1 /* start version */, Order::Ascending, num_txns as u64, num_txns as Version /* ledger version */, false /* fetch events */).unwrap();
             prop_assert_eq!(&block.txns, &txn_list.transactions);
             let txn_infos = txn_list.proof.transaction_infos;
 
@@ -4,7 +4,7 @@
 #![forbid(unsafe_code)]
 
 use anyhow::Result;
-use aptos_crypto::{ed25519::Ed25519PrivateKey, HashValue, PrivateKey, Uniform};
+use aptos_crypto::{ed25519::Ed25519PrivateKey, hash::CryptoHash, HashValue, PrivateKey, Uniform};
 use aptos_temppath::TempPath;
 use aptos_transaction_builder::stdlib::{
     encode_create_parent_vasp_account_script_function,
@@ -15,7 +15,7 @@ use aptos_types::{
     account_address::AccountAddress,
     account_config::{
         from_currency_code_string, testnet_dd_account_address, treasury_compliance_account_address,
-        xus_tag, BalanceResource, XUS_NAME,
+        xus_tag, AccountResource, BalanceResource, XUS_NAME,
     },
     account_state::AccountState,
     account_state_blob::AccountStateBlob,
@@ -74,6 +74,21 @@ fn test_empty_db() {
         waypoint
     );
 
+    let genesis_txn_with_proof = db_rw.reader.get_genesis_transaction_with_proof().unwrap();
+    assert_eq!(genesis_txn_with_proof.version, 0);
+    assert_eq!(genesis_txn_with_proof.transaction, genesis_txn);
+    assert_eq!(
+        genesis_txn_with_proof
+            .proof
+            .transaction_info()
+            .transaction_hash(),
+        genesis_txn.hash()
+    );
+    genesis_txn_with_proof
+        .proof
+        .verify(startup_info.latest_ledger_info.ledger_info(), 0)
+        .unwrap();
+
     let initial_accumulator = db_rw
         .reader
         .get_accumulator_summary(waypoint.version())
@@ -355,6 +370,8 @@ fn test_new_genesis() {
     let db = DbReaderWriter::new(AptosDB::new_for_test(&tmp_dir));
     let waypoint = bootstrap_genesis::<AptosVM>(&db, &genesis_txn).unwrap();
     let signer = ValidatorSigner::new(genesis.1[0].data.address, genesis.1[0].key.clone());
+    let genesis_validator_set = db.reader.get_validator_set(0).unwrap().unwrap();
+    assert_eq!(genesis_validator_set.payload().len(), 1);
 
     // Mint for 2 demo accounts.
     let (account1, account1_key, account2, account2_key) = get_demo_accounts();
@@ -365,6 +382,32 @@ fn test_new_genesis() {
     execute_and_commit(vec![txn1, txn2, txn3, txn4], &db, &signer);
     assert_eq!(get_balance(&account1, &db), 2_000_000);
     assert_eq!(get_balance(&account2, &db), 2_000_000);
+    let latest_version = db.reader.get_latest_version().unwrap();
+    let tc_account_blob = db
+        .reader
+        .get_account_state_with_proof_by_version(
+            treasury_compliance_account_address(),
+            latest_version,
+        )
+        .unwrap()
+        .0
+        .unwrap();
+    assert_eq!(
+        db.reader
+            .get_account_sequence_number(treasury_compliance_account_address(), latest_version)
+            .unwrap(),
+        Some(
+            AccountResource::try_from(&tc_account_blob)
+                .unwrap()
+                .sequence_number()
+        )
+    );
+    assert_eq!(
+        db.reader
+            .get_account_sequence_number(AccountAddress::random(), latest_version)
+            .unwrap(),
+        None
+    );
 
     let trusted_state = TrustedState::from_epoch_waypoint(waypoint);
     let initial_accumulator = db
@@ -424,6 +467,16 @@ fn test_new_genesis() {
     assert_eq!(trusted_state.version(), 5);
     assert!(state_proof.consistency_proof().is_empty());
 
+    // Validator set changed by the new genesis, while the historical one is still readable.
+    assert_eq!(
+        db.reader.get_validator_set(5).unwrap(),
+        Some(ValidatorSet::new(vec![]))
+    );
+    assert_eq!(
+        db.reader.get_validator_set(0).unwrap(),
+        Some(genesis_validator_set)
+    );
+
     // Effect of bootstrapping reflected.
     assert_eq!(get_balance(&account1, &db), 1_000_000);
     // State before new genesis accessible.
@@ -16,7 +16,7 @@ use event_notifications::{EventNotificationSender, EventSubscriptionService};
 use executor::components::apply_chunk_output::IntoLedgerView;
 use executor_types::ChunkExecutorTrait;
 use std::sync::Arc;
-use storage_interface::DbReader;
+use storage_interface::{DbReader, Order};
 
 /// Proxies interactions with execution and storage for state synchronization
 pub trait ExecutorProxyTrait: Send {
@@ -144,7 +144,13 @@ impl<C: ChunkExecutorTrait> ExecutorProxyTrait for ExecutorProxy<C> {
             .checked_add(1)
             .ok_or_else(|| Error::IntegerOverflow("Starting version has overflown!".into()))?;
         self.storage
-            .get_transactions(starting_version, limit, target_version, false)
+            .get_transactions(
+                starting_version,
+                Order::Ascending,
+                limit,
+                target_version,
+                false,
+            )
             .map_err(|error| {
                 Error::UnexpectedError(format!("Failed to get transactions from storage {}", error))
             })
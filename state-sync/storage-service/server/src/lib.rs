@@ -20,7 +20,7 @@ use bounded_executor::BoundedExecutor;
 use futures::stream::StreamExt;
 use serde::{Deserialize, Serialize};
 use std::{sync::Arc, time::Duration};
-use storage_interface::DbReader;
+use storage_interface::{DbReader, Order};
 use storage_service_types::{
     AccountStatesChunkWithProofRequest, CompleteDataRange, DataSummary,
     EpochEndingLedgerInfoRequest, ProtocolMetadata, Result, ServerProtocolVersion,
@@ -488,6 +488,7 @@ impl StorageReaderInterface for StorageReader {
             .storage
             .get_transactions(
                 start_version,
+                Order::Ascending,
                 expected_num_transactions,
                 proof_version,
                 include_events,
@@ -37,7 +37,7 @@ use network::{
     },
 };
 use std::{collections::BTreeMap, sync::Arc};
-use storage_interface::DbReader;
+use storage_interface::{DbReader, Order};
 use storage_service_types::{
     AccountStatesChunkWithProofRequest, CompleteDataRange, DataSummary,
     EpochEndingLedgerInfoRequest, ProtocolMetadata, ServerProtocolVersion, StorageServerSummary,
@@ -453,6 +453,7 @@ impl DbReader for MockDbReader {
     fn get_transactions(
         &self,
         start_version: Version,
+        _order: Order,
         batch_size: u64,
         _ledger_version: Version,
         fetch_events: bool,
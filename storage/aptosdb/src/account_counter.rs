@@ -0,0 +1,139 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module provides `AccountCounter` which counts the accounts in the latest state tree in a
+//! background thread, keeping the full tree walk off the commit path.
+
+use crate::{metrics::DIEM_STORAGE_LATEST_ACCOUNT_COUNT, state_store::StateStore};
+use aptos_infallible::Mutex;
+use aptos_types::transaction::Version;
+use std::{
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Arc,
+    },
+    thread::JoinHandle,
+};
+
+enum Command {
+    Count { version: Version },
+    Quit,
+}
+
+/// Owns the counting thread, created on construction and joined on destruction.
+#[derive(Debug)]
+pub(crate) struct AccountCounter {
+    worker_thread: Option<JoinHandle<()>>,
+    command_sender: Mutex<Sender<Command>>,
+    /// The version last counted and the number of accounts at it, `None` until the first count
+    /// finishes.
+    latest_count: Arc<Mutex<Option<(Version, usize)>>>,
+}
+
+impl AccountCounter {
+    pub fn new(state_store: Arc<StateStore>) -> Self {
+        let (command_sender, command_receiver) = channel();
+        let latest_count = Arc::new(Mutex::new(None));
+        let worker = Worker {
+            state_store,
+            command_receiver,
+            latest_count: Arc::clone(&latest_count),
+        };
+        let worker_thread = std::thread::Builder::new()
+            .name("aptosdb_account_counter".into())
+            .spawn(move || worker.work())
+            .expect("Creating account counter thread should succeed.");
+
+        Self {
+            worker_thread: Some(worker_thread),
+            command_sender: Mutex::new(command_sender),
+            latest_count,
+        }
+    }
+
+    /// Returns the last computed account count, which may lag behind the latest version.
+    pub fn get_latest_count(&self) -> Option<usize> {
+        self.latest_count.lock().map(|(_version, count)| count)
+    }
+
+    /// Asks the worker thread to count the accounts at `latest_version`. Requests piling up while
+    /// a count is in progress are coalesced into one for the largest version.
+    pub fn wake(&self, latest_version: Version) {
+        self.command_sender
+            .lock()
+            .send(Command::Count {
+                version: latest_version,
+            })
+            .expect("Receiver should not destruct prematurely.");
+    }
+
+    /// (For tests only.) Notifies the worker thread and waits for it to finish counting at
+    /// `latest_version` by polling the cached count.
+    #[cfg(any(test, feature = "fuzzing"))]
+    pub fn wake_and_wait(&self, latest_version: Version) -> anyhow::Result<()> {
+        use std::{
+            thread::sleep,
+            time::{Duration, Instant},
+        };
+
+        self.wake(latest_version);
+
+        const TIMEOUT: Duration = Duration::from_secs(10);
+        let end = Instant::now() + TIMEOUT;
+        while Instant::now() < end {
+            if matches!(*self.latest_count.lock(), Some((version, _)) if version >= latest_version)
+            {
+                return Ok(());
+            }
+            sleep(Duration::from_millis(1));
+        }
+        anyhow::bail!("Timeout waiting for account counter worker.");
+    }
+}
+
+impl Drop for AccountCounter {
+    fn drop(&mut self) {
+        self.command_sender
+            .lock()
+            .send(Command::Quit)
+            .expect("Receiver should not destruct.");
+        self.worker_thread
+            .take()
+            .expect("Worker thread must exist.")
+            .join()
+            .expect("Worker thread should join peacefully.");
+    }
+}
+
+struct Worker {
+    state_store: Arc<StateStore>,
+    command_receiver: Receiver<Command>,
+    latest_count: Arc<Mutex<Option<(Version, usize)>>>,
+}
+
+impl Worker {
+    fn work(self) {
+        while let Ok(command) = self.command_receiver.recv() {
+            let mut version = match command {
+                Command::Count { version } => version,
+                Command::Quit => return,
+            };
+            // Only the latest of the pending requests is worth counting.
+            for command in self.command_receiver.try_iter() {
+                match command {
+                    Command::Count { version: v } => version = version.max(v),
+                    Command::Quit => return,
+                }
+            }
+
+            match self.state_store.get_account_count(version) {
+                Ok(count) => {
+                    DIEM_STORAGE_LATEST_ACCOUNT_COUNT.set(count as i64);
+                    *self.latest_count.lock() = Some((version, count));
+                }
+                // -1 for "not fully migrated" or error on get_account_count()
+                Err(_) => DIEM_STORAGE_LATEST_ACCOUNT_COUNT.set(-1),
+            }
+        }
+    }
+}
@@ -4,24 +4,46 @@
 use super::*;
 #[allow(unused_imports)]
 use crate::{
-    schema::jellyfish_merkle_node::JellyfishMerkleNodeSchema, test_helper::arb_blocks_to_commit,
+    backup::backup_handler::read_exported_state,
+    schema::{
+        jellyfish_merkle_node::JellyfishMerkleNodeSchema, transaction::TransactionSchema,
+        transaction_by_payload_type::TransactionByPayloadTypeSchema,
+        transaction_info::TransactionInfoSchema,
+    },
+    test_helper::{
+        arb_blocks_to_commit, arb_blocks_to_commit_with_block_events,
+        arb_blocks_to_commit_with_modules, arb_blocks_to_commit_with_version_bumps,
+        module_publisher_address,
+    },
 };
+use aptos_config::config::ColumnFamilyTuning;
 use aptos_crypto::hash::CryptoHash;
 #[allow(unused_imports)]
-use aptos_jellyfish_merkle::node_type::{Node, NodeKey};
+use aptos_jellyfish_merkle::{
+    iterator::JellyfishMerkleIterator,
+    node_type::{Node, NodeKey},
+    JellyfishMerkleTree,
+};
 use aptos_temppath::TempPath;
-use aptos_types::transaction::Transaction;
 #[allow(unused_imports)]
 use aptos_types::{
     account_address::{AccountAddress, HashAccountAddress},
     account_config::AccountResource,
     contract_event::ContractEvent,
     ledger_info::LedgerInfo,
+    move_resource::MoveStructType,
     proof::SparseMerkleLeafNode,
     vm_status::{KeptVMStatus, StatusCode},
 };
+use aptos_types::{block_info::BlockInfo, block_metadata::BlockMetadata, transaction::Transaction};
+use move_core_types::identifier::Identifier;
 use proptest::prelude::*;
-use std::collections::HashMap;
+use schemadb::SchemaBatch;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    time::Duration,
+};
+use storage_interface::{CommittedVersion, EventFilter};
 
 fn verify_epochs(db: &AptosDB, ledger_infos_with_sigs: &[LedgerInfoWithSignatures]) {
     const LIMIT: usize = 2;
@@ -56,6 +78,38 @@ fn verify_epochs(db: &AptosDB, ledger_infos_with_sigs: &[LedgerInfoWithSignature
         .collect();
     assert_eq!(actual_epoch_change_lis, expected_epoch_change_lis);
 
+    let streamed_epoch_change_lis = db
+        .get_epoch_ending_ledger_info_iter(0, latest_epoch)
+        .unwrap()
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+    assert_eq!(streamed_epoch_change_lis.len() as u64, latest_epoch);
+    assert_eq!(streamed_epoch_change_lis, expected_epoch_change_lis);
+
+    let epoch_ranges = db.get_epoch_ranges(0, u64::max_value()).unwrap();
+    assert_eq!(epoch_ranges.len(), expected_epoch_change_lis.len());
+    let mut next_first_version = 0;
+    for (i, ((epoch, first_version, last_version, li), expected_li)) in epoch_ranges
+        .iter()
+        .zip(expected_epoch_change_lis.iter())
+        .enumerate()
+    {
+        assert_eq!(*epoch, i as u64);
+        assert_eq!(*first_version, next_first_version);
+        assert!(first_version <= last_version);
+        assert_eq!(*last_version, expected_li.ledger_info().version());
+        assert_eq!(li, expected_li);
+        next_first_version = last_version + 1;
+    }
+    if let Some((_, first_version, last_version, li)) = epoch_ranges.last() {
+        // Starting from a later epoch gives the same range.
+        let epoch = li.ledger_info().epoch();
+        assert_eq!(
+            db.get_epoch_ranges(epoch, epoch + 1).unwrap(),
+            vec![(epoch, *first_version, *last_version, li.clone())]
+        );
+    }
+
     let mut last_ver = 0;
     for li in ledger_infos_with_sigs {
         let this_ver = li.ledger_info().version();
@@ -84,12 +138,18 @@ pub fn test_save_blocks_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWit
     let mut cur_ver = 0;
     let mut all_committed_txns = vec![];
     for (batch_idx, (txns_to_commit, ledger_info_with_sigs)) in input.iter().enumerate() {
-        db.save_transactions(
-            txns_to_commit,
-            cur_ver, /* first_version */
-            Some(ledger_info_with_sigs),
-        )
-        .unwrap();
+        let root_hash = db
+            .save_transactions_and_get_root(
+                txns_to_commit,
+                cur_ver, /* first_version */
+                Some(ledger_info_with_sigs),
+            )
+            .unwrap();
+        assert_eq!(
+            root_hash,
+            db.get_accumulator_root_hash(ledger_info_with_sigs.ledger_info().version())
+                .unwrap()
+        );
 
         assert_eq!(
             db.ledger_store.get_latest_ledger_info().unwrap(),
@@ -111,6 +171,16 @@ pub fn test_save_blocks_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWit
             &all_committed_txns,
             ledger_info_with_sigs.ledger_info(),
         );
+        verify_latest_event_sequence_numbers(
+            &db,
+            &all_committed_txns,
+            ledger_info_with_sigs.ledger_info(),
+        );
+        verify_get_events_by_version_range(
+            &db,
+            &all_committed_txns,
+            ledger_info_with_sigs.ledger_info(),
+        );
 
         cur_ver += txns_to_commit.len() as u64;
     }
@@ -118,6 +188,10 @@ pub fn test_save_blocks_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWit
     let first_batch = input.first().unwrap().0.clone();
     let first_batch_ledger_info = input.first().unwrap().1.clone();
     let latest_ledger_info = input.last().unwrap().1.clone();
+    verify_get_transactions_by_versions(&db, latest_ledger_info.ledger_info());
+    verify_transaction_iter(&db, latest_ledger_info.ledger_info());
+    #[cfg(any(test, feature = "raw-db-access"))]
+    verify_rocksdb_handle(&db, latest_ledger_info.ledger_info());
     // Verify an old batch with the latest LedgerInfo.
     verify_committed_transactions(
         &db,
@@ -136,6 +210,69 @@ pub fn test_save_blocks_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWit
     );
     let (_, ledger_infos_with_sigs): (Vec<_>, Vec<_>) = input.iter().cloned().unzip();
     verify_epochs(&db, &ledger_infos_with_sigs);
+    verify_state_proof_with_limit(&db, &latest_ledger_info);
+}
+
+#[cfg(any(test, feature = "raw-db-access"))]
+fn verify_rocksdb_handle(db: &AptosDB, ledger_info: &LedgerInfo) {
+    let handle = db.rocksdb_handle();
+    for version in 0..=ledger_info.version() {
+        let raw_txn_info = handle
+            .get::<TransactionInfoSchema>(&version)
+            .unwrap()
+            .unwrap();
+        let txn = db
+            .get_transaction_by_version(version, ledger_info.version(), false)
+            .unwrap();
+        assert_eq!(
+            bcs::to_bytes(&raw_txn_info).unwrap(),
+            bcs::to_bytes(txn.proof.transaction_info()).unwrap()
+        );
+    }
+}
+
+fn verify_state_proof_with_limit(db: &AptosDB, latest_ledger_info: &LedgerInfoWithSignatures) {
+    const MAX_EPOCHS_PER_PROOF: usize = 1;
+    let known_version = 0;
+    let known_epoch = db.ledger_store.get_epoch(known_version).unwrap();
+    let end_epoch = latest_ledger_info.ledger_info().next_block_epoch();
+
+    let state_proof = db
+        .get_state_proof_with_ledger_info_and_limit(
+            known_version,
+            latest_ledger_info.clone(),
+            Some(MAX_EPOCHS_PER_PROOF),
+        )
+        .unwrap();
+    let epoch_changes = state_proof.epoch_changes();
+    assert!(epoch_changes.ledger_info_with_sigs.len() <= MAX_EPOCHS_PER_PROOF);
+    assert_eq!(
+        epoch_changes.more,
+        end_epoch - known_epoch > MAX_EPOCHS_PER_PROOF as u64
+    );
+
+    // The consistency proof must chain from the known version up to the verifiable ledger info.
+    let verifiable_li = if epoch_changes.more {
+        epoch_changes
+            .ledger_info_with_sigs
+            .last()
+            .unwrap()
+            .ledger_info()
+    } else {
+        latest_ledger_info.ledger_info()
+    };
+    db.get_accumulator_summary(known_version)
+        .unwrap()
+        .try_extend_with_proof(state_proof.consistency_proof(), verifiable_li)
+        .unwrap();
+
+    assert!(db
+        .get_state_proof_with_ledger_info_and_limit(
+            known_version,
+            latest_ledger_info.clone(),
+            Some(0)
+        )
+        .is_err());
 }
 
 fn test_sync_transactions_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
@@ -174,6 +311,139 @@ fn test_sync_transactions_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoW
     }
 }
 
+fn test_save_conflicting_ledger_info_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) {
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in &input {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+    }
+
+    // Re-committing the identical ledger info is allowed.
+    let latest_ledger_info = input.last().unwrap().1.clone();
+    db.save_transactions(&[], cur_ver, Some(&latest_ledger_info))
+        .unwrap();
+
+    // A different ledger info at the same version is rejected.
+    let conflicting_ledger_info = LedgerInfoWithSignatures::new(
+        LedgerInfo::new(
+            latest_ledger_info.ledger_info().commit_info().clone(),
+            HashValue::random(),
+        ),
+        BTreeMap::new(),
+    );
+    let err = db
+        .save_transactions(&[], cur_ver, Some(&conflicting_ledger_info))
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<AptosDbError>(),
+        Some(AptosDbError::LedgerInfoConflict(v)) if *v == cur_ver - 1
+    ));
+    assert_eq!(db.get_latest_ledger_info().unwrap(), latest_ledger_info);
+}
+
+fn test_health_check_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+    assert!(!db.health_check().unwrap().is_healthy());
+
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in &input {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+    }
+
+    let health_status = db.health_check().unwrap();
+    assert_eq!(
+        health_status,
+        HealthStatus {
+            latest_version: Some(cur_ver - 1),
+            first_viable_version: 0,
+            pruner_backlog: None,
+            pruner_keeping_up: true,
+            num_background_errors: 0,
+        }
+    );
+    assert!(health_status.is_healthy());
+}
+
+fn test_committed_version_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+    let tmp_dir2 = TempPath::new();
+    let db2 = AptosDB::new_for_test(&tmp_dir2);
+
+    let mut cur_ver = 0;
+    for (i, (txns_to_commit, ledger_info_with_sigs)) in input.iter().enumerate() {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        if i == 0 {
+            db2.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+                .unwrap();
+        }
+        cur_ver += txns_to_commit.len() as u64;
+    }
+    let latest_version = cur_ver - 1;
+
+    assert!(CommittedVersion::new(&db, latest_version + 1).is_err());
+    let committed_version = CommittedVersion::new(&db, latest_version).unwrap();
+    assert_eq!(committed_version.version(), latest_version);
+    let txn_with_proof = db
+        .get_transaction_by_committed_version(committed_version, false)
+        .unwrap();
+    assert_eq!(txn_with_proof.version, latest_version);
+    db.get_account_state_with_proof_by_committed_version(
+        AccountAddress::random(),
+        committed_version,
+    )
+    .unwrap();
+
+    // A version checked against another DB is rejected if it's not committed here.
+    let db2_latest_version = db2.get_latest_version().unwrap();
+    if db2_latest_version < latest_version {
+        assert!(db2
+            .get_transaction_by_committed_version(committed_version, false)
+            .is_err());
+        assert!(db2
+            .get_account_state_with_proof_by_committed_version(
+                AccountAddress::random(),
+                committed_version,
+            )
+            .is_err());
+    }
+}
+
+fn test_commit_ledger_info_only_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) {
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+
+    let (txns_to_commit, ledger_info_with_sigs) = input.first().unwrap();
+    // Nothing to attach the ledger info to yet.
+    assert!(db.commit_ledger_info_only(ledger_info_with_sigs).is_err());
+
+    db.save_transactions(txns_to_commit, 0, None).unwrap();
+    // A ledger info at another version is rejected.
+    let (_, last_ledger_info_with_sigs) = input.last().unwrap();
+    if last_ledger_info_with_sigs.ledger_info().version()
+        != ledger_info_with_sigs.ledger_info().version()
+    {
+        assert!(db
+            .commit_ledger_info_only(last_ledger_info_with_sigs)
+            .is_err());
+    }
+
+    db.commit_ledger_info_only(ledger_info_with_sigs).unwrap();
+    assert_eq!(db.get_latest_ledger_info().unwrap(), *ledger_info_with_sigs);
+}
+
 fn get_events_by_event_key(
     db: &AptosDB,
     ledger_info: &LedgerInfo,
@@ -318,6 +588,96 @@ fn verify_events_by_event_key(
         .unwrap();
 }
 
+fn verify_events_with_transaction_info_proofs(
+    db: &AptosDB,
+    events: Vec<(EventKey, Vec<(Version, ContractEvent)>)>,
+    ledger_info: &LedgerInfo,
+) {
+    for (event_key, events) in events {
+        let first_seq = events.first().unwrap().1.sequence_number();
+        let limit = events.len() as u64;
+        let with_txn_info_proofs = db
+            .get_events_with_transaction_info_proofs(
+                &event_key,
+                first_seq,
+                Order::Ascending,
+                limit,
+                Some(ledger_info.version()),
+            )
+            .unwrap();
+        let with_full_proofs = db
+            .get_events_with_proofs(
+                &event_key,
+                first_seq,
+                Order::Ascending,
+                limit,
+                Some(ledger_info.version()),
+            )
+            .unwrap();
+        assert_eq!(with_txn_info_proofs.len(), events.len());
+
+        for ((reduced, full), (version, event)) in with_txn_info_proofs
+            .iter()
+            .zip(with_full_proofs.iter())
+            .zip(events.iter())
+        {
+            assert_eq!(&reduced.event, event);
+            assert_eq!(reduced.transaction_version, *version);
+            assert_eq!(reduced.event_index, full.event_index);
+            reduced
+                .verify(
+                    ledger_info,
+                    &event_key,
+                    event.sequence_number(),
+                    *version,
+                    full.event_index,
+                )
+                .unwrap();
+            // Only the event accumulator proof is left out.
+            assert_eq!(
+                &reduced.transaction_info_with_proof,
+                full.proof.transaction_info_with_proof()
+            );
+            assert!(bcs::serialized_size(reduced).unwrap() < bcs::serialized_size(full).unwrap());
+        }
+    }
+}
+
+fn verify_events_multi_key(
+    db: &AptosDB,
+    events: Vec<(EventKey, Vec<(Version, ContractEvent)>)>,
+    ledger_info: &LedgerInfo,
+) {
+    let requests = events
+        .iter()
+        .flat_map(|(event_key, events)| {
+            let first_seq = events.first().unwrap().1.sequence_number();
+            let limit = events.len() as u64;
+            vec![
+                (*event_key, first_seq, Order::Ascending, limit),
+                (*event_key, u64::max_value(), Order::Descending, limit),
+            ]
+        })
+        .collect::<Vec<_>>();
+    let results = db
+        .get_events_multi_key(&requests, ledger_info.version())
+        .unwrap();
+    assert_eq!(results.len(), requests.len());
+    for ((event_key, start, order, limit), events_with_proof) in requests.iter().zip(results) {
+        assert_eq!(
+            events_with_proof,
+            db.get_events_with_proofs(
+                event_key,
+                *start,
+                *order,
+                *limit,
+                Some(ledger_info.version()),
+            )
+            .unwrap()
+        );
+    }
+}
+
 fn group_events_by_event_key(
     first_version: Version,
     txns_to_commit: &[TransactionToCommit],
@@ -334,6 +694,62 @@ fn group_events_by_event_key(
     event_key_to_events.into_iter().collect()
 }
 
+fn verify_latest_event_sequence_numbers(
+    db: &AptosDB,
+    committed_txns: &[TransactionToCommit],
+    ledger_info: &LedgerInfo,
+) {
+    let events = group_events_by_event_key(0, committed_txns);
+    let (event_keys, expected): (Vec<_>, Vec<_>) = events
+        .iter()
+        .map(|(event_key, events)| (*event_key, Some(events.last().unwrap().1.sequence_number())))
+        // keys without events
+        .chain(std::iter::repeat_with(|| (EventKey::random(), None)).take(2))
+        .unzip();
+
+    // keys with events only, without events only, and a mix of both
+    let num_with_events = events.len();
+    for range in [
+        0..num_with_events,
+        num_with_events..event_keys.len(),
+        0..event_keys.len(),
+    ] {
+        assert_eq!(
+            db.get_latest_event_sequence_numbers(&event_keys[range.clone()], ledger_info.version())
+                .unwrap(),
+            &expected[range]
+        );
+    }
+}
+
+fn verify_get_events_by_version_range(
+    db: &AptosDB,
+    committed_txns: &[TransactionToCommit],
+    ledger_info: &LedgerInfo,
+) {
+    let ledger_version = ledger_info.version();
+    let start_version = ledger_version / 2;
+    let expected: Vec<_> = committed_txns
+        .iter()
+        .enumerate()
+        .skip(start_version as usize)
+        .filter(|(_, txn)| !txn.events().is_empty())
+        .map(|(version, txn)| (version as Version, txn.events().to_vec()))
+        .collect();
+    assert_eq!(
+        db.get_events_by_version_range(start_version, ledger_version)
+            .unwrap(),
+        expected
+    );
+
+    assert!(db
+        .get_events_by_version_range(ledger_version, ledger_version + 1)
+        .is_err());
+    assert!(db
+        .get_events_by_version_range(ledger_version + 1, ledger_version)
+        .is_err());
+}
+
 fn verify_get_event_by_version(
     db: &AptosDB,
     committed_txns: &[TransactionToCommit],
@@ -341,6 +757,30 @@ fn verify_get_event_by_version(
 ) {
     let events = group_events_by_event_key(0, committed_txns);
 
+    // fetching several keys at once should match fetching them one by one
+    let event_keys: Vec<_> = events
+        .iter()
+        .map(|(event_key, _)| *event_key)
+        .chain(std::iter::once(EventKey::random()))
+        .collect();
+    for event_version in 0..=ledger_info.version() {
+        let events_by_version = db
+            .get_events_by_version_with_proof_multi(
+                &event_keys,
+                event_version,
+                ledger_info.version(),
+            )
+            .unwrap();
+        assert_eq!(events_by_version.len(), event_keys.len());
+        for (event_key, event_by_version) in event_keys.iter().zip(events_by_version) {
+            assert_eq!(
+                event_by_version,
+                db.get_event_by_version_with_proof(event_key, event_version, ledger_info.version())
+                    .unwrap()
+            );
+        }
+    }
+
     // just exhaustively check all versions for each set of events
     for (event_key, events) in events {
         for event_version in 0..=ledger_info.version() {
@@ -409,6 +849,7 @@ fn verify_account_txns(
                 .get_account_transactions(
                     account,
                     first_seq_num,
+                    Order::Ascending,
                     limit,
                     true, /* include_events */
                     ledger_info.version(),
@@ -425,6 +866,88 @@ fn verify_account_txns(
                 )
                 .unwrap();
 
+            // Only transactions at even versions carry events.
+            let filtered_txns_with_proof = db
+                .get_account_transactions_with_events_filter(
+                    account,
+                    first_seq_num,
+                    Order::Ascending,
+                    limit,
+                    &|version| version % 2 == 0,
+                    ledger_info.version(),
+                )
+                .unwrap();
+            assert_eq!(filtered_txns_with_proof.len(), acct_txns_with_proof.len());
+            for (filtered, full) in filtered_txns_with_proof
+                .inner()
+                .iter()
+                .zip(acct_txns_with_proof.inner())
+            {
+                assert_eq!(filtered.transaction, full.transaction);
+                if filtered.version % 2 == 0 {
+                    assert_eq!(filtered.events, full.events);
+                } else {
+                    assert!(filtered.events.is_none());
+                }
+            }
+
+            // The latest half of the transactions, walking backwards from the latest, come back
+            // in ascending order.
+            let num_latest = (last_seq_num - first_seq_num) / 2 + 1;
+            let latest_txns_with_proof = db
+                .get_account_transactions(
+                    account,
+                    u64::max_value(),
+                    Order::Descending,
+                    num_latest,
+                    true, /* include_events */
+                    ledger_info.version(),
+                )
+                .unwrap();
+            latest_txns_with_proof
+                .verify(
+                    ledger_info,
+                    account,
+                    last_seq_num + 1 - num_latest,
+                    num_latest,
+                    true,
+                    ledger_info.version(),
+                )
+                .unwrap();
+            assert_eq!(
+                latest_txns_with_proof.inner(),
+                &acct_txns_with_proof.inner()[acct_txns_with_proof.len() - num_latest as usize..],
+            );
+            assert_eq!(
+                db.get_account_transactions(
+                    account,
+                    last_seq_num,
+                    Order::Descending,
+                    num_latest,
+                    true,
+                    ledger_info.version(),
+                )
+                .unwrap(),
+                latest_txns_with_proof,
+            );
+            assert_eq!(
+                db.get_account_transaction_count(account, ledger_info.version())
+                    .unwrap(),
+                last_seq_num + 1,
+            );
+            // A cursor beyond the latest sequence number yields nothing.
+            assert!(db
+                .get_account_transactions(
+                    account,
+                    last_seq_num + 1,
+                    Order::Descending,
+                    num_latest,
+                    true,
+                    ledger_info.version(),
+                )
+                .unwrap()
+                .is_empty());
+
             let txns_and_events = acct_txns_with_proof
                 .into_inner()
                 .into_iter()
@@ -436,6 +959,13 @@ fn verify_account_txns(
         .collect::<HashMap<_, _>>();
 
     assert_eq!(actual_txns_by_account, expected_txns_by_account);
+
+    // An account that never sent a transaction.
+    assert_eq!(
+        db.get_account_transaction_count(AccountAddress::random(), ledger_info.version())
+            .unwrap(),
+        0,
+    );
 }
 
 fn group_txns_by_account(
@@ -454,6 +984,64 @@ fn group_txns_by_account(
     account_to_txns
 }
 
+fn verify_get_transactions_by_versions(db: &AptosDB, ledger_info: &LedgerInfo) {
+    let ledger_version = ledger_info.version();
+    let versions = [ledger_version, 0, ledger_version / 2, ledger_version, 0];
+    let txns_with_proof = db
+        .get_transactions_by_versions(&versions, ledger_version, true)
+        .unwrap();
+    assert_eq!(txns_with_proof.len(), versions.len());
+    for (version, txn_with_proof) in versions.iter().zip(txns_with_proof) {
+        assert_eq!(txn_with_proof.version, *version);
+        assert_eq!(
+            txn_with_proof,
+            db.get_transaction_by_version(*version, ledger_version, true)
+                .unwrap()
+        );
+    }
+
+    assert!(db
+        .get_transactions_by_versions(&[], ledger_version, true)
+        .unwrap()
+        .is_empty());
+    assert!(db
+        .get_transactions_by_versions(&[0; MAX_LIMIT as usize + 1], ledger_version, false)
+        .is_err());
+}
+
+fn verify_transaction_iter(db: &AptosDB, ledger_info: &LedgerInfo) {
+    let ledger_version = ledger_info.version();
+    let start_version = ledger_version / 2;
+
+    // Runs past `ledger_version`, so stops there.
+    let txns_with_proof = db
+        .get_transaction_iter(start_version, ledger_version + 10, ledger_version)
+        .unwrap()
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+    assert_eq!(
+        txns_with_proof.len() as u64,
+        ledger_version - start_version + 1
+    );
+    for (version, txn_with_proof) in (start_version..).zip(txns_with_proof) {
+        assert_eq!(
+            txn_with_proof,
+            db.get_transaction_by_version(version, ledger_version, false)
+                .unwrap()
+        );
+    }
+
+    // Consumers can stop early.
+    assert_eq!(
+        db.get_transaction_iter(0, ledger_version + 1, ledger_version)
+            .unwrap()
+            .take(1)
+            .count(),
+        1
+    );
+    assert!(db.get_transaction_iter(0, 1, ledger_version + 1).is_err());
+}
+
 fn verify_committed_transactions(
     db: &AptosDB,
     txns_to_commit: &[TransactionToCommit],
@@ -506,8 +1094,33 @@ fn verify_committed_transactions(
             .verify_user_txn(ledger_info, cur_ver, txn.sender(), txn.sequence_number())
             .unwrap();
 
+        let requests = [
+            (txn.sender(), txn.sequence_number()),
+            (txn.sender(), u64::max_value()),
+            (AccountAddress::random(), 0),
+        ];
+        let txns_with_proof = db
+            .get_account_transactions_multi(&requests, ledger_version, true)
+            .unwrap();
+        assert_eq!(txns_with_proof.len(), requests.len());
+        assert!(txns_with_proof[0].is_some());
+        for ((address, seq_num), txn_with_proof) in requests.iter().zip(txns_with_proof) {
+            assert_eq!(
+                txn_with_proof,
+                db.get_account_transaction(*address, *seq_num, true, ledger_version)
+                    .unwrap()
+            );
+        }
+
         let acct_txns_with_proof = db
-            .get_account_transactions(txn.sender(), txn.sequence_number(), 1, true, ledger_version)
+            .get_account_transactions(
+                txn.sender(),
+                txn.sequence_number(),
+                Order::Ascending,
+                1,
+                true,
+                ledger_version,
+            )
             .unwrap();
         acct_txns_with_proof
             .verify(
@@ -522,23 +1135,106 @@ fn verify_committed_transactions(
         assert_eq!(acct_txns_with_proof.len(), 1);
 
         let txn_list_with_proof = db
-            .get_transactions(cur_ver, 1, ledger_version, true /* fetch_events */)
+            .get_transactions(
+                cur_ver,
+                Order::Ascending,
+                1,
+                ledger_version,
+                true, /* fetch_events */
+            )
             .unwrap();
         txn_list_with_proof
             .verify(ledger_info, Some(cur_ver))
             .unwrap();
         assert_eq!(txn_list_with_proof.transactions.len(), 1);
 
-        let txn_output_list_with_proof = db
-            .get_transaction_outputs(cur_ver, 1, ledger_version)
+        // The same transaction and its predecessor, if any, walking backwards.
+        let txn_list_with_proof = db
+            .get_transactions(cur_ver, Order::Descending, 2, ledger_version, true)
             .unwrap();
-        txn_output_list_with_proof
-            .verify(ledger_info, Some(cur_ver))
+        let first_ver = cur_ver.saturating_sub(1);
+        txn_list_with_proof
+            .verify(ledger_info, Some(first_ver))
             .unwrap();
-        assert_eq!(txn_output_list_with_proof.transactions_and_outputs.len(), 1);
-
-        // Fetch and verify account states.
-        for (addr, expected_blob) in txn_to_commit.account_states() {
+        assert_eq!(
+            txn_list_with_proof,
+            db.get_transactions(
+                first_ver,
+                Order::Ascending,
+                cur_ver - first_ver + 1,
+                ledger_version,
+                true
+            )
+            .unwrap()
+        );
+        for order in [Order::Ascending, Order::Descending] {
+            assert!(db
+                .get_transactions(ledger_version + 1, order, 1, ledger_version, true)
+                .unwrap()
+                .transactions
+                .is_empty());
+        }
+
+        let txn_output_list_with_proof = db
+            .get_transaction_outputs(cur_ver, 1, ledger_version)
+            .unwrap();
+        txn_output_list_with_proof
+            .verify(ledger_info, Some(cur_ver))
+            .unwrap();
+        assert_eq!(txn_output_list_with_proof.transactions_and_outputs.len(), 1);
+
+        let summaries = db
+            .get_transaction_execution_summaries(cur_ver, 1, ledger_version)
+            .unwrap();
+        assert_eq!(
+            summaries,
+            txn_output_list_with_proof
+                .transactions_and_outputs
+                .iter()
+                .map(|(_, txn_output)| (
+                    cur_ver,
+                    txn_output.gas_used(),
+                    txn_output.status().as_kept_status().unwrap()
+                ))
+                .collect::<Vec<_>>()
+        );
+
+        let filter = EventFilter {
+            event_keys: txn_to_commit
+                .events()
+                .first()
+                .map(|event| std::iter::once(*event.key()).collect()),
+            type_tags: None,
+        };
+        let filtered_txn_output_list_with_proof = db
+            .get_transaction_outputs_filtered(cur_ver, 1, ledger_version, &filter)
+            .unwrap();
+        filtered_txn_output_list_with_proof
+            .proof
+            .verify(ledger_info, Some(cur_ver))
+            .unwrap();
+        for ((txn, txn_output), (filtered_txn, filtered_txn_output)) in txn_output_list_with_proof
+            .transactions_and_outputs
+            .iter()
+            .zip(
+                filtered_txn_output_list_with_proof
+                    .transactions_and_outputs
+                    .iter(),
+            )
+        {
+            assert_eq!(txn, filtered_txn);
+            assert_eq!(txn_output.write_set(), filtered_txn_output.write_set());
+            let expected_events: Vec<_> = txn_output
+                .events()
+                .iter()
+                .filter(|event| filter.matches(event))
+                .cloned()
+                .collect();
+            assert_eq!(filtered_txn_output.events(), expected_events.as_slice());
+        }
+
+        // Fetch and verify account states.
+        for (addr, expected_blob) in txn_to_commit.account_states() {
             let account_state_with_proof = db
                 .get_account_state_with_proof(*addr, cur_ver, ledger_version)
                 .unwrap();
@@ -546,21 +1242,1423 @@ fn verify_committed_transactions(
             account_state_with_proof
                 .verify(ledger_info, cur_ver, *addr)
                 .unwrap();
+
+            let tag = AccountResource::struct_tag();
+            let (resource, resource_proof) = db
+                .get_resource_with_proof(*addr, &tag, cur_ver, ledger_version)
+                .unwrap();
+            assert_eq!(
+                resource,
+                AccountState::try_from(expected_blob)
+                    .unwrap()
+                    .get(&tag.access_vector())
+                    .cloned()
+            );
+            assert_eq!(resource_proof, account_state_with_proof);
+
+            let latest_ledger_info = db.get_latest_ledger_info().unwrap();
+            db.get_latest_account_state_with_proof(*addr)
+                .unwrap()
+                .verify(
+                    latest_ledger_info.ledger_info(),
+                    latest_ledger_info.ledger_info().version(),
+                    *addr,
+                )
+                .unwrap();
+
+            let (latest_blob, latest_version) =
+                db.get_latest_account_state_and_version(*addr).unwrap();
+            assert_eq!(latest_version, latest_ledger_info.ledger_info().version());
+            let (blob, proof) = db
+                .get_account_state_with_proof_by_version(*addr, latest_version)
+                .unwrap();
+            assert_eq!(blob, latest_blob);
+            proof
+                .verify(
+                    db.state_store.get_root_hash(latest_version).unwrap(),
+                    addr.hash(),
+                    blob.as_ref(),
+                )
+                .unwrap();
         }
 
         cur_ver += 1;
     }
 
-    // Fetch and verify events.
-    verify_events_by_event_key(
-        db,
-        group_events_by_event_key(first_version, txns_to_commit),
-        ledger_info,
-        is_latest,
+    // Fetch and verify events.
+    verify_events_by_event_key(
+        db,
+        group_events_by_event_key(first_version, txns_to_commit),
+        ledger_info,
+        is_latest,
+    );
+    verify_events_with_transaction_info_proofs(
+        db,
+        group_events_by_event_key(first_version, txns_to_commit),
+        ledger_info,
+    );
+    verify_events_multi_key(
+        db,
+        group_events_by_event_key(first_version, txns_to_commit),
+        ledger_info,
+    );
+
+    // Fetch and verify batch transactions by account
+    verify_account_txns(db, group_txns_by_account(txns_to_commit), ledger_info);
+}
+
+fn test_account_state_change_proof_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) {
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+    }
+
+    let earlier_ledger_info = input.first().unwrap().1.ledger_info();
+    let ledger_info = input.last().unwrap().1.ledger_info();
+    let earlier_version = earlier_ledger_info.version();
+    let version = ledger_info.version();
+    let earlier_accumulator = db.get_accumulator_summary(earlier_version).unwrap();
+    let consistency_proof = db
+        .get_accumulator_consistency_proof(Some(earlier_version), version)
+        .unwrap();
+
+    let changed_addresses = input
+        .last()
+        .unwrap()
+        .0
+        .iter()
+        .flat_map(|txn_to_commit| txn_to_commit.account_states().keys().cloned());
+    // An account touched by neither block never changes.
+    let addresses = changed_addresses.chain(std::iter::once(AccountAddress::random()));
+    // The account state as of `version` according to the transactions committed, the version of
+    // each being its index.
+    let all_txns: Vec<_> = input
+        .iter()
+        .flat_map(|(txns_to_commit, _ledger_info_with_sigs)| txns_to_commit)
+        .collect();
+    let expected_blob = |address, version: Version| {
+        all_txns[..=version as usize]
+            .iter()
+            .rev()
+            .find_map(|txn_to_commit| txn_to_commit.account_states().get(&address).cloned())
+    };
+    for address in addresses {
+        let earlier = db
+            .get_account_state_with_proof(address, earlier_version, earlier_version)
+            .unwrap();
+        let later = db
+            .get_account_state_with_proof(address, version, version)
+            .unwrap();
+        let changed = later
+            .verify_change_since(
+                ledger_info,
+                &earlier,
+                earlier_ledger_info,
+                &earlier_accumulator,
+                &consistency_proof,
+                address,
+            )
+            .unwrap();
+        assert_eq!(
+            changed,
+            expected_blob(address, earlier_version) != expected_blob(address, version)
+        );
+
+        // Swapping the versions is rejected.
+        if earlier_version != version {
+            assert!(earlier
+                .verify_change_since(
+                    earlier_ledger_info,
+                    &later,
+                    ledger_info,
+                    &earlier_accumulator,
+                    &consistency_proof,
+                    address,
+                )
+                .is_err());
+        }
+    }
+}
+
+fn test_secondary_catch_up_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+
+    let (first_txns_to_commit, first_ledger_info_with_sigs) = input.first().unwrap();
+    db.save_transactions(first_txns_to_commit, 0, Some(first_ledger_info_with_sigs))
+        .unwrap();
+
+    let secondary_tmp_dir = TempPath::new();
+    secondary_tmp_dir.create_as_dir().unwrap();
+    let secondary_db = AptosDB::open_as_secondary(
+        tmp_dir.path().to_path_buf(),
+        secondary_tmp_dir.path().to_path_buf(),
+        RocksdbConfig::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        secondary_db.get_latest_ledger_info().unwrap(),
+        *first_ledger_info_with_sigs
+    );
+
+    let mut cur_ver = first_txns_to_commit.len() as u64;
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter().skip(1) {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+
+        secondary_db.try_catch_up_with_primary().unwrap();
+        assert_eq!(
+            secondary_db.get_latest_ledger_info().unwrap(),
+            *ledger_info_with_sigs
+        );
+        assert_eq!(
+            secondary_db.get_startup_info().unwrap(),
+            db.get_startup_info().unwrap()
+        );
+    }
+}
+
+fn test_get_jmt_node_raw_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+    }
+
+    for version in 0..cur_ver {
+        let root_node_key = NodeKey::new_empty_path(version).encode().unwrap();
+        let raw_node = db.get_jmt_node_raw(&root_node_key).unwrap().unwrap();
+        let node: Node<AccountStateBlob> = Node::decode(&raw_node).unwrap();
+        assert_eq!(node.hash(), db.state_store.get_root_hash(version).unwrap());
+        assert_eq!(db.get_jmt_node_decoded(&root_node_key).unwrap(), Some(node));
+    }
+    let missing_node_key = NodeKey::new_empty_path(cur_ver).encode().unwrap();
+    assert_eq!(db.get_jmt_node_raw(&missing_node_key).unwrap(), None);
+}
+
+fn test_get_transaction_versions_by_payload_type_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) {
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+
+    let mut cur_ver = 0;
+    let mut expected = BTreeMap::<TransactionPayloadType, Vec<Version>>::new();
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        for txn_to_commit in txns_to_commit {
+            if let Ok(txn) = txn_to_commit.transaction().as_signed_user_txn() {
+                expected
+                    .entry(txn.payload().payload_type())
+                    .or_default()
+                    .push(cur_ver);
+            }
+            cur_ver += 1;
+        }
+    }
+
+    let payload_types = [
+        TransactionPayloadType::WriteSet,
+        TransactionPayloadType::Script,
+        TransactionPayloadType::ModuleBundle,
+        TransactionPayloadType::ScriptFunction,
+    ];
+    let get_all = |payload_type| {
+        db.get_transaction_versions_by_payload_type(payload_type, 0, cur_ver)
+            .unwrap()
+    };
+    for payload_type in payload_types {
+        let versions = expected.get(&payload_type).cloned().unwrap_or_default();
+        assert_eq!(get_all(payload_type), versions);
+        // Paging from the middle.
+        if let Some(version) = versions.get(versions.len() / 2) {
+            assert_eq!(
+                db.get_transaction_versions_by_payload_type(payload_type, *version, 1)
+                    .unwrap(),
+                vec![*version],
+            );
+        }
+    }
+
+    // Drop the index as if the DB predated it, then backfill it.
+    let mut batch = SchemaBatch::new();
+    for (payload_type, versions) in &expected {
+        for version in versions {
+            batch
+                .delete::<TransactionByPayloadTypeSchema>(&(*payload_type, *version))
+                .unwrap();
+        }
+    }
+    db.db.write_schemas(batch).unwrap();
+    for payload_type in payload_types {
+        assert!(get_all(payload_type).is_empty());
+    }
+    assert_eq!(
+        db.backfill_transaction_by_payload_type_index().unwrap(),
+        expected
+            .values()
+            .map(|versions| versions.len() as u64)
+            .sum::<u64>(),
+    );
+    for payload_type in payload_types {
+        assert_eq!(
+            get_all(payload_type),
+            expected.get(&payload_type).cloned().unwrap_or_default(),
+        );
+    }
+}
+
+fn test_nonzero_genesis_version_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) {
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+    }
+    let snapshot_version = cur_ver - 1;
+
+    // Seed a fresh DB with the state snapshot and the accumulator frozen subtrees at `cur_ver`.
+    let seeded_tmp_dir = TempPath::new();
+    let seeded_db = Arc::new(AptosDB::new_for_test(&seeded_tmp_dir).with_genesis_version(cur_ver));
+    let mut exported = Vec::new();
+    db.get_backup_handler()
+        .export_state(snapshot_version, &mut exported)
+        .unwrap();
+    let accounts = read_exported_state(exported.as_slice())
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+    let proof = db
+        .get_backup_handler()
+        .get_account_state_range_proof(accounts.last().unwrap().0, snapshot_version)
+        .unwrap();
+    let mut receiver = seeded_db
+        .get_state_snapshot_receiver(
+            snapshot_version,
+            db.state_store.get_root_hash(snapshot_version).unwrap(),
+        )
+        .unwrap();
+    receiver.add_chunk(accounts, proof).unwrap();
+    receiver.finish_box().unwrap();
+    let mut left_siblings = db.ledger_store.get_frozen_subtree_hashes(cur_ver).unwrap();
+    left_siblings.reverse();
+    seeded_db
+        .get_restore_handler()
+        .confirm_or_save_frozen_subtrees(cur_ver, &left_siblings)
+        .unwrap();
+
+    let tree_state = seeded_db.get_latest_tree_state().unwrap();
+    assert_eq!(tree_state, db.get_latest_tree_state().unwrap());
+    assert_eq!(tree_state.num_transactions, cur_ver);
+    assert_eq!(
+        seeded_db.health_check().unwrap().first_viable_version,
+        cur_ver
+    );
+
+    // Reopened without the genesis version, the very same data reads as an empty DB, so the
+    // assertions above only hold because the seed is honored.
+    drop(seeded_db);
+    let unseeded_db = AptosDB::new_for_test(&seeded_tmp_dir);
+    let unseeded_tree_state = unseeded_db.get_latest_tree_state().unwrap();
+    assert_ne!(unseeded_tree_state, tree_state);
+    assert_eq!(unseeded_tree_state.num_transactions, 0);
+    assert_eq!(unseeded_db.health_check().unwrap().first_viable_version, 0);
+}
+
+fn test_latest_account_count_cached_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) {
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+    assert_eq!(db.latest_account_count_cached(), None);
+
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+    }
+    let latest_version = cur_ver - 1;
+
+    db.account_counter.wake_and_wait(latest_version).unwrap();
+    assert_eq!(
+        db.latest_account_count_cached(),
+        Some(db.get_account_count(latest_version).unwrap())
+    );
+}
+
+fn test_get_version_config_history_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) {
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+
+    let mut cur_ver = 0;
+    let mut expected: Vec<(Version, OnChainVersion)> = Vec::new();
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+
+        let ledger_info = ledger_info_with_sigs.ledger_info();
+        if ledger_info.ends_epoch() {
+            let config = OnChainVersion {
+                major: ledger_info.epoch() / 2,
+            };
+            if expected.last().map_or(true, |(_, prev)| prev != &config) {
+                expected.push((ledger_info.version(), config));
+            }
+        }
+    }
+
+    assert_eq!(db.get_version_config_history().unwrap(), expected);
+}
+
+fn test_get_events_with_proofs_up_to_bytes_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) {
+    const MAX_REQUEST_LIMIT: u64 = 2;
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::open(
+        &tmp_dir,
+        false, /* readonly */
+        NO_OP_STORAGE_PRUNER_CONFIG,
+        RocksdbConfig {
+            max_request_limit: Some(MAX_REQUEST_LIMIT),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+    }
+    let all_txns: Vec<_> = input
+        .iter()
+        .flat_map(|(txns_to_commit, _)| txns_to_commit.iter().cloned())
+        .collect();
+
+    for (event_key, events) in group_events_by_event_key(0, &all_txns) {
+        let first_seq = events.first().unwrap().1.sequence_number();
+        let last_seq = events.last().unwrap().1.sequence_number();
+        let event_size = bcs::serialized_size(
+            &db.get_events_with_proofs(&event_key, first_seq, Order::Ascending, 1, None)
+                .unwrap()[0],
+        )
+        .unwrap();
+
+        for max_bytes in [0, event_size * 2, usize::max_value()] {
+            for (order, start) in [
+                (Order::Ascending, first_seq),
+                (Order::Descending, u64::max_value()),
+            ] {
+                let mut traversed = Vec::new();
+                let mut cursor = Some(start);
+                while let Some(start) = cursor {
+                    let (page, next) = db
+                        .get_events_with_proofs_up_to_bytes(&event_key, start, order, max_bytes)
+                        .unwrap();
+                    assert!(!page.is_empty());
+                    assert!(page.len() as u64 <= MAX_REQUEST_LIMIT);
+                    // Only the request limit stops a page short of the unlimited byte budget.
+                    if max_bytes == usize::max_value() {
+                        assert!(next.is_none() || page.len() as u64 == MAX_REQUEST_LIMIT);
+                    }
+                    let page_bytes: usize =
+                        page.iter().map(|e| bcs::serialized_size(e).unwrap()).sum();
+                    assert!(page.len() == 1 || page_bytes <= max_bytes);
+                    traversed.extend(page.into_iter().map(|e| (e.transaction_version, e.event)));
+                    cursor = next;
+                }
+                if order == Order::Descending {
+                    traversed.reverse();
+                }
+                assert_eq!(traversed, events);
+                assert_eq!(traversed.last().unwrap().1.sequence_number(), last_seq);
+            }
+        }
+    }
+}
+
+fn test_verify_state_root_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+    }
+    let latest_version = cur_ver - 1;
+    for version in 0..=latest_version {
+        db.verify_state_root(version).unwrap();
+    }
+
+    // Overwrite the value of a leaf written by the latest transaction, leaving the hashes stored
+    // in its ancestors intact.
+    let mut iter = db
+        .db
+        .iter::<JellyfishMerkleNodeSchema>(schemadb::ReadOptions::default())
+        .unwrap();
+    iter.seek_to_first();
+    let (node_key, leaf) = iter
+        .map(Result::unwrap)
+        .find_map(|(node_key, node)| match node {
+            Node::Leaf(leaf) if node_key.version() == latest_version => Some((node_key, leaf)),
+            _ => None,
+        })
+        .unwrap();
+    db.db
+        .put::<JellyfishMerkleNodeSchema>(
+            &node_key,
+            &Node::new_leaf(
+                leaf.account_key(),
+                AccountStateBlob::from(b"corrupted".to_vec()),
+            ),
+        )
+        .unwrap();
+
+    assert_ne!(
+        db.state_store.recompute_root_hash(latest_version).unwrap(),
+        db.state_store.get_root_hash(latest_version).unwrap()
+    );
+    assert!(db.verify_state_root(latest_version).is_err());
+}
+
+fn test_get_transactions_verified_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) {
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+    }
+    let latest_version = cur_ver - 1;
+    assert_eq!(
+        db.get_transactions_verified(0, cur_ver, latest_version, true)
+            .unwrap(),
+        db.get_transactions(0, Order::Ascending, cur_ver, latest_version, true)
+            .unwrap()
+    );
+
+    // Corrupt a stored transaction info, which the unverified read path happily returns.
+    let corrupted_version = latest_version / 2;
+    let txn_info = db
+        .ledger_store
+        .get_transaction_info(corrupted_version)
+        .unwrap();
+    let corrupted_txn_info = TransactionInfo::new(
+        txn_info.transaction_hash(),
+        txn_info.state_change_hash(),
+        txn_info.event_root_hash(),
+        txn_info.gas_used() + 1,
+        txn_info.status().clone(),
+    );
+    db.db
+        .put::<TransactionInfoSchema>(&corrupted_version, &corrupted_txn_info)
+        .unwrap();
+
+    db.get_transactions(0, Order::Ascending, cur_ver, latest_version, true)
+        .unwrap();
+    assert!(db
+        .get_transactions_verified(0, cur_ver, latest_version, true)
+        .is_err());
+    assert!(db
+        .get_transactions_verified(corrupted_version, 1, latest_version, false)
+        .is_err());
+}
+
+fn test_verify_integrity_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+    }
+    let latest_version = cur_ver - 1;
+    db.verify_integrity(0, cur_ver).unwrap();
+    db.verify_integrity(cur_ver, cur_ver).unwrap();
+    assert_db_error(
+        db.verify_integrity(0, cur_ver + 1),
+        |e| matches!(e, AptosDbError::VersionTooNew(v, l) if *v == cur_ver && *l == latest_version),
+    );
+
+    // Replace a stored transaction, which no longer matches its transaction info.
+    let corrupted_version = latest_version / 2;
+    db.db
+        .put::<TransactionSchema>(&corrupted_version, &Transaction::StateCheckpoint)
+        .unwrap();
+
+    db.verify_integrity(0, corrupted_version).unwrap();
+    assert_db_error(
+        db.verify_integrity(0, cur_ver),
+        |e| matches!(e, AptosDbError::Inconsistent(v, "transaction", _) if *v == corrupted_version),
+    );
+}
+
+fn assert_db_error<T: std::fmt::Debug>(
+    result: Result<T>,
+    matches: impl FnOnce(&AptosDbError) -> bool,
+) {
+    let err = result.unwrap_err();
+    let db_error = err
+        .downcast_ref::<AptosDbError>()
+        .unwrap_or_else(|| panic!("Not an AptosDbError: {:?}", err));
+    assert!(matches(db_error), "Unexpected AptosDbError: {:?}", db_error);
+}
+
+fn test_error_kinds_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
+    let tmp_dir = TempPath::new();
+    let mut db = AptosDB::new_for_test(&tmp_dir);
+
+    // A ledger info not matching the transactions.
+    let (first_txns, first_ledger_info_with_sigs) = input.first().unwrap();
+    let mut bad_ledger_info = first_ledger_info_with_sigs.ledger_info().clone();
+    bad_ledger_info.set_executed_state_id(HashValue::zero());
+    assert_db_error(
+        db.save_transactions(
+            first_txns,
+            0,
+            Some(&LedgerInfoWithSignatures::new(
+                bad_ledger_info,
+                BTreeMap::new(),
+            )),
+        ),
+        |e| matches!(e, AptosDbError::RootHashMismatch(expected, _) if *expected == HashValue::zero()),
+    );
+
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+    }
+    let latest_version = cur_ver - 1;
+
+    assert_db_error(
+        db.get_transactions(0, Order::Ascending, MAX_LIMIT + 1, latest_version, false),
+        |e| matches!(e, AptosDbError::TooManyRequested(_, MAX_LIMIT)),
+    );
+    assert_db_error(
+        db.ledger_store.get_transaction_info(latest_version + 1),
+        |e| matches!(e, AptosDbError::NotFound(_)),
+    );
+    assert_db_error(
+        db.get_account_state_with_proof(AccountAddress::ZERO, latest_version, latest_version + 1),
+        |e| matches!(e, AptosDbError::VersionTooNew(v, l) if *v == latest_version + 1 && *l == latest_version),
+    );
+    assert_db_error(
+        db.get_event_by_version_with_proof(&EventKey::random(), 0, latest_version + 1),
+        |e| matches!(e, AptosDbError::VersionTooNew(_, _)),
+    );
+
+    assert_eq!(db.get_first_readable_version().unwrap(), 0);
+
+    if latest_version == 0 {
+        return;
+    }
+    db.pruner = Some(Pruner::new(
+        Arc::clone(&db.db),
+        StoragePrunerConfig {
+            state_store_prune_window: Some(0),
+            default_prune_window: Some(0),
+            max_version_to_prune_per_batch: Some(100),
+            ledger_max_version_to_prune_per_batch: None,
+        },
+        Arc::clone(&db.transaction_store),
+        Arc::clone(&db.ledger_store),
+        Arc::clone(&db.event_store),
+    ));
+    let pruner = db.pruner.as_ref().unwrap();
+    for pruner_index in 0..5 {
+        pruner.wake_and_wait(latest_version, pruner_index).unwrap();
+    }
+    assert_db_error(
+        db.get_transactions(0, Order::Ascending, 1, latest_version, false),
+        |e| matches!(e, AptosDbError::Pruned(0, _)),
+    );
+    let first_readable_version = db.get_first_readable_version().unwrap();
+    assert_eq!(
+        first_readable_version,
+        pruner.get_least_readable_ledger_version()
+    );
+    assert_db_error(
+        db.get_transaction_by_version(first_readable_version - 1, latest_version, false),
+        |e| matches!(e, AptosDbError::Pruned(v, l) if *v == first_readable_version - 1 && *l == first_readable_version),
+    );
+    db.get_transaction_by_version(first_readable_version, latest_version, true)
+        .unwrap();
+    assert_db_error(
+        db.get_account_state_with_proof_by_version(AccountAddress::ZERO, 0),
+        |e| matches!(e, AptosDbError::Pruned(0, _)),
+    );
+    assert_db_error(db.pin_version(0), |e| {
+        matches!(e, AptosDbError::Pruned(0, _))
+    });
+}
+
+fn test_prune_up_to_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
+    const PRUNE_WINDOW: Version = 1;
+    let tmp_dir = TempPath::new();
+    let mut db = AptosDB::new_for_test(&tmp_dir);
+
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+    }
+    let latest_version = cur_ver - 1;
+
+    // Without a pruner, nothing is pruned.
+    assert_eq!(db.prune_up_to(latest_version).unwrap(), 0);
+    db.get_transaction_by_version(0, latest_version, false)
+        .unwrap();
+
+    if latest_version < PRUNE_WINDOW {
+        return;
+    }
+    db.pruner = Some(Pruner::new(
+        Arc::clone(&db.db),
+        StoragePrunerConfig {
+            state_store_prune_window: Some(PRUNE_WINDOW),
+            default_prune_window: Some(PRUNE_WINDOW),
+            max_version_to_prune_per_batch: Some(1),
+            ledger_max_version_to_prune_per_batch: Some(1),
+        },
+        Arc::clone(&db.transaction_store),
+        Arc::clone(&db.ledger_store),
+        Arc::clone(&db.event_store),
+    ));
+
+    // Versions within the prune window can't be pruned.
+    assert!(db.prune_up_to(latest_version - PRUNE_WINDOW + 1).is_err());
+
+    let target_version = latest_version - PRUNE_WINDOW;
+    assert_eq!(db.prune_up_to(target_version).unwrap(), target_version);
+    assert_eq!(db.get_first_readable_version().unwrap(), target_version);
+    if target_version > 0 {
+        assert_db_error(
+            db.get_transaction_by_version(target_version - 1, latest_version, false),
+            |e| matches!(e, AptosDbError::Pruned(_, _)),
+        );
+        assert_db_error(
+            db.get_account_state_with_proof_by_version(AccountAddress::ZERO, target_version - 1),
+            |e| matches!(e, AptosDbError::Pruned(_, _)),
+        );
+    }
+    db.get_transaction_by_version(target_version, latest_version, true)
+        .unwrap();
+
+    // Pruning again up to the same version is a no-op.
+    assert_eq!(db.prune_up_to(target_version).unwrap(), target_version);
+}
+
+fn test_pause_pruner_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
+    const PRUNE_WINDOW: Version = 1;
+    let tmp_dir = TempPath::new();
+    let mut db = AptosDB::new_for_test(&tmp_dir);
+    db.pruner = Some(Pruner::new(
+        Arc::clone(&db.db),
+        StoragePrunerConfig {
+            state_store_prune_window: Some(PRUNE_WINDOW),
+            default_prune_window: Some(PRUNE_WINDOW),
+            max_version_to_prune_per_batch: Some(1),
+            ledger_max_version_to_prune_per_batch: Some(1),
+        },
+        Arc::clone(&db.transaction_store),
+        Arc::clone(&db.ledger_store),
+        Arc::clone(&db.event_store),
+    ));
+
+    db.set_pruner_enabled(false);
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+    }
+    let latest_version = cur_ver - 1;
+
+    // Nothing is pruned while paused, and manual pruning is refused.
+    std::thread::sleep(Duration::from_millis(100));
+    assert_eq!(db.get_first_readable_version().unwrap(), 0);
+    db.get_transaction_by_version(0, latest_version, true)
+        .unwrap();
+    assert!(db.prune_up_to(0).is_err());
+
+    // Once resumed, the pruner catches up with the commits made while paused.
+    db.set_pruner_enabled(true);
+    let expected_version = latest_version.saturating_sub(PRUNE_WINDOW);
+    let deadline = std::time::Instant::now() + Duration::from_secs(10);
+    while db.get_first_readable_version().unwrap() < expected_version {
+        assert!(
+            std::time::Instant::now() < deadline,
+            "Timeout waiting for the pruner to catch up."
+        );
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(db.get_first_readable_version().unwrap(), expected_version);
+    db.get_transaction_by_version(expected_version, latest_version, true)
+        .unwrap();
+}
+
+fn test_get_state_key_iter_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+
+    let (last_block, blocks) = input.split_last().unwrap();
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in blocks {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+    }
+    if cur_ver == 0 {
+        assert!(db.get_state_key_iter(0).is_err());
+        return;
+    }
+    let version = cur_ver - 1;
+    let iter = db.get_state_key_iter(version).unwrap();
+    assert!(db.get_state_key_iter(version + 1).is_err());
+
+    // Committing more doesn't affect an iterator already created.
+    let (txns_to_commit, ledger_info_with_sigs) = last_block;
+    db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+        .unwrap();
+    let account_states = iter.collect::<Result<Vec<_>>>().unwrap();
+    assert_eq!(
+        account_states,
+        db.get_backup_handler()
+            .get_account_iter(version)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap()
+    );
+    assert_eq!(account_states.len(), db.get_account_count(version).unwrap());
+}
+
+fn test_get_account_state_history_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) {
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+
+    let mut cur_ver = 0;
+    let mut addresses = HashSet::new();
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+        for txn in txns_to_commit {
+            addresses.extend(txn.account_states().keys().cloned());
+        }
+    }
+    let latest_version = cur_ver - 1;
+    // An address that's never been touched has no history.
+    addresses.insert(AccountAddress::ZERO);
+
+    for address in addresses {
+        for start_version in [0, latest_version / 2, latest_version] {
+            let mut expected_history: Vec<(Version, Option<AccountStateBlob>)> = Vec::new();
+            for version in start_version..=latest_version {
+                let (blob, _proof) = db
+                    .get_account_state_with_proof_by_version(address, version)
+                    .unwrap();
+                if expected_history.last().map(|(_, last_blob)| last_blob) != Some(&blob) {
+                    expected_history.push((version, blob));
+                }
+            }
+            assert_eq!(
+                db.get_account_state_history(address, start_version, latest_version)
+                    .unwrap(),
+                expected_history
+            );
+        }
+    }
+
+    assert!(db
+        .get_account_state_history(AccountAddress::ZERO, 0, latest_version + 1)
+        .is_err());
+    assert_db_error(
+        db.get_account_state_history(AccountAddress::ZERO, 0, MAX_LIMIT),
+        |e| matches!(e, AptosDbError::TooManyRequested(_, MAX_LIMIT)),
+    );
+    assert!(db
+        .get_account_state_history(AccountAddress::ZERO, 0, Version::max_value())
+        .is_err());
+}
+
+fn test_get_modules_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+
+    let mut cur_ver = 0;
+    let mut addresses = HashSet::new();
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+        for txn in txns_to_commit {
+            addresses.extend(txn.account_states().keys().cloned());
+        }
+    }
+    addresses.insert(AccountAddress::ZERO);
+
+    // Several modules per address, interleaved across addresses, with duplicates.
+    let module_ids: Vec<_> = ["a", "b", "a"]
+        .iter()
+        .flat_map(|name| {
+            addresses
+                .iter()
+                .map(move |address| ModuleId::new(*address, Identifier::new(*name).unwrap()))
+        })
+        .collect();
+    let expected: Vec<_> = module_ids
+        .iter()
+        .map(|module_id| db.get_module(module_id).unwrap())
+        .collect();
+    assert_eq!(db.get_modules(&module_ids).unwrap(), expected);
+    assert!(db.get_modules(&[]).unwrap().is_empty());
+
+    // The modules republished by the last block are read, and missing ones are `None`.
+    let last_block_index = (input.len() - 1) as u8;
+    let module_id =
+        |name: &str| ModuleId::new(module_publisher_address(), Identifier::new(name).unwrap());
+    assert_eq!(
+        db.get_modules(&[module_id("b"), module_id("c"), module_id("a")])
+            .unwrap(),
+        vec![
+            Some(vec![last_block_index, 1]),
+            None,
+            Some(vec![last_block_index, 0]),
+        ]
+    );
+
+    assert_db_error(
+        db.get_modules(&vec![module_id("a"); MAX_LIMIT as usize + 1]),
+        |e| matches!(e, AptosDbError::TooManyRequested(_, MAX_LIMIT)),
+    );
+}
+
+fn test_get_resource_at_version_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) {
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+    }
+    let latest_version = cur_ver - 1;
+
+    let tag = AccountResource::struct_tag();
+    let mut version = 0;
+    for (txns_to_commit, _ledger_info_with_sigs) in input.iter() {
+        for txn in txns_to_commit {
+            for (address, blob) in txn.account_states() {
+                let expected = AccountState::try_from(blob)
+                    .unwrap()
+                    .get(&tag.access_vector())
+                    .cloned();
+                assert!(expected.is_some());
+                assert_eq!(
+                    db.get_resource_at_version(address, &tag, version).unwrap(),
+                    expected
+                );
+                if version == latest_version {
+                    assert_eq!(db.get_resource(address, &tag).unwrap(), expected);
+                }
+            }
+            version += 1;
+        }
+    }
+
+    let module_id = ModuleId::new(AccountAddress::ZERO, Identifier::new("m").unwrap());
+    assert_eq!(
+        db.get_module_at_version(&module_id, latest_version)
+            .unwrap(),
+        None
+    );
+    assert!(db
+        .get_module_at_version(&module_id, latest_version + 1)
+        .is_err());
+}
+
+fn test_configured_max_epochs_per_state_proof_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) {
+    const MAX_EPOCHS_PER_STATE_PROOF: usize = 1;
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::open(
+        &tmp_dir,
+        false, /* readonly */
+        NO_OP_STORAGE_PRUNER_CONFIG,
+        RocksdbConfig {
+            max_epochs_per_state_proof: Some(MAX_EPOCHS_PER_STATE_PROOF),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+    }
+    let latest_ledger_info = db.get_latest_ledger_info().unwrap();
+    let end_epoch = latest_ledger_info.ledger_info().next_block_epoch();
+    let known_epoch = db.ledger_store.get_epoch(0).unwrap();
+
+    // A client at genesis is paced to one epoch per proof, however far behind it is.
+    let state_proof = db.get_state_proof(0).unwrap();
+    let epoch_changes = state_proof.epoch_changes();
+    assert!(epoch_changes.ledger_info_with_sigs.len() <= MAX_EPOCHS_PER_STATE_PROOF);
+    assert_eq!(
+        epoch_changes.more,
+        end_epoch - known_epoch > MAX_EPOCHS_PER_STATE_PROOF as u64
+    );
+    let verifiable_li = if epoch_changes.more {
+        epoch_changes
+            .ledger_info_with_sigs
+            .last()
+            .unwrap()
+            .ledger_info()
+    } else {
+        latest_ledger_info.ledger_info()
+    };
+    db.get_accumulator_summary(0)
+        .unwrap()
+        .try_extend_with_proof(state_proof.consistency_proof(), verifiable_li)
+        .unwrap();
+
+    // Asking for more epochs per proof than configured doesn't lift the limit.
+    let state_proof = db
+        .get_state_proof_with_ledger_info_and_limit(0, latest_ledger_info, Some(usize::MAX))
+        .unwrap();
+    assert_eq!(state_proof.epoch_changes(), epoch_changes);
+}
+
+fn verify_state_tail_proof(db: &AptosDB, version: Version, after_index: u64) -> bool {
+    let proof = db.get_state_tail_proof(version, after_index).unwrap();
+
+    // The leaf at `after_index` and its left siblings, as known to a syncing client.
+    let (key, _blob) = JellyfishMerkleIterator::new_by_index(
+        Arc::clone(&db.state_store),
+        version,
+        after_index as usize,
+    )
+    .unwrap()
+    .next()
+    .unwrap()
+    .unwrap();
+    let (_blob, leaf_proof): (Option<AccountStateBlob>, _) =
+        JellyfishMerkleTree::new(&*db.state_store)
+            .get_with_proof(key, version)
+            .unwrap();
+    let left_siblings = key
+        .iter_bits()
+        .rev()
+        .skip(HashValue::LENGTH_IN_BITS - leaf_proof.siblings().len())
+        .zip(leaf_proof.siblings())
+        .filter_map(|(bit, sibling)| bit.then(|| *sibling))
+        .collect();
+
+    proof
+        .verify(
+            db.state_store.get_root_hash(version).unwrap(),
+            leaf_proof.leaf().unwrap(),
+            left_siblings,
+        )
+        .unwrap();
+    proof
+        .right_siblings()
+        .iter()
+        .all(|sibling| *sibling == *SPARSE_MERKLE_PLACEHOLDER_HASH)
+}
+
+fn test_get_state_tail_proof_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) {
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+    }
+    let version = cur_ver - 1;
+    let num_leaves = db.state_store.get_account_count(version).unwrap() as u64;
+
+    // Past the last leaf: no more leaves.
+    assert!(verify_state_tail_proof(&db, version, num_leaves - 1));
+    // Past any other leaf: more leaves to come.
+    for after_index in 0..num_leaves - 1 {
+        assert!(!verify_state_tail_proof(&db, version, after_index));
+    }
+    // There's no leaf to prove the tail after.
+    assert!(db.get_state_tail_proof(version, num_leaves).is_err());
+}
+
+fn test_prune_before_timestamp_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) {
+    const PRUNE_WINDOW: Version = 1;
+    let tmp_dir = TempPath::new();
+    let mut db = AptosDB::new_for_test(&tmp_dir);
+
+    // The `i`-th block is proposed at timestamp `i + 1`.
+    let mut cur_ver = 0;
+    let mut block_first_versions = Vec::new();
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        block_first_versions.push(cur_ver);
+        cur_ver += txns_to_commit.len() as u64;
+    }
+    let latest_version = cur_ver - 1;
+
+    // Without a pruner, nothing is pruned.
+    assert_eq!(db.prune_before_timestamp(u64::MAX).unwrap(), 0);
+
+    db.pruner = Some(Pruner::new(
+        Arc::clone(&db.db),
+        StoragePrunerConfig {
+            state_store_prune_window: Some(PRUNE_WINDOW),
+            default_prune_window: Some(PRUNE_WINDOW),
+            max_version_to_prune_per_batch: Some(100),
+            ledger_max_version_to_prune_per_batch: None,
+        },
+        Arc::clone(&db.transaction_store),
+        Arc::clone(&db.ledger_store),
+        Arc::clone(&db.event_store),
+    ));
+
+    // Nothing is known to be before the first block.
+    assert!(db.prune_before_timestamp(1).is_err());
+    if input.len() < 2 {
+        return;
+    }
+
+    // Everything before the last block is pruned, unless within the prune window.
+    let last_block_index = input.len() - 1;
+    let cutoff_version = db
+        .prune_before_timestamp(last_block_index as u64 + 1)
+        .unwrap();
+    assert_eq!(
+        cutoff_version,
+        std::cmp::min(
+            block_first_versions[last_block_index],
+            latest_version - PRUNE_WINDOW
+        )
+    );
+    assert_eq!(db.get_first_readable_version().unwrap(), cutoff_version);
+    if cutoff_version > 0 {
+        assert_db_error(
+            db.get_transaction_by_version(cutoff_version - 1, latest_version, false),
+            |e| matches!(e, AptosDbError::Pruned(_, _)),
+        );
+    }
+    db.get_transaction_by_version(cutoff_version, latest_version, true)
+        .unwrap();
+}
+
+fn test_get_multiproof_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+
+    let mut cur_ver = 0;
+    let mut addresses = vec![AccountAddress::random()];
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+        for txn_to_commit in txns_to_commit {
+            addresses.extend(txn_to_commit.account_states().keys());
+        }
+    }
+    let version = cur_ver - 1;
+    let root_hash = db
+        .ledger_store
+        .get_transaction_info(version)
+        .unwrap()
+        .state_change_hash();
+
+    let (account_states, multiproof) = db.get_multiproof(&addresses, version).unwrap();
+    let elements: Vec<_> = account_states
+        .iter()
+        .map(|(address, blob)| (address.hash(), blob.as_ref()))
+        .collect();
+    multiproof.verify(root_hash, &elements).unwrap();
+
+    let mut individual_proofs_size = 0;
+    for (address, blob) in &account_states {
+        let (expected_blob, proof) = db
+            .get_account_state_with_proof_by_version(*address, version)
+            .unwrap();
+        assert_eq!(*blob, expected_blob);
+        individual_proofs_size += bcs::to_bytes(&proof).unwrap().len();
+    }
+    if account_states.len() > 1 {
+        assert!(bcs::to_bytes(&multiproof).unwrap().len() < individual_proofs_size);
+    }
+}
+
+fn verify_consistent_at(db: &AptosDB, ledger_info_with_sigs: Option<&LedgerInfoWithSignatures>) {
+    assert_eq!(
+        db.ledger_store.get_latest_ledger_info_option().unwrap(),
+        ledger_info_with_sigs.cloned()
+    );
+    if let Some(ledger_info_with_sigs) = ledger_info_with_sigs {
+        let ledger_info = ledger_info_with_sigs.ledger_info();
+        let version = ledger_info.version();
+        db.get_transactions(0, Order::Ascending, version + 1, version, true)
+            .unwrap()
+            .verify(ledger_info, Some(0))
+            .unwrap();
+        assert_eq!(
+            db.state_store.get_root_hash(version).unwrap(),
+            db.ledger_store
+                .get_transaction_info(version)
+                .unwrap()
+                .state_change_hash()
+        );
+    }
+}
+
+fn test_commit_failure_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
+    let tmp_dir = TempPath::new();
+    let mut db = AptosDB::new_for_test(&tmp_dir);
+
+    let mut cur_ver = 0;
+    let mut prev_ledger_info_with_sigs = None;
+    for (batch_idx, (txns_to_commit, ledger_info_with_sigs)) in input.iter().enumerate() {
+        let failure = if batch_idx % 2 == 0 {
+            CommitFailure::BeforeWrite
+        } else {
+            CommitFailure::AfterWritingCf(TRANSACTION_CF_NAME)
+        };
+        db.inject_commit_failure(0, failure);
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap_err();
+        verify_consistent_at(&db, prev_ledger_info_with_sigs);
+
+        // Still consistent after a restart.
+        drop(db);
+        db = AptosDB::new_for_test(&tmp_dir);
+        verify_consistent_at(&db, prev_ledger_info_with_sigs);
+
+        // Retrying succeeds.
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        verify_consistent_at(&db, Some(ledger_info_with_sigs));
+
+        cur_ver += txns_to_commit.len() as u64;
+        prev_ledger_info_with_sigs = Some(ledger_info_with_sigs);
+    }
+
+    // The failure is injected after the given number of commits, and only once.
+    if input.len() >= 2 {
+        let tmp_dir = TempPath::new();
+        let db = AptosDB::new_for_test(&tmp_dir);
+        db.inject_commit_failure(1, CommitFailure::BeforeWrite);
+        let (txns_to_commit, ledger_info_with_sigs) = &input[0];
+        db.save_transactions(txns_to_commit, 0, Some(ledger_info_with_sigs))
+            .unwrap();
+        let first_version = txns_to_commit.len() as u64;
+        let (txns_to_commit, ledger_info_with_sigs) = &input[1];
+        db.save_transactions(txns_to_commit, first_version, Some(ledger_info_with_sigs))
+            .unwrap_err();
+        db.save_transactions(txns_to_commit, first_version, Some(ledger_info_with_sigs))
+            .unwrap();
+        verify_consistent_at(&db, Some(ledger_info_with_sigs));
+    }
+}
+
+fn test_write_buffer_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+    let buffered_tmp_dir = TempPath::new();
+    let buffered_db =
+        AptosDB::new_for_test(&buffered_tmp_dir).with_write_buffer(WriteBufferConfig {
+            max_buffered_txns: 3,
+            max_buffered_duration: Duration::from_secs(3600),
+        });
+
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        let root_hash = db
+            .save_transactions_and_get_root(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        let buffered_root_hash = buffered_db
+            .save_transactions_and_get_root(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        assert_eq!(buffered_root_hash, root_hash);
+        cur_ver += txns_to_commit.len() as u64;
+    }
+    let latest_version = cur_ver - 1;
+
+    buffered_db.wait_for_commit(latest_version).unwrap();
+    assert!(buffered_db.wait_for_commit(latest_version + 1).is_err());
+    assert_eq!(
+        buffered_db.get_latest_ledger_info().unwrap(),
+        db.get_latest_ledger_info().unwrap()
+    );
+    assert_eq!(
+        buffered_db.get_startup_info().unwrap(),
+        db.get_startup_info().unwrap()
+    );
+    assert_eq!(
+        buffered_db
+            .get_transaction_outputs(0, cur_ver, latest_version)
+            .unwrap(),
+        db.get_transaction_outputs(0, cur_ver, latest_version)
+            .unwrap()
+    );
+    for (txns_to_commit, _) in input.iter() {
+        for txn_to_commit in txns_to_commit {
+            for addr in txn_to_commit.account_states().keys() {
+                assert_eq!(
+                    buffered_db.get_latest_account_state(*addr).unwrap(),
+                    db.get_latest_account_state(*addr).unwrap()
+                );
+            }
+        }
+    }
+}
+
+fn test_account_transaction_count_with_state_pruned_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) {
+    let tmp_dir = TempPath::new();
+    let mut db = AptosDB::new_for_test(&tmp_dir);
+
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+    }
+    let latest_version = cur_ver - 1;
+    if latest_version == 0 {
+        return;
+    }
+    let senders: HashSet<_> = input
+        .iter()
+        .flat_map(|(txns_to_commit, _ledger_info_with_sigs)| txns_to_commit)
+        .filter_map(|txn_to_commit| txn_to_commit.transaction().as_signed_user_txn().ok())
+        .map(|txn| txn.sender())
+        .collect();
+    let expected_counts: Vec<_> = senders
+        .iter()
+        .map(|sender| db.get_account_transaction_count(*sender, 0).unwrap())
+        .collect();
+
+    // Prune the state only, keeping all the transactions.
+    db.pruner = Some(Pruner::new(
+        Arc::clone(&db.db),
+        StoragePrunerConfig {
+            state_store_prune_window: Some(0),
+            default_prune_window: Some(latest_version + 1),
+            max_version_to_prune_per_batch: Some(100),
+            ledger_max_version_to_prune_per_batch: None,
+        },
+        Arc::clone(&db.transaction_store),
+        Arc::clone(&db.ledger_store),
+        Arc::clone(&db.event_store),
+    ));
+    db.pruner
+        .as_ref()
+        .unwrap()
+        .wake_and_wait(latest_version, 0 /* pruner_index */)
+        .unwrap();
+    assert_db_error(
+        db.get_account_state_with_proof_by_version(AccountAddress::ZERO, 0),
+        |e| matches!(e, AptosDbError::Pruned(0, _)),
     );
 
-    // Fetch and verify batch transactions by account
-    verify_account_txns(db, group_txns_by_account(txns_to_commit), ledger_info);
+    // The transactions are still counted, without the state to check the CRSN resource against.
+    for (sender, expected_count) in senders.iter().zip(expected_counts) {
+        assert_eq!(
+            db.get_account_transaction_count(*sender, 0).unwrap(),
+            expected_count
+        );
+    }
+}
+
+fn test_prunable_bytes_estimate_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) {
+    let tmp_dir = TempPath::new();
+    let mut db = AptosDB::new_for_test(&tmp_dir);
+
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+    }
+    let latest_version = cur_ver - 1;
+    // Nothing is prunable without a pruner.
+    assert_eq!(db.get_prunable_bytes_estimate().unwrap(), 0);
+
+    db.db.flush_all().unwrap();
+    db.pruner = Some(Pruner::new(
+        Arc::clone(&db.db),
+        StoragePrunerConfig {
+            state_store_prune_window: Some(0),
+            default_prune_window: Some(0),
+            max_version_to_prune_per_batch: Some(100),
+            ledger_max_version_to_prune_per_batch: None,
+        },
+        Arc::clone(&db.transaction_store),
+        Arc::clone(&db.ledger_store),
+        Arc::clone(&db.event_store),
+    ));
+    if latest_version == 0 {
+        return;
+    }
+    assert!(db.get_prunable_bytes_estimate().unwrap() > 0);
+
+    let pruner = db.pruner.as_ref().unwrap();
+    for pruner_index in 0..5 {
+        pruner.wake_and_wait(latest_version, pruner_index).unwrap();
+    }
+    assert_eq!(db.get_prunable_bytes_estimate().unwrap(), 0);
 }
 
 proptest! {
@@ -575,6 +2673,156 @@ proptest! {
     fn test_sync_transactions(input in arb_blocks_to_commit()) {
         test_sync_transactions_impl(input);
     }
+
+    #[test]
+    fn test_health_check(input in arb_blocks_to_commit()) {
+        test_health_check_impl(input);
+    }
+
+    #[test]
+    fn test_committed_version(input in arb_blocks_to_commit()) {
+        test_committed_version_impl(input);
+    }
+
+    #[test]
+    fn test_commit_ledger_info_only(input in arb_blocks_to_commit()) {
+        test_commit_ledger_info_only_impl(input);
+    }
+
+    #[test]
+    fn test_account_state_change_proof(input in arb_blocks_to_commit()) {
+        test_account_state_change_proof_impl(input);
+    }
+
+    #[test]
+    fn test_secondary_catch_up(input in arb_blocks_to_commit()) {
+        test_secondary_catch_up_impl(input);
+    }
+
+    #[test]
+    fn test_get_jmt_node_raw(input in arb_blocks_to_commit()) {
+        test_get_jmt_node_raw_impl(input);
+    }
+
+    #[test]
+    fn test_get_transaction_versions_by_payload_type(input in arb_blocks_to_commit()) {
+        test_get_transaction_versions_by_payload_type_impl(input);
+    }
+
+    #[test]
+    fn test_nonzero_genesis_version(input in arb_blocks_to_commit()) {
+        test_nonzero_genesis_version_impl(input);
+    }
+
+    #[test]
+    fn test_latest_account_count_cached(input in arb_blocks_to_commit()) {
+        test_latest_account_count_cached_impl(input);
+    }
+
+    #[test]
+    fn test_get_version_config_history(input in arb_blocks_to_commit_with_version_bumps()) {
+        test_get_version_config_history_impl(input);
+    }
+
+    #[test]
+    fn test_get_events_with_proofs_up_to_bytes(input in arb_blocks_to_commit()) {
+        test_get_events_with_proofs_up_to_bytes_impl(input);
+    }
+
+    #[test]
+    fn test_verify_state_root(input in arb_blocks_to_commit()) {
+        test_verify_state_root_impl(input);
+    }
+
+    #[test]
+    fn test_get_transactions_verified(input in arb_blocks_to_commit()) {
+        test_get_transactions_verified_impl(input);
+    }
+
+    #[test]
+    fn test_get_state_tail_proof(input in arb_blocks_to_commit()) {
+        test_get_state_tail_proof_impl(input);
+    }
+
+    #[test]
+    fn test_commit_failure(input in arb_blocks_to_commit()) {
+        test_commit_failure_impl(input);
+    }
+
+    #[test]
+    fn test_prune_before_timestamp(input in arb_blocks_to_commit_with_block_events()) {
+        test_prune_before_timestamp_impl(input);
+    }
+
+    #[test]
+    fn test_get_multiproof(input in arb_blocks_to_commit()) {
+        test_get_multiproof_impl(input);
+    }
+
+    #[test]
+    fn test_prune_up_to(input in arb_blocks_to_commit()) {
+        test_prune_up_to_impl(input);
+    }
+
+    #[test]
+    fn test_pause_pruner(input in arb_blocks_to_commit()) {
+        test_pause_pruner_impl(input);
+    }
+
+    #[test]
+    fn test_get_state_key_iter(input in arb_blocks_to_commit()) {
+        test_get_state_key_iter_impl(input);
+    }
+
+    #[test]
+    fn test_get_account_state_history(input in arb_blocks_to_commit()) {
+        test_get_account_state_history_impl(input);
+    }
+
+    #[test]
+    fn test_get_modules(input in arb_blocks_to_commit_with_modules()) {
+        test_get_modules_impl(input);
+    }
+
+    #[test]
+    fn test_get_resource_at_version(input in arb_blocks_to_commit()) {
+        test_get_resource_at_version_impl(input);
+    }
+
+    #[test]
+    fn test_configured_max_epochs_per_state_proof(input in arb_blocks_to_commit()) {
+        test_configured_max_epochs_per_state_proof_impl(input);
+    }
+
+    #[test]
+    fn test_verify_integrity(input in arb_blocks_to_commit()) {
+        test_verify_integrity_impl(input);
+    }
+
+    #[test]
+    fn test_error_kinds(input in arb_blocks_to_commit()) {
+        test_error_kinds_impl(input);
+    }
+
+    #[test]
+    fn test_write_buffer(input in arb_blocks_to_commit()) {
+        test_write_buffer_impl(input);
+    }
+
+    #[test]
+    fn test_account_transaction_count_with_state_pruned(input in arb_blocks_to_commit()) {
+        test_account_transaction_count_with_state_pruned_impl(input);
+    }
+
+    #[test]
+    fn test_prunable_bytes_estimate(input in arb_blocks_to_commit()) {
+        test_prunable_bytes_estimate_impl(input);
+    }
+
+    #[test]
+    fn test_save_conflicting_ledger_info(input in arb_blocks_to_commit()) {
+        test_save_conflicting_ledger_info_impl(input);
+    }
 }
 
 #[test]
@@ -615,10 +2863,238 @@ fn test_too_many_requested() {
     let tmp_dir = TempPath::new();
     let db = AptosDB::new_for_test(&tmp_dir);
 
-    assert!(db.get_transactions(0, 1001 /* limit */, 0, true).is_err());
+    assert!(db
+        .get_transactions(0, Order::Ascending, 1001 /* limit */, 0, true)
+        .is_err());
     assert!(db.get_transaction_outputs(0, 1001 /* limit */, 0).is_err());
 }
 
+#[test]
+fn test_configured_max_request_limit() {
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::open(
+        &tmp_dir,
+        false, /* readonly */
+        NO_OP_STORAGE_PRUNER_CONFIG,
+        RocksdbConfig {
+            max_request_limit: Some(2),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    assert_db_error(db.get_transactions(0, Order::Ascending, 3, 0, true), |e| {
+        matches!(e, AptosDbError::TooManyRequested(3, 2))
+    });
+    assert_db_error(
+        db.get_events_with_proofs(&EventKey::random(), 0, Order::Ascending, 3, Some(0)),
+        |e| matches!(e, AptosDbError::TooManyRequested(3, 2)),
+    );
+    // The limits of a multi-key request add up.
+    assert_db_error(
+        db.get_events_multi_key(
+            &[
+                (EventKey::random(), 0, Order::Ascending, 2),
+                (EventKey::random(), 0, Order::Ascending, 1),
+            ],
+            0,
+        ),
+        |e| matches!(e, AptosDbError::TooManyRequested(3, 2)),
+    );
+    assert_db_error(
+        db.get_account_transactions(AccountAddress::random(), 0, Order::Ascending, 3, true, 0),
+        |e| matches!(e, AptosDbError::TooManyRequested(3, 2)),
+    );
+    assert!(db
+        .get_account_transactions(AccountAddress::random(), 0, Order::Ascending, 2, true, 0)
+        .unwrap()
+        .is_empty());
+    assert_db_error(
+        db.get_account_transactions(
+            AccountAddress::random(),
+            u64::max_value(),
+            Order::Descending,
+            3,
+            true,
+            0,
+        ),
+        |e| matches!(e, AptosDbError::TooManyRequested(3, 2)),
+    );
+}
+
+#[test]
+fn test_zero_max_request_limit() {
+    let tmp_dir = TempPath::new();
+    assert!(AptosDB::open(
+        &tmp_dir,
+        false, /* readonly */
+        NO_OP_STORAGE_PRUNER_CONFIG,
+        RocksdbConfig {
+            max_request_limit: Some(0),
+            ..Default::default()
+        },
+    )
+    .is_err());
+}
+
+#[test]
+fn test_zero_max_epochs_per_state_proof() {
+    let tmp_dir = TempPath::new();
+    assert!(AptosDB::open(
+        &tmp_dir,
+        false, /* readonly */
+        NO_OP_STORAGE_PRUNER_CONFIG,
+        RocksdbConfig {
+            max_epochs_per_state_proof: Some(0),
+            ..Default::default()
+        },
+    )
+    .is_err());
+}
+
+#[test]
+fn test_api_latency_sampling_rate() {
+    const NUM_CALLS: u64 = 100;
+    let num_observed = |api_name| {
+        DIEM_STORAGE_API_LATENCY_SECONDS
+            .with_label_values(&[api_name, "Ok"])
+            .get_sample_count()
+    };
+
+    set_api_latency_sampling_rate(1);
+    for _ in 0..NUM_CALLS {
+        gauged_api("test_sampling_rate_1", || Ok(())).unwrap();
+    }
+    assert_eq!(num_observed("test_sampling_rate_1"), NUM_CALLS);
+
+    set_api_latency_sampling_rate(10);
+    for _ in 0..NUM_CALLS {
+        gauged_api("test_sampling_rate_10", || Ok(())).unwrap();
+    }
+    set_api_latency_sampling_rate(1);
+    assert_eq!(num_observed("test_sampling_rate_10"), NUM_CALLS / 10);
+
+    // Errors are counted regardless of sampling.
+    set_api_latency_sampling_rate(NUM_CALLS * 2);
+    for _ in 0..NUM_CALLS {
+        gauged_api::<(), _>("test_sampling_rate_errors", || Err(format_err!("error"))).unwrap_err();
+    }
+    set_api_latency_sampling_rate(1);
+    assert_eq!(
+        DIEM_STORAGE_API_ERRORS
+            .with_label_values(&["test_sampling_rate_errors"])
+            .get(),
+        NUM_CALLS
+    );
+}
+
+#[test]
+fn test_write_stall_properties() {
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+
+    assert!(!db.is_write_stalled().unwrap());
+    assert_eq!(db.current_delayed_write_rate().unwrap(), 0);
+}
+
+#[test]
+fn test_open_with_cf_compaction_styles() {
+    let tmp_dir = TempPath::new();
+    let mut rocksdb_config = RocksdbConfig::default();
+    rocksdb_config
+        .cf_compaction_styles
+        .insert(TRANSACTION_CF_NAME.to_string(), CompactionStyle::Fifo);
+    let db = AptosDB::open(&tmp_dir, false, NO_OP_STORAGE_PRUNER_CONFIG, rocksdb_config).unwrap();
+
+    let txn = Transaction::StateCheckpoint;
+    let mut cs = ChangeSet::new();
+    db.transaction_store
+        .put_transaction(0, &txn, &mut cs)
+        .unwrap();
+    db.db.write_schemas(cs.batch).unwrap();
+    assert_eq!(db.transaction_store.get_transaction(0).unwrap(), txn);
+
+    // Unknown column families are rejected.
+    let tmp_dir = TempPath::new();
+    let mut rocksdb_config = RocksdbConfig::default();
+    rocksdb_config
+        .cf_compaction_styles
+        .insert("no_such_cf".to_string(), CompactionStyle::Universal);
+    assert!(AptosDB::open(&tmp_dir, false, NO_OP_STORAGE_PRUNER_CONFIG, rocksdb_config).is_err());
+}
+
+#[test]
+fn test_open_with_cf_tuning() {
+    let tmp_dir = TempPath::new();
+    let mut rocksdb_config = RocksdbConfig::default();
+    rocksdb_config.cf_tuning.insert(
+        JELLYFISH_MERKLE_NODE_CF_NAME.to_string(),
+        ColumnFamilyTuning {
+            block_cache_size: Some(1 << 20),
+            bloom_filter_bits_per_key: Some(10),
+            compression: None,
+        },
+    );
+    rocksdb_config.cf_tuning.insert(
+        TRANSACTION_CF_NAME.to_string(),
+        ColumnFamilyTuning {
+            compression: Some(CompressionType::Zstd),
+            ..Default::default()
+        },
+    );
+    let db = AptosDB::open(&tmp_dir, false, NO_OP_STORAGE_PRUNER_CONFIG, rocksdb_config).unwrap();
+
+    let txn = Transaction::StateCheckpoint;
+    let mut cs = ChangeSet::new();
+    db.transaction_store
+        .put_transaction(0, &txn, &mut cs)
+        .unwrap();
+    db.db.write_schemas(cs.batch).unwrap();
+    assert_eq!(db.transaction_store.get_transaction(0).unwrap(), txn);
+
+    // Unknown column families are rejected.
+    let tmp_dir = TempPath::new();
+    let mut rocksdb_config = RocksdbConfig::default();
+    rocksdb_config
+        .cf_tuning
+        .insert("no_such_cf".to_string(), ColumnFamilyTuning::default());
+    assert!(AptosDB::open(&tmp_dir, false, NO_OP_STORAGE_PRUNER_CONFIG, rocksdb_config).is_err());
+}
+
+#[test]
+fn test_open_with_shared_block_cache() {
+    let tmp_dir = TempPath::new();
+    let mut rocksdb_config = RocksdbConfig::default();
+    rocksdb_config.shared_block_cache_size = Some(4 << 20);
+    rocksdb_config.cf_tuning.insert(
+        JELLYFISH_MERKLE_NODE_CF_NAME.to_string(),
+        ColumnFamilyTuning {
+            block_cache_size: Some(1 << 20),
+            ..Default::default()
+        },
+    );
+    let db = AptosDB::open(&tmp_dir, false, NO_OP_STORAGE_PRUNER_CONFIG, rocksdb_config).unwrap();
+
+    for cf_name in [TRANSACTION_CF_NAME, EVENT_CF_NAME] {
+        assert_eq!(
+            db.db
+                .get_property(cf_name, "rocksdb.block-cache-capacity")
+                .unwrap(),
+            4 << 20
+        );
+    }
+    assert_eq!(
+        db.db
+            .get_property(
+                JELLYFISH_MERKLE_NODE_CF_NAME,
+                "rocksdb.block-cache-capacity"
+            )
+            .unwrap(),
+        1 << 20
+    );
+    db.update_rocksdb_properties().unwrap();
+}
+
 #[test]
 fn test_get_latest_tree_state() {
     let tmp_dir = TempPath::new();
@@ -658,6 +3134,185 @@ fn test_get_latest_tree_state() {
         bootstrapped,
         TreeState::new(1, vec![txn_info.hash()], txn_info.state_change_hash())
     );
+    assert_eq!(db.get_tree_state(0).unwrap(), bootstrapped);
+
+    // historical tree states stay available as more transactions come in
+    let txn_info1 = TransactionInfo::new(
+        HashValue::random(),
+        HashValue::random(),
+        HashValue::random(),
+        0,
+        KeptVMStatus::MiscellaneousError,
+    );
+    put_transaction_info(&db, 1, &txn_info1);
+    assert_eq!(
+        db.get_tree_state(1).unwrap(),
+        db.get_latest_tree_state().unwrap()
+    );
+    assert_eq!(db.get_tree_state(0).unwrap(), bootstrapped);
+    assert!(db.get_tree_state(2).is_err());
+}
+
+#[test]
+fn test_get_transaction_block_membership_proof() {
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+
+    // Blocks: [0, 1] (genesis), [2, 3, 4], [5, 6]
+    let block_metadata = |round| {
+        Transaction::BlockMetadata(BlockMetadata::new(
+            HashValue::random(),
+            round,
+            round, /* timestamp_usecs */
+            vec![],
+            AccountAddress::ZERO,
+        ))
+    };
+    let txns = vec![
+        Transaction::StateCheckpoint,
+        Transaction::StateCheckpoint,
+        block_metadata(1),
+        Transaction::StateCheckpoint,
+        Transaction::StateCheckpoint,
+        block_metadata(2),
+        Transaction::StateCheckpoint,
+    ];
+    let txn_infos: Vec<_> = txns
+        .iter()
+        .map(|_| {
+            TransactionInfo::new(
+                HashValue::random(),
+                HashValue::random(),
+                HashValue::random(),
+                0,
+                KeptVMStatus::Executed,
+            )
+        })
+        .collect();
+
+    let mut cs = ChangeSet::new();
+    for (version, txn) in txns.iter().enumerate() {
+        db.transaction_store
+            .put_transaction(version as Version, txn, &mut cs)
+            .unwrap();
+    }
+    let root_hash = db
+        .ledger_store
+        .put_transaction_infos(0, &txn_infos, &mut cs)
+        .unwrap();
+    db.db.write_schemas(cs.batch).unwrap();
+    let ledger_version = txns.len() as Version - 1;
+    db.ledger_store
+        .set_latest_ledger_info(LedgerInfoWithSignatures::new(
+            LedgerInfo::new(
+                BlockInfo::new(0, 0, HashValue::zero(), root_hash, ledger_version, 0, None),
+                HashValue::zero(),
+            ),
+            BTreeMap::new(),
+        ));
+
+    for &(start, len) in &[(0, 2), (2, 3), (5, 2)] {
+        let leaf_hashes: Vec<_> = txn_infos[start..start + len]
+            .iter()
+            .map(CryptoHash::hash)
+            .collect();
+        for version in start..start + len {
+            let (block_start_version, proof) = db
+                .get_transaction_block_membership_proof(version as Version)
+                .unwrap();
+            assert_eq!(block_start_version, start as Version);
+            proof
+                .verify(root_hash, Some(block_start_version), &leaf_hashes)
+                .unwrap();
+        }
+    }
+    assert!(db
+        .get_transaction_block_membership_proof(ledger_version + 1)
+        .is_err());
+}
+
+#[test]
+fn test_get_block_metadata() {
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+
+    let proposer = AccountAddress::random();
+    let block_metadata = BlockMetadata::new(HashValue::random(), 1, 1, vec![], proposer);
+    let txns = vec![
+        Transaction::StateCheckpoint,
+        Transaction::BlockMetadata(block_metadata.clone()),
+        Transaction::StateCheckpoint,
+    ];
+    let mut cs = ChangeSet::new();
+    for (version, txn) in txns.iter().enumerate() {
+        db.transaction_store
+            .put_transaction(version as Version, txn, &mut cs)
+            .unwrap();
+    }
+    db.db.write_schemas(cs.batch).unwrap();
+
+    // Genesis has no block metadata.
+    assert_eq!(db.get_block_metadata(0).unwrap(), None);
+    for version in 1..=2 {
+        let (block_version, actual) = db.get_block_metadata(version).unwrap().unwrap();
+        assert_eq!(block_version, 1);
+        assert_eq!(actual.proposer(), proposer);
+        assert_eq!(actual, block_metadata);
+    }
+}
+
+#[test]
+fn test_get_parallel_execution_config() {
+    use aptos_types::{
+        account_state::AccountState,
+        on_chain_config::{
+            access_path_for_config, config_address, OnChainConfig, ParallelExecutionConfig,
+            ReadWriteSetAnalysis,
+        },
+    };
+
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+
+    let config_path = access_path_for_config(ParallelExecutionConfig::CONFIG_ID).path;
+    let disabled_config = bcs::to_bytes(&Option::<Vec<u8>>::None).unwrap();
+    let enabled_config = bcs::to_bytes(&Some(
+        bcs::to_bytes(&ReadWriteSetAnalysis::V1(BTreeMap::new())).unwrap(),
+    ))
+    .unwrap();
+
+    // Version 0 has no config published, version 1 has parallel execution disabled and version 2
+    // has it enabled.
+    let account_state_sets = vec![None, Some(disabled_config), Some(enabled_config)]
+        .into_iter()
+        .map(|config| {
+            let mut account_state = AccountState::default();
+            if let Some(config) = config {
+                account_state.insert(config_path.clone(), config);
+            }
+            let blob = AccountStateBlob::try_from(&account_state).unwrap();
+            vec![(config_address(), blob)].into_iter().collect()
+        })
+        .collect();
+    let mut cs = ChangeSet::new();
+    db.state_store
+        .put_account_state_sets(account_state_sets, None, 0, &mut cs)
+        .unwrap();
+    db.db.write_schemas(cs.batch).unwrap();
+
+    assert_eq!(db.get_parallel_execution_config(0).unwrap(), None);
+    assert!(db
+        .get_parallel_execution_config(1)
+        .unwrap()
+        .unwrap()
+        .read_write_analysis_result
+        .is_none());
+    assert!(db
+        .get_parallel_execution_config(2)
+        .unwrap()
+        .unwrap()
+        .read_write_analysis_result
+        .is_some());
 }
 
 fn put_transaction_info(db: &AptosDB, version: Version, txn_info: &TransactionInfo) {
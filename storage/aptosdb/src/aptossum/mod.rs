@@ -1,7 +1,7 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{AptosDB, Order, MAX_LIMIT};
+use crate::{AptosDB, Order};
 use anyhow::{ensure, format_err, Result};
 use aptos_config::config::{RocksdbConfig, NO_OP_STORAGE_PRUNER_CONFIG};
 use aptos_types::{
@@ -92,9 +92,11 @@ impl Aptossum {
             from_seq,
             to_seq
         );
+        // Page by the limit the DB enforces on each request.
+        let limit = self.db.max_request_limit;
         Ok((from_seq..to_seq)
-            .step_by(MAX_LIMIT as usize)
-            .map(|seq| self.db.get_events(key, seq, Order::Ascending, MAX_LIMIT))
+            .step_by(limit as usize)
+            .map(|seq| self.db.get_events(key, seq, Order::Ascending, limit))
             .collect::<Result<Vec<_>>>()?
             .into_iter()
             .flatten()
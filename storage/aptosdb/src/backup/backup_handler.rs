@@ -2,14 +2,25 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    backup::{
+        manifest::{
+            StateSnapshotChunkManifest, StateSnapshotManifest, TransactionBackupManifest,
+            TransactionChunkManifest,
+        },
+        progress::{ProgressCallback, ProgressReporter},
+        storage::{read_records, save_bcs, save_records, write_record, BackupStorage},
+    },
     event_store::EventStore,
+    ledger_counters::LedgerCounters,
     ledger_store::LedgerStore,
     metrics::{
         BACKUP_EPOCH_ENDING_EPOCH, BACKUP_STATE_SNAPSHOT_LEAF_IDX, BACKUP_STATE_SNAPSHOT_VERSION,
         BACKUP_TXN_VERSION,
     },
     state_store::StateStore,
+    system_store::SystemStore,
     transaction_store::TransactionStore,
+    AptosDbError,
 };
 use anyhow::{anyhow, ensure, Result};
 use aptos_crypto::hash::HashValue;
@@ -23,7 +34,11 @@ use aptos_types::{
 };
 use itertools::zip_eq;
 use serde::{Deserialize, Serialize};
-use std::{fmt, sync::Arc};
+use std::{
+    fmt,
+    io::{Read, Write},
+    sync::Arc,
+};
 
 /// `BackupHandler` provides functionalities for AptosDB data backup.
 #[derive(Clone)]
@@ -32,6 +47,8 @@ pub struct BackupHandler {
     transaction_store: Arc<TransactionStore>,
     state_store: Arc<StateStore>,
     event_store: Arc<EventStore>,
+    system_store: Arc<SystemStore>,
+    storage: Option<Arc<dyn BackupStorage>>,
 }
 
 impl BackupHandler {
@@ -40,15 +57,30 @@ impl BackupHandler {
         transaction_store: Arc<TransactionStore>,
         state_store: Arc<StateStore>,
         event_store: Arc<EventStore>,
+        system_store: Arc<SystemStore>,
     ) -> Self {
         Self {
             ledger_store,
             transaction_store,
             state_store,
             event_store,
+            system_store,
+            storage: None,
         }
     }
 
+    /// Sets the storage `backup_state_snapshot` and `backup_transactions` write to.
+    pub fn with_storage(mut self, storage: Arc<dyn BackupStorage>) -> Self {
+        self.storage = Some(storage);
+        self
+    }
+
+    fn storage(&self) -> Result<&dyn BackupStorage> {
+        self.storage
+            .as_deref()
+            .ok_or_else(|| anyhow!("No backup storage configured."))
+    }
+
     /// Gets an iterator that yields a range of transactions.
     pub fn get_transaction_iter(
         &self,
@@ -104,13 +136,41 @@ impl BackupHandler {
         &self,
         version: Version,
     ) -> Result<Box<dyn Iterator<Item = Result<(HashValue, AccountStateBlob)>> + Send + Sync>> {
-        let iterator = JellyfishMerkleIterator::new(
+        self.account_iter(version, None)
+    }
+
+    /// Gets an iterator which yields the accounts changed after `base_version` with their states
+    /// at `version` in key order, along with a few unchanged ones moved in the tree, see
+    /// `JellyfishMerkleIterator::changed_since`. Only the parts of the tree changed since
+    /// `base_version` are read.
+    pub fn get_changed_account_iter(
+        &self,
+        base_version: Version,
+        version: Version,
+    ) -> Result<Box<dyn Iterator<Item = Result<(HashValue, AccountStateBlob)>> + Send + Sync>> {
+        ensure!(
+            base_version < version,
+            "Base version {} is not older than version {}.",
+            base_version,
+            version,
+        );
+        self.account_iter(version, Some(base_version))
+    }
+
+    fn account_iter(
+        &self,
+        version: Version,
+        base_version: Option<Version>,
+    ) -> Result<Box<dyn Iterator<Item = Result<(HashValue, AccountStateBlob)>> + Send + Sync>> {
+        let mut iterator = JellyfishMerkleIterator::new(
             Arc::clone(&self.state_store),
             version,
             HashValue::zero(),
-        )?
-        .enumerate()
-        .map(move |(idx, res)| {
+        )?;
+        if let Some(base_version) = base_version {
+            iterator = iterator.changed_since(base_version);
+        }
+        let iterator = iterator.enumerate().map(move |(idx, res)| {
             BACKUP_STATE_SNAPSHOT_VERSION.set(version as i64);
             BACKUP_STATE_SNAPSHOT_LEAF_IDX.set(idx as i64);
             res
@@ -118,6 +178,233 @@ impl BackupHandler {
         Ok(Box::new(iterator))
     }
 
+    /// Writes all accounts in the state tree at `version` to `writer` in key order, each as a
+    /// BCS encoded `(HashValue, AccountStateBlob)` pair prefixed by its length as a little endian
+    /// `u32`. Returns the number of accounts written. See [`read_exported_state`] for the reverse.
+    pub fn export_state<W: Write>(&self, version: Version, mut writer: W) -> Result<usize> {
+        let mut num_accounts = 0;
+        for res in self.get_account_iter(version)? {
+            write_record(&mut writer, &res?)?;
+            num_accounts += 1;
+        }
+        writer.flush()?;
+        Ok(num_accounts)
+    }
+
+    /// Backs up the state snapshot at `version` to the storage, in chunks of up to `chunk_size`
+    /// accounts, each in the file `{name}.chunk_{i}` in the format of `export_state` along with
+    /// the proof of its range in `{name}.chunk_{i}.proof`, finished by the manifest
+    /// `{name}.manifest`, which is returned. The progress is reported to `progress`, if any,
+    /// after each chunk.
+    pub fn backup_state_snapshot(
+        &self,
+        version: Version,
+        chunk_size: usize,
+        name: &str,
+        progress: Option<ProgressCallback>,
+    ) -> Result<StateSnapshotManifest> {
+        let progress = ProgressReporter::new(
+            progress,
+            self.state_store.get_account_count(version)? as u64,
+        );
+        self.backup_accounts(
+            self.get_account_iter(version)?,
+            version,
+            None, /* base_version */
+            chunk_size,
+            name,
+            progress,
+        )
+    }
+
+    /// Like `backup_state_snapshot`, but backs up an incremental state snapshot holding only the
+    /// accounts changed after `base_version`, see `get_changed_account_iter`, to be restored via
+    /// `RestoreHandler::restore_state_snapshot_diff` on top of a state snapshot, full or
+    /// incremental, at `base_version`, which the manifest records. The chunks come without range
+    /// proofs, the restore checks the root hash after applying all of them instead. If
+    /// `progress` is set, the changed accounts are walked once more beforehand to count them.
+    pub fn backup_state_snapshot_diff(
+        &self,
+        base_version: Version,
+        version: Version,
+        chunk_size: usize,
+        name: &str,
+        progress: Option<ProgressCallback>,
+    ) -> Result<StateSnapshotManifest> {
+        let num_accounts = if progress.is_some() {
+            self.get_changed_account_iter(base_version, version)?
+                .try_fold(0u64, |num_accounts, res| res.map(|_| num_accounts + 1))?
+        } else {
+            0
+        };
+        self.backup_accounts(
+            self.get_changed_account_iter(base_version, version)?,
+            version,
+            Some(base_version),
+            chunk_size,
+            name,
+            ProgressReporter::new(progress, num_accounts),
+        )
+    }
+
+    /// Writes the accounts yielded by `account_iter` in key order as a state snapshot at
+    /// `version`, incremental on top of `base_version` if set, see `backup_state_snapshot`.
+    fn backup_accounts(
+        &self,
+        mut account_iter: impl Iterator<Item = Result<(HashValue, AccountStateBlob)>>,
+        version: Version,
+        base_version: Option<Version>,
+        chunk_size: usize,
+        name: &str,
+        mut progress: ProgressReporter,
+    ) -> Result<StateSnapshotManifest> {
+        ensure!(chunk_size > 0, "Chunk size must be positive.");
+        let storage = self.storage()?;
+        let root_hash = self.state_store.get_root_hash(version)?;
+
+        let mut chunks = Vec::new();
+        loop {
+            let accounts = account_iter
+                .by_ref()
+                .take(chunk_size)
+                .collect::<Result<Vec<_>>>()?;
+            let last_key = match accounts.last() {
+                Some((key, _blob)) => *key,
+                None => break,
+            };
+            let first_idx = chunks
+                .last()
+                .map_or(0, |c: &StateSnapshotChunkManifest| c.last_idx + 1);
+
+            let accounts_name = format!("{}.chunk_{}", name, chunks.len());
+            let (accounts_checksum, num_bytes) = save_records(storage, &accounts_name, &accounts)?;
+            // The accounts of an incremental snapshot don't make up a range to prove.
+            let proof = match base_version {
+                Some(_) => None,
+                None => {
+                    let proof_name = format!("{}.proof", accounts_name);
+                    save_bcs(
+                        storage,
+                        &proof_name,
+                        &self.get_account_state_range_proof(last_key, version)?,
+                    )?;
+                    Some(proof_name)
+                }
+            };
+
+            chunks.push(StateSnapshotChunkManifest {
+                first_idx,
+                last_idx: first_idx + accounts.len() - 1,
+                last_key,
+                accounts: accounts_name,
+                accounts_checksum,
+                proof,
+            });
+            progress.add_chunk(num_bytes, accounts.len() as u64, version);
+        }
+
+        let manifest = StateSnapshotManifest {
+            version,
+            base_version,
+            root_hash,
+            chunks,
+        };
+        save_bcs(storage, &format!("{}.manifest", name), &manifest)?;
+        Ok(manifest)
+    }
+
+    /// Backs up `num_transactions` transactions from `first_version` to the storage, in chunks
+    /// of up to `chunk_size` transactions, each in the file `{name}.chunk_{i}` along with the
+    /// proof of its range in `{name}.chunk_{i}.proof`, finished by the manifest
+    /// `{name}.manifest`, which is returned. The progress is reported to `progress`, if any,
+    /// after each chunk.
+    pub fn backup_transactions(
+        &self,
+        first_version: Version,
+        num_transactions: usize,
+        chunk_size: usize,
+        name: &str,
+        progress: Option<ProgressCallback>,
+    ) -> Result<TransactionBackupManifest> {
+        ensure!(chunk_size > 0, "Chunk size must be positive.");
+        ensure!(num_transactions > 0, "No transactions to back up.");
+        let storage = self.storage()?;
+        let mut progress = ProgressReporter::new(progress, num_transactions as u64);
+
+        let mut chunks = Vec::new();
+        let mut txn_iter = self.get_transaction_iter(first_version, num_transactions)?;
+        loop {
+            let txns = txn_iter
+                .by_ref()
+                .take(chunk_size)
+                .collect::<Result<Vec<_>>>()?;
+            if txns.is_empty() {
+                break;
+            }
+            let chunk_first_version = chunks
+                .last()
+                .map_or(first_version, |c: &TransactionChunkManifest| {
+                    c.last_version + 1
+                });
+            let chunk_last_version = chunk_first_version + txns.len() as Version - 1;
+
+            let transactions_name = format!("{}.chunk_{}", name, chunks.len());
+            let (transactions_checksum, num_bytes) =
+                save_records(storage, &transactions_name, &txns)?;
+            let proof_name = format!("{}.proof", transactions_name);
+            save_bcs(
+                storage,
+                &proof_name,
+                &self.get_transaction_range_proof(chunk_first_version, chunk_last_version)?,
+            )?;
+
+            chunks.push(TransactionChunkManifest {
+                first_version: chunk_first_version,
+                last_version: chunk_last_version,
+                transactions: transactions_name,
+                transactions_checksum,
+                proof: proof_name,
+            });
+            progress.add_chunk(num_bytes, txns.len() as u64, chunk_last_version);
+        }
+
+        let manifest = TransactionBackupManifest {
+            first_version,
+            last_version: first_version + num_transactions as Version - 1,
+            chunks,
+        };
+        save_bcs(storage, &format!("{}.manifest", name), &manifest)?;
+        Ok(manifest)
+    }
+
+    /// Like `backup_transactions`, but backs up all the transactions committed after
+    /// `base_version`, as an increment on top of a backup up to `base_version`, see also
+    /// `backup_state_snapshot_diff`. The manifest records `base_version + 1` as its first version.
+    pub fn backup_transactions_since(
+        &self,
+        base_version: Version,
+        chunk_size: usize,
+        name: &str,
+        progress: Option<ProgressCallback>,
+    ) -> Result<TransactionBackupManifest> {
+        let first_version = base_version
+            .checked_add(1)
+            .ok_or_else(|| anyhow!("No transactions after version {}.", base_version))?;
+        let num_transactions = match self.ledger_store.get_latest_transaction_info_option()? {
+            Some((latest_version, _txn_info)) if latest_version >= first_version => {
+                latest_version - first_version + 1
+            }
+            _ => 0,
+        };
+        self.backup_transactions(
+            first_version,
+            num_transactions as usize,
+            chunk_size,
+            name,
+            progress,
+        )
+    }
+
     /// Gets the proof that proves a range of accounts.
     pub fn get_account_state_range_proof(
         &self,
@@ -128,6 +415,16 @@ impl BackupHandler {
             .get_account_state_range_proof(rightmost_key, version)
     }
 
+    /// Gets the cumulative ledger counters as of `version`, to be imported along with a state
+    /// snapshot at the same version via `RestoreHandler::confirm_or_save_ledger_counters`.
+    pub fn export_ledger_counters(&self, version: Version) -> Result<LedgerCounters> {
+        self.system_store
+            .get_ledger_counters(version)?
+            .ok_or_else(|| {
+                AptosDbError::NotFound(format!("Ledger counters at version {}", version)).into()
+            })
+    }
+
     /// Gets the epoch, commited version, and synced version of the DB.
     pub fn get_db_state(&self) -> Result<Option<DbState>> {
         self.ledger_store
@@ -183,6 +480,14 @@ impl BackupHandler {
     }
 }
 
+/// Decodes the stream written by [`BackupHandler::export_state`], yielding the accounts in key
+/// order, ready to be fed into a state snapshot receiver.
+pub fn read_exported_state<R: Read>(
+    reader: R,
+) -> impl Iterator<Item = Result<(HashValue, AccountStateBlob)>> {
+    read_records(reader)
+}
+
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct DbState {
     pub epoch: u64,
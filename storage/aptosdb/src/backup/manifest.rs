@@ -0,0 +1,100 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines the manifests of backups written to a
+//! [`BackupStorage`](crate::backup::storage::BackupStorage), listing the files the backup consists
+//! of.
+
+use anyhow::{anyhow, ensure, Result};
+use aptos_crypto::HashValue;
+use aptos_types::transaction::Version;
+use serde::{Deserialize, Serialize};
+
+/// A chunk of a state snapshot backup, holding the accounts with indices in
+/// [`first_idx`, `last_idx`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct StateSnapshotChunkManifest {
+    pub first_idx: usize,
+    pub last_idx: usize,
+    /// Key of the last account in the chunk.
+    pub last_key: HashValue,
+    /// Name of the file holding the `(HashValue, AccountStateBlob)` records of the chunk.
+    pub accounts: String,
+    /// SHA3-256 of the content of `accounts`.
+    pub accounts_checksum: HashValue,
+    /// Name of the file holding the BCS serialized `SparseMerkleRangeProof` proving the chunk
+    /// against `StateSnapshotManifest::root_hash`. `None` in an incremental snapshot, whose
+    /// accounts aren't contiguous.
+    pub proof: Option<String>,
+}
+
+impl StateSnapshotChunkManifest {
+    /// Returns the number of accounts in the chunk. Errors if the indices don't make up a valid
+    /// range, the manifest being read from a possibly corrupted backup.
+    pub fn num_accounts(&self) -> Result<usize> {
+        ensure!(
+            self.first_idx <= self.last_idx,
+            "Chunk {} has first index {} beyond last index {}.",
+            self.accounts,
+            self.first_idx,
+            self.last_idx,
+        );
+        (self.last_idx - self.first_idx)
+            .checked_add(1)
+            .ok_or_else(|| anyhow!("Chunk {} holds too many accounts.", self.accounts))
+    }
+}
+
+/// The manifest of a state snapshot backup, see `BackupHandler::backup_state_snapshot` and
+/// `BackupHandler::backup_state_snapshot_diff`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct StateSnapshotManifest {
+    pub version: Version,
+    /// The version of the snapshot an incremental snapshot is to be restored on top of, `None`
+    /// for a full snapshot.
+    pub base_version: Option<Version>,
+    /// Root hash of the state tree at `version`.
+    pub root_hash: HashValue,
+    pub chunks: Vec<StateSnapshotChunkManifest>,
+}
+
+/// A chunk of a transaction backup, holding the transactions with versions in
+/// [`first_version`, `last_version`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TransactionChunkManifest {
+    pub first_version: Version,
+    pub last_version: Version,
+    /// Name of the file holding the `(Transaction, TransactionInfo, Vec<ContractEvent>)` records
+    /// of the chunk.
+    pub transactions: String,
+    /// SHA3-256 of the content of `transactions`.
+    pub transactions_checksum: HashValue,
+    /// Name of the file holding the BCS serialized
+    /// `(TransactionAccumulatorRangeProof, LedgerInfoWithSignatures)` proving the chunk.
+    pub proof: String,
+}
+
+impl TransactionChunkManifest {
+    /// Returns the number of transactions in the chunk. Errors if the versions don't make up a
+    /// valid range, the manifest being read from a possibly corrupted backup.
+    pub fn num_transactions(&self) -> Result<u64> {
+        ensure!(
+            self.first_version <= self.last_version,
+            "Chunk {} has first version {} beyond last version {}.",
+            self.transactions,
+            self.first_version,
+            self.last_version,
+        );
+        (self.last_version - self.first_version)
+            .checked_add(1)
+            .ok_or_else(|| anyhow!("Chunk {} holds too many transactions.", self.transactions))
+    }
+}
+
+/// The manifest of a transaction backup, see `BackupHandler::backup_transactions`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TransactionBackupManifest {
+    pub first_version: Version,
+    pub last_version: Version,
+    pub chunks: Vec<TransactionChunkManifest>,
+}
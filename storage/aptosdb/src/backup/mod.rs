@@ -2,7 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod backup_handler;
+pub mod manifest;
+pub mod progress;
 pub mod restore_handler;
+pub mod storage;
 
 #[cfg(test)]
 mod test;
@@ -0,0 +1,60 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines [`BackupProgress`], reported after each chunk by the backup and restore
+//! operations of `BackupHandler` and `RestoreHandler` to an optional [`ProgressCallback`], so that
+//! tools can render it, e.g. as a progress bar with an ETA.
+
+use aptos_types::transaction::Version;
+use std::sync::Arc;
+
+/// The progress of a backup or restore.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct BackupProgress {
+    /// Bytes of chunks written to the backup storage by a backup, or read from it by a restore,
+    /// so far.
+    pub bytes: u64,
+    /// Number of transactions, or accounts of a state snapshot, processed so far.
+    pub items_processed: u64,
+    /// Total number of transactions, or accounts of a state snapshot, to process.
+    pub total_items: u64,
+    /// The last version processed, or the version of the state snapshot.
+    pub current_version: Version,
+}
+
+/// Receives the [`BackupProgress`] of an operation after each chunk.
+pub type ProgressCallback = Arc<dyn Fn(BackupProgress) + Send + Sync>;
+
+/// Accumulates the progress of an operation and reports it to the callback, if any.
+pub(crate) struct ProgressReporter {
+    callback: Option<ProgressCallback>,
+    progress: BackupProgress,
+}
+
+impl ProgressReporter {
+    pub fn new(callback: Option<ProgressCallback>, total_items: u64) -> Self {
+        Self {
+            callback,
+            progress: BackupProgress {
+                total_items,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Records `num_items` items processed before, e.g. by an interrupted restoration, without
+    /// reporting them.
+    pub fn skip(&mut self, num_items: u64) {
+        self.progress.items_processed += num_items;
+    }
+
+    /// Records a chunk of `num_items` items and `bytes` bytes, up to `current_version`.
+    pub fn add_chunk(&mut self, bytes: u64, num_items: u64, current_version: Version) {
+        self.progress.bytes += bytes;
+        self.progress.items_processed += num_items;
+        self.progress.current_version = current_version;
+        if let Some(callback) = &self.callback {
+            callback(self.progress);
+        }
+    }
+}
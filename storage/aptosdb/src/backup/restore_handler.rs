@@ -2,23 +2,55 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    change_set::ChangeSet, event_store::EventStore, ledger_store::LedgerStore,
-    schema::transaction_accumulator::TransactionAccumulatorSchema, state_store::StateStore,
-    transaction_store::TransactionStore, AptosDB,
+    backup::{
+        manifest::{
+            StateSnapshotChunkManifest, StateSnapshotManifest, TransactionBackupManifest,
+            TransactionChunkManifest,
+        },
+        progress::{ProgressCallback, ProgressReporter},
+        storage::{load_bcs, load_records, BackupStorage},
+    },
+    change_set::ChangeSet,
+    event_store::EventStore,
+    ledger_counters::LedgerCounters,
+    ledger_store::LedgerStore,
+    schema::transaction_accumulator::TransactionAccumulatorSchema,
+    state_store::StateStore,
+    system_store::SystemStore,
+    transaction_store::TransactionStore,
+    AptosDB, AptosDbError,
 };
-use anyhow::{ensure, Result};
-use aptos_crypto::{hash::SPARSE_MERKLE_PLACEHOLDER_HASH, HashValue};
+use anyhow::{anyhow, bail, ensure, Result};
+use aptos_crypto::{
+    hash::{CryptoHash, EventAccumulatorHasher, SPARSE_MERKLE_PLACEHOLDER_HASH},
+    HashValue,
+};
+use aptos_infallible::Mutex;
 use aptos_jellyfish_merkle::restore::JellyfishMerkleRestore;
 use aptos_types::{
     account_state_blob::AccountStateBlob,
     contract_event::ContractEvent,
     ledger_info::LedgerInfoWithSignatures,
-    proof::{definition::LeafCount, position::FrozenSubTreeIterator},
+    proof::{
+        accumulator::InMemoryAccumulator, definition::LeafCount, position::FrozenSubTreeIterator,
+        SparseMerkleRangeProof, TransactionAccumulatorRangeProof,
+    },
     transaction::{Transaction, TransactionInfo, Version, PRE_GENESIS_VERSION},
 };
 use schemadb::DB;
-use std::sync::Arc;
-use storage_interface::{DbReader, TreeState};
+use std::{
+    collections::BTreeMap,
+    panic::{self, AssertUnwindSafe},
+    sync::{mpsc, Arc},
+    thread,
+};
+use storage_interface::{DbReader, StateSnapshotReceiver, TreeState};
+
+type TransactionChunk = (
+    Vec<Transaction>,
+    Vec<TransactionInfo>,
+    Vec<Vec<ContractEvent>>,
+);
 
 /// Provides functionalities for AptosDB data restore.
 #[derive(Clone)]
@@ -29,6 +61,8 @@ pub struct RestoreHandler {
     transaction_store: Arc<TransactionStore>,
     state_store: Arc<StateStore>,
     event_store: Arc<EventStore>,
+    system_store: Arc<SystemStore>,
+    storage: Option<Arc<dyn BackupStorage>>,
 }
 
 impl RestoreHandler {
@@ -39,6 +73,7 @@ impl RestoreHandler {
         transaction_store: Arc<TransactionStore>,
         state_store: Arc<StateStore>,
         event_store: Arc<EventStore>,
+        system_store: Arc<SystemStore>,
     ) -> Self {
         Self {
             db,
@@ -47,9 +82,23 @@ impl RestoreHandler {
             transaction_store,
             state_store,
             event_store,
+            system_store,
+            storage: None,
         }
     }
 
+    /// Sets the storage `restore_state_snapshot` and `restore_transactions` read from.
+    pub fn with_storage(mut self, storage: Arc<dyn BackupStorage>) -> Self {
+        self.storage = Some(storage);
+        self
+    }
+
+    fn storage(&self) -> Result<&dyn BackupStorage> {
+        self.storage
+            .as_deref()
+            .ok_or_else(|| anyhow!("No backup storage configured."))
+    }
+
     pub fn get_state_restore_receiver(
         &self,
         version: Version,
@@ -62,6 +111,330 @@ impl RestoreHandler {
         )
     }
 
+    /// Restores the state snapshot backed up by `BackupHandler::backup_state_snapshot` as `name`
+    /// from the storage. Each chunk is checked against its checksum in the manifest and its proof
+    /// against the root hash in the manifest before being written, so a corrupted backup fails
+    /// the restore without committing the bad chunk. An interrupted restoration of the snapshot
+    /// is resumed, skipping the chunks restored already. The progress is reported to `progress`,
+    /// if any, after each chunk. Returns the manifest.
+    pub fn restore_state_snapshot(
+        &self,
+        name: &str,
+        progress: Option<ProgressCallback>,
+    ) -> Result<StateSnapshotManifest> {
+        let storage = self.storage()?;
+        let manifest: StateSnapshotManifest = load_bcs(storage, &format!("{}.manifest", name))?;
+        if let Some(base_version) = manifest.base_version {
+            bail!(
+                "{} is an incremental state snapshot on top of version {}.",
+                name,
+                base_version,
+            );
+        }
+        let mut progress = ProgressReporter::new(progress, total_accounts(&manifest.chunks)?);
+
+        let mut receiver = JellyfishMerkleRestore::resume(
+            Arc::clone(&self.state_store),
+            manifest.version,
+            manifest.root_hash,
+        )?;
+        let next_idx = receiver.num_keys_received();
+        progress.skip(next_idx as u64);
+        for chunk in &manifest.chunks {
+            if chunk.last_idx < next_idx {
+                continue;
+            }
+            ensure!(
+                chunk.first_idx >= next_idx,
+                "Restoration stopped at account {}, in the middle of chunk {}.",
+                next_idx,
+                chunk.accounts,
+            );
+            let (accounts, num_bytes) = self.load_state_snapshot_chunk(chunk)?;
+            let proof_name = chunk
+                .proof
+                .as_ref()
+                .ok_or_else(|| anyhow!("Chunk {} has no proof.", chunk.accounts))?;
+            let proof: SparseMerkleRangeProof = load_bcs(storage, proof_name)?;
+            let num_accounts = accounts.len() as u64;
+            receiver.add_chunk(accounts, proof)?;
+            progress.add_chunk(num_bytes, num_accounts, manifest.version);
+        }
+        receiver.finish()?;
+        Ok(manifest)
+    }
+
+    /// Restores the incremental state snapshot backed up by
+    /// `BackupHandler::backup_state_snapshot_diff` as `name` from the storage, on top of the state
+    /// restored at its base version already. Each chunk is checked against its checksum in the
+    /// manifest, but as the chunks of an increment can't be proven on their own, the tree updated
+    /// with all of them is checked against the root hash in the manifest before anything is
+    /// written. The accounts of the increment are thus all held in memory until then. The
+    /// progress is reported to `progress`, if any, after each chunk is loaded. Returns the
+    /// manifest.
+    pub fn restore_state_snapshot_diff(
+        &self,
+        name: &str,
+        progress: Option<ProgressCallback>,
+    ) -> Result<StateSnapshotManifest> {
+        let storage = self.storage()?;
+        let manifest: StateSnapshotManifest = load_bcs(storage, &format!("{}.manifest", name))?;
+        let base_version = manifest
+            .base_version
+            .ok_or_else(|| anyhow!("{} is a full state snapshot.", name))?;
+        ensure!(
+            self.state_store
+                .get_root_hash_option(base_version)?
+                .is_some(),
+            "State at the base version {} of {} is not restored.",
+            base_version,
+            name,
+        );
+        let mut progress = ProgressReporter::new(progress, total_accounts(&manifest.chunks)?);
+
+        let mut accounts = Vec::new();
+        for chunk in &manifest.chunks {
+            let (chunk_accounts, num_bytes) = self.load_state_snapshot_chunk(chunk)?;
+            progress.add_chunk(num_bytes, chunk_accounts.len() as u64, manifest.version);
+            accounts.extend(chunk_accounts);
+        }
+        let mut cs = ChangeSet::new();
+        let root_hash = self.state_store.put_account_states_on_base(
+            accounts,
+            base_version,
+            manifest.version,
+            &mut cs,
+        )?;
+        ensure!(
+            root_hash == manifest.root_hash,
+            AptosDbError::RootHashMismatch(manifest.root_hash, root_hash),
+        );
+        self.db.write_schemas(cs.batch)?;
+        Ok(manifest)
+    }
+
+    /// Loads the accounts of a state snapshot chunk from the storage, checking them against the
+    /// checksum, number of accounts and last key in the manifest. Returns the accounts and their
+    /// size in bytes.
+    fn load_state_snapshot_chunk(
+        &self,
+        chunk: &StateSnapshotChunkManifest,
+    ) -> Result<(Vec<(HashValue, AccountStateBlob)>, u64)> {
+        let (accounts, num_bytes): (Vec<(HashValue, AccountStateBlob)>, _) =
+            load_records(self.storage()?, &chunk.accounts, chunk.accounts_checksum)?;
+        let expected_num_accounts = chunk.num_accounts()?;
+        ensure!(
+            accounts.len() == expected_num_accounts,
+            "Chunk {} holds {} accounts, expected {}.",
+            chunk.accounts,
+            accounts.len(),
+            expected_num_accounts,
+        );
+        ensure!(
+            accounts.last().map(|(key, _blob)| *key) == Some(chunk.last_key),
+            "Last key of chunk {} doesn't match the manifest.",
+            chunk.accounts,
+        );
+        Ok((accounts, num_bytes))
+    }
+
+    /// Restores the transactions backed up by `BackupHandler::backup_transactions` as `name` from
+    /// the storage, verifying each chunk against its checksum in the manifest and against the
+    /// transaction accumulator root hash in the `LedgerInfo` it was backed up with. The
+    /// signatures of the `LedgerInfo`s are not verified, they are to be checked against the epoch
+    /// ending `LedgerInfo`s restored beforehand. The progress is reported to `progress`, if any,
+    /// after each chunk. Returns the manifest.
+    pub fn restore_transactions(
+        &self,
+        name: &str,
+        progress: Option<ProgressCallback>,
+    ) -> Result<TransactionBackupManifest> {
+        let storage = self.storage()?;
+        let manifest: TransactionBackupManifest = load_bcs(storage, &format!("{}.manifest", name))?;
+        let mut progress = ProgressReporter::new(progress, total_transactions(&manifest.chunks)?);
+
+        for chunk in &manifest.chunks {
+            let ((txns, txn_infos, events), num_bytes) = self.load_transaction_chunk(chunk)?;
+            self.save_transactions(chunk.first_version, &txns, &txn_infos, &events)?;
+            progress.add_chunk(num_bytes, txns.len() as u64, chunk.last_version);
+        }
+        Ok(manifest)
+    }
+
+    /// Like `restore_transactions`, but restores the transaction `chunks` of a backup, loading
+    /// and verifying them on `num_workers` threads while committing them in version order on the
+    /// calling thread. At most `2 * num_workers` chunks are loaded ahead of the next one to
+    /// commit, bounding the memory used when the commits fall behind. The progress is reported to
+    /// `progress`, if any, after each chunk is committed.
+    pub fn restore_transactions_parallel(
+        &self,
+        chunks: &[TransactionChunkManifest],
+        num_workers: usize,
+        progress: Option<ProgressCallback>,
+    ) -> Result<()> {
+        ensure!(num_workers > 0, "Number of workers must be positive.");
+        self.storage()?;
+        // Validate the ranges of the chunks before spawning any workers.
+        let total_txns = total_transactions(chunks)?;
+        for (prev, chunk) in chunks.iter().zip(chunks.iter().skip(1)) {
+            ensure!(
+                Some(chunk.first_version) == prev.last_version.checked_add(1),
+                "Chunk {} doesn't follow chunk {}.",
+                chunk.transactions,
+                prev.transactions,
+            );
+        }
+
+        let chunks = Arc::new(chunks.to_vec());
+        let (job_sender, job_receiver) = mpsc::channel::<usize>();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+        let (result_sender, result_receiver) = mpsc::channel();
+        let workers = (0..num_workers)
+            .map(|i| {
+                let handler = self.clone();
+                let chunks = Arc::clone(&chunks);
+                let job_receiver = Arc::clone(&job_receiver);
+                let result_sender = result_sender.clone();
+                thread::Builder::new()
+                    .name(format!("restore-{}", i))
+                    .spawn(move || loop {
+                        let idx = match job_receiver.lock().recv() {
+                            Ok(idx) => idx,
+                            Err(_) => break,
+                        };
+                        // Report a panic as an error of the chunk, since the committing thread
+                        // would otherwise wait on it forever while the other workers are alive.
+                        let res = panic::catch_unwind(AssertUnwindSafe(|| {
+                            handler.load_transaction_chunk(&chunks[idx])
+                        }))
+                        .unwrap_or_else(|_| {
+                            Err(anyhow!(
+                                "Restore worker panicked loading chunk {}.",
+                                chunks[idx].transactions
+                            ))
+                        });
+                        if result_sender.send((idx, res)).is_err() {
+                            break;
+                        }
+                    })
+                    .map_err(Into::into)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        drop(result_sender);
+
+        let res = self.commit_transaction_chunks(
+            &chunks,
+            &job_sender,
+            &result_receiver,
+            2 * num_workers,
+            &mut ProgressReporter::new(progress, total_txns),
+        );
+
+        // Let the workers exit, including after an error.
+        drop(job_sender);
+        drop(result_receiver);
+        for worker in workers {
+            worker
+                .join()
+                .map_err(|_| anyhow!("Restore worker panicked."))?;
+        }
+        res
+    }
+
+    /// Has the workers load `chunks` via `job_sender` and `result_receiver`, with at most
+    /// `max_in_flight` of them loaded ahead of the next one to commit, and commits them in order.
+    fn commit_transaction_chunks(
+        &self,
+        chunks: &[TransactionChunkManifest],
+        job_sender: &mpsc::Sender<usize>,
+        result_receiver: &mpsc::Receiver<(usize, Result<(TransactionChunk, u64)>)>,
+        max_in_flight: usize,
+        progress: &mut ProgressReporter,
+    ) -> Result<()> {
+        let mut next_to_load = 0;
+        let mut next_to_commit = 0;
+        // Chunks loaded out of order, waiting for the ones before them to be committed.
+        let mut loaded = BTreeMap::new();
+        while next_to_commit < chunks.len() {
+            while next_to_load < chunks.len() && next_to_load < next_to_commit + max_in_flight {
+                job_sender.send(next_to_load)?;
+                next_to_load += 1;
+            }
+            match loaded.remove(&next_to_commit) {
+                Some(((txns, txn_infos, events), num_bytes)) => {
+                    let chunk = &chunks[next_to_commit];
+                    self.save_transactions(chunk.first_version, &txns, &txn_infos, &events)?;
+                    progress.add_chunk(num_bytes, txns.len() as u64, chunk.last_version);
+                    next_to_commit += 1;
+                }
+                None => {
+                    let (idx, res) = result_receiver
+                        .recv()
+                        .map_err(|_| anyhow!("Restore workers exited unexpectedly."))?;
+                    loaded.insert(idx, res?);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Loads a chunk of transactions from the storage, verifying it against its checksum and
+    /// proof. Returns the chunk and its size in bytes.
+    fn load_transaction_chunk(
+        &self,
+        chunk: &TransactionChunkManifest,
+    ) -> Result<(TransactionChunk, u64)> {
+        let storage = self.storage()?;
+        let (records, num_bytes): (Vec<(Transaction, TransactionInfo, Vec<ContractEvent>)>, _) =
+            load_records(storage, &chunk.transactions, chunk.transactions_checksum)?;
+        let (txns, txn_infos, events): TransactionChunk = records.into_iter().fold(
+            (Vec::new(), Vec::new(), Vec::new()),
+            |(mut txns, mut txn_infos, mut events), (txn, txn_info, txn_events)| {
+                txns.push(txn);
+                txn_infos.push(txn_info);
+                events.push(txn_events);
+                (txns, txn_infos, events)
+            },
+        );
+        let expected_num_txns = chunk.num_transactions()?;
+        ensure!(
+            txns.len() as Version == expected_num_txns,
+            "Chunk {} holds {} transactions, expected {}.",
+            chunk.transactions,
+            txns.len(),
+            expected_num_txns,
+        );
+
+        let (range_proof, ledger_info): (
+            TransactionAccumulatorRangeProof,
+            LedgerInfoWithSignatures,
+        ) = load_bcs(storage, &chunk.proof)?;
+        range_proof.verify(
+            ledger_info.ledger_info().transaction_accumulator_hash(),
+            Some(chunk.first_version),
+            &txn_infos.iter().map(CryptoHash::hash).collect::<Vec<_>>(),
+        )?;
+        for ((txn, txn_info), txn_events) in txns.iter().zip(&txn_infos).zip(&events) {
+            ensure!(
+                txn.hash() == txn_info.transaction_hash(),
+                "Transaction hash mismatch, expected: {}, got: {}",
+                txn_info.transaction_hash(),
+                txn.hash(),
+            );
+            let event_hashes = txn_events.iter().map(CryptoHash::hash).collect::<Vec<_>>();
+            let event_root_hash =
+                InMemoryAccumulator::<EventAccumulatorHasher>::from_leaves(&event_hashes)
+                    .root_hash();
+            ensure!(
+                event_root_hash == txn_info.event_root_hash(),
+                "Event root hash mismatch, expected: {}, got: {}",
+                txn_info.event_root_hash(),
+                event_root_hash,
+            );
+        }
+        Ok(((txns, txn_infos, events), num_bytes))
+    }
+
     pub fn save_ledger_infos(&self, ledger_infos: &[LedgerInfoWithSignatures]) -> Result<()> {
         ensure!(!ledger_infos.is_empty(), "No LedgerInfos to save.");
 
@@ -72,7 +445,7 @@ impl RestoreHandler {
             .collect::<Result<Vec<_>>>()?;
         self.db.write_schemas(cs.batch)?;
 
-        if let Some(li) = self.ledger_store.get_latest_ledger_info_option() {
+        if let Some(li) = self.ledger_store.get_latest_ledger_info_option()? {
             if li.ledger_info().epoch() > ledger_infos.last().unwrap().ledger_info().epoch() {
                 // No need to update latest ledger info.
                 return Ok(());
@@ -84,6 +457,18 @@ impl RestoreHandler {
         Ok(())
     }
 
+    /// Imports the cumulative ledger counters as of `version` exported by
+    /// `BackupHandler::export_ledger_counters`, so that the counters are bumped from them once
+    /// transactions after `version` are committed, instead of from zeros.
+    pub fn confirm_or_save_ledger_counters(
+        &self,
+        version: Version,
+        counters: &LedgerCounters,
+    ) -> Result<()> {
+        self.system_store
+            .confirm_or_save_ledger_counters(version, counters)
+    }
+
     pub fn confirm_or_save_frozen_subtrees(
         &self,
         num_leaves: LeafCount,
@@ -165,3 +550,26 @@ impl RestoreHandler {
             .map_or(0, |(ver, _txn_info)| ver + 1))
     }
 }
+
+/// Returns the total number of accounts in the state snapshot `chunks`, erroring on an invalid
+/// index rather than trusting the manifest.
+fn total_accounts(chunks: &[StateSnapshotChunkManifest]) -> Result<u64> {
+    let num_accounts = match chunks.last() {
+        Some(chunk) => chunk
+            .last_idx
+            .checked_add(1)
+            .ok_or_else(|| anyhow!("Chunk {} holds too many accounts.", chunk.accounts))?,
+        None => 0,
+    };
+    Ok(num_accounts as u64)
+}
+
+/// Returns the total number of transactions in `chunks`, erroring on a chunk with an invalid
+/// range rather than trusting the manifest.
+fn total_transactions(chunks: &[TransactionChunkManifest]) -> Result<u64> {
+    chunks.iter().try_fold(0u64, |total, chunk| {
+        total
+            .checked_add(chunk.num_transactions()?)
+            .ok_or_else(|| anyhow!("Too many transactions in the chunks."))
+    })
+}
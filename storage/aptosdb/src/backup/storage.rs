@@ -0,0 +1,195 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines [`BackupStorage`], the abstraction over where backups taken by
+//! [`BackupHandler`](crate::backup::backup_handler::BackupHandler) are written to and restored
+//! by [`RestoreHandler`](crate::backup::restore_handler::RestoreHandler) from, with
+//! [`LocalFs`] as the implementation on the local file system.
+//!
+//! # Chunking contract
+//!
+//! A backup consists of a number of files, each written in full, sequentially, through a single
+//! writer returned by [`BackupStorage::create_writer`], and never modified after the writer is
+//! flushed and dropped. Large data is split into chunks of a bounded number of items, each chunk
+//! in its own file, so a backend never has to hold more than one chunk to upload it, and can
+//! upload the data written to a file in parts (e.g. S3 multipart uploads) as it arrives. A file
+//! must only become visible to `open_reader` once completely written. The manifest listing the
+//! chunks of a backup is written last, so a backup without its manifest is incomplete.
+
+use anyhow::{ensure, Result};
+use aptos_crypto::HashValue;
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufReader, BufWriter, ErrorKind, Read, Write},
+    path::PathBuf,
+};
+use tiny_keccak::{Hasher, Sha3};
+
+/// Storage for backup files, addressed by name.
+pub trait BackupStorage: Send + Sync {
+    /// Creates the file `name` for writing. Fails if it already exists.
+    fn create_writer(&self, name: &str) -> Result<Box<dyn Write + Send>>;
+
+    /// Opens the file `name`, written via `create_writer`, for reading.
+    fn open_reader(&self, name: &str) -> Result<Box<dyn Read + Send>>;
+}
+
+/// Stores backup files in a local directory.
+#[derive(Clone, Debug)]
+pub struct LocalFs {
+    dir: PathBuf,
+}
+
+impl LocalFs {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn path(&self, name: &str) -> Result<PathBuf> {
+        ensure!(
+            !name.is_empty() && !name.contains(std::path::is_separator) && name != "..",
+            "Invalid backup file name: {:?}",
+            name,
+        );
+        Ok(self.dir.join(name))
+    }
+}
+
+impl BackupStorage for LocalFs {
+    fn create_writer(&self, name: &str) -> Result<Box<dyn Write + Send>> {
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(self.path(name)?)?;
+        Ok(Box::new(BufWriter::new(file)))
+    }
+
+    fn open_reader(&self, name: &str) -> Result<Box<dyn Read + Send>> {
+        Ok(Box::new(BufReader::new(File::open(self.path(name)?)?)))
+    }
+}
+
+/// Writes `value` BCS serialized to the new file `name`.
+pub fn save_bcs<T: Serialize>(storage: &dyn BackupStorage, name: &str, value: &T) -> Result<()> {
+    let mut writer = storage.create_writer(name)?;
+    writer.write_all(&bcs::to_bytes(value)?)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads the file `name` written by [`save_bcs`].
+pub fn load_bcs<T: DeserializeOwned>(storage: &dyn BackupStorage, name: &str) -> Result<T> {
+    let mut bytes = Vec::new();
+    storage.open_reader(name)?.read_to_end(&mut bytes)?;
+    Ok(bcs::from_bytes(&bytes)?)
+}
+
+/// Writes `records` to the new file `name` in the format of [`write_record`], one by one as they
+/// are serialized. Returns the checksum of the file, to be passed to [`load_records`] when reading
+/// it back, and its size.
+pub(crate) fn save_records<T: Serialize>(
+    storage: &dyn BackupStorage,
+    name: &str,
+    records: &[T],
+) -> Result<(HashValue, u64)> {
+    let mut writer = HashingWriter::new(storage.create_writer(name)?);
+    for record in records {
+        write_record(&mut writer, record)?;
+    }
+    writer.flush()?;
+    Ok(writer.finish())
+}
+
+/// Passes the bytes written through to `inner`, keeping their SHA3-256 and count, so that the
+/// checksum of a file is known once written without holding its content.
+struct HashingWriter<W> {
+    inner: W,
+    sha3: Sha3,
+    num_bytes: u64,
+}
+
+impl<W: Write> HashingWriter<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            sha3: Sha3::v256(),
+            num_bytes: 0,
+        }
+    }
+
+    /// Returns the SHA3-256 of the bytes written, the same as `HashValue::sha3_256_of` of them,
+    /// and their count.
+    fn finish(self) -> (HashValue, u64) {
+        let mut hash = [0u8; HashValue::LENGTH];
+        self.sha3.finalize(&mut hash);
+        (HashValue::new(hash), self.num_bytes)
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let num_written = self.inner.write(buf)?;
+        self.sha3.update(&buf[..num_written]);
+        self.num_bytes += num_written as u64;
+        Ok(num_written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Reads the records in the file `name` written by [`save_records`], failing if the file doesn't
+/// match `expected_checksum`, e.g. it was truncated or corrupted in storage. Returns the records
+/// and the size of the file.
+pub(crate) fn load_records<T: DeserializeOwned>(
+    storage: &dyn BackupStorage,
+    name: &str,
+    expected_checksum: HashValue,
+) -> Result<(Vec<T>, u64)> {
+    let mut bytes = Vec::new();
+    storage.open_reader(name)?.read_to_end(&mut bytes)?;
+    let checksum = HashValue::sha3_256_of(&bytes);
+    ensure!(
+        checksum == expected_checksum,
+        "Checksum mismatch for backup chunk {}, expected: {}, actual: {}",
+        name,
+        expected_checksum,
+        checksum,
+    );
+    let records = read_records(bytes.as_slice()).collect::<Result<_>>()?;
+    Ok((records, bytes.len() as u64))
+}
+
+/// Writes `record` BCS serialized, prefixed by its length as a little endian `u32`.
+pub(crate) fn write_record<W: Write + ?Sized, T: Serialize>(
+    writer: &mut W,
+    record: &T,
+) -> Result<()> {
+    let bytes = bcs::to_bytes(record)?;
+    writer.write_u32::<LittleEndian>(bytes.len() as u32)?;
+    writer.write_all(&bytes)?;
+    Ok(())
+}
+
+/// Decodes the records written by [`write_record`] till the end of `reader`.
+pub(crate) fn read_records<R: Read, T: DeserializeOwned>(
+    mut reader: R,
+) -> impl Iterator<Item = Result<T>> {
+    std::iter::from_fn(move || {
+        let len = match reader.read_u32::<LittleEndian>() {
+            Ok(len) => len,
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return None,
+            Err(e) => return Some(Err(e.into())),
+        };
+        let mut bytes = vec![0u8; len as usize];
+        Some(
+            reader
+                .read_exact(&mut bytes)
+                .map_err(Into::into)
+                .and_then(|_| Ok(bcs::from_bytes(&bytes)?)),
+        )
+    })
+}
@@ -1,11 +1,27 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{test_helper::arb_blocks_to_commit, AptosDB};
+use crate::{
+    backup::{
+        backup_handler::read_exported_state,
+        manifest::{StateSnapshotChunkManifest, TransactionChunkManifest},
+        progress::BackupProgress,
+        storage::{load_records, save_bcs, save_records, BackupStorage, LocalFs},
+    },
+    test_helper::arb_blocks_to_commit,
+    AptosDB, GetRestoreHandler,
+};
 use anyhow::Result;
+use aptos_crypto::HashValue;
+use aptos_infallible::Mutex;
 use aptos_temppath::TempPath;
+use aptos_types::{
+    contract_event::ContractEvent,
+    transaction::{Transaction, TransactionInfo},
+};
 use proptest::prelude::*;
-use storage_interface::DbWriter;
+use std::sync::Arc;
+use storage_interface::{DbReader, DbWriter};
 
 proptest! {
     #![proptest_config(ProptestConfig::with_cases(10))]
@@ -41,4 +57,538 @@ proptest! {
             .unwrap();
         prop_assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn test_export_state(input in arb_blocks_to_commit()) {
+        let tmp_dir = TempPath::new();
+        let db = AptosDB::new_for_test(&tmp_dir);
+
+        let mut cur_ver = 0;
+        for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+            db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+                .unwrap();
+            cur_ver += txns_to_commit.len() as u64;
+        }
+        let version = cur_ver - 1;
+
+        let mut exported = Vec::new();
+        let num_accounts = db
+            .get_backup_handler()
+            .export_state(version, &mut exported)
+            .unwrap();
+        prop_assert_eq!(num_accounts, db.get_account_count(version).unwrap());
+
+        let accounts = read_exported_state(exported.as_slice())
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        prop_assert_eq!(accounts.len(), num_accounts);
+
+        // Re-importing into an empty DB reproduces the state root.
+        let expected_root_hash = db.state_store.get_root_hash(version).unwrap();
+        let proof = db
+            .get_backup_handler()
+            .get_account_state_range_proof(accounts.last().unwrap().0, version)
+            .unwrap();
+        let tmp_dir2 = TempPath::new();
+        let db2 = AptosDB::new_for_test(&tmp_dir2);
+        let mut receiver = db2
+            .get_state_snapshot_receiver(version, expected_root_hash)
+            .unwrap();
+        receiver.add_chunk(accounts, proof).unwrap();
+        receiver.finish_box().unwrap();
+        prop_assert_eq!(db2.state_store.get_root_hash(version).unwrap(), expected_root_hash);
+    }
+
+    #[test]
+    fn test_ledger_counters_round_trip(input in arb_blocks_to_commit()) {
+        prop_assume!(input.len() >= 2);
+        let tmp_dir = TempPath::new();
+        let db = AptosDB::new_for_test(&tmp_dir);
+
+        let mut cur_ver = 0;
+        for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+            db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+                .unwrap();
+            cur_ver += txns_to_commit.len() as u64;
+        }
+        let (last_txns, last_ledger_info_with_sigs) = input.last().unwrap();
+        let last_version = cur_ver - 1;
+        let version = cur_ver - last_txns.len() as u64 - 1;
+
+        // Clone the DB as of `version`: state snapshot, frozen subtrees and ledger counters.
+        let tmp_dir2 = TempPath::new();
+        let db2 = Arc::new(AptosDB::new_for_test(&tmp_dir2).with_genesis_version(version + 1));
+        let mut exported = Vec::new();
+        db.get_backup_handler()
+            .export_state(version, &mut exported)
+            .unwrap();
+        let accounts = read_exported_state(exported.as_slice())
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let proof = db
+            .get_backup_handler()
+            .get_account_state_range_proof(accounts.last().unwrap().0, version)
+            .unwrap();
+        let mut receiver = db2
+            .get_state_snapshot_receiver(version, db.state_store.get_root_hash(version).unwrap())
+            .unwrap();
+        receiver.add_chunk(accounts, proof).unwrap();
+        receiver.finish_box().unwrap();
+        let mut left_siblings = db.ledger_store.get_frozen_subtree_hashes(version + 1).unwrap();
+        left_siblings.reverse();
+        let restore_handler = db2.get_restore_handler();
+        restore_handler
+            .confirm_or_save_frozen_subtrees(version + 1, &left_siblings)
+            .unwrap();
+
+        let counters = db.get_backup_handler().export_ledger_counters(version).unwrap();
+        restore_handler
+            .confirm_or_save_ledger_counters(version, &counters)
+            .unwrap();
+        // Importing again is fine as long as the counters agree.
+        restore_handler
+            .confirm_or_save_ledger_counters(version, &counters)
+            .unwrap();
+        prop_assert!(restore_handler
+            .confirm_or_save_ledger_counters(version, &Default::default())
+            .is_err());
+        prop_assert_eq!(
+            &db2.get_backup_handler().export_ledger_counters(version).unwrap(),
+            &counters
+        );
+
+        // Committing the last block bumps the counters from the imported base, arriving at the
+        // same values as the original DB.
+        db2.save_transactions(last_txns, version + 1, Some(last_ledger_info_with_sigs))
+            .unwrap();
+        prop_assert_eq!(
+            db2.get_backup_handler().export_ledger_counters(last_version).unwrap(),
+            db.get_backup_handler().export_ledger_counters(last_version).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_incremental_backup(input in arb_blocks_to_commit()) {
+        prop_assume!(input.len() >= 2);
+        let tmp_dir = TempPath::new();
+        let db = AptosDB::new_for_test(&tmp_dir);
+
+        let (first_blocks, last_blocks) = input.split_at(input.len() / 2);
+        let mut cur_ver = 0;
+        for (txns_to_commit, ledger_info_with_sigs) in first_blocks {
+            db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+                .unwrap();
+            cur_ver += txns_to_commit.len() as u64;
+        }
+        let base_version = cur_ver - 1;
+
+        // Take a full backup and restore it into a fresh DB.
+        let backup_dir = TempPath::new();
+        backup_dir.create_as_dir().unwrap();
+        let storage: Arc<dyn BackupStorage> =
+            Arc::new(LocalFs::new(backup_dir.path().to_path_buf()));
+        let backup_handler = db.get_backup_handler().with_storage(Arc::clone(&storage));
+        let full_manifest = backup_handler
+            .backup_state_snapshot(base_version, 2, "state", None)
+            .unwrap();
+        prop_assert_eq!(full_manifest.base_version, None);
+        let tmp_dir2 = TempPath::new();
+        let db2 = Arc::new(AptosDB::new_for_test(&tmp_dir2));
+        let restore_handler = db2.get_restore_handler().with_storage(Arc::clone(&storage));
+        restore_handler.restore_state_snapshot("state", None).unwrap();
+
+        // Commit more blocks and take an incremental backup on top of the full one.
+        let mut expected_txns = Vec::new();
+        for (txns_to_commit, ledger_info_with_sigs) in last_blocks {
+            db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+                .unwrap();
+            cur_ver += txns_to_commit.len() as u64;
+            expected_txns.extend(txns_to_commit.iter().map(|txn| txn.transaction().clone()));
+        }
+        let version = cur_ver - 1;
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let reported_clone = Arc::clone(&reported);
+        let diff_manifest = backup_handler
+            .backup_state_snapshot_diff(
+                base_version,
+                version,
+                2,
+                "state_diff",
+                Some(Arc::new(move |progress: BackupProgress| {
+                    reported_clone.lock().push(progress)
+                })),
+            )
+            .unwrap();
+        prop_assert_eq!(diff_manifest.base_version, Some(base_version));
+        prop_assert!(diff_manifest.chunks.iter().all(|chunk| chunk.proof.is_none()));
+        let num_accounts = diff_manifest.chunks.last().unwrap().last_idx + 1;
+        prop_assert!(num_accounts <= db.get_account_count(version).unwrap());
+        let reported = reported.lock().clone();
+        prop_assert_eq!(reported.len(), diff_manifest.chunks.len());
+        prop_assert_eq!(reported.last().unwrap().total_items, num_accounts as u64);
+        prop_assert!(backup_handler
+            .backup_state_snapshot_diff(version, version, 2, "state_diff2", None)
+            .is_err());
+
+        let txn_manifest = backup_handler
+            .backup_transactions_since(base_version, 3, "txns", None)
+            .unwrap();
+        prop_assert_eq!(txn_manifest.first_version, base_version + 1);
+        prop_assert_eq!(txn_manifest.last_version, version);
+        let mut txns = Vec::new();
+        for chunk in &txn_manifest.chunks {
+            let (records, _num_bytes): (
+                Vec<(Transaction, TransactionInfo, Vec<ContractEvent>)>,
+                _,
+            ) = load_records(&*storage, &chunk.transactions, chunk.transactions_checksum)
+                .unwrap();
+            txns.extend(records.into_iter().map(|(txn, _txn_info, _events)| txn));
+        }
+        prop_assert_eq!(txns, expected_txns);
+        prop_assert!(backup_handler
+            .backup_transactions_since(version, 3, "txns2", None)
+            .is_err());
+
+        // Full and incremental snapshots are restored by their own methods.
+        prop_assert!(restore_handler.restore_state_snapshot("state_diff", None).is_err());
+        prop_assert!(restore_handler.restore_state_snapshot_diff("state", None).is_err());
+
+        // An increment can't be applied without its base.
+        let tmp_dir3 = TempPath::new();
+        let db3 = Arc::new(AptosDB::new_for_test(&tmp_dir3));
+        prop_assert!(db3
+            .get_restore_handler()
+            .with_storage(Arc::clone(&storage))
+            .restore_state_snapshot_diff("state_diff", None)
+            .is_err());
+
+        // Nor with a mismatching root hash.
+        let mut bad_manifest = diff_manifest.clone();
+        bad_manifest.version = version + 1;
+        bad_manifest.root_hash = HashValue::zero();
+        save_bcs(&*storage, "bad_diff.manifest", &bad_manifest).unwrap();
+        prop_assert!(restore_handler.restore_state_snapshot_diff("bad_diff", None).is_err());
+        prop_assert!(db2.state_store.get_root_hash_option(version + 1).unwrap().is_none());
+
+        // Restoring the increment reproduces the state root.
+        prop_assert_eq!(
+            restore_handler.restore_state_snapshot_diff("state_diff", None).unwrap(),
+            diff_manifest
+        );
+        prop_assert_eq!(
+            db2.state_store.get_root_hash(version).unwrap(),
+            db.state_store.get_root_hash(version).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_backup_restore_via_storage(input in arb_blocks_to_commit()) {
+        let tmp_dir = TempPath::new();
+        let db = AptosDB::new_for_test(&tmp_dir);
+
+        let mut cur_ver = 0;
+        for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+            db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+                .unwrap();
+            cur_ver += txns_to_commit.len() as u64;
+        }
+        let version = cur_ver - 1;
+
+        let backup_dir = TempPath::new();
+        backup_dir.create_as_dir().unwrap();
+        let storage: Arc<dyn BackupStorage> =
+            Arc::new(LocalFs::new(backup_dir.path().to_path_buf()));
+        prop_assert!(db
+            .get_backup_handler()
+            .backup_state_snapshot(version, 2, "state", None)
+            .is_err());
+        let backup_handler = db.get_backup_handler().with_storage(Arc::clone(&storage));
+        let state_manifest = backup_handler
+            .backup_state_snapshot(version, 2, "state", None)
+            .unwrap();
+        prop_assert_eq!(
+            state_manifest.chunks.last().unwrap().last_idx + 1,
+            db.get_account_count(version).unwrap()
+        );
+        let txn_manifest = backup_handler
+            .backup_transactions(0, cur_ver as usize, 3, "txns", None)
+            .unwrap();
+        prop_assert_eq!(txn_manifest.chunks.last().unwrap().last_version, version);
+        // Backup files are never overwritten.
+        prop_assert!(backup_handler.backup_state_snapshot(version, 2, "state", None).is_err());
+
+        let tmp_dir2 = TempPath::new();
+        let db2 = Arc::new(AptosDB::new_for_test(&tmp_dir2));
+        let restore_handler = db2.get_restore_handler().with_storage(storage);
+        prop_assert_eq!(restore_handler.restore_transactions("txns", None).unwrap(), txn_manifest);
+        prop_assert_eq!(
+            restore_handler.restore_state_snapshot("state", None).unwrap(),
+            state_manifest
+        );
+        prop_assert_eq!(
+            db2.state_store.get_root_hash(version).unwrap(),
+            db.state_store.get_root_hash(version).unwrap()
+        );
+        let restored_txns = db2
+            .get_backup_handler()
+            .get_transaction_iter(0, cur_ver as usize)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let expected_txns = db
+            .get_backup_handler()
+            .get_transaction_iter(0, cur_ver as usize)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        prop_assert_eq!(restored_txns, expected_txns);
+        prop_assert!(restore_handler.restore_state_snapshot("missing", None).is_err());
+    }
+
+    #[test]
+    fn test_restore_corrupted_backup(input in arb_blocks_to_commit()) {
+        let tmp_dir = TempPath::new();
+        let db = AptosDB::new_for_test(&tmp_dir);
+
+        let mut cur_ver = 0;
+        for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+            db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+                .unwrap();
+            cur_ver += txns_to_commit.len() as u64;
+        }
+        let version = cur_ver - 1;
+
+        let backup_dir = TempPath::new();
+        backup_dir.create_as_dir().unwrap();
+        let storage: Arc<dyn BackupStorage> =
+            Arc::new(LocalFs::new(backup_dir.path().to_path_buf()));
+        let backup_handler = db.get_backup_handler().with_storage(Arc::clone(&storage));
+        backup_handler
+            .backup_state_snapshot(version, 2, "state", None)
+            .unwrap();
+        backup_handler
+            .backup_transactions(0, cur_ver as usize, 3, "txns", None)
+            .unwrap();
+
+        // Flip a byte in the first chunk of each backup.
+        for name in &["state.chunk_0", "txns.chunk_0"] {
+            let path = backup_dir.path().join(name);
+            let mut bytes = std::fs::read(&path).unwrap();
+            *bytes.last_mut().unwrap() ^= 0xff;
+            std::fs::write(&path, bytes).unwrap();
+        }
+
+        let tmp_dir2 = TempPath::new();
+        let db2 = Arc::new(AptosDB::new_for_test(&tmp_dir2));
+        let restore_handler = db2.get_restore_handler().with_storage(storage);
+        let err = restore_handler.restore_state_snapshot("state", None).unwrap_err();
+        prop_assert!(err.to_string().contains("state.chunk_0"));
+        prop_assert!(db2.state_store.get_root_hash_option(version).unwrap().is_none());
+        let err = restore_handler.restore_transactions("txns", None).unwrap_err();
+        prop_assert!(err.to_string().contains("txns.chunk_0"));
+        prop_assert_eq!(restore_handler.get_next_expected_transaction_version().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_resume_state_snapshot_restore(input in arb_blocks_to_commit()) {
+        let tmp_dir = TempPath::new();
+        let db = AptosDB::new_for_test(&tmp_dir);
+
+        let mut cur_ver = 0;
+        for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+            db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+                .unwrap();
+            cur_ver += txns_to_commit.len() as u64;
+        }
+        let version = cur_ver - 1;
+        let root_hash = db.state_store.get_root_hash(version).unwrap();
+        prop_assume!(db.get_account_count(version).unwrap() > 2);
+
+        let backup_dir = TempPath::new();
+        backup_dir.create_as_dir().unwrap();
+        let storage: Arc<dyn BackupStorage> =
+            Arc::new(LocalFs::new(backup_dir.path().to_path_buf()));
+        let backup_handler = db.get_backup_handler().with_storage(Arc::clone(&storage));
+        backup_handler
+            .backup_state_snapshot(version, 2, "state", None)
+            .unwrap();
+
+        // Restore the first chunk, then stop as if the process crashed.
+        let tmp_dir2 = TempPath::new();
+        let db2 = Arc::new(AptosDB::new_for_test(&tmp_dir2));
+        {
+            let accounts = backup_handler
+                .get_account_iter(version)
+                .unwrap()
+                .take(2)
+                .collect::<Result<Vec<_>>>()
+                .unwrap();
+            let proof = backup_handler
+                .get_account_state_range_proof(accounts.last().unwrap().0, version)
+                .unwrap();
+            let mut receiver = db2.get_state_snapshot_receiver(version, root_hash).unwrap();
+            receiver.add_chunk(accounts, proof).unwrap();
+        }
+
+        prop_assert!(db2
+            .resume_state_snapshot_receiver(version, HashValue::zero())
+            .is_err());
+        prop_assert_eq!(
+            db2.resume_state_snapshot_receiver(version, root_hash)
+                .unwrap()
+                .num_keys_received(),
+            2
+        );
+
+        // Restoring from the backup picks up after the first chunk.
+        db2.get_restore_handler()
+            .with_storage(storage)
+            .restore_state_snapshot("state", None)
+            .unwrap();
+        prop_assert_eq!(db2.state_store.get_root_hash(version).unwrap(), root_hash);
+        prop_assert_eq!(
+            db2.get_backup_handler()
+                .get_account_iter(version)
+                .unwrap()
+                .collect::<Result<Vec<_>>>()
+                .unwrap(),
+            backup_handler
+                .get_account_iter(version)
+                .unwrap()
+                .collect::<Result<Vec<_>>>()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_restore_transactions_parallel(input in arb_blocks_to_commit()) {
+        let tmp_dir = TempPath::new();
+        let db = AptosDB::new_for_test(&tmp_dir);
+
+        let mut cur_ver = 0;
+        for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+            db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+                .unwrap();
+            cur_ver += txns_to_commit.len() as u64;
+        }
+
+        let backup_dir = TempPath::new();
+        backup_dir.create_as_dir().unwrap();
+        let storage: Arc<dyn BackupStorage> =
+            Arc::new(LocalFs::new(backup_dir.path().to_path_buf()));
+        let manifest = db
+            .get_backup_handler()
+            .with_storage(Arc::clone(&storage))
+            .backup_transactions(0, cur_ver as usize, 2, "txns", None)
+            .unwrap();
+
+        let tmp_dir2 = TempPath::new();
+        let db2 = Arc::new(AptosDB::new_for_test(&tmp_dir2));
+        let restore_handler = db2.get_restore_handler().with_storage(storage);
+        // Chunks must be contiguous.
+        let mut gapped_chunks = manifest.chunks.clone();
+        if gapped_chunks.len() > 2 {
+            gapped_chunks.remove(1);
+            prop_assert!(restore_handler
+                .restore_transactions_parallel(&gapped_chunks, 3, None)
+                .is_err());
+        }
+        // Their ranges must be valid too.
+        let mut inverted_chunk = manifest.chunks[0].clone();
+        inverted_chunk.first_version = inverted_chunk.last_version + 1;
+        prop_assert!(restore_handler
+            .restore_transactions_parallel(&[inverted_chunk], 3, None)
+            .is_err());
+        // Nothing can follow a chunk ending at the maximum version.
+        let mut last_chunk = manifest.chunks[0].clone();
+        last_chunk.first_version = u64::max_value();
+        last_chunk.last_version = u64::max_value();
+        prop_assert!(restore_handler
+            .restore_transactions_parallel(&[last_chunk, manifest.chunks[0].clone()], 3, None)
+            .is_err());
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let reported_clone = Arc::clone(&reported);
+        restore_handler
+            .restore_transactions_parallel(
+                &manifest.chunks,
+                3,
+                Some(Arc::new(move |progress: BackupProgress| {
+                    reported_clone.lock().push(progress)
+                })),
+            )
+            .unwrap();
+        // Progress is reported once per chunk, in version order.
+        let reported = reported.lock().clone();
+        prop_assert_eq!(reported.len(), manifest.chunks.len());
+        for (progress, chunk) in reported.iter().zip(&manifest.chunks) {
+            prop_assert_eq!(progress.current_version, chunk.last_version);
+            prop_assert_eq!(progress.items_processed, chunk.last_version + 1);
+            prop_assert_eq!(progress.total_items, cur_ver);
+        }
+
+        let restored = db2
+            .get_backup_handler()
+            .get_transaction_iter(0, cur_ver as usize)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let expected = db
+            .get_backup_handler()
+            .get_transaction_iter(0, cur_ver as usize)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        prop_assert_eq!(restored, expected);
+        prop_assert_eq!(
+            db2.ledger_store.get_root_hash(cur_ver - 1).unwrap(),
+            db.ledger_store.get_root_hash(cur_ver - 1).unwrap()
+        );
+    }
+}
+
+#[test]
+fn test_chunk_manifest_ranges() {
+    let state_chunk = |first_idx, last_idx| StateSnapshotChunkManifest {
+        first_idx,
+        last_idx,
+        last_key: HashValue::zero(),
+        accounts: "state.chunk_0".to_string(),
+        accounts_checksum: HashValue::zero(),
+        proof: Some("state.chunk_0.proof".to_string()),
+    };
+    assert_eq!(state_chunk(0, 0).num_accounts().unwrap(), 1);
+    assert_eq!(state_chunk(3, 7).num_accounts().unwrap(), 5);
+    // Manifests come from the backup storage, bad indices are errors rather than panics.
+    assert!(state_chunk(7, 3).num_accounts().is_err());
+    assert!(state_chunk(0, usize::MAX).num_accounts().is_err());
+
+    let txn_chunk = |first_version, last_version| TransactionChunkManifest {
+        first_version,
+        last_version,
+        transactions: "txns.chunk_0".to_string(),
+        transactions_checksum: HashValue::zero(),
+        proof: "txns.proof_0".to_string(),
+    };
+    assert_eq!(txn_chunk(0, 0).num_transactions().unwrap(), 1);
+    assert_eq!(txn_chunk(3, 7).num_transactions().unwrap(), 5);
+    assert!(txn_chunk(7, 3).num_transactions().is_err());
+    assert!(txn_chunk(0, u64::MAX).num_transactions().is_err());
+}
+
+#[test]
+fn test_save_records_checksum() {
+    let backup_dir = TempPath::new();
+    backup_dir.create_as_dir().unwrap();
+    let storage = LocalFs::new(backup_dir.path().to_path_buf());
+    let records: Vec<(u64, Vec<u8>)> = (0..100).map(|i| (i, vec![i as u8; i as usize])).collect();
+
+    let (checksum, num_bytes) = save_records(&storage, "records", &records).unwrap();
+    // The checksum and size computed while streaming match those of the file written.
+    let bytes = std::fs::read(backup_dir.path().join("records")).unwrap();
+    assert_eq!(checksum, HashValue::sha3_256_of(&bytes));
+    assert_eq!(num_bytes, bytes.len() as u64);
+    assert_eq!(
+        load_records::<(u64, Vec<u8>)>(&storage, "records", checksum).unwrap(),
+        (records, num_bytes)
+    );
 }
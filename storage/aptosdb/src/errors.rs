@@ -2,7 +2,22 @@
 // SPDX-License-Identifier: Apache-2.0
 
 //! This module defines error types used by [`AptosDB`](crate::AptosDB).
+//!
+//! APIs return `anyhow::Result`, but the following common failure modes are always raised as an
+//! [`AptosDbError`], so that callers can tell them apart via `anyhow::Error::downcast_ref`:
+//!
+//! - [`AptosDbError::NotFound`]: the requested item doesn't exist in the DB.
+//! - [`AptosDbError::TooManyRequested`]: the request exceeds the per-request limit of the API.
+//! - [`AptosDbError::Pruned`]: the requested version has been pruned.
+//! - [`AptosDbError::VersionTooNew`]: the requested version is newer than the ledger version the
+//!   request is against, or than the latest committed version.
+//! - [`AptosDbError::RootHashMismatch`]: a computed root hash doesn't match the expected one.
+//! - [`AptosDbError::Inconsistent`]: data found by `AptosDB::verify_integrity` to contradict the
+//!   rest of the DB.
+//!
+//! Other failures, e.g. malformed requests and DB corruption, are plain `anyhow::Error`s.
 
+use aptos_crypto::HashValue;
 use thiserror::Error;
 
 /// This enum defines errors commonly used among [`AptosDB`](crate::AptosDB) APIs.
@@ -14,4 +29,20 @@ pub enum AptosDbError {
     /// Requested too many items.
     #[error("Too many items requested: at least {0} requested, max is {1}")]
     TooManyRequested(u64, u64),
+    /// A different ledger info already exists at the same version.
+    #[error("A conflicting ledger info already exists at version {0}.")]
+    LedgerInfoConflict(u64),
+    /// A requested version is pruned, the second field being the least readable version.
+    #[error("Version {0} is already pruned, least readable version is {1}.")]
+    Pruned(u64, u64),
+    /// A requested version is newer than the version given as the second field.
+    #[error("Version {0} is newer than version {1}.")]
+    VersionTooNew(u64, u64),
+    /// A computed root hash (the second field) doesn't match the expected one (the first field).
+    #[error("Root hash mismatch: expected {0}, actual {1}.")]
+    RootHashMismatch(HashValue, HashValue),
+    /// The data of a subsystem (the second field) at a version (the first field) is inconsistent
+    /// with the rest of the DB, for the reason given as the third field.
+    #[error("Inconsistent {1} at version {0}: {2}")]
+    Inconsistent(u64, &'static str, String),
 }
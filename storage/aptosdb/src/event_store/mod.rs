@@ -84,7 +84,7 @@ impl EventStore {
         })
     }
 
-    fn get_event_by_version_and_index(
+    pub fn get_event_by_version_and_index(
         &self,
         version: Version,
         index: u64,
@@ -362,24 +362,42 @@ impl EventStore {
             .ok_or_else(|| format_err!("A block with non-zero seq num started at version 0."))
     }
 
-    /// Prunes the events by key store for a set of events
+    /// Returns the keys in the event by key index, i.e. `(EventKey, sequence number)`, of the
+    /// events emitted in the version range [begin, end), in the order of version and index.
+    pub fn get_event_index_keys_by_version_range(
+        &self,
+        begin: Version,
+        end: Version,
+    ) -> Result<impl Iterator<Item = Result<(EventKey, u64)>> + '_> {
+        let mut iter = self.db.iter::<EventSchema>(ReadOptions::default())?;
+        iter.seek(&begin)?;
+
+        Ok(iter
+            .take_while(move |res| {
+                res.as_ref()
+                    .map_or(true, |((version, _index), _event)| *version < end)
+            })
+            .map(|res| res.map(|(_key, event)| (*event.key(), event.sequence_number()))))
+    }
+
+    /// Prunes the events by key store for a set of event index keys, see
+    /// `get_event_index_keys_by_version_range`.
     pub fn prune_events_by_key(
         &self,
-        candidate_events: &[ContractEvent],
+        index_keys: &[(EventKey, u64)],
         db_batch: &mut SchemaBatch,
     ) -> anyhow::Result<()> {
         let mut sequence_range_by_event_keys: HashMap<EventKey, (u64, u64)> = HashMap::new();
 
-        candidate_events.iter().for_each(|event| {
-            let event_key = event.key();
+        index_keys.iter().for_each(|(event_key, seq_num)| {
             // Events should be sorted by sequence numbers, so the first sequence number for the
             // event key should be the minimum
             match sequence_range_by_event_keys.entry(*event_key) {
                 Entry::Occupied(mut occupied) => {
-                    occupied.insert((occupied.get().0, event.sequence_number()));
+                    occupied.insert((occupied.get().0, *seq_num));
                 }
                 Entry::Vacant(vacant) => {
-                    vacant.insert((event.sequence_number(), event.sequence_number()));
+                    vacant.insert((*seq_num, *seq_num));
                 }
             }
         });
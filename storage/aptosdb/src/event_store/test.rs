@@ -118,6 +118,39 @@ proptest! {
     }
 }
 
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+
+    #[test]
+    fn test_get_event_index_keys_by_version_range(
+        event_batches in vec(vec(any::<ContractEvent>().no_shrink(), 0..3), 1..10),
+        begin in 0..10u64,
+        num_versions in 0..10u64,
+    ) {
+        let tmp_dir = TempPath::new();
+        let db = AptosDB::new_for_test(&tmp_dir);
+        let store = &db.event_store;
+        for (version, events) in event_batches.iter().enumerate() {
+            save(store, version as Version, events);
+        }
+
+        let end = begin + num_versions;
+        let expected: Vec<_> = event_batches
+            .iter()
+            .enumerate()
+            .filter(|(version, _)| (begin..end).contains(&(*version as Version)))
+            .flat_map(|(_, events)| events)
+            .map(|event| (*event.key(), event.sequence_number()))
+            .collect();
+        let index_keys = store
+            .get_event_index_keys_by_version_range(begin, end)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        prop_assert_eq!(index_keys, expected);
+    }
+}
+
 fn traverse_events_by_key(
     store: &EventStore,
     event_key: &EventKey,
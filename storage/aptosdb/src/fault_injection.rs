@@ -0,0 +1,61 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module provides a failure point in the commit path of [`AptosDB`](crate::AptosDB), for
+//! testing crash recovery and retry logic deterministically. Only compiled in tests and with the
+//! `fuzzing` feature.
+
+use anyhow::{bail, Result};
+use aptos_infallible::Mutex;
+use schemadb::{ColumnFamilyName, SchemaBatch, DB};
+
+/// How an injected commit failure manifests.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CommitFailure {
+    /// The commit fails before anything is written.
+    BeforeWrite,
+    /// Only the updates to the given column family are written before the commit fails,
+    /// simulating a write torn between column families. (A real commit is a single atomic DB
+    /// write.)
+    AfterWritingCf(ColumnFamilyName),
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct CommitFailureInjector {
+    /// The number of commits still allowed to succeed and the failure to inject after that.
+    pending: Mutex<Option<(usize, CommitFailure)>>,
+}
+
+impl CommitFailureInjector {
+    pub fn inject(&self, num_commits_to_skip: usize, failure: CommitFailure) {
+        *self.pending.lock() = Some((num_commits_to_skip, failure));
+    }
+
+    /// Writes `batch` to `db`, unless a failure is due, in which case it's injected, once.
+    pub fn write_schemas(&self, db: &DB, mut batch: SchemaBatch) -> Result<()> {
+        let failure = {
+            let mut pending = self.pending.lock();
+            match pending.as_mut() {
+                Some((0, failure)) => {
+                    let failure = *failure;
+                    *pending = None;
+                    Some(failure)
+                }
+                Some((num_commits_to_skip, _)) => {
+                    *num_commits_to_skip -= 1;
+                    None
+                }
+                None => None,
+            }
+        };
+
+        match failure {
+            None => db.write_schemas(batch),
+            Some(CommitFailure::BeforeWrite) => bail!("Injected commit failure before write."),
+            Some(CommitFailure::AfterWritingCf(cf_name)) => {
+                db.write_schemas(batch.split_off_cf(cf_name))?;
+                bail!("Injected commit failure after writing CF {}.", cf_name)
+            }
+        }
+    }
+}
@@ -121,7 +121,7 @@ impl LedgerCounterBumps {
 
 /// Represents ledger counter values at a certain version.
 #[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
-pub(crate) struct LedgerCounters {
+pub struct LedgerCounters {
     counters: InnerLedgerCounters,
 }
 
@@ -157,6 +157,12 @@ impl LedgerCounters {
     }
 }
 
+impl Default for LedgerCounters {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[cfg(test)]
 prop_compose! {
     pub(crate) fn ledger_counters_strategy()(
@@ -42,28 +42,40 @@ pub struct LedgerStore {
     /// cache it in memory in order to avoid reading DB and deserializing the object frequently. It
     /// should be updated every time new ledger info and signatures are persisted.
     latest_ledger_info: ArcSwap<Option<LedgerInfoWithSignatures>>,
+
+    /// Whether the DB is written to by another instance, in which case the cached latest ledger
+    /// info would go stale, so it's always read from the DB instead.
+    read_only: bool,
 }
 
 impl LedgerStore {
     pub fn new(db: Arc<DB>) -> Self {
+        Self::new_impl(db, false /* read_only */)
+    }
+
+    /// Creates a `LedgerStore` on a DB written to by another instance.
+    pub fn new_read_only(db: Arc<DB>) -> Self {
+        Self::new_impl(db, true /* read_only */)
+    }
+
+    fn new_impl(db: Arc<DB>, read_only: bool) -> Self {
         // Upon restart, read the latest ledger info and signatures and cache them in memory.
-        let ledger_info = {
-            let mut iter = db
-                .iter::<LedgerInfoSchema>(ReadOptions::default())
-                .expect("Constructing iterator should work.");
-            iter.seek_to_last();
-            iter.next()
-                .transpose()
-                .expect("Reading latest ledger info from DB should work.")
-                .map(|kv| kv.1)
-        };
+        let ledger_info = Self::read_latest_ledger_info(&db)
+            .expect("Reading latest ledger info from DB should work.");
 
         Self {
             db,
             latest_ledger_info: ArcSwap::from(Arc::new(ledger_info)),
+            read_only,
         }
     }
 
+    fn read_latest_ledger_info(db: &DB) -> Result<Option<LedgerInfoWithSignatures>> {
+        let mut iter = db.iter::<LedgerInfoSchema>(ReadOptions::default())?;
+        iter.seek_to_last();
+        Ok(iter.next().transpose()?.map(|kv| kv.1))
+    }
+
     pub fn get_epoch(&self, version: Version) -> Result<u64> {
         let mut iter = self
             .db
@@ -118,14 +130,17 @@ impl LedgerStore {
         Ok(li)
     }
 
-    pub fn get_latest_ledger_info_option(&self) -> Option<LedgerInfoWithSignatures> {
+    pub fn get_latest_ledger_info_option(&self) -> Result<Option<LedgerInfoWithSignatures>> {
+        if self.read_only {
+            return Self::read_latest_ledger_info(&self.db);
+        }
         let ledger_info_ptr = self.latest_ledger_info.load();
         let ledger_info: &Option<_> = ledger_info_ptr.deref();
-        ledger_info.clone()
+        Ok(ledger_info.clone())
     }
 
     pub fn get_latest_ledger_info(&self) -> Result<LedgerInfoWithSignatures> {
-        self.get_latest_ledger_info_option()
+        self.get_latest_ledger_info_option()?
             .ok_or_else(|| AptosDbError::NotFound(String::from("Genesis LedgerInfo")).into())
     }
 
@@ -175,7 +190,7 @@ impl LedgerStore {
 
     pub fn get_startup_info(&self) -> Result<Option<StartupInfo>> {
         // Get the latest ledger info. Return None if not bootstrapped.
-        let latest_ledger_info = match self.get_latest_ledger_info_option() {
+        let latest_ledger_info = match self.get_latest_ledger_info_option()? {
             Some(x) => x,
             None => return Ok(None),
         };
@@ -215,7 +230,9 @@ impl LedgerStore {
     pub fn get_transaction_info(&self, version: Version) -> Result<TransactionInfo> {
         self.db
             .get::<TransactionInfoSchema>(&version)?
-            .ok_or_else(|| format_err!("No TransactionInfo at version {}", version))
+            .ok_or_else(|| {
+                AptosDbError::NotFound(format!("TransactionInfo at version {}", version)).into()
+            })
     }
 
     pub fn get_latest_transaction_info_option(&self) -> Result<Option<(Version, TransactionInfo)>> {
@@ -349,6 +366,9 @@ impl LedgerStore {
     }
 
     /// Write `ledger_info` to `cs`.
+    ///
+    /// Refuses to overwrite a different ledger info already persisted at the same version, while
+    /// re-committing an identical one is allowed.
     pub fn put_ledger_info(
         &self,
         ledger_info_with_sigs: &LedgerInfoWithSignatures,
@@ -356,6 +376,14 @@ impl LedgerStore {
     ) -> Result<()> {
         let ledger_info = ledger_info_with_sigs.ledger_info();
 
+        if let Some(existing) = self.db.get::<LedgerInfoSchema>(&ledger_info.epoch())? {
+            if existing.ledger_info().version() == ledger_info.version()
+                && existing != *ledger_info_with_sigs
+            {
+                return Err(AptosDbError::LedgerInfoConflict(ledger_info.version()).into());
+            }
+        }
+
         if ledger_info.ends_epoch() {
             // This is the last version of the current epoch, update the epoch by version index.
             cs.batch
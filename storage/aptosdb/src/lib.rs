@@ -20,14 +20,18 @@ pub mod errors;
 pub mod metrics;
 pub mod schema;
 
+mod account_counter;
 mod change_set;
 mod event_store;
+#[cfg(any(test, feature = "fuzzing"))]
+mod fault_injection;
 mod ledger_counters;
 mod ledger_store;
 mod pruner;
 mod state_store;
 mod system_store;
 mod transaction_store;
+mod write_buffer;
 
 #[cfg(any(test, feature = "fuzzing"))]
 #[allow(dead_code)]
@@ -35,8 +39,14 @@ mod aptosdb_test;
 
 #[cfg(feature = "fuzzing")]
 pub use aptosdb_test::test_save_blocks_impl;
+#[cfg(any(test, feature = "fuzzing"))]
+pub use fault_injection::CommitFailure;
+pub use ledger_counters::{LedgerCounter, LedgerCounters};
+pub use pruner::VersionLease;
+pub use write_buffer::WriteBufferConfig;
 
 use crate::{
+    account_counter::AccountCounter,
     backup::{backup_handler::BackupHandler, restore_handler::RestoreHandler},
     change_set::{ChangeSet, SealedChangeSet},
     errors::AptosDbError,
@@ -44,39 +54,52 @@ use crate::{
     ledger_counters::LedgerCounters,
     ledger_store::LedgerStore,
     metrics::{
-        DIEM_STORAGE_API_LATENCY_SECONDS, DIEM_STORAGE_COMMITTED_TXNS,
-        DIEM_STORAGE_LATEST_ACCOUNT_COUNT, DIEM_STORAGE_LATEST_TXN_VERSION,
-        DIEM_STORAGE_LEDGER_VERSION, DIEM_STORAGE_NEXT_BLOCK_EPOCH,
-        DIEM_STORAGE_OTHER_TIMERS_SECONDS, DIEM_STORAGE_ROCKSDB_PROPERTIES,
+        DIEM_STORAGE_API_ERRORS, DIEM_STORAGE_API_LATENCY_SECONDS, DIEM_STORAGE_COMMITTED_TXNS,
+        DIEM_STORAGE_LATEST_TXN_VERSION, DIEM_STORAGE_LEDGER_VERSION,
+        DIEM_STORAGE_NEXT_BLOCK_EPOCH, DIEM_STORAGE_OTHER_TIMERS_SECONDS, DIEM_STORAGE_PRUNE_FLOOR,
+        DIEM_STORAGE_ROCKSDB_PROPERTIES, DIEM_STORAGE_ROCKSDB_SHARED_BLOCK_CACHE_USAGE_BYTES,
     },
-    pruner::Pruner,
+    pruner::{Pruner, VERSIONED_PRUNER_COLUMN_FAMILIES},
     schema::*,
     state_store::StateStore,
     system_store::SystemStore,
     transaction_store::TransactionStore,
+    write_buffer::WriteBuffer,
 };
 use anyhow::{ensure, format_err, Result};
-use aptos_config::config::{RocksdbConfig, StoragePrunerConfig, NO_OP_STORAGE_PRUNER_CONFIG};
-use aptos_crypto::hash::{HashValue, SPARSE_MERKLE_PLACEHOLDER_HASH};
+use aptos_config::config::{
+    CompactionStyle, CompressionType, RocksdbConfig, StoragePrunerConfig,
+    NO_OP_STORAGE_PRUNER_CONFIG,
+};
+use aptos_crypto::hash::{
+    CryptoHash, EventAccumulatorHasher, HashValue, SPARSE_MERKLE_PLACEHOLDER_HASH,
+};
+use aptos_jellyfish_merkle::{iterator::JellyfishMerkleIterator, node_type::Node};
 use aptos_logger::prelude::*;
 use aptos_types::{
-    account_address::AccountAddress,
+    account_address::{AccountAddress, HashAccountAddress},
     account_state::AccountState,
     account_state_blob::{AccountStateBlob, AccountStateWithProof, AccountStatesChunkWithProof},
-    contract_event::{ContractEvent, EventByVersionWithProof, EventWithProof},
+    block_metadata::BlockMetadata,
+    contract_event::{
+        ContractEvent, EventByVersionWithProof, EventWithProof, EventWithTransactionInfoProof,
+    },
     epoch_change::EpochChangeProof,
     event::EventKey,
     ledger_info::LedgerInfoWithSignatures,
+    on_chain_config::{config_address, Version as OnChainVersion},
     proof::{
-        AccountStateProof, AccumulatorConsistencyProof, EventProof, SparseMerkleProof,
-        TransactionInfoListWithProof,
+        accumulator::InMemoryAccumulator, AccountStateProof, AccumulatorConsistencyProof,
+        EventProof, SparseMerkleMultiProof, SparseMerkleProof, SparseMerkleRangeProof,
+        TransactionAccumulatorRangeProof, TransactionInfoListWithProof,
     },
     state_proof::StateProof,
     transaction::{
         AccountTransactionsWithProof, TransactionInfo, TransactionListWithProof, TransactionOutput,
-        TransactionOutputListWithProof, TransactionToCommit, TransactionWithProof, Version,
-        PRE_GENESIS_VERSION,
+        TransactionOutputListWithProof, TransactionPayloadType, TransactionToCommit,
+        TransactionWithProof, Version, PRE_GENESIS_VERSION,
     },
+    vm_status::KeptVMStatus,
 };
 use itertools::zip_eq;
 use move_core_types::{
@@ -84,13 +107,20 @@ use move_core_types::{
     resolver::{ModuleResolver, ResourceResolver},
 };
 use once_cell::sync::Lazy;
-use schemadb::{ColumnFamilyName, Options, DB, DEFAULT_CF_NAME};
+use schemadb::{
+    BlockBasedOptions, Cache, ColumnFamilyName, DBCompactionStyle, DBCompressionType, Options, DB,
+    DEFAULT_CF_NAME,
+};
 use std::{
-    collections::HashMap,
+    cell::Cell,
+    collections::{hash_map::Entry, HashMap},
     convert::TryFrom,
     iter::Iterator,
     path::Path,
-    sync::{mpsc, Arc, Mutex},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc, Arc, Mutex,
+    },
     thread::{self, JoinHandle},
     time::{Duration, Instant},
 };
@@ -98,11 +128,17 @@ use storage_interface::{
     DbReader, DbWriter, MoveDbReader, Order, StartupInfo, StateSnapshotReceiver, TreeState,
 };
 
+// Default for `RocksdbConfig::max_request_limit`.
 const MAX_LIMIT: u64 = 5000;
+// Number of events fetched with proofs at a time by `get_events_with_proofs_up_to_bytes`.
+const EVENTS_WITH_PROOF_PAGE_SIZE: u64 = 100;
 
 // TODO: Either implement an iteration API to allow a very old client to loop through a long history
 // or guarantee that there is always a recent enough waypoint and client knows to boot from there.
+// Also the default for `RocksdbConfig::max_epochs_per_state_proof`.
 const MAX_NUM_EPOCH_ENDING_LEDGER_INFO: usize = 100;
+// Number of transactions scanned per write when backfilling an index.
+const BACKFILL_BATCH_SIZE: usize = 10_000;
 static ROCKSDB_PROPERTY_MAP: Lazy<HashMap<&str, String>> = Lazy::new(|| {
     [
         "rocksdb.num-immutable-mem-table",
@@ -158,7 +194,75 @@ fn gen_rocksdb_options(config: &RocksdbConfig) -> Options {
     db_opts
 }
 
-fn update_rocksdb_properties(db: &DB) -> Result<()> {
+/// Creates the block caches of the column families configured with one in `cf_tuning`, upfront
+/// since creating a cache can fail.
+fn gen_cf_block_caches(config: &RocksdbConfig) -> Result<HashMap<String, Cache>> {
+    config
+        .cf_tuning
+        .iter()
+        .filter_map(|(cf_name, tuning)| {
+            tuning
+                .block_cache_size
+                .map(|size| Ok((cf_name.clone(), Cache::new_lru_cache(size)?)))
+        })
+        .collect()
+}
+
+fn customize_cf_options(
+    config: &RocksdbConfig,
+    block_caches: &HashMap<String, Cache>,
+    shared_block_cache: Option<&Cache>,
+    cf_name: ColumnFamilyName,
+    cf_opts: &mut Options,
+) {
+    if let Some(compaction_style) = config.cf_compaction_styles.get(cf_name) {
+        cf_opts.set_compaction_style(match compaction_style {
+            CompactionStyle::Level => DBCompactionStyle::Level,
+            CompactionStyle::Universal => DBCompactionStyle::Universal,
+            CompactionStyle::Fifo => DBCompactionStyle::Fifo,
+        });
+    }
+    let tuning = config.cf_tuning.get(cf_name);
+    if let Some(compression) = tuning.and_then(|tuning| tuning.compression) {
+        cf_opts.set_compression_type(match compression {
+            CompressionType::None => DBCompressionType::None,
+            CompressionType::Snappy => DBCompressionType::Snappy,
+            CompressionType::Lz4 => DBCompressionType::Lz4,
+            CompressionType::Zstd => DBCompressionType::Zstd,
+        });
+    }
+    let block_cache = block_caches.get(cf_name).or(shared_block_cache);
+    let bloom_filter_bits_per_key = tuning.and_then(|tuning| tuning.bloom_filter_bits_per_key);
+    if block_cache.is_some() || bloom_filter_bits_per_key.is_some() {
+        let mut table_opts = BlockBasedOptions::default();
+        if let Some(block_cache) = block_cache {
+            table_opts.set_block_cache(block_cache);
+        }
+        if let Some(bits_per_key) = bloom_filter_bits_per_key {
+            table_opts.set_bloom_filter(bits_per_key, false /* block_based */);
+        }
+        cf_opts.set_block_based_table_factory(&table_opts);
+    }
+}
+
+/// A column family using the shared block cache if there's one, for reading the usage of the
+/// cache from, since the block cache properties of a column family are those of its cache.
+fn shared_block_cache_cf(config: &RocksdbConfig) -> Option<ColumnFamilyName> {
+    if config.shared_block_cache_size.is_none() {
+        return None;
+    }
+    AptosDB::column_families().into_iter().find(|cf_name| {
+        config
+            .cf_tuning
+            .get(*cf_name)
+            .map_or(true, |tuning| tuning.block_cache_size.is_none())
+    })
+}
+
+fn update_rocksdb_properties(
+    db: &DB,
+    shared_block_cache_cf: Option<ColumnFamilyName>,
+) -> Result<()> {
     let _timer = DIEM_STORAGE_OTHER_TIMERS_SECONDS
         .with_label_values(&["update_rocksdb_properties"])
         .start_timer();
@@ -169,6 +273,10 @@ fn update_rocksdb_properties(db: &DB) -> Result<()> {
                 .set(db.get_property(cf_name, rockdb_property_name)? as i64);
         }
     }
+    if let Some(cf_name) = shared_block_cache_cf {
+        DIEM_STORAGE_ROCKSDB_SHARED_BLOCK_CACHE_USAGE_BYTES
+            .set(db.get_property(cf_name, "rocksdb.block-cache-usage")? as i64);
+    }
     Ok(())
 }
 
@@ -176,13 +284,14 @@ fn update_rocksdb_properties(db: &DB) -> Result<()> {
 struct RocksdbPropertyReporter {
     sender: Mutex<mpsc::Sender<()>>,
     join_handle: Option<JoinHandle<()>>,
+    shared_block_cache_cf: Option<ColumnFamilyName>,
 }
 
 impl RocksdbPropertyReporter {
-    fn new(db: Arc<DB>) -> Self {
+    fn new(db: Arc<DB>, shared_block_cache_cf: Option<ColumnFamilyName>) -> Self {
         let (send, recv) = mpsc::channel();
         let join_handle = Some(thread::spawn(move || loop {
-            if let Err(e) = update_rocksdb_properties(&db) {
+            if let Err(e) = update_rocksdb_properties(&db, shared_block_cache_cf) {
                 warn!(
                     error = ?e,
                     "Updating rocksdb property failed."
@@ -198,6 +307,7 @@ impl RocksdbPropertyReporter {
         Self {
             sender: Mutex::new(send),
             join_handle,
+            shared_block_cache_cf,
         }
     }
 }
@@ -214,6 +324,28 @@ impl Drop for RocksdbPropertyReporter {
     }
 }
 
+/// A structured readiness signal of an `AptosDB` instance, see [`AptosDB::health_check`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HealthStatus {
+    /// The version of the latest ledger info, `None` if the DB is not bootstrapped.
+    pub latest_version: Option<Version>,
+    /// The smallest version from which all data is still readable, i.e. not pruned.
+    pub first_viable_version: Version,
+    /// How many versions the pruner is behind its target, `None` if the pruner is disabled.
+    pub pruner_backlog: Option<Version>,
+    /// False if any store is behind by more than the prune window plus one batch of the
+    /// sub-pruner handling it, i.e. the state store and the ledger are judged separately.
+    pub pruner_keeping_up: bool,
+    /// Number of background errors reported by RocksDB across all column families.
+    pub num_background_errors: u64,
+}
+
+impl HealthStatus {
+    pub fn is_healthy(&self) -> bool {
+        self.latest_version.is_some() && self.pruner_keeping_up && self.num_background_errors == 0
+    }
+}
+
 /// This holds a handle to the underlying DB responsible for physical storage and provides APIs for
 /// access to the core Diem data structures.
 #[derive(Debug)]
@@ -226,6 +358,19 @@ pub struct AptosDB {
     system_store: Arc<SystemStore>,
     rocksdb_property_reporter: RocksdbPropertyReporter,
     pruner: Option<Pruner>,
+    write_buffer: Option<aptos_infallible::Mutex<WriteBuffer>>,
+    /// Version of the first transaction this DB is expected to hold. Nonzero for DBs seeded from a
+    /// state snapshot, in which case the pre-genesis state is the snapshot at `genesis_version - 1`.
+    genesis_version: Version,
+    account_counter: AccountCounter,
+    /// Maximum number of items a single read request can ask for, see
+    /// `RocksdbConfig::max_request_limit`.
+    max_request_limit: u64,
+    /// Maximum number of epoch ending ledger infos in a state proof, see
+    /// `RocksdbConfig::max_epochs_per_state_proof`.
+    max_epochs_per_state_proof: usize,
+    #[cfg(any(test, feature = "fuzzing"))]
+    commit_failure_injector: fault_injection::CommitFailureInjector,
 }
 
 impl AptosDB {
@@ -240,32 +385,63 @@ impl AptosDB {
             JELLYFISH_MERKLE_NODE_CF_NAME,
             LEDGER_COUNTERS_CF_NAME,
             STALE_NODE_INDEX_CF_NAME,
+            STATE_RESTORE_PROGRESS_CF_NAME,
             TRANSACTION_CF_NAME,
             TRANSACTION_ACCUMULATOR_CF_NAME,
             TRANSACTION_BY_ACCOUNT_CF_NAME,
             TRANSACTION_BY_HASH_CF_NAME,
+            TRANSACTION_BY_PAYLOAD_TYPE_CF_NAME,
             TRANSACTION_INFO_CF_NAME,
             WRITE_SET_CF_NAME,
         ]
     }
 
-    fn new_with_db(db: DB, storage_pruner_config: StoragePrunerConfig) -> Self {
+    fn new_with_db(
+        db: DB,
+        storage_pruner_config: StoragePrunerConfig,
+        rocksdb_config: &RocksdbConfig,
+        readonly: bool,
+    ) -> Self {
         let db = Arc::new(db);
         let transaction_store = Arc::new(TransactionStore::new(Arc::clone(&db)));
         let event_store = Arc::new(EventStore::new(Arc::clone(&db)));
-        let ledger_store = Arc::new(LedgerStore::new(Arc::clone(&db)));
+        let ledger_store = Arc::new(if readonly {
+            LedgerStore::new_read_only(Arc::clone(&db))
+        } else {
+            LedgerStore::new(Arc::clone(&db))
+        });
         let system_store = Arc::new(SystemStore::new(Arc::clone(&db)));
+        let mut state_store = StateStore::new(Arc::clone(&db));
+        if let Some(capacity) = rocksdb_config.account_state_cache_capacity {
+            if capacity > 0 {
+                state_store = state_store.with_account_state_cache(capacity);
+            }
+        }
+        let state_store = Arc::new(state_store);
 
         AptosDB {
             db: Arc::clone(&db),
             event_store: Arc::clone(&event_store),
             ledger_store: Arc::clone(&ledger_store),
-            state_store: Arc::new(StateStore::new(Arc::clone(&db))),
+            state_store: Arc::clone(&state_store),
             transaction_store: Arc::clone(&transaction_store),
             system_store: Arc::clone(&system_store),
-            rocksdb_property_reporter: RocksdbPropertyReporter::new(Arc::clone(&db)),
+            rocksdb_property_reporter: RocksdbPropertyReporter::new(
+                Arc::clone(&db),
+                // The column family options, including the shared block cache, are only applied
+                // when opening for writing.
+                if readonly {
+                    None
+                } else {
+                    shared_block_cache_cf(rocksdb_config)
+                },
+            ),
             pruner: match storage_pruner_config {
-                NO_OP_STORAGE_PRUNER_CONFIG => None,
+                NO_OP_STORAGE_PRUNER_CONFIG => {
+                    // Nothing is ever pruned, so the whole history is retained.
+                    DIEM_STORAGE_PRUNE_FLOOR.set(0);
+                    None
+                }
                 _ => Some(Pruner::new(
                     Arc::clone(&db),
                     storage_pruner_config,
@@ -274,9 +450,47 @@ impl AptosDB {
                     event_store,
                 )),
             },
+            write_buffer: None,
+            genesis_version: 0,
+            account_counter: AccountCounter::new(state_store),
+            max_request_limit: MAX_LIMIT,
+            max_epochs_per_state_proof: MAX_NUM_EPOCH_ENDING_LEDGER_INFO,
+            #[cfg(any(test, feature = "fuzzing"))]
+            commit_failure_injector: Default::default(),
         }
     }
 
+    /// (For tests only.) Makes the commit after the next `num_commits_to_skip` ones fail as
+    /// described by `failure`, once.
+    #[cfg(any(test, feature = "fuzzing"))]
+    pub fn inject_commit_failure(&self, num_commits_to_skip: usize, failure: CommitFailure) {
+        self.commit_failure_injector
+            .inject(num_commits_to_skip, failure);
+    }
+
+    /// Coalesces consecutive `save_transactions` calls into fewer DB writes according to `config`.
+    /// Saved blocks are neither durable nor visible to readers until flushed, see
+    /// [`DbWriter::wait_for_commit`].
+    pub fn with_write_buffer(mut self, config: WriteBufferConfig) -> Self {
+        self.write_buffer = Some(aptos_infallible::Mutex::new(WriteBuffer::new(config)));
+        self
+    }
+
+    /// Sets the version of the first transaction in this DB, for DBs seeded from a state snapshot
+    /// at `genesis_version - 1` and the frozen subtrees of the transaction accumulator at
+    /// `genesis_version` rather than bootstrapped from a genesis transaction at version 0.
+    pub fn with_genesis_version(mut self, genesis_version: Version) -> Self {
+        self.genesis_version = genesis_version;
+        self
+    }
+
+    /// Returns the number of accounts as of the last count done in the background after a commit,
+    /// without walking the state tree. The result may lag behind the latest version and is `None`
+    /// until the first count following the first commit since open finishes.
+    pub fn latest_account_count_cached(&self) -> Option<usize> {
+        self.account_counter.get_latest_count()
+    }
+
     pub fn open<P: AsRef<Path> + Clone>(
         db_root_path: P,
         readonly: bool,
@@ -288,30 +502,56 @@ impl AptosDB {
             "Do not set prune_window when opening readonly.",
         );
 
+        let column_families = Self::column_families();
+        for cf_name in rocksdb_config.cf_compaction_styles.keys() {
+            ensure!(
+                column_families.contains(&cf_name.as_str()),
+                "Unknown column family in cf_compaction_styles: {}",
+                cf_name,
+            );
+        }
+        for cf_name in rocksdb_config.cf_tuning.keys() {
+            ensure!(
+                column_families.contains(&cf_name.as_str()),
+                "Unknown column family in cf_tuning: {}",
+                cf_name,
+            );
+        }
+
         let path = db_root_path.as_ref().join("aptosdb");
         let instant = Instant::now();
 
         let mut rocksdb_opts = gen_rocksdb_options(&rocksdb_config);
 
         let db = if readonly {
-            DB::open_readonly(
-                path.clone(),
-                "aptosdb_ro",
-                Self::column_families(),
-                &rocksdb_opts,
-            )?
+            DB::open_readonly(path.clone(), "aptosdb_ro", column_families, &rocksdb_opts)?
         } else {
             rocksdb_opts.create_if_missing(true);
             rocksdb_opts.create_missing_column_families(true);
-            DB::open(
+            let block_caches = gen_cf_block_caches(&rocksdb_config)?;
+            let shared_block_cache = rocksdb_config
+                .shared_block_cache_size
+                .map(Cache::new_lru_cache)
+                .transpose()?;
+            DB::open_with_cf_opts(
                 path.clone(),
                 "aptosdb",
-                Self::column_families(),
+                column_families,
                 &rocksdb_opts,
+                |cf_name, cf_opts| {
+                    customize_cf_options(
+                        &rocksdb_config,
+                        &block_caches,
+                        shared_block_cache.as_ref(),
+                        cf_name,
+                        cf_opts,
+                    )
+                },
             )?
         };
 
-        let ret = Self::new_with_db(db, storage_pruner_config);
+        let mut ret = Self::new_with_db(db, storage_pruner_config, &rocksdb_config, readonly);
+        ret.set_read_limits(&rocksdb_config)?;
         info!(
             path = path,
             time_ms = %instant.elapsed().as_millis(),
@@ -331,7 +571,7 @@ impl AptosDB {
         rocksdb_config.max_open_files = -1;
         let rocksdb_opts = gen_rocksdb_options(&rocksdb_config);
 
-        Ok(Self::new_with_db(
+        let mut ret = Self::new_with_db(
             DB::open_as_secondary(
                 primary_path,
                 secondary_path,
@@ -340,7 +580,87 @@ impl AptosDB {
                 &rocksdb_opts,
             )?,
             NO_OP_STORAGE_PRUNER_CONFIG,
-        ))
+            &rocksdb_config,
+            true, /* readonly */
+        );
+        ret.set_read_limits(&rocksdb_config)?;
+        Ok(ret)
+    }
+
+    /// Applies the per-request limits configured in `rocksdb_config`.
+    fn set_read_limits(&mut self, rocksdb_config: &RocksdbConfig) -> Result<()> {
+        self.max_request_limit = rocksdb_config.max_request_limit.unwrap_or(MAX_LIMIT);
+        ensure!(self.max_request_limit > 0, "max_request_limit should > 0.");
+        self.max_epochs_per_state_proof = rocksdb_config
+            .max_epochs_per_state_proof
+            .unwrap_or(MAX_NUM_EPOCH_ENDING_LEDGER_INFO);
+        ensure!(
+            self.max_epochs_per_state_proof > 0,
+            "max_epochs_per_state_proof should > 0."
+        );
+        Ok(())
+    }
+
+    /// Synchronously prunes everything committed before `unix_micros`, see `prune_up_to`. The
+    /// timestamp is translated into a version via the `NewBlockEvent`s, so there must be a block
+    /// proposed before and one at or after it, and the resulting version is clamped to the
+    /// configured prune windows. Returns the resolved version, below which everything is pruned,
+    /// or the current first readable version if pruning is disabled.
+    pub fn prune_before_timestamp(&self, unix_micros: u64) -> Result<Version> {
+        gauged_api("prune_before_timestamp", || {
+            let (pruner, latest_version) = match (
+                self.pruner.as_ref(),
+                self.ledger_store.get_latest_transaction_info_option()?,
+            ) {
+                (Some(pruner), Some((latest_version, _))) => (pruner, latest_version),
+                _ => return self.get_first_readable_version(),
+            };
+            let last_version_before_timestamp = self
+                .event_store
+                .get_last_version_before_timestamp(unix_micros, latest_version)?;
+            let target_version = std::cmp::min(
+                last_version_before_timestamp + 1,
+                pruner.get_max_target_version(latest_version),
+            );
+            pruner.prune_up_to(latest_version, target_version)?;
+            Ok(target_version)
+        })
+    }
+
+    /// Returns the handle of the underlying RocksDB instance, for advanced tooling like custom
+    /// iterators or ingesting external SST files.
+    ///
+    /// Use with great care: nothing done through the handle is known to `AptosDB`. Writes through
+    /// it bypass all the invariants maintained by `AptosDB`, e.g. the accumulators, the indices,
+    /// the ledger counters and the pruner progress, and can easily corrupt the DB.
+    #[cfg(any(test, feature = "raw-db-access"))]
+    pub fn rocksdb_handle(&self) -> Arc<DB> {
+        Arc::clone(&self.db)
+    }
+
+    /// Synchronously prunes everything below `target_version`, e.g. to reclaim disk space during a
+    /// maintenance window, returning the new first readable version (see
+    /// `DbReader::get_first_readable_version`). Errors if `target_version` is within the
+    /// configured prune windows of the latest version. A no-op if pruning is disabled.
+    pub fn prune_up_to(&self, target_version: Version) -> Result<Version> {
+        gauged_api("prune_up_to", || {
+            if let Some(pruner) = self.pruner.as_ref() {
+                if let Some((latest_version, _)) =
+                    self.ledger_store.get_latest_transaction_info_option()?
+                {
+                    pruner.prune_up_to(latest_version, target_version)?;
+                }
+            }
+            self.get_first_readable_version()
+        })
+    }
+
+    /// Makes the writes to the primary instance since the last catch-up visible to this secondary
+    /// instance, see [`AptosDB::open_as_secondary`].
+    pub fn try_catch_up_with_primary(&self) -> Result<()> {
+        gauged_api("try_catch_up_with_primary", || {
+            self.db.try_catch_up_with_primary()
+        })
     }
 
     /// This opens db in non-readonly mode, without the pruner.
@@ -355,9 +675,464 @@ impl AptosDB {
         .expect("Unable to open AptosDB")
     }
 
+    /// Returns the serialized Jellyfish Merkle node stored under the encoded `node_key_bytes` as is,
+    /// to help diagnose state tree corruption.
+    pub fn get_jmt_node_raw(&self, node_key_bytes: &[u8]) -> Result<Option<Vec<u8>>> {
+        gauged_api("get_jmt_node_raw", || {
+            self.db
+                .get_raw(JELLYFISH_MERKLE_NODE_CF_NAME, node_key_bytes)
+        })
+    }
+
+    /// Same as [`AptosDB::get_jmt_node_raw`], but decodes the node.
+    pub fn get_jmt_node_decoded(
+        &self,
+        node_key_bytes: &[u8],
+    ) -> Result<Option<Node<AccountStateBlob>>> {
+        self.get_jmt_node_raw(node_key_bytes)?
+            .map(|raw_node| Node::decode(&raw_node))
+            .transpose()
+    }
+
+    /// Returns `(epoch, first_version, last_version, epoch_ending_ledger_info)` for each epoch in
+    /// `[start_epoch, end_epoch)`. The latest epoch is excluded if it's still open.
+    pub fn get_epoch_ranges(
+        &self,
+        start_epoch: u64,
+        end_epoch: u64,
+    ) -> Result<Vec<(u64, Version, Version, LedgerInfoWithSignatures)>> {
+        gauged_api("get_epoch_ranges", || {
+            let latest_epoch = match self.ledger_store.get_latest_ledger_info_option()? {
+                Some(li) => li.ledger_info().next_block_epoch(),
+                None => return Ok(Vec::new()),
+            };
+            let end_epoch = std::cmp::min(end_epoch, latest_epoch);
+            if start_epoch >= end_epoch {
+                return Ok(Vec::new());
+            }
+            error_if_too_many_requested(
+                end_epoch - start_epoch,
+                MAX_NUM_EPOCH_ENDING_LEDGER_INFO as u64,
+            )?;
+
+            let mut first_version = if start_epoch == 0 {
+                0
+            } else {
+                self.ledger_store
+                    .get_latest_ledger_info_in_epoch(start_epoch - 1)?
+                    .ledger_info()
+                    .version()
+                    + 1
+            };
+            let (lis, _) = self.get_epoch_ending_ledger_infos(start_epoch, end_epoch)?;
+            Ok(lis
+                .into_iter()
+                .map(|li| {
+                    let last_version = li.ledger_info().version();
+                    let range = (li.ledger_info().epoch(), first_version, last_version, li);
+                    first_version = last_version + 1;
+                    range
+                })
+                .collect())
+        })
+    }
+
+    /// Checks the state tree at `version` by recomputing its root hash from all the leaf values,
+    /// in memory, and comparing the result to the stored root hash. Meant for checking a DB after
+    /// a restore; the whole tree is read, so this is slow for large states.
+    pub fn verify_state_root(&self, version: Version) -> Result<()> {
+        gauged_api("verify_state_root", || {
+            let stored_root_hash = self.state_store.get_root_hash(version)?;
+            let recomputed_root_hash = self.state_store.recompute_root_hash(version)?;
+            if stored_root_hash != recomputed_root_hash {
+                return Err(anyhow::Error::from(AptosDbError::RootHashMismatch(
+                    stored_root_hash,
+                    recomputed_root_hash,
+                ))
+                .context(format!(
+                    "State root at version {} recomputed from leaves",
+                    version
+                )));
+            }
+            Ok(())
+        })
+    }
+
+    /// Checks that the data of versions in `[start_version, end_version)` is mutually consistent:
+    /// that each transaction info is in the transaction accumulator and matches the transaction,
+    /// that its event root hash matches the stored events and that its state root hash matches
+    /// the state tree. Meant for operators suspecting DB corruption. Fails with
+    /// `AptosDbError::Inconsistent` naming the version and subsystem of the first inconsistency
+    /// found.
+    pub fn verify_integrity(&self, start_version: Version, end_version: Version) -> Result<()> {
+        gauged_api("verify_integrity", || {
+            ensure!(
+                start_version <= end_version,
+                "Bad version range [{}, {})",
+                start_version,
+                end_version,
+            );
+            if start_version == end_version {
+                return Ok(());
+            }
+            let latest_version = self.get_latest_version()?;
+            ensure!(
+                end_version - 1 <= latest_version,
+                AptosDbError::VersionTooNew(end_version - 1, latest_version),
+            );
+            self.error_if_ledger_pruned(start_version)?;
+            self.error_if_state_pruned(start_version)?;
+
+            let inconsistent = |version, subsystem, reason: String| {
+                Err(AptosDbError::Inconsistent(version, subsystem, reason).into())
+            };
+            let accumulator_root_hash = self.ledger_store.get_root_hash(latest_version)?;
+            for version in start_version..end_version {
+                let txn_info_with_proof = self
+                    .ledger_store
+                    .get_transaction_info_with_proof(version, latest_version)?;
+                let txn_info = &txn_info_with_proof.transaction_info;
+                if let Err(err) = txn_info_with_proof
+                    .ledger_info_to_transaction_info_proof
+                    .verify(accumulator_root_hash, txn_info.hash(), version)
+                {
+                    return inconsistent(version, "transaction accumulator", err.to_string());
+                }
+
+                let txn_hash = self.transaction_store.get_transaction(version)?.hash();
+                if txn_hash != txn_info.transaction_hash() {
+                    return inconsistent(
+                        version,
+                        "transaction",
+                        format!(
+                            "hash {} differs from {} in the transaction info",
+                            txn_hash,
+                            txn_info.transaction_hash()
+                        ),
+                    );
+                }
+
+                let event_hashes: Vec<_> = self
+                    .event_store
+                    .get_events_by_version(version)?
+                    .iter()
+                    .map(CryptoHash::hash)
+                    .collect();
+                let event_root_hash =
+                    InMemoryAccumulator::<EventAccumulatorHasher>::from_leaves(&event_hashes)
+                        .root_hash();
+                if event_root_hash != txn_info.event_root_hash() {
+                    return inconsistent(
+                        version,
+                        "event accumulator",
+                        format!(
+                            "root hash {} differs from {} in the transaction info",
+                            event_root_hash,
+                            txn_info.event_root_hash()
+                        ),
+                    );
+                }
+
+                let state_root_hash = self.state_store.get_root_hash(version)?;
+                if state_root_hash != txn_info.state_change_hash() {
+                    return inconsistent(
+                        version,
+                        "state tree",
+                        format!(
+                            "root hash {} differs from {} in the transaction info",
+                            state_root_hash,
+                            txn_info.state_change_hash()
+                        ),
+                    );
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Gets the proof that there are no leaves with index greater than `after_index` in the state
+    /// tree at `version`, letting a state syncing client that has received the leaf at
+    /// `after_index` confirm it has the whole tree: after verifying the returned proof against
+    /// the root hash with that leaf as the rightmost known leaf, the client checks that all right
+    /// siblings are `SPARSE_MERKLE_PLACEHOLDER_HASH`. Fails with `AptosDbError::NotFound` if
+    /// `after_index` is not less than the number of leaves.
+    pub fn get_state_tail_proof(
+        &self,
+        version: Version,
+        after_index: u64,
+    ) -> Result<SparseMerkleRangeProof> {
+        gauged_api("get_state_tail_proof", || {
+            self.error_if_state_pruned(version)?;
+            self.state_store
+                .get_state_tail_proof(version, after_index as usize)
+        })
+    }
+
+    /// Gets the account state blobs of `addresses` at `version`, together with a single proof
+    /// authenticating all of them against the state root hash, which shares the siblings common
+    /// to the paths of the accounts and is thus much smaller than the individual proofs. The blobs
+    /// are returned in ascending order of the hashed addresses, without duplicates, which is the
+    /// order `SparseMerkleMultiProof::verify` expects.
+    pub fn get_multiproof(
+        &self,
+        addresses: &[AccountAddress],
+        version: Version,
+    ) -> Result<(
+        Vec<(AccountAddress, Option<AccountStateBlob>)>,
+        SparseMerkleMultiProof<AccountStateBlob>,
+    )> {
+        gauged_api("get_multiproof", || {
+            error_if_too_many_requested(addresses.len() as u64, self.max_request_limit)?;
+            self.error_if_state_pruned(version)?;
+
+            let mut addresses = addresses.to_vec();
+            addresses.sort_by_key(|address| address.hash());
+            addresses.dedup();
+
+            let mut account_states = Vec::with_capacity(addresses.len());
+            let mut proofs = Vec::with_capacity(addresses.len());
+            for address in addresses {
+                let (blob, proof) = self
+                    .state_store
+                    .get_account_state_with_proof_by_version(address, version)?;
+                account_states.push((address, blob));
+                proofs.push((address.hash(), proof));
+            }
+            let multiproof = SparseMerkleMultiProof::from_proofs(&proofs)?;
+            Ok((account_states, multiproof))
+        })
+    }
+
+    /// Returns the on-chain protocol `Version` config in effect from genesis, followed by each
+    /// change to it, paired with the version of the reconfiguration (the epoch ending version)
+    /// that made it take effect. Config changes only take effect at epoch boundaries, so only the
+    /// state at epoch ending versions is read, which fails if any of those has been pruned.
+    pub fn get_version_config_history(&self) -> Result<Vec<(Version, OnChainVersion)>> {
+        gauged_api("get_version_config_history", || {
+            let latest_epoch = match self.ledger_store.get_latest_ledger_info_option()? {
+                Some(li) => li.ledger_info().next_block_epoch(),
+                None => return Ok(Vec::new()),
+            };
+
+            let mut history: Vec<(Version, OnChainVersion)> = Vec::new();
+            for li in self
+                .ledger_store
+                .get_epoch_ending_ledger_info_iter(0, latest_epoch)?
+            {
+                let version = li?.ledger_info().version();
+                let config = match self
+                    .state_store
+                    .get_account_state_with_proof_by_version(config_address(), version)?
+                    .0
+                {
+                    Some(blob) => AccountState::try_from(&blob)?.get_config::<OnChainVersion>()?,
+                    None => None,
+                };
+                if let Some(config) = config {
+                    if history.last().map_or(true, |(_, prev)| prev != &config) {
+                        history.push((version, config));
+                    }
+                }
+            }
+            Ok(history)
+        })
+    }
+
+    /// Returns the versions of the user transactions with `payload_type`, starting at
+    /// `start_version`, at most `limit` of them, in ascending order. Transactions committed before
+    /// the index was introduced are only found once
+    /// [`Self::backfill_transaction_by_payload_type_index`] has been run.
+    pub fn get_transaction_versions_by_payload_type(
+        &self,
+        payload_type: TransactionPayloadType,
+        start_version: Version,
+        limit: u64,
+    ) -> Result<Vec<Version>> {
+        gauged_api("get_transaction_versions_by_payload_type", || {
+            error_if_too_many_requested(limit, self.max_request_limit)?;
+            let ledger_version = match self.ledger_store.get_latest_ledger_info_option()? {
+                Some(li) => li.ledger_info().version(),
+                None => return Ok(Vec::new()),
+            };
+            self.error_if_ledger_pruned(start_version)?;
+
+            self.transaction_store
+                .get_transaction_versions_by_payload_type(
+                    payload_type,
+                    start_version,
+                    limit,
+                    ledger_version,
+                )
+        })
+    }
+
+    /// Indexes the transactions already in the DB by payload type. A DB created before the index
+    /// was introduced only has entries for the transactions committed since, so this should be run
+    /// once over it, with the ledger pruner disabled, before relying on
+    /// [`Self::get_transaction_versions_by_payload_type`]. Running it again is harmless. Returns
+    /// the number of user transactions indexed.
+    pub fn backfill_transaction_by_payload_type_index(&self) -> Result<u64> {
+        gauged_api("backfill_transaction_by_payload_type_index", || {
+            self.transaction_store
+                .backfill_transaction_by_payload_type(BACKFILL_BATCH_SIZE)
+        })
+    }
+
+    /// Returns the versions in `[from_version, to_version]` at which the state of `address` was
+    /// modified. Unlike [`DbReader::get_account_transactions`], which lists the transactions sent
+    /// by the account, this is derived from the write sets and so also includes transactions sent
+    /// by other accounts.
+    pub fn get_account_state_change_versions(
+        &self,
+        address: AccountAddress,
+        from_version: Version,
+        to_version: Version,
+    ) -> Result<Vec<Version>> {
+        gauged_api("get_account_state_change_versions", || {
+            if from_version > to_version {
+                return Ok(Vec::new());
+            }
+            error_if_too_many_requested(
+                (to_version - from_version).saturating_add(1),
+                self.max_request_limit,
+            )?;
+
+            self.transaction_store.get_account_state_change_versions(
+                address,
+                from_version,
+                to_version,
+            )
+        })
+    }
+
+    /// Estimates how many bytes on disk are taken by data the pruner is due to remove but hasn't
+    /// removed yet, i.e. how much space catching up with pruning would reclaim. Returns 0 if the
+    /// pruner is disabled.
+    ///
+    /// Stale jellyfish merkle nodes are counted via the stale node index and sized by the average
+    /// node size, while other stores are assumed to spread their SST size evenly over versions.
+    pub fn get_prunable_bytes_estimate(&self) -> Result<u64> {
+        gauged_api("get_prunable_bytes_estimate", || {
+            let pruner = match self.pruner.as_ref() {
+                Some(pruner) => pruner,
+                None => return Ok(0),
+            };
+            let latest_version = match self.ledger_store.get_latest_transaction_info_option()? {
+                Some((version, _)) => version,
+                None => return Ok(0),
+            };
+            let ranges = pruner.get_prunable_version_ranges(latest_version);
+
+            // Nodes stale since a version up to the least readable one are already pruned (but
+            // the index entries might not be purged yet).
+            let (state_least_readable_version, state_target_version) = ranges[0];
+            let num_stale_nodes = self
+                .state_store
+                .get_num_stale_nodes(state_least_readable_version + 1, state_target_version)?;
+            let mut prunable_bytes =
+                num_stale_nodes * self.get_average_entry_bytes(JELLYFISH_MERKLE_NODE_CF_NAME)?;
+
+            let num_versions = latest_version + 1;
+            for ((begin, end), cf_names) in ranges[1..]
+                .iter()
+                .zip(VERSIONED_PRUNER_COLUMN_FAMILIES.iter())
+            {
+                if end <= begin {
+                    continue;
+                }
+                for cf_name in cf_names.iter() {
+                    let sst_bytes = self
+                        .db
+                        .get_property(cf_name, "rocksdb.total-sst-files-size")?;
+                    prunable_bytes += (sst_bytes as u128 * (end - begin) as u128
+                        / (num_versions - begin) as u128)
+                        as u64;
+                }
+            }
+
+            Ok(prunable_bytes)
+        })
+    }
+
+    fn get_average_entry_bytes(&self, cf_name: ColumnFamilyName) -> Result<u64> {
+        let sst_bytes = self
+            .db
+            .get_property(cf_name, "rocksdb.total-sst-files-size")?;
+        let num_keys = self.db.get_property(cf_name, "rocksdb.estimate-num-keys")?;
+        Ok(if num_keys == 0 {
+            0
+        } else {
+            sst_bytes / num_keys
+        })
+    }
+
     /// This force the db to update rocksdb properties immediately.
     pub fn update_rocksdb_properties(&self) -> Result<()> {
-        update_rocksdb_properties(&self.db)
+        update_rocksdb_properties(
+            &self.db,
+            self.rocksdb_property_reporter.shared_block_cache_cf,
+        )
+    }
+
+    /// Returns true if RocksDB has stopped accepting writes, e.g. because too many memtables or L0
+    /// files are pending flush or compaction. Callers can use it to apply backpressure before
+    /// commits start timing out.
+    pub fn is_write_stalled(&self) -> Result<bool> {
+        Ok(self
+            .db
+            .get_property(DEFAULT_CF_NAME, "rocksdb.is-write-stopped")?
+            != 0)
+    }
+
+    /// Returns the rate (in bytes per second) RocksDB currently throttles writes to, or 0 if
+    /// writes are not being delayed.
+    pub fn current_delayed_write_rate(&self) -> Result<u64> {
+        self.db
+            .get_property(DEFAULT_CF_NAME, "rocksdb.actual-delayed-write-rate")
+    }
+
+    /// Guarantees `version` stays readable until the returned lease is dropped, e.g. for the
+    /// duration of a long-running query. Nothing newer than the oldest pinned version is pruned
+    /// while the lease is held, so disk usage keeps growing until it's dropped.
+    pub fn pin_version(&self, version: Version) -> Result<VersionLease> {
+        match self.pruner.as_ref() {
+            Some(pruner) => pruner.pin_version(version),
+            None => Ok(VersionLease::new_unpinned(version)),
+        }
+    }
+
+    /// Cheap, read-only probe of the DB health, meant for load balancers fronting read replicas.
+    pub fn health_check(&self) -> Result<HealthStatus> {
+        gauged_api("health_check", || {
+            let latest_version = self
+                .ledger_store
+                .get_latest_ledger_info_option()?
+                .map(|li| li.ledger_info().version());
+            let (first_viable_version, pruner_backlog, pruner_keeping_up) = match &self.pruner {
+                Some(pruner) => {
+                    let latest_version = latest_version.unwrap_or(0);
+                    (
+                        pruner.get_least_readable_version(),
+                        Some(pruner.get_pruner_backlog(latest_version)),
+                        pruner.is_keeping_up(latest_version),
+                    )
+                }
+                None => (0, None, true),
+            };
+            let first_viable_version = std::cmp::max(first_viable_version, self.genesis_version);
+            let num_background_errors = Self::column_families()
+                .into_iter()
+                .map(|cf_name| self.db.get_property(cf_name, "rocksdb.background-errors"))
+                .sum::<Result<u64>>()?;
+
+            Ok(HealthStatus {
+                latest_version,
+                first_viable_version,
+                pruner_backlog,
+                pruner_keeping_up,
+                num_background_errors,
+            })
+        })
     }
 
     /// Returns ledger infos reflecting epoch bumps starting with the given epoch. If there are no
@@ -376,12 +1151,8 @@ impl AptosDB {
         )
     }
 
-    fn get_epoch_ending_ledger_infos_impl(
-        &self,
-        start_epoch: u64,
-        end_epoch: u64,
-        limit: usize,
-    ) -> Result<(Vec<LedgerInfoWithSignatures>, bool)> {
+    /// Errors unless `[start_epoch, end_epoch)` is a valid range of epochs that have ended.
+    fn ensure_closed_epoch_range(&self, start_epoch: u64, end_epoch: u64) -> Result<()> {
         ensure!(
             start_epoch <= end_epoch,
             "Bad epoch range [{}, {})",
@@ -401,6 +1172,16 @@ impl AptosDB {
             end_epoch,
             latest_epoch - 1,  // okay to -1 because genesis LedgerInfo has .next_block_epoch() == 1
         );
+        Ok(())
+    }
+
+    fn get_epoch_ending_ledger_infos_impl(
+        &self,
+        start_epoch: u64,
+        end_epoch: u64,
+        limit: usize,
+    ) -> Result<(Vec<LedgerInfoWithSignatures>, bool)> {
+        self.ensure_closed_epoch_range(start_epoch, end_epoch)?;
 
         let (paging_epoch, more) = if end_epoch - start_epoch > limit as u64 {
             (start_epoch + limit as u64, true)
@@ -422,20 +1203,189 @@ impl AptosDB {
         Ok((lis, more))
     }
 
-    fn get_transaction_with_proof(
+    /// Same as `DbReader::get_state_proof_with_ledger_info`, but the `EpochChangeProof` carries at
+    /// most `max_epochs_per_proof` ledger infos (further capped by
+    /// `RocksdbConfig::max_epochs_per_state_proof`), with `more` set when truncated. Useful for
+    /// clients on constrained links that prefer smaller proofs over fewer round trips.
+    pub fn get_state_proof_with_ledger_info_and_limit(
         &self,
-        version: Version,
-        ledger_version: Version,
-        fetch_events: bool,
-    ) -> Result<TransactionWithProof> {
-        let proof = self
-            .ledger_store
-            .get_transaction_info_with_proof(version, ledger_version)?;
-        let transaction = self.transaction_store.get_transaction(version)?;
+        known_version: u64,
+        ledger_info_with_sigs: LedgerInfoWithSignatures,
+        max_epochs_per_proof: Option<usize>,
+    ) -> Result<StateProof> {
+        gauged_api("get_state_proof_with_ledger_info_and_limit", || {
+            let limit = max_epochs_per_proof.map_or(self.max_epochs_per_state_proof, |max| {
+                std::cmp::min(max, self.max_epochs_per_state_proof)
+            });
+            ensure!(limit > 0, "max_epochs_per_proof should > 0.");
+            self.get_state_proof_with_ledger_info_impl(known_version, ledger_info_with_sigs, limit)
+        })
+    }
 
-        // If events were requested, also fetch those.
-        let events = if fetch_events {
-            Some(self.event_store.get_events_by_version(version)?)
+    fn get_state_proof_with_ledger_info_impl(
+        &self,
+        known_version: u64,
+        ledger_info_with_sigs: LedgerInfoWithSignatures,
+        max_epochs_per_proof: usize,
+    ) -> Result<StateProof> {
+        let ledger_info = ledger_info_with_sigs.ledger_info();
+        ensure!(
+            known_version <= ledger_info.version(),
+            AptosDbError::VersionTooNew(known_version, ledger_info.version()),
+        );
+        let known_epoch = self.ledger_store.get_epoch(known_version)?;
+        let end_epoch = ledger_info.next_block_epoch();
+        let epoch_change_proof = if known_epoch < end_epoch {
+            let (ledger_infos_with_sigs, more) = self.get_epoch_ending_ledger_infos_impl(
+                known_epoch,
+                end_epoch,
+                max_epochs_per_proof,
+            )?;
+            EpochChangeProof::new(ledger_infos_with_sigs, more)
+        } else {
+            EpochChangeProof::new(vec![], /* more = */ false)
+        };
+
+        // Only return a consistency proof up to the verifiable end LI. If a
+        // client still needs to sync more epoch change LI's, then they cannot
+        // verify the latest LI nor verify a consistency proof up to the latest
+        // LI. If the client needs more epochs, we just return the consistency
+        // proof up to the last epoch change LI.
+        let verifiable_li = if epoch_change_proof.more {
+            epoch_change_proof
+                .ledger_info_with_sigs
+                .last()
+                .ok_or_else(|| format_err!(
+                    "No epoch changes despite claiming the client needs to sync more epochs: known_epoch={}, end_epoch={}",
+                    known_epoch, end_epoch,
+                ))?
+                .ledger_info()
+        } else {
+            ledger_info
+        };
+
+        let consistency_proof = self
+            .ledger_store
+            .get_consistency_proof(Some(known_version), verifiable_li.version())?;
+        Ok(StateProof::new(
+            ledger_info_with_sigs,
+            epoch_change_proof,
+            consistency_proof,
+        ))
+    }
+
+    /// Returns the version of the first transaction of the block containing `version`, together
+    /// with a range proof covering all transactions in that block towards the latest ledger info.
+    /// A block is bounded by `BlockMetadata` transactions, so together with the transaction infos
+    /// of the block this authenticates that the transaction at `version` belongs to the block.
+    pub fn get_transaction_block_membership_proof(
+        &self,
+        version: Version,
+    ) -> Result<(Version, TransactionAccumulatorRangeProof)> {
+        gauged_api("get_transaction_block_membership_proof", || {
+            let ledger_version = self.get_latest_version()?;
+            ensure!(
+                version <= ledger_version,
+                AptosDbError::VersionTooNew(version, ledger_version),
+            );
+
+            // Transactions preceding the first block metadata transaction belong to genesis.
+            let block_start_version = self
+                .transaction_store
+                .get_block_metadata(version)?
+                .map_or(0, |(v, _block_meta)| v);
+            let num_txns = self
+                .transaction_store
+                .get_num_transactions_in_block(block_start_version, ledger_version)?;
+            let proof = self.ledger_store.get_transaction_range_proof(
+                Some(block_start_version),
+                num_txns,
+                ledger_version,
+            )?;
+
+            Ok((block_start_version, proof))
+        })
+    }
+
+    fn get_account_transactions_impl(
+        &self,
+        address: AccountAddress,
+        start_seq_num: u64,
+        order: Order,
+        limit: u64,
+        include_events: &dyn Fn(Version) -> bool,
+        ledger_version: Version,
+    ) -> Result<AccountTransactionsWithProof> {
+        error_if_too_many_requested(limit, self.max_request_limit)?;
+        if limit == 0 {
+            return Ok(AccountTransactionsWithProof::new_empty());
+        }
+
+        let cursor = if order == Order::Descending && start_seq_num == u64::max_value() {
+            // Caller wants the latest, figure out the latest seq_num.
+            match self
+                .transaction_store
+                .get_latest_account_sequence_number(address, ledger_version)?
+            {
+                Some(seq_num) => seq_num,
+                None => return Ok(AccountTransactionsWithProof::new_empty()),
+            }
+        } else {
+            start_seq_num
+        };
+
+        // Convert requested range and order to a range in ascending order.
+        let (first_seq_num, real_limit) = get_first_seq_num_and_limit(order, cursor, limit)?;
+
+        let mut txn_versions = self
+            .transaction_store
+            .get_account_transaction_version_iter(
+                address,
+                first_seq_num,
+                real_limit,
+                ledger_version,
+            )?
+            .collect::<Result<Vec<_>>>()?;
+
+        // When descending, a cursor beyond the latest sequence number is a bad request, as it is
+        // for events, so return an empty list rather than the tail of the range.
+        if order == Order::Descending {
+            if let Some((seq_num, _)) = txn_versions.last() {
+                if *seq_num < cursor {
+                    txn_versions = Vec::new();
+                }
+            }
+        }
+
+        let txns_with_proofs = txn_versions
+            .into_iter()
+            .map(|(_seq_num, txn_version)| {
+                self.get_transaction_with_proof(
+                    txn_version,
+                    ledger_version,
+                    include_events(txn_version),
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(AccountTransactionsWithProof::new(txns_with_proofs))
+    }
+
+    fn get_transaction_with_proof(
+        &self,
+        version: Version,
+        ledger_version: Version,
+        fetch_events: bool,
+    ) -> Result<TransactionWithProof> {
+        self.error_if_ledger_pruned(version)?;
+        let proof = self
+            .ledger_store
+            .get_transaction_info_with_proof(version, ledger_version)?;
+        let transaction = self.transaction_store.get_transaction(version)?;
+
+        // If events were requested, also fetch those.
+        let events = if fetch_events {
+            Some(self.event_store.get_events_by_version(version)?)
         } else {
             None
         };
@@ -448,6 +1398,109 @@ impl AptosDB {
         })
     }
 
+    /// Lazily reads up to `limit` transactions starting at `start_version`, with proofs against
+    /// `ledger_version` but without events, one version at a time, so that consumers of large
+    /// ranges can bound their own memory. Stops at `ledger_version` even if `limit` runs past it.
+    /// DB errors are yielded as `Err` items.
+    pub fn get_transaction_iter(
+        &self,
+        start_version: Version,
+        limit: u64,
+        ledger_version: Version,
+    ) -> Result<impl Iterator<Item = Result<TransactionWithProof>> + '_> {
+        let latest_version = self.get_latest_version()?;
+        ensure!(
+            ledger_version <= latest_version,
+            AptosDbError::VersionTooNew(ledger_version, latest_version),
+        );
+        self.error_if_ledger_pruned(start_version)?;
+
+        let end_version = std::cmp::min(
+            start_version.saturating_add(limit),
+            ledger_version.saturating_add(1),
+        );
+        Ok((start_version..end_version)
+            .map(move |version| self.get_transaction_with_proof(version, ledger_version, false)))
+    }
+
+    /// Lazily walks all the account states at `version` in key order, without proofs, e.g. for
+    /// dumping the whole state. Newer commits don't affect the snapshot, and the version is pinned
+    /// against pruning while the iterator is alive.
+    pub fn get_state_key_iter(
+        &self,
+        version: Version,
+    ) -> Result<impl Iterator<Item = Result<(HashValue, AccountStateBlob)>>> {
+        let latest_version = self.get_latest_version()?;
+        ensure!(
+            version <= latest_version,
+            AptosDbError::VersionTooNew(version, latest_version),
+        );
+        self.error_if_state_pruned(version)?;
+        let lease = self.pin_version(version)?;
+
+        let iter = JellyfishMerkleIterator::new(
+            Arc::clone(&self.state_store),
+            version,
+            HashValue::zero(),
+        )?;
+        Ok(iter.map(move |res| {
+            // Keeps the version pinned for as long as the iterator lives.
+            let _lease = &lease;
+            res
+        }))
+    }
+
+    /// Reads the modules `module_ids` at the latest version, in the order requested, loading the
+    /// state of each account involved only once no matter how many of its modules are requested.
+    pub fn get_modules(&self, module_ids: &[ModuleId]) -> Result<Vec<Option<Vec<u8>>>> {
+        error_if_too_many_requested(module_ids.len() as u64, self.max_request_limit)?;
+        let version = self.get_latest_version()?;
+        let mut account_states = HashMap::new();
+        for module_id in module_ids {
+            let address = *module_id.address();
+            if let Entry::Vacant(entry) = account_states.entry(address) {
+                let (blob, _proof) =
+                    self.get_account_state_with_proof_by_version(address, version)?;
+                entry.insert(blob.as_ref().map(AccountState::try_from).transpose()?);
+            }
+        }
+
+        Ok(module_ids
+            .iter()
+            .map(|module_id| {
+                account_states[module_id.address()]
+                    .as_ref()
+                    .and_then(|account_state| account_state.get(&module_id.access_vector()))
+                    .cloned()
+            })
+            .collect())
+    }
+
+    /// Reads the module `module_id` as of `version`, e.g. to resolve the dependencies of a
+    /// transaction being replayed as they were when it was committed.
+    pub fn get_module_at_version(
+        &self,
+        module_id: &ModuleId,
+        version: Version,
+    ) -> Result<Option<Vec<u8>>> {
+        self.get_account_state_value_at_version(
+            *module_id.address(),
+            &module_id.access_vector(),
+            version,
+        )
+    }
+
+    /// Reads the resource `tag` under `address` as of `version`, for the same purpose as
+    /// [`AptosDB::get_module_at_version`].
+    pub fn get_resource_at_version(
+        &self,
+        address: &AccountAddress,
+        tag: &StructTag,
+        version: Version,
+    ) -> Result<Option<Vec<u8>>> {
+        self.get_account_state_value_at_version(*address, &tag.access_vector(), version)
+    }
+
     // ================================== Backup APIs ===================================
 
     /// Gets an instance of `BackupHandler` for data backup purpose.
@@ -457,6 +1510,7 @@ impl AptosDB {
             Arc::clone(&self.transaction_store),
             Arc::clone(&self.state_store),
             Arc::clone(&self.event_store),
+            Arc::clone(&self.system_store),
         )
     }
 
@@ -473,15 +1527,119 @@ impl AptosDB {
     }
 
     // ================================== Private APIs ==================================
-    fn get_events_with_proof_by_event_key(
+    fn get_account_state_value_at_version(
+        &self,
+        address: AccountAddress,
+        path: &[u8],
+        version: Version,
+    ) -> Result<Option<Vec<u8>>> {
+        let latest_version = self.get_latest_version()?;
+        ensure!(
+            version <= latest_version,
+            AptosDbError::VersionTooNew(version, latest_version),
+        );
+        let (account_state_blob, _proof) =
+            self.get_account_state_with_proof_by_version(address, version)?;
+        if let Some(account_state_blob) = account_state_blob {
+            let account_state = AccountState::try_from(&account_state_blob)?;
+            Ok(account_state.get(path).cloned())
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn ensure_event_by_version_request(
+        &self,
+        event_version: u64,
+        proof_version: u64,
+    ) -> Result<()> {
+        let latest_version = self.get_latest_version()?;
+        ensure!(
+            proof_version <= latest_version,
+            AptosDbError::VersionTooNew(proof_version, latest_version),
+        );
+        ensure!(
+            event_version <= proof_version,
+            AptosDbError::VersionTooNew(event_version, proof_version),
+        );
+        Ok(())
+    }
+
+    fn get_event_by_version_with_proof_impl(
+        &self,
+        event_key: &EventKey,
+        event_version: u64,
+        proof_version: u64,
+    ) -> Result<EventByVersionWithProof> {
+        // Get the latest sequence number of an event at or before the
+        // requested event_version.
+        let maybe_seq_num = self
+            .event_store
+            .get_latest_sequence_number(event_version, event_key)?;
+
+        let (lower_bound_incl, upper_bound_excl) = if let Some(seq_num) = maybe_seq_num {
+            // We need to request the surrounding events (surrounding
+            // as in E_i.version <= event_version < E_{i+1}.version) in order
+            // to prove that there are no intermediate events, i.e.,
+            // E_j, where E_i.version < E_j.version <= event_version.
+            //
+            // This limit also works for the case where `event_version` is
+            // after the latest event, since the upper bound will just be None.
+            let limit = 2;
+
+            let events = self.get_events_with_proof_by_event_key(
+                event_key,
+                seq_num,
+                Order::Ascending,
+                limit,
+                proof_version,
+            )?;
+
+            let mut events_iter = events.into_iter();
+            let lower_bound_incl = events_iter.next();
+            let upper_bound_excl = events_iter.next();
+            assert_eq!(events_iter.len(), 0);
+
+            (lower_bound_incl, upper_bound_excl)
+        } else {
+            // Since there is no event at or before `event_version`, we need to
+            // show that either (1.) there are no events or (2.) events start
+            // at some later version.
+            let seq_num = 0;
+            let limit = 1;
+
+            let events = self.get_events_with_proof_by_event_key(
+                event_key,
+                seq_num,
+                Order::Ascending,
+                limit,
+                proof_version,
+            )?;
+
+            let mut events_iter = events.into_iter();
+            let upper_bound_excl = events_iter.next();
+            assert_eq!(events_iter.len(), 0);
+
+            (None, upper_bound_excl)
+        };
+
+        Ok(EventByVersionWithProof::new(
+            lower_bound_incl,
+            upper_bound_excl,
+        ))
+    }
+
+    /// Looks up the `(sequence_number, version, index)` of the events requested by
+    /// `get_events_with_proof_by_event_key` and friends, in ascending order.
+    fn lookup_events_by_key_in_order(
         &self,
         event_key: &EventKey,
         start_seq_num: u64,
         order: Order,
         limit: u64,
         ledger_version: Version,
-    ) -> Result<Vec<EventWithProof>> {
-        error_if_too_many_requested(limit, MAX_LIMIT)?;
+    ) -> Result<Vec<(u64, Version, u64)>> {
+        error_if_too_many_requested(limit, self.max_request_limit)?;
         let get_latest = order == Order::Descending && start_seq_num == u64::max_value();
 
         let cursor = if get_latest {
@@ -519,6 +1677,24 @@ impl AptosDB {
             }
         }
 
+        Ok(event_indices)
+    }
+
+    fn get_events_with_proof_by_event_key(
+        &self,
+        event_key: &EventKey,
+        start_seq_num: u64,
+        order: Order,
+        limit: u64,
+        ledger_version: Version,
+    ) -> Result<Vec<EventWithProof>> {
+        let event_indices = self.lookup_events_by_key_in_order(
+            event_key,
+            start_seq_num,
+            order,
+            limit,
+            ledger_version,
+        )?;
         let mut events_with_proof = event_indices
             .into_iter()
             .map(|(seq, ver, idx)| {
@@ -545,6 +1721,43 @@ impl AptosDB {
         Ok(events_with_proof)
     }
 
+    fn get_events_with_transaction_info_proof_by_event_key(
+        &self,
+        event_key: &EventKey,
+        start_seq_num: u64,
+        order: Order,
+        limit: u64,
+        ledger_version: Version,
+    ) -> Result<Vec<EventWithTransactionInfoProof>> {
+        let mut events_with_proof = self
+            .lookup_events_by_key_in_order(event_key, start_seq_num, order, limit, ledger_version)?
+            .into_iter()
+            .map(|(seq, ver, idx)| {
+                let event = self.event_store.get_event_by_version_and_index(ver, idx)?;
+                ensure!(
+                    seq == event.sequence_number(),
+                    "Index broken, expected seq:{}, actual:{}",
+                    seq,
+                    event.sequence_number()
+                );
+                let txn_info_with_proof = self
+                    .ledger_store
+                    .get_transaction_info_with_proof(ver, ledger_version)?;
+                Ok(EventWithTransactionInfoProof::new(
+                    ver,
+                    idx,
+                    event,
+                    txn_info_with_proof,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if order == Order::Descending {
+            events_with_proof.reverse();
+        }
+
+        Ok(events_with_proof)
+    }
+
     /// Convert a `ChangeSet` to `SealedChangeSet`.
     ///
     /// Specifically, counter increases are added to current counter values and converted to DB
@@ -646,16 +1859,154 @@ impl AptosDB {
     /// state of some transaction by leveraging rocksdb atomicity support. Also committed are the
     /// LedgerCounters.
     fn commit(&self, sealed_cs: SealedChangeSet) -> Result<()> {
+        #[cfg(any(test, feature = "fuzzing"))]
+        self.commit_failure_injector
+            .write_schemas(&self.db, sealed_cs.batch)?;
+        #[cfg(not(any(test, feature = "fuzzing")))]
         self.db.write_schemas(sealed_cs.batch)?;
 
         Ok(())
     }
 
-    fn wake_pruner(&self, latest_version: Version) {
+    /// Verifies and persists `txns_to_commit` along with the ledger infos in one DB write, the
+    /// last ledger info being checked against the resulting root hash.
+    fn commit_transactions(
+        &self,
+        txns_to_commit: &[TransactionToCommit],
+        first_version: Version,
+        ledger_infos_with_sigs: &[&LedgerInfoWithSignatures],
+    ) -> Result<HashValue> {
+        let num_txns = txns_to_commit.len() as u64;
+
+        // Gather db mutations to `batch`.
+        let mut cs = ChangeSet::new();
+
+        let new_root_hash = self.save_transactions_impl(txns_to_commit, first_version, &mut cs)?;
+
+        // If expected ledger info is provided, verify result root hash and save the ledger info.
+        if let Some(x) = ledger_infos_with_sigs.last() {
+            let expected_root_hash = x.ledger_info().transaction_accumulator_hash();
+            ensure!(
+                new_root_hash == expected_root_hash,
+                AptosDbError::RootHashMismatch(expected_root_hash, new_root_hash),
+            );
+        }
+        for x in ledger_infos_with_sigs {
+            self.ledger_store.put_ledger_info(x, &mut cs)?;
+        }
+
+        // Persist.
+        let (sealed_cs, counters) = self.seal_change_set(first_version, num_txns, cs)?;
+        {
+            let _timer = DIEM_STORAGE_OTHER_TIMERS_SECONDS
+                .with_label_values(&["save_transactions_commit"])
+                .start_timer();
+            self.commit(sealed_cs)?;
+        }
+
+        // Once everything is successfully persisted, update the latest in-memory ledger info.
+        if let Some(x) = ledger_infos_with_sigs.last() {
+            self.ledger_store.set_latest_ledger_info((*x).clone());
+
+            DIEM_STORAGE_LEDGER_VERSION.set(x.ledger_info().version() as i64);
+            DIEM_STORAGE_NEXT_BLOCK_EPOCH.set(x.ledger_info().next_block_epoch() as i64);
+        }
+
+        // Only increment counter if commit succeeds and there are at least one transaction written
+        // to the storage. That's also when we'd inform the pruner thread to work.
+        if num_txns > 0 {
+            let last_version = first_version + num_txns - 1;
+            DIEM_STORAGE_COMMITTED_TXNS.inc_by(num_txns);
+            DIEM_STORAGE_LATEST_TXN_VERSION.set(last_version as i64);
+            counters
+                .expect("Counters should be bumped with transactions being saved.")
+                .bump_op_counters();
+            self.account_counter.wake(last_version);
+            self.wake_pruner(last_version);
+        }
+
+        Ok(new_root_hash)
+    }
+
+    /// Errors with `AptosDbError::Pruned` if the state at `version` has been pruned.
+    fn error_if_state_pruned(&self, version: Version) -> Result<()> {
+        if let Some(pruner) = self.pruner.as_ref() {
+            let least_readable_version = pruner.get_least_readable_state_store_version();
+            ensure!(
+                version >= least_readable_version,
+                AptosDbError::Pruned(version, least_readable_version),
+            );
+        }
+        Ok(())
+    }
+
+    /// Errors with `AptosDbError::Pruned` if transactions, events etc. at `version` have been
+    /// pruned.
+    fn error_if_ledger_pruned(&self, version: Version) -> Result<()> {
+        if let Some(pruner) = self.pruner.as_ref() {
+            let least_readable_version = pruner.get_least_readable_ledger_version();
+            ensure!(
+                version >= least_readable_version,
+                AptosDbError::Pruned(version, least_readable_version),
+            );
+        }
+        Ok(())
+    }
+
+    /// The tree state before the first transaction is committed, i.e. the seeded frozen subtrees
+    /// and state snapshot if `genesis_version` is nonzero, otherwise the (possibly empty)
+    /// pre-genesis state.
+    fn get_pre_genesis_tree_state(&self) -> Result<TreeState> {
+        if self.genesis_version == 0 {
+            return Ok(TreeState::new(
+                0,
+                vec![],
+                self.state_store
+                    .get_root_hash_option(PRE_GENESIS_VERSION)?
+                    .unwrap_or(*SPARSE_MERKLE_PLACEHOLDER_HASH),
+            ));
+        }
+
+        Ok(TreeState::new(
+            self.genesis_version,
+            self.ledger_store
+                .get_frozen_subtree_hashes(self.genesis_version)?,
+            self.state_store
+                .get_root_hash_option(self.genesis_version - 1)?
+                .unwrap_or(*SPARSE_MERKLE_PLACEHOLDER_HASH),
+        ))
+    }
+
+    /// Commits everything in the write buffer in one DB write. The buffer is emptied even if the
+    /// commit fails, in which case none of the buffered blocks is persisted.
+    fn flush_write_buffer(&self, write_buffer: &mut WriteBuffer) -> Result<()> {
+        if let Some((first_version, txns_to_commit, ledger_infos_with_sigs)) = write_buffer.take() {
+            self.commit_transactions(
+                &txns_to_commit,
+                first_version,
+                &ledger_infos_with_sigs.iter().collect::<Vec<_>>(),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Notifies the pruner, if enabled, of `latest_version`. Normally this happens on every
+    /// commit, but it's useful to have the pruner delete an existing backlog without committing,
+    /// e.g. when benchmarking it.
+    pub fn wake_pruner(&self, latest_version: Version) {
         if let Some(pruner) = self.pruner.as_ref() {
             pruner.wake(latest_version)
         }
     }
+
+    /// Pauses or resumes the pruner, if enabled, e.g. to spare the I/O for commits during a large
+    /// state sync catch-up. While paused nothing is pruned, but the latest versions keep being
+    /// recorded, so the backlog is processed once resumed.
+    pub fn set_pruner_enabled(&self, enabled: bool) {
+        if let Some(pruner) = self.pruner.as_ref() {
+            pruner.set_enabled(enabled)
+        }
+    }
 }
 
 impl DbReader for AptosDB {
@@ -671,6 +2022,41 @@ impl DbReader for AptosDB {
         })
     }
 
+    fn get_epoch_ending_ledger_info_iter(
+        &self,
+        start_epoch: u64,
+        end_epoch: u64,
+    ) -> Result<Box<dyn Iterator<Item = Result<LedgerInfoWithSignatures>> + '_>> {
+        gauged_api("get_epoch_ending_ledger_info_iter", || {
+            self.ensure_closed_epoch_range(start_epoch, end_epoch)?;
+
+            let mut ledger_infos = self
+                .ledger_store
+                .get_epoch_ending_ledger_info_iter(start_epoch, end_epoch)?;
+            let mut next_epoch = start_epoch;
+            let iter: Box<dyn Iterator<Item = Result<LedgerInfoWithSignatures>> + '_> =
+                Box::new(std::iter::from_fn(move || {
+                    if next_epoch >= end_epoch {
+                        return None;
+                    }
+                    let res = ledger_infos.next().unwrap_or_else(|| {
+                        Err(format_err!(
+                            "DB corruption: missing epoch ending ledger info for epoch {}",
+                            next_epoch
+                        ))
+                    });
+                    // Stop after the first error.
+                    next_epoch = if res.is_ok() {
+                        next_epoch + 1
+                    } else {
+                        end_epoch
+                    };
+                    Some(res)
+                }));
+            Ok(iter)
+        })
+    }
+
     fn get_latest_account_state(
         &self,
         address: AccountAddress,
@@ -685,6 +2071,43 @@ impl DbReader for AptosDB {
         })
     }
 
+    fn get_latest_account_state_and_version(
+        &self,
+        address: AccountAddress,
+    ) -> Result<(Option<AccountStateBlob>, Version)> {
+        gauged_api("get_latest_account_state_and_version", || {
+            let ledger_info_with_sigs = self.ledger_store.get_latest_ledger_info()?;
+            let version = ledger_info_with_sigs.ledger_info().version();
+            let (blob, _proof) = self
+                .state_store
+                .get_account_state_with_proof_by_version(address, version)?;
+            Ok((blob, version))
+        })
+    }
+
+    /// Returns the account state at the latest version together with a proof anchored to the
+    /// latest ledger info, which the client can verify against `get_latest_ledger_info`.
+    fn get_latest_account_state_with_proof(
+        &self,
+        address: AccountAddress,
+    ) -> Result<AccountStateWithProof> {
+        gauged_api("get_latest_account_state_with_proof", || {
+            let ledger_info_with_sigs = self.ledger_store.get_latest_ledger_info()?;
+            let version = ledger_info_with_sigs.ledger_info().version();
+            let txn_info_with_proof = self
+                .ledger_store
+                .get_transaction_info_with_proof(version, version)?;
+            let (account_state_blob, sparse_merkle_proof) = self
+                .state_store
+                .get_account_state_with_proof_by_version(address, version)?;
+            Ok(AccountStateWithProof::new(
+                version,
+                account_state_blob,
+                AccountStateProof::new(txn_info_with_proof, sparse_merkle_proof),
+            ))
+        })
+    }
+
     fn get_latest_ledger_info(&self) -> Result<LedgerInfoWithSignatures> {
         gauged_api("get_latest_ledger_info", || {
             self.ledger_store.get_latest_ledger_info()
@@ -700,11 +2123,74 @@ impl DbReader for AptosDB {
     ) -> Result<Option<TransactionWithProof>> {
         gauged_api("get_account_transaction", || {
             self.transaction_store
-                .get_account_transaction_version(address, seq_num, ledger_version)?
-                .map(|txn_version| {
-                    self.get_transaction_with_proof(txn_version, ledger_version, include_events)
+                .get_account_transaction_version(address, seq_num, ledger_version)?
+                .map(|txn_version| {
+                    self.get_transaction_with_proof(txn_version, ledger_version, include_events)
+                })
+                .transpose()
+        })
+    }
+
+    fn get_account_transactions_multi(
+        &self,
+        requests: &[(AccountAddress, u64)],
+        ledger_version: Version,
+        include_events: bool,
+    ) -> Result<Vec<Option<TransactionWithProof>>> {
+        gauged_api("get_account_transactions_multi", || {
+            error_if_too_many_requested(requests.len() as u64, self.max_request_limit)?;
+
+            requests
+                .iter()
+                .map(|(address, seq_num)| {
+                    self.transaction_store
+                        .get_account_transaction_version(*address, *seq_num, ledger_version)?
+                        .map(|txn_version| {
+                            self.get_transaction_with_proof(
+                                txn_version,
+                                ledger_version,
+                                include_events,
+                            )
+                        })
+                        .transpose()
+                })
+                .collect()
+        })
+    }
+
+    fn get_account_transaction_count(
+        &self,
+        address: AccountAddress,
+        ledger_version: Version,
+    ) -> Result<u64> {
+        gauged_api("get_account_transaction_count", || {
+            // The state may have been pruned while the transactions are still around, in which
+            // case the count can't be checked against the CRSN resource.
+            let state_pruned = self.error_if_state_pruned(ledger_version).is_err();
+            let blob = if state_pruned {
+                None
+            } else {
+                self.state_store
+                    .get_account_state_by_version(address, ledger_version)?
+            };
+            if let Some(blob) = blob {
+                ensure!(
+                    AccountState::try_from(&blob)?
+                        .get_crsn_resource()?
+                        .is_none(),
+                    "Account {} uses conflict-resistant sequence numbers, its transactions can't \
+                     be counted by sequence number.",
+                    address,
+                );
+            }
+
+            self.transaction_store
+                .get_latest_account_sequence_number(address, ledger_version)?
+                .map_or(Ok(0), |seq_num| {
+                    seq_num
+                        .checked_add(1)
+                        .ok_or_else(|| format_err!("Seq num overflowed."))
                 })
-                .transpose()
         })
     }
 
@@ -712,28 +2198,41 @@ impl DbReader for AptosDB {
         &self,
         address: AccountAddress,
         start_seq_num: u64,
+        order: Order,
         limit: u64,
         include_events: bool,
         ledger_version: Version,
     ) -> Result<AccountTransactionsWithProof> {
         gauged_api("get_account_transactions", || {
-            error_if_too_many_requested(limit, MAX_LIMIT)?;
-
-            let txns_with_proofs = self
-                .transaction_store
-                .get_account_transaction_version_iter(
-                    address,
-                    start_seq_num,
-                    limit,
-                    ledger_version,
-                )?
-                .map(|result| {
-                    let (_seq_num, txn_version) = result?;
-                    self.get_transaction_with_proof(txn_version, ledger_version, include_events)
-                })
-                .collect::<Result<Vec<_>>>()?;
+            self.get_account_transactions_impl(
+                address,
+                start_seq_num,
+                order,
+                limit,
+                &|_version| include_events,
+                ledger_version,
+            )
+        })
+    }
 
-            Ok(AccountTransactionsWithProof::new(txns_with_proofs))
+    fn get_account_transactions_with_events_filter(
+        &self,
+        address: AccountAddress,
+        start_seq_num: u64,
+        order: Order,
+        limit: u64,
+        include_events: &dyn Fn(Version) -> bool,
+        ledger_version: Version,
+    ) -> Result<AccountTransactionsWithProof> {
+        gauged_api("get_account_transactions_with_events_filter", || {
+            self.get_account_transactions_impl(
+                address,
+                start_seq_num,
+                order,
+                limit,
+                include_events,
+                ledger_version,
+            )
         })
     }
 
@@ -760,25 +2259,58 @@ impl DbReader for AptosDB {
         self.get_transaction_with_proof(version, ledger_version, fetch_events)
     }
 
+    fn get_transactions_by_versions(
+        &self,
+        versions: &[Version],
+        ledger_version: Version,
+        fetch_events: bool,
+    ) -> Result<Vec<TransactionWithProof>> {
+        gauged_api("get_transactions_by_versions", || {
+            error_if_too_many_requested(versions.len() as u64, self.max_request_limit)?;
+
+            let mut txns_by_version = HashMap::new();
+            for version in versions {
+                if !txns_by_version.contains_key(version) {
+                    txns_by_version.insert(
+                        *version,
+                        self.get_transaction_with_proof(*version, ledger_version, fetch_events)?,
+                    );
+                }
+            }
+            Ok(versions
+                .iter()
+                .map(|version| txns_by_version[version].clone())
+                .collect())
+        })
+    }
+
     // ======================= State Synchronizer Internal APIs ===================================
     /// Gets a batch of transactions for the purpose of synchronizing state to another node.
     ///
+    /// With `Order::Ascending`, up to `limit` transactions starting from `start_version` are
+    /// returned, and with `Order::Descending`, up to `limit` transactions ending at
+    /// `start_version`. Either way, the result is the contiguous range of transactions in
+    /// ascending order, with `first_transaction_version` set to the lowest version returned.
+    ///
     /// This is used by the State Synchronizer module internally.
     fn get_transactions(
         &self,
         start_version: Version,
+        order: Order,
         limit: u64,
         ledger_version: Version,
         fetch_events: bool,
     ) -> Result<TransactionListWithProof> {
         gauged_api("get_transactions", || {
-            error_if_too_many_requested(limit, MAX_LIMIT)?;
+            error_if_too_many_requested(limit, self.max_request_limit)?;
 
             if start_version > ledger_version || limit == 0 {
                 return Ok(TransactionListWithProof::new_empty());
             }
 
+            let (start_version, limit) = get_first_seq_num_and_limit(order, start_version, limit)?;
             let limit = std::cmp::min(limit, ledger_version - start_version + 1);
+            self.error_if_ledger_pruned(start_version)?;
 
             let txns = (start_version..start_version + limit)
                 .map(|version| self.transaction_store.get_transaction(version))
@@ -823,6 +2355,20 @@ impl DbReader for AptosDB {
         self.transaction_store.get_first_write_set_version()
     }
 
+    fn get_first_readable_version(&self) -> Result<Version> {
+        gauged_api("get_first_readable_version", || {
+            let least_readable_version = self
+                .pruner
+                .as_ref()
+                .map_or(0, |pruner| pruner.get_least_readable_ledger_version());
+            let first_txn_version = self
+                .transaction_store
+                .get_first_txn_version()?
+                .unwrap_or(self.genesis_version);
+            Ok(std::cmp::max(least_readable_version, first_txn_version))
+        })
+    }
+
     /// Gets a batch of transactions for the purpose of synchronizing state to another node.
     ///
     /// This is used by the State Synchronizer module internally.
@@ -833,7 +2379,7 @@ impl DbReader for AptosDB {
         ledger_version: Version,
     ) -> Result<TransactionOutputListWithProof> {
         gauged_api("get_transactions_outputs", || {
-            error_if_too_many_requested(limit, MAX_LIMIT)?;
+            error_if_too_many_requested(limit, self.max_request_limit)?;
 
             if start_version > ledger_version || limit == 0 {
                 return Ok(TransactionOutputListWithProof::new_empty());
@@ -875,6 +2421,67 @@ impl DbReader for AptosDB {
         })
     }
 
+    fn get_transaction_execution_summaries(
+        &self,
+        start_version: Version,
+        limit: u64,
+        ledger_version: Version,
+    ) -> Result<Vec<(Version, u64, KeptVMStatus)>> {
+        gauged_api("get_transaction_execution_summaries", || {
+            error_if_too_many_requested(limit, self.max_request_limit)?;
+
+            if start_version > ledger_version || limit == 0 {
+                return Ok(Vec::new());
+            }
+
+            let limit = std::cmp::min(limit, ledger_version - start_version + 1);
+            zip_eq(
+                start_version..start_version + limit,
+                self.ledger_store
+                    .get_transaction_info_iter(start_version, limit as usize)?,
+            )
+            .map(|(version, txn_info)| {
+                let txn_info = txn_info?;
+                Ok((version, txn_info.gas_used(), txn_info.status().clone()))
+            })
+            .collect()
+        })
+    }
+
+    fn get_events_by_version_range(
+        &self,
+        start_version: Version,
+        end_version: Version,
+    ) -> Result<Vec<(Version, Vec<ContractEvent>)>> {
+        gauged_api("get_events_by_version_range", || {
+            ensure!(
+                start_version <= end_version,
+                "Bad version range [{}, {}]",
+                start_version,
+                end_version,
+            );
+            error_if_too_many_requested(
+                (end_version - start_version).saturating_add(1),
+                self.max_request_limit,
+            )?;
+            let latest_version = self.get_latest_version()?;
+            ensure!(
+                end_version <= latest_version,
+                AptosDbError::VersionTooNew(end_version, latest_version),
+            );
+            self.error_if_ledger_pruned(start_version)?;
+
+            let mut events_by_version = Vec::new();
+            for version in start_version..=end_version {
+                let events = self.event_store.get_events_by_version(version)?;
+                if !events.is_empty() {
+                    events_by_version.push((version, events));
+                }
+            }
+            Ok(events_by_version)
+        })
+    }
+
     fn get_events(
         &self,
         event_key: &EventKey,
@@ -912,6 +2519,95 @@ impl DbReader for AptosDB {
         })
     }
 
+    fn get_events_multi_key(
+        &self,
+        requests: &[(EventKey, u64, Order, u64)],
+        ledger_version: Version,
+    ) -> Result<Vec<Vec<EventWithProof>>> {
+        gauged_api("get_events_multi_key", || {
+            let total_limit = requests
+                .iter()
+                .fold(0u64, |total, (_, _, _, limit)| total.saturating_add(*limit));
+            error_if_too_many_requested(total_limit, self.max_request_limit)?;
+
+            requests
+                .iter()
+                .map(|(event_key, start, order, limit)| {
+                    self.get_events_with_proof_by_event_key(
+                        event_key,
+                        *start,
+                        *order,
+                        *limit,
+                        ledger_version,
+                    )
+                })
+                .collect()
+        })
+    }
+
+    fn get_events_with_transaction_info_proofs(
+        &self,
+        event_key: &EventKey,
+        start: u64,
+        order: Order,
+        limit: u64,
+        known_version: Option<u64>,
+    ) -> Result<Vec<EventWithTransactionInfoProof>> {
+        gauged_api("get_events_with_transaction_info_proofs", || {
+            let version = match known_version {
+                Some(version) => version,
+                None => self.get_latest_version()?,
+            };
+            self.get_events_with_transaction_info_proof_by_event_key(
+                event_key, start, order, limit, version,
+            )
+        })
+    }
+
+    fn get_events_with_proofs_up_to_bytes(
+        &self,
+        event_key: &EventKey,
+        start: u64,
+        order: Order,
+        max_bytes: usize,
+    ) -> Result<(Vec<EventWithProof>, Option<u64>)> {
+        gauged_api("get_events_with_proofs_up_to_bytes", || {
+            let version = self.get_latest_version()?;
+            let page_size = std::cmp::min(EVENTS_WITH_PROOF_PAGE_SIZE, self.max_request_limit);
+            let mut events_with_proof = Vec::new();
+            let mut total_bytes = 0;
+            let mut cursor = start;
+            loop {
+                let page = self.get_events_with_proof_by_event_key(
+                    event_key, cursor, order, page_size, version,
+                )?;
+                let page_len = page.len() as u64;
+                for event_with_proof in page {
+                    let seq_num = event_with_proof.event.sequence_number();
+                    // No matter how large `max_bytes` is, return no more than the request limit.
+                    if events_with_proof.len() as u64 == self.max_request_limit {
+                        return Ok((events_with_proof, Some(seq_num)));
+                    }
+                    total_bytes += bcs::serialized_size(&event_with_proof)?;
+                    if total_bytes > max_bytes && !events_with_proof.is_empty() {
+                        return Ok((events_with_proof, Some(seq_num)));
+                    }
+                    events_with_proof.push(event_with_proof);
+                }
+
+                let last_seq_num = match events_with_proof.last() {
+                    Some(e) if page_len == page_size => e.event.sequence_number(),
+                    _ => return Ok((events_with_proof, None)),
+                };
+                cursor = match order {
+                    Order::Ascending => last_seq_num + 1,
+                    Order::Descending if last_seq_num > 0 => last_seq_num - 1,
+                    Order::Descending => return Ok((events_with_proof, None)),
+                };
+            }
+        })
+    }
+
     /// Gets ledger info at specified version and ensures it's an epoch ending.
     fn get_epoch_ending_ledger_info(&self, version: u64) -> Result<LedgerInfoWithSignatures> {
         gauged_api("get_epoch_ending_ledger_info", || {
@@ -925,49 +2621,11 @@ impl DbReader for AptosDB {
         ledger_info_with_sigs: LedgerInfoWithSignatures,
     ) -> Result<StateProof> {
         gauged_api("get_state_proof_with_ledger_info", || {
-            let ledger_info = ledger_info_with_sigs.ledger_info();
-            ensure!(
-                known_version <= ledger_info.version(),
-                "Client known_version {} larger than ledger version {}.",
+            self.get_state_proof_with_ledger_info_impl(
                 known_version,
-                ledger_info.version(),
-            );
-            let known_epoch = self.ledger_store.get_epoch(known_version)?;
-            let end_epoch = ledger_info.next_block_epoch();
-            let epoch_change_proof = if known_epoch < end_epoch {
-                let (ledger_infos_with_sigs, more) =
-                    self.get_epoch_ending_ledger_infos(known_epoch, end_epoch)?;
-                EpochChangeProof::new(ledger_infos_with_sigs, more)
-            } else {
-                EpochChangeProof::new(vec![], /* more = */ false)
-            };
-
-            // Only return a consistency proof up to the verifiable end LI. If a
-            // client still needs to sync more epoch change LI's, then they cannot
-            // verify the latest LI nor verify a consistency proof up to the latest
-            // LI. If the client needs more epochs, we just return the consistency
-            // proof up to the last epoch change LI.
-            let verifiable_li = if epoch_change_proof.more {
-                epoch_change_proof
-                    .ledger_info_with_sigs
-                    .last()
-                    .ok_or_else(|| format_err!(
-                        "No epoch changes despite claiming the client needs to sync more epochs: known_epoch={}, end_epoch={}",
-                        known_epoch, end_epoch,
-                    ))?
-                    .ledger_info()
-            } else {
-                ledger_info
-            };
-
-            let consistency_proof = self
-                .ledger_store
-                .get_consistency_proof(Some(known_version), verifiable_li.version())?;
-            Ok(StateProof::new(
                 ledger_info_with_sigs,
-                epoch_change_proof,
-                consistency_proof,
-            ))
+                self.max_epochs_per_state_proof,
+            )
         })
     }
 
@@ -987,19 +2645,16 @@ impl DbReader for AptosDB {
         gauged_api("get_account_state_with_proof", || {
             ensure!(
                 version <= ledger_version,
-                "The queried version {} should be equal to or older than ledger version {}.",
-                version,
-                ledger_version
+                AptosDbError::VersionTooNew(version, ledger_version),
             );
             {
                 let latest_version = self.get_latest_version()?;
                 ensure!(
                     ledger_version <= latest_version,
-                    "ledger_version specified {} is greater than committed version {}.",
-                    ledger_version,
-                    latest_version
+                    AptosDbError::VersionTooNew(ledger_version, latest_version),
                 );
             }
+            self.error_if_state_pruned(version)?;
 
             let txn_info_with_proof = self
                 .ledger_store
@@ -1015,6 +2670,26 @@ impl DbReader for AptosDB {
         })
     }
 
+    fn get_resource_with_proof(
+        &self,
+        address: AccountAddress,
+        tag: &StructTag,
+        version: Version,
+        ledger_version: Version,
+    ) -> Result<(Option<Vec<u8>>, AccountStateWithProof)> {
+        gauged_api("get_resource_with_proof", || {
+            let account_state_with_proof =
+                self.get_account_state_with_proof(address, version, ledger_version)?;
+            let resource = match &account_state_with_proof.blob {
+                Some(blob) => AccountState::try_from(blob)?
+                    .get(&tag.access_vector())
+                    .cloned(),
+                None => None,
+            };
+            Ok((resource, account_state_with_proof))
+        })
+    }
+
     fn get_startup_info(&self) -> Result<Option<StartupInfo>> {
         gauged_api("get_startup_info", || self.ledger_store.get_startup_info())
     }
@@ -1028,24 +2703,50 @@ impl DbReader for AptosDB {
         SparseMerkleProof<AccountStateBlob>,
     )> {
         gauged_api("get_account_state_with_proof_by_version", || {
+            self.error_if_state_pruned(version)?;
             self.state_store
                 .get_account_state_with_proof_by_version(address, version)
         })
     }
 
+    fn get_account_state_history(
+        &self,
+        address: AccountAddress,
+        start_version: Version,
+        end_version: Version,
+    ) -> Result<Vec<(Version, Option<AccountStateBlob>)>> {
+        gauged_api("get_account_state_history", || {
+            ensure!(
+                start_version <= end_version,
+                "Invalid version range [{}, {}].",
+                start_version,
+                end_version,
+            );
+            let num_versions = (end_version - start_version)
+                .checked_add(1)
+                .ok_or_else(|| {
+                    format_err!("Too many versions in [{}, {}].", start_version, end_version)
+                })?;
+            error_if_too_many_requested(num_versions, self.max_request_limit)?;
+            let latest_version = self.get_latest_version()?;
+            ensure!(
+                end_version <= latest_version,
+                AptosDbError::VersionTooNew(end_version, latest_version),
+            );
+            self.error_if_state_pruned(start_version)?;
+
+            self.state_store
+                .get_account_state_history(address, start_version, end_version)
+        })
+    }
+
     fn get_latest_tree_state(&self) -> Result<TreeState> {
         gauged_api("get_latest_tree_state", || {
             let tree_state = match self.ledger_store.get_latest_transaction_info_option()? {
                 Some((version, txn_info)) => {
                     self.ledger_store.get_tree_state(version + 1, txn_info)?
                 }
-                None => TreeState::new(
-                    0,
-                    vec![],
-                    self.state_store
-                        .get_root_hash_option(PRE_GENESIS_VERSION)?
-                        .unwrap_or(*SPARSE_MERKLE_PLACEHOLDER_HASH),
-                ),
+                None => self.get_pre_genesis_tree_state()?,
             };
 
             info!(
@@ -1059,6 +2760,19 @@ impl DbReader for AptosDB {
         })
     }
 
+    fn get_tree_state(&self, version: Version) -> Result<TreeState> {
+        gauged_api("get_tree_state", || {
+            let txn_info = self.ledger_store.get_transaction_info(version)?;
+            self.ledger_store.get_tree_state(version + 1, txn_info)
+        })
+    }
+
+    fn get_block_metadata(&self, version: Version) -> Result<Option<(Version, BlockMetadata)>> {
+        gauged_api("get_block_metadata", || {
+            self.transaction_store.get_block_metadata(version)
+        })
+    }
+
     fn get_block_timestamp(&self, version: u64) -> Result<u64> {
         gauged_api("get_block_timestamp", || {
             let ts = match self.transaction_store.get_block_metadata(version)? {
@@ -1077,75 +2791,47 @@ impl DbReader for AptosDB {
         proof_version: u64,
     ) -> Result<EventByVersionWithProof> {
         gauged_api("get_event_by_version_with_proof", || {
-            let latest_version = self.get_latest_version()?;
-            ensure!(
-                proof_version <= latest_version,
-                "cannot construct proofs for a version that doesn't exist yet: proof_version: {}, latest_version: {}",
-                proof_version, latest_version,
-            );
-            ensure!(
-                event_version <= proof_version,
-                "event_version {} must be <= proof_version {}",
-                event_version,
-                proof_version,
-            );
-
-            // Get the latest sequence number of an event at or before the
-            // requested event_version.
-            let maybe_seq_num = self
-                .event_store
-                .get_latest_sequence_number(event_version, event_key)?;
-
-            let (lower_bound_incl, upper_bound_excl) = if let Some(seq_num) = maybe_seq_num {
-                // We need to request the surrounding events (surrounding
-                // as in E_i.version <= event_version < E_{i+1}.version) in order
-                // to prove that there are no intermediate events, i.e.,
-                // E_j, where E_i.version < E_j.version <= event_version.
-                //
-                // This limit also works for the case where `event_version` is
-                // after the latest event, since the upper bound will just be None.
-                let limit = 2;
-
-                let events = self.get_events_with_proof_by_event_key(
-                    event_key,
-                    seq_num,
-                    Order::Ascending,
-                    limit,
-                    proof_version,
-                )?;
-
-                let mut events_iter = events.into_iter();
-                let lower_bound_incl = events_iter.next();
-                let upper_bound_excl = events_iter.next();
-                assert_eq!(events_iter.len(), 0);
-
-                (lower_bound_incl, upper_bound_excl)
-            } else {
-                // Since there is no event at or before `event_version`, we need to
-                // show that either (1.) there are no events or (2.) events start
-                // at some later version.
-                let seq_num = 0;
-                let limit = 1;
-
-                let events = self.get_events_with_proof_by_event_key(
-                    event_key,
-                    seq_num,
-                    Order::Ascending,
-                    limit,
-                    proof_version,
-                )?;
-
-                let mut events_iter = events.into_iter();
-                let upper_bound_excl = events_iter.next();
-                assert_eq!(events_iter.len(), 0);
+            self.ensure_event_by_version_request(event_version, proof_version)?;
+            self.get_event_by_version_with_proof_impl(event_key, event_version, proof_version)
+        })
+    }
 
-                (None, upper_bound_excl)
-            };
+    fn get_events_by_version_with_proof_multi(
+        &self,
+        event_keys: &[EventKey],
+        event_version: u64,
+        proof_version: u64,
+    ) -> Result<Vec<EventByVersionWithProof>> {
+        gauged_api("get_events_by_version_with_proof_multi", || {
+            error_if_too_many_requested(event_keys.len() as u64, self.max_request_limit)?;
+            self.ensure_event_by_version_request(event_version, proof_version)?;
+            event_keys
+                .iter()
+                .map(|event_key| {
+                    self.get_event_by_version_with_proof_impl(
+                        event_key,
+                        event_version,
+                        proof_version,
+                    )
+                })
+                .collect()
+        })
+    }
 
-            Ok(EventByVersionWithProof::new(
-                lower_bound_incl,
-                upper_bound_excl,
-            ))
+    fn get_latest_event_sequence_numbers(
+        &self,
+        event_keys: &[EventKey],
+        ledger_version: Version,
+    ) -> Result<Vec<Option<u64>>> {
+        gauged_api("get_latest_event_sequence_numbers", || {
+            error_if_too_many_requested(event_keys.len() as u64, self.max_request_limit)?;
+            event_keys
+                .iter()
+                .map(|event_key| {
+                    self.event_store
+                        .get_latest_sequence_number(ledger_version, event_key)
+                })
+                .collect()
         })
     }
 
@@ -1201,27 +2887,36 @@ impl DbReader for AptosDB {
         })
     }
 
+    fn get_account_chunk_with_proof_rev(
+        &self,
+        version: Version,
+        last_index: usize,
+        chunk_size: usize,
+    ) -> Result<AccountStatesChunkWithProof> {
+        gauged_api("get_account_chunk_with_proof_rev", || {
+            self.state_store
+                .get_account_chunk_with_proof_rev(version, last_index, chunk_size)
+        })
+    }
+
     fn get_state_prune_window(&self) -> Option<usize> {
         self.pruner
             .as_ref()
             .map(|x| x.get_state_store_pruner_window() as usize)
     }
+
+    fn get_ledger_prune_window(&self) -> Option<usize> {
+        self.pruner
+            .as_ref()
+            .map(|x| x.get_ledger_pruner_window() as usize)
+    }
 }
 
 impl ModuleResolver for AptosDB {
     type Error = anyhow::Error;
 
     fn get_module(&self, module_id: &ModuleId) -> Result<Option<Vec<u8>>> {
-        let (account_state_with_proof, _) = self.get_account_state_with_proof_by_version(
-            *module_id.address(),
-            self.get_latest_version()?,
-        )?;
-        if let Some(account_state_blob) = account_state_with_proof {
-            let account_state = AccountState::try_from(&account_state_blob)?;
-            Ok(account_state.get(&module_id.access_vector()).cloned())
-        } else {
-            Ok(None)
-        }
+        self.get_module_at_version(module_id, self.get_latest_version()?)
     }
 }
 
@@ -1229,14 +2924,7 @@ impl ResourceResolver for AptosDB {
     type Error = anyhow::Error;
 
     fn get_resource(&self, address: &AccountAddress, tag: &StructTag) -> Result<Option<Vec<u8>>> {
-        let (account_state_with_proof, _) =
-            self.get_account_state_with_proof_by_version(*address, self.get_latest_version()?)?;
-        if let Some(account_state_blob) = account_state_with_proof {
-            let account_state = AccountState::try_from(&account_state_blob)?;
-            Ok(account_state.get(&tag.access_vector()).cloned())
-        } else {
-            Ok(None)
-        }
+        self.get_resource_at_version(address, tag, self.get_latest_version()?)
     }
 }
 
@@ -1254,6 +2942,16 @@ impl DbWriter for AptosDB {
         first_version: Version,
         ledger_info_with_sigs: Option<&LedgerInfoWithSignatures>,
     ) -> Result<()> {
+        self.save_transactions_and_get_root(txns_to_commit, first_version, ledger_info_with_sigs)
+            .map(|_| ())
+    }
+
+    fn save_transactions_and_get_root(
+        &self,
+        txns_to_commit: &[TransactionToCommit],
+        first_version: Version,
+        ledger_info_with_sigs: Option<&LedgerInfoWithSignatures>,
+    ) -> Result<HashValue> {
         gauged_api("save_transactions", || {
             let num_txns = txns_to_commit.len() as u64;
             // ledger_info_with_sigs could be None if we are doing state synchronization. In this case
@@ -1274,65 +2972,78 @@ impl DbWriter for AptosDB {
                 );
             }
 
-            // Gather db mutations to `batch`.
-            let mut cs = ChangeSet::new();
-
-            let new_root_hash =
-                self.save_transactions_impl(txns_to_commit, first_version, &mut cs)?;
-
-            // If expected ledger info is provided, verify result root hash and save the ledger info.
-            if let Some(x) = ledger_info_with_sigs {
-                let expected_root_hash = x.ledger_info().transaction_accumulator_hash();
-                ensure!(
-                    new_root_hash == expected_root_hash,
-                    "Root hash calculated doesn't match expected. {:?} vs {:?}",
-                    new_root_hash,
-                    expected_root_hash,
-                );
-
-                self.ledger_store.put_ledger_info(x, &mut cs)?;
-            }
-
-            // Persist.
-            let (sealed_cs, counters) = self.seal_change_set(first_version, num_txns, cs)?;
-            {
-                let _timer = DIEM_STORAGE_OTHER_TIMERS_SECONDS
-                    .with_label_values(&["save_transactions_commit"])
-                    .start_timer();
-                self.commit(sealed_cs)?;
-            }
-
-            // Once everything is successfully persisted, update the latest in-memory ledger info.
-            if let Some(x) = ledger_info_with_sigs {
-                self.ledger_store.set_latest_ledger_info(x.clone());
-
-                DIEM_STORAGE_LEDGER_VERSION.set(x.ledger_info().version() as i64);
-                DIEM_STORAGE_NEXT_BLOCK_EPOCH.set(x.ledger_info().next_block_epoch() as i64);
+            match self.write_buffer.as_ref() {
+                Some(write_buffer) => {
+                    let mut write_buffer = write_buffer.lock();
+                    let new_root_hash = write_buffer.push(
+                        txns_to_commit,
+                        first_version,
+                        ledger_info_with_sigs,
+                        || {
+                            InMemoryAccumulator::new(
+                                self.ledger_store.get_frozen_subtree_hashes(first_version)?,
+                                first_version,
+                            )
+                        },
+                    )?;
+                    if write_buffer.should_flush() {
+                        self.flush_write_buffer(&mut write_buffer)?;
+                    }
+                    Ok(new_root_hash)
+                }
+                None => self.commit_transactions(
+                    txns_to_commit,
+                    first_version,
+                    &ledger_info_with_sigs.into_iter().collect::<Vec<_>>(),
+                ),
             }
+        })
+    }
 
-            // Only increment counter if commit succeeds and there are at least one transaction written
-            // to the storage. That's also when we'd inform the pruner thread to work.
-            if num_txns > 0 {
-                let last_version = first_version + num_txns - 1;
-                DIEM_STORAGE_COMMITTED_TXNS.inc_by(num_txns);
-                DIEM_STORAGE_LATEST_TXN_VERSION.set(last_version as i64);
-                counters
-                    .expect("Counters should be bumped with transactions being saved.")
-                    .bump_op_counters();
-                // -1 for "not fully migrated", -2 for "error on get_account_count()"
-                DIEM_STORAGE_LATEST_ACCOUNT_COUNT.set(
-                    self.state_store
-                        .get_account_count(last_version)
-                        .map_or(-1, |c| c as i64),
-                );
-
-                self.wake_pruner(last_version);
+    fn wait_for_commit(&self, version: Version) -> Result<()> {
+        gauged_api("wait_for_commit", || {
+            if let Some(write_buffer) = self.write_buffer.as_ref() {
+                let mut write_buffer = write_buffer.lock();
+                if write_buffer
+                    .first_version()
+                    .map_or(false, |first_version| first_version <= version)
+                    || write_buffer.should_flush()
+                {
+                    self.flush_write_buffer(&mut write_buffer)?;
+                }
             }
-
+            let latest_version = self
+                .ledger_store
+                .get_latest_transaction_info_option()?
+                .map(|(version, _)| version);
+            ensure!(
+                latest_version.map_or(false, |latest_version| version <= latest_version),
+                "Version {} has not been saved. Latest version: {:?}.",
+                version,
+                latest_version,
+            );
             Ok(())
         })
     }
 
+    fn commit_ledger_info_only(
+        &self,
+        ledger_info_with_sigs: &LedgerInfoWithSignatures,
+    ) -> Result<()> {
+        let (latest_version, _) = self
+            .ledger_store
+            .get_latest_transaction_info_option()?
+            .ok_or_else(|| format_err!("No transaction has been committed."))?;
+        let version = ledger_info_with_sigs.ledger_info().version();
+        ensure!(
+            version == latest_version,
+            "Ledger info version {} doesn't match the latest committed version {}.",
+            version,
+            latest_version,
+        );
+        self.save_transactions(&[], version + 1, Some(ledger_info_with_sigs))
+    }
+
     fn get_state_snapshot_receiver(
         &self,
         version: Version,
@@ -1343,6 +3054,17 @@ impl DbWriter for AptosDB {
                 .get_snapshot_receiver(version, expected_root_hash)
         })
     }
+
+    fn resume_state_snapshot_receiver(
+        &self,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<Box<dyn StateSnapshotReceiver<AccountStateBlob>>> {
+        gauged_api("resume_state_snapshot_receiver", || {
+            self.state_store
+                .resume_snapshot_receiver(version, expected_root_hash)
+        })
+    }
 }
 
 // Convert requested range and order to a range in ascending order.
@@ -1372,15 +3094,48 @@ impl GetRestoreHandler for Arc<AptosDB> {
             Arc::clone(&self.transaction_store),
             Arc::clone(&self.state_store),
             Arc::clone(&self.event_store),
+            Arc::clone(&self.system_store),
         )
     }
 }
 
+static API_LATENCY_SAMPLING_RATE: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    static API_CALLS_SINCE_LAST_SAMPLE: Cell<u64> = Cell::new(0);
+}
+
+/// Makes `DIEM_STORAGE_API_LATENCY_SECONDS` observe only one in every `rate` API calls (counted
+/// per thread) instead of all of them, saving the clock reads on the hot path under high read
+/// QPS. A `rate` of 0 is treated as 1. Applies to all `AptosDB` instances in the process.
+///
+/// The latency distribution stays accurate as long as call patterns don't correlate with the
+/// sampling, but the histogram counts undercount calls by a factor of `rate`, and rare slow calls
+/// are likely to go unobserved. Errors are still logged and counted in `DIEM_STORAGE_API_ERRORS`
+/// regardless of sampling.
+pub fn set_api_latency_sampling_rate(rate: u64) {
+    API_LATENCY_SAMPLING_RATE.store(rate.max(1), Ordering::Relaxed);
+}
+
+fn should_sample_api_latency() -> bool {
+    let rate = API_LATENCY_SAMPLING_RATE.load(Ordering::Relaxed);
+    API_CALLS_SINCE_LAST_SAMPLE.with(|calls| {
+        let num_calls = calls.get() + 1;
+        if num_calls >= rate {
+            calls.set(0);
+            true
+        } else {
+            calls.set(num_calls);
+            false
+        }
+    })
+}
+
 fn gauged_api<T, F>(api_name: &'static str, api_impl: F) -> Result<T>
 where
     F: FnOnce() -> Result<T>,
 {
-    let timer = Instant::now();
+    let timer = should_sample_api_latency().then(Instant::now);
 
     let res = api_impl();
 
@@ -1392,12 +3147,15 @@ where
                 error = ?e,
                 "AptosDB API returned error."
             );
+            DIEM_STORAGE_API_ERRORS.with_label_values(&[api_name]).inc();
             "Err"
         }
     };
-    DIEM_STORAGE_API_LATENCY_SECONDS
-        .with_label_values(&[api_name, res_type])
-        .observe(timer.elapsed().as_secs_f64());
+    if let Some(timer) = timer {
+        DIEM_STORAGE_API_LATENCY_SECONDS
+            .with_label_values(&[api_name, res_type])
+            .observe(timer.elapsed().as_secs_f64());
+    }
 
     res
 }
@@ -2,8 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use aptos_metrics::{
-    register_histogram_vec, register_int_counter, register_int_gauge, register_int_gauge_vec,
-    HistogramVec, IntCounter, IntGauge, IntGaugeVec,
+    register_histogram_vec, register_int_counter, register_int_counter_vec, register_int_gauge,
+    register_int_gauge_vec, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
 };
 use once_cell::sync::Lazy;
 
@@ -63,6 +63,16 @@ pub static DIEM_STORAGE_PRUNE_WINDOW: Lazy<IntGauge> = Lazy::new(|| {
     register_int_gauge!("aptos_storage_prune_window", "Aptos storage prune window").unwrap()
 });
 
+/// Smallest version still readable from all the pruned stores, i.e. the start of the retained
+/// history window.
+pub static DIEM_STORAGE_PRUNE_FLOOR: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "aptos_storage_prune_floor",
+        "Aptos storage least version readable from all pruned stores"
+    )
+    .unwrap()
+});
+
 /// DB pruner least readable versions
 pub static DIEM_PRUNER_LEAST_READABLE_VERSION: Lazy<IntGaugeVec> = Lazy::new(|| {
     register_int_gauge_vec!(
@@ -76,6 +86,34 @@ pub static DIEM_PRUNER_LEAST_READABLE_VERSION: Lazy<IntGaugeVec> = Lazy::new(||
     .unwrap()
 });
 
+/// Number of versions each DB pruner is behind its target, i.e. the latest version minus the prune
+/// window (unless a version is pinned), minus the least readable version. Alert on this growing to
+/// catch the pruner falling behind commits.
+pub static DIEM_PRUNER_PENDING_VERSIONS: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        // metric name
+        "aptos_pruner_pending_versions",
+        // metric description
+        "Aptos pruner number of versions left to prune to reach the target",
+        // metric labels (dimensions)
+        &["pruner_name",]
+    )
+    .unwrap()
+});
+
+/// Lookups in the account state cache, by whether they hit.
+pub static DIEM_STORAGE_ACCOUNT_STATE_CACHE_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        // metric name
+        "aptos_storage_account_state_cache_requests",
+        // metric description
+        "Aptos storage account state cache lookups",
+        // metric labels (dimensions)
+        &["result"]
+    )
+    .unwrap()
+});
+
 pub static DIEM_STORAGE_API_LATENCY_SECONDS: Lazy<HistogramVec> = Lazy::new(|| {
     register_histogram_vec!(
         // metric name
@@ -88,6 +126,28 @@ pub static DIEM_STORAGE_API_LATENCY_SECONDS: Lazy<HistogramVec> = Lazy::new(|| {
     .unwrap()
 });
 
+pub static DIEM_STORAGE_API_ERRORS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        // metric name
+        "aptos_storage_api_errors",
+        // metric description
+        "Number of errors returned by Aptos storage apis, not subject to latency sampling",
+        // metric labels (dimensions)
+        &["api_name"]
+    )
+    .unwrap()
+});
+
+/// Usage of the block cache shared by column families, see
+/// `RocksdbConfig::shared_block_cache_size`.
+pub static DIEM_STORAGE_ROCKSDB_SHARED_BLOCK_CACHE_USAGE_BYTES: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "aptos_rocksdb_shared_block_cache_usage_bytes",
+        "Usage of the rocksdb block cache shared by column families."
+    )
+    .unwrap()
+});
+
 pub static DIEM_STORAGE_OTHER_TIMERS_SECONDS: Lazy<HistogramVec> = Lazy::new(|| {
     register_histogram_vec!(
         // metric name
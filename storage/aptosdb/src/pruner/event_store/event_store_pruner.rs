@@ -1,15 +1,15 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 use crate::{
-    event::EventSchema, metrics::DIEM_PRUNER_LEAST_READABLE_VERSION, pruner::db_pruner::DBPruner,
+    event::EventSchema,
+    metrics::{DIEM_PRUNER_LEAST_READABLE_VERSION, DIEM_PRUNER_PENDING_VERSIONS},
+    pruner::db_pruner::DBPruner,
     EventStore,
 };
 use aptos_types::{
-    contract_event::ContractEvent,
     event::EventKey,
     transaction::{AtomicVersion, Version},
 };
-use itertools::Itertools;
 use schemadb::{ReadOptions, SchemaBatch, DB};
 use std::{
     collections::HashSet,
@@ -35,11 +35,16 @@ impl DBPruner for EventStorePruner {
         // Current target version  might be less than the target version to ensure we don't prune
         // more than max_version in one go.
         let current_target_version = self.get_currrent_batch_target(max_versions);
-        let candidate_events = self
-            .get_pruning_candidate_events(self.least_readable_version(), current_target_version)?;
+        let index_keys = self
+            .event_store
+            .get_event_index_keys_by_version_range(
+                self.least_readable_version(),
+                current_target_version,
+            )?
+            .collect::<anyhow::Result<Vec<_>>>()?;
 
         let event_keys: HashSet<EventKey> =
-            candidate_events.iter().map(|event| *event.key()).collect();
+            index_keys.iter().map(|(event_key, _)| *event_key).collect();
 
         self.event_store.prune_events_by_version(
             event_keys,
@@ -49,7 +54,7 @@ impl DBPruner for EventStorePruner {
         )?;
 
         self.event_store
-            .prune_events_by_key(&candidate_events, db_batch)?;
+            .prune_events_by_key(&index_keys, db_batch)?;
 
         self.event_store.prune_event_accumulator(
             self.least_readable_version(),
@@ -92,6 +97,9 @@ impl DBPruner for EventStorePruner {
         DIEM_PRUNER_LEAST_READABLE_VERSION
             .with_label_values(&["event_store"])
             .set(least_readable_version as i64);
+        DIEM_PRUNER_PENDING_VERSIONS
+            .with_label_values(&["event_store"])
+            .set(self.target_version().saturating_sub(least_readable_version) as i64);
     }
 }
 
@@ -104,15 +112,4 @@ impl EventStorePruner {
             least_readable_version: AtomicVersion::new(0),
         }
     }
-
-    fn get_pruning_candidate_events(
-        &self,
-        start: Version,
-        end: Version,
-    ) -> anyhow::Result<Vec<ContractEvent>> {
-        self.event_store
-            .get_events_by_version_iter(start, (end - start) as usize)?
-            .flatten_ok()
-            .collect()
-    }
 }
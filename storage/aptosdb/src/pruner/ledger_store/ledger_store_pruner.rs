@@ -1,8 +1,10 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 use crate::{
-    metrics::DIEM_PRUNER_LEAST_READABLE_VERSION, pruner::db_pruner::DBPruner,
-    schema::ledger_counters::LedgerCountersSchema, LedgerStore,
+    metrics::{DIEM_PRUNER_LEAST_READABLE_VERSION, DIEM_PRUNER_PENDING_VERSIONS},
+    pruner::db_pruner::DBPruner,
+    schema::ledger_counters::LedgerCountersSchema,
+    LedgerStore,
 };
 use aptos_types::transaction::{AtomicVersion, Version};
 use schemadb::{ReadOptions, SchemaBatch, DB};
@@ -61,6 +63,9 @@ impl DBPruner for LedgerStorePruner {
         DIEM_PRUNER_LEAST_READABLE_VERSION
             .with_label_values(&["ledger_store"])
             .set(least_readable_version as i64);
+        DIEM_PRUNER_PENDING_VERSIONS
+            .with_label_values(&["ledger_store"])
+            .set(self.target_version().saturating_sub(least_readable_version) as i64);
     }
 }
 
@@ -1,7 +1,7 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-//! This module provides `Pruner` which manages a thread pruning old data in the background and is
+//! This module provides `Pruner` which manages threads pruning old data in the background and is
 //! meant to be triggered by other threads as they commit new data to the DB.
 
 mod db_pruner;
@@ -16,41 +16,130 @@ use crate::metrics::DIEM_STORAGE_PRUNE_WINDOW;
 use aptos_config::config::StoragePrunerConfig;
 use aptos_infallible::Mutex;
 
-use crate::{EventStore, LedgerStore, TransactionStore};
+use crate::{
+    pruner::{
+        db_pruner::DBPruner,
+        event_store::event_store_pruner::EventStorePruner,
+        ledger_store::ledger_store_pruner::LedgerStorePruner,
+        state_store::StateStorePruner,
+        transaction_store::{
+            transaction_store_pruner::TransactionStorePruner, write_set_pruner::WriteSetPruner,
+        },
+    },
+    schema::{
+        EVENT_ACCUMULATOR_CF_NAME, EVENT_BY_KEY_CF_NAME, EVENT_BY_VERSION_CF_NAME, EVENT_CF_NAME,
+        LEDGER_COUNTERS_CF_NAME, TRANSACTION_ACCUMULATOR_CF_NAME, TRANSACTION_BY_ACCOUNT_CF_NAME,
+        TRANSACTION_BY_HASH_CF_NAME, TRANSACTION_BY_PAYLOAD_TYPE_CF_NAME, TRANSACTION_CF_NAME,
+        TRANSACTION_INFO_CF_NAME, WRITE_SET_CF_NAME,
+    },
+    AptosDbError, EventStore, LedgerStore, TransactionStore,
+};
 use aptos_types::transaction::Version;
-use schemadb::DB;
+use itertools::zip_eq;
+use schemadb::{ColumnFamilyName, DB};
 use std::{
+    collections::BTreeMap,
+    ops::Range,
     sync::{
+        atomic::{AtomicBool, Ordering},
         mpsc::{channel, Sender},
         Arc,
     },
     thread::JoinHandle,
+    time::{Duration, Instant},
 };
 use worker::{Command, Worker};
 
+/// Column families pruned by version by each of the pruners other than the state store pruner, in
+/// the order of the pruner indices.
+pub(crate) const VERSIONED_PRUNER_COLUMN_FAMILIES: [&[ColumnFamilyName]; 4] = [
+    &[
+        TRANSACTION_CF_NAME,
+        TRANSACTION_BY_HASH_CF_NAME,
+        TRANSACTION_BY_ACCOUNT_CF_NAME,
+        TRANSACTION_BY_PAYLOAD_TYPE_CF_NAME,
+        TRANSACTION_INFO_CF_NAME,
+        TRANSACTION_ACCUMULATOR_CF_NAME,
+    ],
+    &[LEDGER_COUNTERS_CF_NAME],
+    &[
+        EVENT_CF_NAME,
+        EVENT_BY_KEY_CF_NAME,
+        EVENT_BY_VERSION_CF_NAME,
+        EVENT_ACCUMULATOR_CF_NAME,
+    ],
+    &[WRITE_SET_CF_NAME],
+];
+
+/// Number of pruners, i.e. the state store pruner plus the versioned pruners.
+const NUM_PRUNERS: usize = 1 + VERSIONED_PRUNER_COLUMN_FAMILIES.len();
+
+/// How long `Pruner::prune_up_to` waits for the worker threads to make progress before giving up.
+const PRUNE_UP_TO_STALL_TIMEOUT: Duration = Duration::from_secs(60);
+
 /// The `Pruner` is meant to be part of a `AptosDB` instance and runs in the background to prune old
 /// data.
 ///
-/// It creates a worker thread on construction and joins it on destruction. When destructed, it
-/// quits the worker thread eagerly without waiting for all pending work to be done.
+/// It consists of two independently configured sub-pruners, one pruning the state store and the
+/// other pruning the ledger (transactions, events etc.), so that e.g. the full transaction history
+/// can be kept while the state store is pruned aggressively.
 #[derive(Debug)]
 pub(crate) struct Pruner {
-    /// DB version window, which dictates how many versions of state store
-    /// to keep.
-    state_store_prune_window: Version,
-    /// DB version window, which dictates how many version of other stores like transaction, ledger
-    /// info, events etc to keep.
-    default_prune_window: Version,
-    /// The worker thread handle, created upon Pruner instance construction and joined upon its
-    /// destruction. It only becomes `None` after joined in `drop()`.
-    worker_thread: Option<JoinHandle<()>>,
-    /// The sender side of the channel talking to the worker thread.
-    command_sender: Mutex<Sender<Command>>,
-    /// (For tests) A way for the worker thread to inform the `Pruner` the pruning progress. If it
-    /// sets value to `V`, all versions before `V` can no longer be accessed. This is protected by Mutex
-    /// as this is accessed both by the Pruner thread and the worker thread.
-    #[allow(dead_code)]
+    /// Prunes the state store, i.e. the Jellyfish Merkle nodes and the stale node index, keeping
+    /// the versions in `state_store_prune_window`.
+    state_store_pruner: SubPruner,
+    /// Prunes the other stores like transaction, ledger info, events etc, keeping the versions in
+    /// `default_prune_window`.
+    ledger_pruner: SubPruner,
+    /// (For tests) A way for the worker threads to inform the `Pruner` the pruning progress, in
+    /// the order of the pruner indices. If an entry is set to `V`, all versions before `V` can no
+    /// longer be accessed from the corresponding store. This is protected by Mutex as this is
+    /// accessed both by the Pruner thread and the worker threads.
     least_readable_version: Arc<Mutex<Vec<Version>>>,
+    /// Versions pinned by outstanding `VersionLease`s, with the number of leases on each. Pruning
+    /// targets never go beyond the smallest pinned version.
+    pinned_versions: Arc<Mutex<BTreeMap<Version, usize>>>,
+    /// For each store, in the order of the pruner indices, the version up to which the batch the
+    /// worker threads are working on may prune. Versions below it can't be pinned any more even
+    /// if still readable.
+    batch_target_versions: Arc<Mutex<Vec<Version>>>,
+    /// Shared with the worker threads, which don't prune anything while this is false.
+    enabled: Arc<AtomicBool>,
+}
+
+/// Keeps a version from being pruned while alive, see [`Pruner::pin_version`].
+#[derive(Debug)]
+pub struct VersionLease {
+    version: Version,
+    /// `None` if the DB runs without a pruner, in which case nothing needs to be released.
+    pinned_versions: Option<Arc<Mutex<BTreeMap<Version, usize>>>>,
+}
+
+impl VersionLease {
+    pub(crate) fn new_unpinned(version: Version) -> Self {
+        Self {
+            version,
+            pinned_versions: None,
+        }
+    }
+
+    pub fn version(&self) -> Version {
+        self.version
+    }
+}
+
+impl Drop for VersionLease {
+    fn drop(&mut self) {
+        if let Some(pinned_versions) = self.pinned_versions.as_ref() {
+            let mut pinned_versions = pinned_versions.lock();
+            if let Some(count) = pinned_versions.get_mut(&self.version) {
+                *count -= 1;
+                if *count == 0 {
+                    pinned_versions.remove(&self.version);
+                }
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -62,82 +151,404 @@ pub enum PrunerIndex {
     WriteSetPrunerIndex,
 }
 
-impl Pruner {
-    /// Creates a worker thread that waits on a channel for pruning commands.
-    pub fn new(
+/// A worker thread pruning a subset of the stores, see [`Pruner`].
+///
+/// It creates the worker thread on construction and joins it on destruction. When destructed, it
+/// quits the worker thread eagerly without waiting for all pending work to be done.
+#[derive(Debug)]
+struct SubPruner {
+    /// DB version window, which dictates how many versions of the stores to keep.
+    prune_window: Version,
+    /// The most versions the worker thread prunes in one batch.
+    max_version_to_prune_per_batch: u64,
+    /// Indices of the pruned stores, in terms of `PrunerIndex`.
+    pruner_indices: Range<usize>,
+    /// The worker thread handle, created upon SubPruner instance construction and joined upon its
+    /// destruction. It only becomes `None` after joined in `drop()`.
+    worker_thread: Option<JoinHandle<()>>,
+    /// The sender side of the channel talking to the worker thread.
+    command_sender: Mutex<Sender<Command>>,
+    /// The error the last batch of the worker thread failed with, if any.
+    last_error: Arc<Mutex<Option<String>>>,
+}
+
+impl SubPruner {
+    /// Creates a worker thread driving `db_pruners` that waits on a channel for pruning commands.
+    fn new(
+        thread_name: &str,
         db: Arc<DB>,
-        storage_pruner_config: StoragePrunerConfig,
-        transaction_store: Arc<TransactionStore>,
-        ledger_store: Arc<LedgerStore>,
-        event_store: Arc<EventStore>,
+        db_pruners: Vec<Arc<dyn DBPruner + Send + Sync>>,
+        pruner_indices: Range<usize>,
+        prune_window: Version,
+        max_version_to_prune_per_batch: u64,
+        least_readable_version: Arc<Mutex<Vec<Version>>>,
+        enabled: Arc<AtomicBool>,
+        pinned_versions: Arc<Mutex<BTreeMap<Version, usize>>>,
+        batch_target_versions: Arc<Mutex<Vec<Version>>>,
     ) -> Self {
+        assert_eq!(db_pruners.len(), pruner_indices.len());
         let (command_sender, command_receiver) = channel();
-
-        let least_readable_version = Arc::new(Mutex::new(vec![0, 0, 0, 0, 0]));
-        let worker_progress_clone = Arc::clone(&least_readable_version);
-
-        DIEM_STORAGE_PRUNE_WINDOW.set(
-            storage_pruner_config
-                .state_store_prune_window
-                .expect("State store pruner window is required") as i64,
-        );
+        let last_error = Arc::new(Mutex::new(None));
         let worker = Worker::new(
             db,
-            transaction_store,
-            ledger_store,
-            event_store,
+            db_pruners,
             command_receiver,
             least_readable_version,
-            storage_pruner_config
-                .max_version_to_prune_per_batch
-                .expect("Max version to prune per batch is expected"),
+            pruner_indices.start,
+            max_version_to_prune_per_batch,
+            enabled,
+            pinned_versions,
+            batch_target_versions,
+            Arc::clone(&last_error),
         );
         let worker_thread = std::thread::Builder::new()
-            .name("aptosdb_pruner".into())
+            .name(thread_name.into())
             .spawn(move || worker.work())
             .expect("Creating pruner thread should succeed.");
 
         Self {
-            state_store_prune_window: storage_pruner_config
-                .state_store_prune_window
-                .expect("State store prune window must be specified"),
-            default_prune_window: storage_pruner_config
-                .default_prune_window
-                .expect("Default prune window must be specified"),
+            prune_window,
+            max_version_to_prune_per_batch,
+            pruner_indices,
             worker_thread: Some(worker_thread),
             command_sender: Mutex::new(command_sender),
-            least_readable_version: worker_progress_clone,
+            last_error,
         }
     }
 
+    /// Sends pruning command to the worker thread, `target_db_versions` being the targets of all
+    /// the stores in the order of the pruner indices.
+    fn wake(&self, target_db_versions: &[Version]) {
+        self.command_sender
+            .lock()
+            .send(Command::Prune {
+                target_db_versions: target_db_versions[self.pruner_indices.clone()].to_vec(),
+            })
+            .expect("Receiver should not destruct prematurely.");
+    }
+}
+
+impl Drop for SubPruner {
+    fn drop(&mut self) {
+        self.command_sender
+            .lock()
+            .send(Command::Quit)
+            .expect("Receiver should not destruct.");
+        self.worker_thread
+            .take()
+            .expect("Worker thread must exist.")
+            .join()
+            .expect("Worker thread should join peacefully.");
+    }
+}
+
+impl Pruner {
+    /// Creates the worker threads of the state store and ledger sub-pruners.
+    pub fn new(
+        db: Arc<DB>,
+        storage_pruner_config: StoragePrunerConfig,
+        transaction_store: Arc<TransactionStore>,
+        ledger_store: Arc<LedgerStore>,
+        event_store: Arc<EventStore>,
+    ) -> Self {
+        let least_readable_version = Arc::new(Mutex::new(vec![0; NUM_PRUNERS]));
+        let enabled = Arc::new(AtomicBool::new(true));
+        let pinned_versions = Arc::new(Mutex::new(BTreeMap::new()));
+        let batch_target_versions = Arc::new(Mutex::new(vec![0; NUM_PRUNERS]));
+
+        let state_store_prune_window = storage_pruner_config
+            .state_store_prune_window
+            .expect("State store prune window must be specified");
+        let max_version_to_prune_per_batch = storage_pruner_config
+            .max_version_to_prune_per_batch
+            .expect("Max version to prune per batch is expected");
+        DIEM_STORAGE_PRUNE_WINDOW.set(state_store_prune_window as i64);
+        let state_store_pruner = SubPruner::new(
+            "aptosdb_state_pruner",
+            Arc::clone(&db),
+            vec![Arc::new(StateStorePruner::new(
+                Arc::clone(&db),
+                0,
+                Instant::now(),
+            ))],
+            0..1,
+            state_store_prune_window,
+            max_version_to_prune_per_batch,
+            Arc::clone(&least_readable_version),
+            Arc::clone(&enabled),
+            Arc::clone(&pinned_versions),
+            Arc::clone(&batch_target_versions),
+        );
+
+        let ledger_pruner = SubPruner::new(
+            "aptosdb_ledger_pruner",
+            Arc::clone(&db),
+            vec![
+                Arc::new(TransactionStorePruner::new(
+                    Arc::clone(&db),
+                    Arc::clone(&transaction_store),
+                )),
+                Arc::new(LedgerStorePruner::new(Arc::clone(&db), ledger_store)),
+                Arc::new(EventStorePruner::new(Arc::clone(&db), event_store)),
+                Arc::new(WriteSetPruner::new(Arc::clone(&db), transaction_store)),
+            ],
+            1..NUM_PRUNERS,
+            storage_pruner_config
+                .default_prune_window
+                .expect("Default prune window must be specified"),
+            storage_pruner_config
+                .ledger_max_version_to_prune_per_batch
+                .unwrap_or(max_version_to_prune_per_batch),
+            Arc::clone(&least_readable_version),
+            Arc::clone(&enabled),
+            Arc::clone(&pinned_versions),
+            Arc::clone(&batch_target_versions),
+        );
+
+        Self {
+            state_store_pruner,
+            ledger_pruner,
+            least_readable_version,
+            pinned_versions,
+            batch_target_versions,
+            enabled,
+        }
+    }
+
+    /// Pauses or resumes the worker threads. While paused nothing is deleted, but the targets
+    /// keep being updated by `wake()`, so the backlog is processed once resumed.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
     pub fn get_state_store_pruner_window(&self) -> Version {
-        self.state_store_prune_window
+        self.state_store_pruner.prune_window
     }
 
-    /// Sends pruning command to the worker thread when necessary.
-    pub fn wake(&self, latest_version: Version) {
-        let least_readable_state_store_version =
-            latest_version.saturating_sub(self.state_store_prune_window);
-        let least_readable_default_store_version =
-            latest_version.saturating_sub(self.default_prune_window);
+    pub fn get_ledger_pruner_window(&self) -> Version {
+        self.ledger_pruner.prune_window
+    }
 
-        self.command_sender
+    /// Returns the smallest version from which all the pruned stores are still readable.
+    pub fn get_least_readable_version(&self) -> Version {
+        self.least_readable_version
             .lock()
-            .send(Command::Prune {
-                target_db_versions: vec![
-                    least_readable_state_store_version,
-                    least_readable_default_store_version,
-                    least_readable_default_store_version,
-                    least_readable_default_store_version,
-                    least_readable_default_store_version,
-                ],
+            .iter()
+            .max()
+            .cloned()
+            .unwrap_or(0)
+    }
+
+    /// Returns the smallest version from which the state store is still readable.
+    pub fn get_least_readable_state_store_version(&self) -> Version {
+        self.least_readable_version.lock()[0]
+    }
+
+    /// Returns the smallest version from which all the pruned stores other than the state store
+    /// are still readable.
+    pub fn get_least_readable_ledger_version(&self) -> Version {
+        self.least_readable_version.lock()[1..]
+            .iter()
+            .max()
+            .cloned()
+            .unwrap_or(0)
+    }
+
+    /// Returns the largest number of versions any store is behind the target implied by
+    /// `latest_version` and its prune window.
+    pub fn get_pruner_backlog(&self, latest_version: Version) -> Version {
+        self.get_pruner_backlogs(latest_version)
+            .into_iter()
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns whether both sub-pruners keep up at `latest_version`, i.e. none of their stores is
+    /// behind its target by more than the sub-pruner's own prune window plus one batch. The batch
+    /// is the slack of a worker thread that is busy with it, and the window bounds the extra
+    /// versions kept around to as many as the sub-pruner is configured to keep anyway.
+    pub fn is_keeping_up(&self, latest_version: Version) -> bool {
+        let backlogs = self.get_pruner_backlogs(latest_version);
+        [&self.state_store_pruner, &self.ledger_pruner]
+            .iter()
+            .all(|sub_pruner| {
+                let max_backlog = sub_pruner
+                    .prune_window
+                    .saturating_add(sub_pruner.max_version_to_prune_per_batch);
+                backlogs[sub_pruner.pruner_indices.clone()]
+                    .iter()
+                    .all(|backlog| *backlog <= max_backlog)
             })
-            .expect("Receiver should not destruct prematurely.");
+    }
+
+    /// Returns, for each store, how many versions it is behind the target implied by
+    /// `latest_version` and its prune window, in the order of the pruner indices.
+    fn get_pruner_backlogs(&self, latest_version: Version) -> Vec<Version> {
+        zip_eq(
+            self.get_target_versions(latest_version),
+            self.least_readable_version.lock().iter(),
+        )
+        .map(|(target, least_readable)| target.saturating_sub(*least_readable))
+        .collect()
+    }
+
+    /// Returns, for each store, the range of versions `[least_readable, target)` that is due to be
+    /// pruned given `latest_version`, in the order of the pruner indices.
+    pub fn get_prunable_version_ranges(&self, latest_version: Version) -> Vec<(Version, Version)> {
+        zip_eq(
+            self.least_readable_version.lock().iter(),
+            self.get_target_versions(latest_version),
+        )
+        .map(|(least_readable, target)| (*least_readable, target.max(*least_readable)))
+        .collect()
+    }
+
+    /// Prevents the pruner from advancing past `version` until the returned lease is dropped.
+    ///
+    /// Note that nothing newer than the oldest pinned version gets pruned while the lease is held,
+    /// so the DB grows as if the prune window were extended by however far the ledger advances in
+    /// the meantime. Leases are meant to be short lived.
+    ///
+    /// The check is done under the same lock the worker threads plan their batches under, so a
+    /// version is either pinned before a batch that would prune it is planned, or refused.
+    pub fn pin_version(&self, version: Version) -> anyhow::Result<VersionLease> {
+        let mut pinned_versions = self.pinned_versions.lock();
+        let least_readable_version = std::cmp::max(
+            self.get_least_readable_version(),
+            self.batch_target_versions
+                .lock()
+                .iter()
+                .max()
+                .cloned()
+                .unwrap_or(0),
+        );
+        anyhow::ensure!(
+            version >= least_readable_version,
+            AptosDbError::Pruned(version, least_readable_version),
+        );
+        *pinned_versions.entry(version).or_insert(0) += 1;
+
+        Ok(VersionLease {
+            version,
+            pinned_versions: Some(Arc::clone(&self.pinned_versions)),
+        })
+    }
+
+    /// Returns the largest version `prune_up_to` accepts given `latest_version`, i.e. the one
+    /// implied by the smaller of the prune windows.
+    pub fn get_max_target_version(&self, latest_version: Version) -> Version {
+        latest_version.saturating_sub(std::cmp::min(
+            self.get_state_store_pruner_window(),
+            self.get_ledger_pruner_window(),
+        ))
+    }
+
+    /// Prunes all the stores up to `target_version`, blocking until done. Each store is pruned no
+    /// further than its prune window and any pinned version allow, and errors if `target_version`
+    /// is beyond what the smaller of the prune windows allows.
+    ///
+    /// Also errors, leaving whatever has been pruned so far in place, if the pruner gets paused,
+    /// if a worker thread fails a batch, or if no progress is made for `PRUNE_UP_TO_STALL_TIMEOUT`.
+    pub fn prune_up_to(
+        &self,
+        latest_version: Version,
+        target_version: Version,
+    ) -> anyhow::Result<()> {
+        use std::thread::sleep;
+
+        anyhow::ensure!(self.is_enabled(), "Can't prune while the pruner is paused.");
+        let max_target_version = self.get_max_target_version(latest_version);
+        anyhow::ensure!(
+            target_version <= max_target_version,
+            "Can't prune beyond version {} given latest version {}, requested {}.",
+            max_target_version,
+            latest_version,
+            target_version,
+        );
+
+        let target_db_versions: Vec<Version> = zip_eq(
+            self.get_target_versions(latest_version),
+            self.least_readable_version.lock().iter(),
+        )
+        .map(|(target, least_readable)| target.min(target_version).max(*least_readable))
+        .collect();
+        // Don't fail on what went wrong before this call, the targets sent below are retried.
+        for sub_pruner in [&self.state_store_pruner, &self.ledger_pruner] {
+            *sub_pruner.last_error.lock() = None;
+        }
+        let mut least_readable_versions = self.least_readable_version.lock().clone();
+        let mut last_progress = Instant::now();
+        loop {
+            // A version pinned meanwhile caps the targets, see `Worker::plan_batch`.
+            let min_pinned_version = self.pinned_versions.lock().keys().next().cloned();
+            let current_least_readable_versions = self.least_readable_version.lock().clone();
+            if zip_eq(&current_least_readable_versions, &target_db_versions).all(
+                |(least_readable, target)| {
+                    *least_readable >= min_pinned_version.map_or(*target, |v| v.min(*target))
+                },
+            ) {
+                return Ok(());
+            }
+            anyhow::ensure!(self.is_enabled(), "Pruner paused while pruning.");
+            for sub_pruner in [&self.state_store_pruner, &self.ledger_pruner] {
+                if let Some(error) = sub_pruner.last_error.lock().as_ref() {
+                    anyhow::bail!("Pruner worker failed: {}", error);
+                }
+            }
+            if current_least_readable_versions != least_readable_versions {
+                least_readable_versions = current_least_readable_versions;
+                last_progress = Instant::now();
+            }
+            anyhow::ensure!(
+                last_progress.elapsed() < PRUNE_UP_TO_STALL_TIMEOUT,
+                "Pruner made no progress in {:?}.",
+                PRUNE_UP_TO_STALL_TIMEOUT,
+            );
+            // Keep resending the targets, in case they are overridden by a concurrent `wake()`.
+            self.state_store_pruner.wake(&target_db_versions);
+            self.ledger_pruner.wake(&target_db_versions);
+            sleep(Duration::from_millis(10));
+        }
+    }
+
+    fn get_target_versions(&self, latest_version: Version) -> Vec<Version> {
+        let min_pinned_version = self
+            .pinned_versions
+            .lock()
+            .keys()
+            .next()
+            .cloned()
+            .unwrap_or(Version::max_value());
+        let least_readable_state_store_version = latest_version
+            .saturating_sub(self.get_state_store_pruner_window())
+            .min(min_pinned_version);
+        let least_readable_default_store_version = latest_version
+            .saturating_sub(self.get_ledger_pruner_window())
+            .min(min_pinned_version);
+
+        vec![
+            least_readable_state_store_version,
+            least_readable_default_store_version,
+            least_readable_default_store_version,
+            least_readable_default_store_version,
+            least_readable_default_store_version,
+        ]
+    }
+
+    /// Sends pruning commands to the worker threads of both sub-pruners.
+    pub fn wake(&self, latest_version: Version) {
+        let target_db_versions = self.get_target_versions(latest_version);
+        self.state_store_pruner.wake(&target_db_versions);
+        self.ledger_pruner.wake(&target_db_versions);
     }
 
     /// (For tests only.) Notifies the worker thread and waits for it to finish its job by polling
     /// an internal counter.
-    #[cfg(test)]
+    #[cfg(any(test, feature = "fuzzing"))]
     pub fn wake_and_wait(
         &self,
         latest_version: Version,
@@ -150,10 +561,11 @@ impl Pruner {
 
         self.wake(latest_version);
 
-        if latest_version > self.state_store_prune_window
-            || latest_version > self.default_prune_window
+        let state_store_prune_window = self.get_state_store_pruner_window();
+        if latest_version > state_store_prune_window
+            || latest_version > self.get_ledger_pruner_window()
         {
-            let least_readable_state_store_version = latest_version - self.state_store_prune_window;
+            let least_readable_state_store_version = latest_version - state_store_prune_window;
             // Assuming no big pruning chunks will be issued by a test.
             const TIMEOUT: Duration = Duration::from_secs(10);
             let end = Instant::now() + TIMEOUT;
@@ -175,17 +587,3 @@ impl Pruner {
         Ok(())
     }
 }
-
-impl Drop for Pruner {
-    fn drop(&mut self) {
-        self.command_sender
-            .lock()
-            .send(Command::Quit)
-            .expect("Receiver should not destruct.");
-        self.worker_thread
-            .take()
-            .expect("Worker thread must exist.")
-            .join()
-            .expect("Worker thread should join peacefully.");
-    }
-}
@@ -2,8 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    jellyfish_merkle_node::JellyfishMerkleNodeSchema, metrics::DIEM_PRUNER_LEAST_READABLE_VERSION,
-    pruner::db_pruner::DBPruner, stale_node_index::StaleNodeIndexSchema,
+    jellyfish_merkle_node::JellyfishMerkleNodeSchema,
+    metrics::{DIEM_PRUNER_LEAST_READABLE_VERSION, DIEM_PRUNER_PENDING_VERSIONS},
+    pruner::db_pruner::DBPruner,
+    stale_node_index::StaleNodeIndexSchema,
     DIEM_STORAGE_OTHER_TIMERS_SECONDS,
 };
 use aptos_infallible::Mutex;
@@ -98,6 +100,9 @@ impl DBPruner for StateStorePruner {
         DIEM_PRUNER_LEAST_READABLE_VERSION
             .with_label_values(&["state_store"])
             .set(least_readable_version as i64);
+        DIEM_PRUNER_PENDING_VERSIONS
+            .with_label_values(&["state_store"])
+            .set(self.target_version().saturating_sub(least_readable_version) as i64);
     }
 }
 
@@ -57,6 +57,7 @@ fn test_state_store_pruner() {
             state_store_prune_window: Some(0),
             default_prune_window: Some(0),
             max_version_to_prune_per_batch: Some(100),
+            ledger_max_version_to_prune_per_batch: None,
         },
         Arc::clone(transaction_store),
         Arc::clone(&aptos_db.ledger_store),
@@ -127,6 +128,232 @@ fn test_state_store_pruner() {
     }
 }
 
+#[test]
+fn test_pinned_version_not_pruned() {
+    let address = AccountAddress::new([1u8; AccountAddress::LENGTH]);
+    let value0 = AccountStateBlob::from(vec![0x01]);
+    let value1 = AccountStateBlob::from(vec![0x02]);
+    let value2 = AccountStateBlob::from(vec![0x03]);
+
+    let tmp_dir = TempPath::new();
+    let aptos_db = AptosDB::new_for_test(&tmp_dir);
+    let db = aptos_db.db;
+    let state_store = &StateStore::new(Arc::clone(&db));
+    let pruner = Pruner::new(
+        Arc::clone(&db),
+        StoragePrunerConfig {
+            state_store_prune_window: Some(0),
+            default_prune_window: Some(0),
+            max_version_to_prune_per_batch: Some(100),
+            ledger_max_version_to_prune_per_batch: None,
+        },
+        Arc::clone(&aptos_db.transaction_store),
+        Arc::clone(&aptos_db.ledger_store),
+        Arc::clone(&aptos_db.event_store),
+    );
+
+    put_account_state_set(&db, state_store, vec![(address, value0.clone())], 0);
+    put_account_state_set(&db, state_store, vec![(address, value1)], 1);
+    put_account_state_set(&db, state_store, vec![(address, value2.clone())], 2);
+
+    let lease = pruner.pin_version(0).unwrap();
+    assert_eq!(lease.version(), 0);
+    // Pruning doesn't go beyond the pinned version, `prune_up_to` returns once the targets capped
+    // at it are reached.
+    pruner
+        .prune_up_to(2 /* latest_version */, 2 /* target_version */)
+        .unwrap();
+    assert_eq!(pruner.get_least_readable_state_store_version(), 0);
+    verify_state_in_store(state_store, address, Some(&value0), 0);
+
+    // Once the lease is dropped, the version becomes prunable.
+    drop(lease);
+    pruner
+        .wake_and_wait(
+            2, /* latest_version */
+            PrunerIndex::StateStorePrunerIndex as usize,
+        )
+        .unwrap();
+    assert!(state_store
+        .get_account_state_with_proof_by_version(address, 0)
+        .is_err());
+    verify_state_in_store(state_store, address, Some(&value2), 2);
+    assert!(pruner.pin_version(0).is_err());
+}
+
+#[test]
+fn test_version_pinned_after_target_sent_not_pruned() {
+    let address = AccountAddress::new([1u8; AccountAddress::LENGTH]);
+    let value0 = AccountStateBlob::from(vec![0x01]);
+    let value1 = AccountStateBlob::from(vec![0x02]);
+
+    let tmp_dir = TempPath::new();
+    let aptos_db = AptosDB::new_for_test(&tmp_dir);
+    let db = aptos_db.db;
+    let state_store = &StateStore::new(Arc::clone(&db));
+    put_account_state_set(&db, state_store, vec![(address, value0.clone())], 0);
+    put_account_state_set(&db, state_store, vec![(address, value1)], 1);
+
+    // Drive a worker by hand, to interleave deterministically with pinning.
+    let (command_sender, command_receiver) = channel();
+    let pinned_versions = Arc::new(Mutex::new(BTreeMap::new()));
+    let mut worker = Worker::new(
+        Arc::clone(&db),
+        vec![Arc::new(StateStorePruner::new(
+            Arc::clone(&db),
+            0,
+            Instant::now(),
+        ))],
+        command_receiver,
+        Arc::new(Mutex::new(vec![0])),
+        0,   /* progress_offset */
+        100, /* max_version_to_prune_per_batch */
+        Arc::new(AtomicBool::new(true)),
+        Arc::clone(&pinned_versions),
+        Arc::new(Mutex::new(vec![0])),
+        Arc::new(Mutex::new(None)),
+    );
+    worker.initialize();
+
+    // The worker has already received a target beyond the version when it gets pinned.
+    command_sender
+        .send(Command::Prune {
+            target_db_versions: vec![1],
+        })
+        .unwrap();
+    assert!(worker.receive_commands());
+    pinned_versions.lock().insert(0, 1);
+
+    worker.prune_batch();
+    verify_state_in_store(state_store, address, Some(&value0), 0);
+
+    // Once unpinned, the next target is honored.
+    pinned_versions.lock().clear();
+    command_sender
+        .send(Command::Prune {
+            target_db_versions: vec![1],
+        })
+        .unwrap();
+    assert!(worker.receive_commands());
+    worker.prune_batch();
+    assert!(state_store
+        .get_account_state_with_proof_by_version(address, 0)
+        .is_err());
+}
+
+#[test]
+fn test_prune_floor() {
+    let address = AccountAddress::new([1u8; AccountAddress::LENGTH]);
+
+    let tmp_dir = TempPath::new();
+    let aptos_db = AptosDB::new_for_test(&tmp_dir);
+    let db = aptos_db.db;
+    let state_store = &StateStore::new(Arc::clone(&db));
+    let pruner = Pruner::new(
+        Arc::clone(&db),
+        StoragePrunerConfig {
+            state_store_prune_window: Some(0),
+            default_prune_window: Some(0),
+            max_version_to_prune_per_batch: Some(100),
+            ledger_max_version_to_prune_per_batch: None,
+        },
+        Arc::clone(&aptos_db.transaction_store),
+        Arc::clone(&aptos_db.ledger_store),
+        Arc::clone(&aptos_db.event_store),
+    );
+
+    for version in 0..3 {
+        put_account_state_set(
+            &db,
+            state_store,
+            vec![(address, AccountStateBlob::from(vec![version as u8]))],
+            version,
+        );
+    }
+
+    pruner
+        .wake_and_wait(
+            2, /* latest_version */
+            PrunerIndex::StateStorePrunerIndex as usize,
+        )
+        .unwrap();
+    // The prune floor gauge is process wide and set by any DB in the test binary, so check the
+    // per-instance progress it's derived from instead.
+    assert_eq!(pruner.get_least_readable_state_store_version(), 2);
+    assert!(pruner.get_least_readable_version() >= 2);
+}
+
+#[test]
+fn test_independent_state_store_and_ledger_prune_windows() {
+    let address = AccountAddress::new([1u8; AccountAddress::LENGTH]);
+    let value0 = AccountStateBlob::from(vec![0x01]);
+    let value1 = AccountStateBlob::from(vec![0x02]);
+
+    let tmp_dir = TempPath::new();
+    let aptos_db = AptosDB::new_for_test(&tmp_dir);
+    let db = aptos_db.db;
+    let state_store = &StateStore::new(Arc::clone(&db));
+    let pruner = Pruner::new(
+        Arc::clone(&db),
+        StoragePrunerConfig {
+            state_store_prune_window: Some(0),
+            default_prune_window: Some(1_000_000),
+            max_version_to_prune_per_batch: Some(100),
+            ledger_max_version_to_prune_per_batch: Some(10),
+        },
+        Arc::clone(&aptos_db.transaction_store),
+        Arc::clone(&aptos_db.ledger_store),
+        Arc::clone(&aptos_db.event_store),
+    );
+    assert_eq!(pruner.get_state_store_pruner_window(), 0);
+    assert_eq!(pruner.get_ledger_pruner_window(), 1_000_000);
+
+    put_account_state_set(&db, state_store, vec![(address, value0)], 0);
+    put_account_state_set(&db, state_store, vec![(address, value1.clone())], 1);
+
+    // The state store is pruned, while the ledger is kept in full.
+    pruner
+        .wake_and_wait(
+            1, /* latest_version */
+            PrunerIndex::StateStorePrunerIndex as usize,
+        )
+        .unwrap();
+    assert!(state_store
+        .get_account_state_with_proof_by_version(address, 0)
+        .is_err());
+    verify_state_in_store(state_store, address, Some(&value1), 1);
+    assert!(pruner.get_least_readable_state_store_version() >= 1);
+    assert_eq!(pruner.get_least_readable_ledger_version(), 0);
+    assert_eq!(
+        pruner.get_prunable_version_ranges(1 /* latest_version */)[1..],
+        [(0, 0); 4]
+    );
+}
+
+#[test]
+fn test_is_keeping_up() {
+    let tmp_dir = TempPath::new();
+    let aptos_db = AptosDB::new_for_test(&tmp_dir);
+    let pruner = Pruner::new(
+        Arc::clone(&aptos_db.db),
+        StoragePrunerConfig {
+            state_store_prune_window: Some(1000),
+            default_prune_window: Some(0),
+            max_version_to_prune_per_batch: Some(100),
+            ledger_max_version_to_prune_per_batch: Some(10),
+        },
+        Arc::clone(&aptos_db.transaction_store),
+        Arc::clone(&aptos_db.ledger_store),
+        Arc::clone(&aptos_db.event_store),
+    );
+    // Nothing is pruned unless the workers are woken up.
+    assert!(pruner.is_keeping_up(10 /* latest_version */));
+    // The ledger falls behind by more than its own window and batch, even though the backlog is
+    // well within the state store window.
+    assert_eq!(pruner.get_pruner_backlog(11 /* latest_version */), 11);
+    assert!(!pruner.is_keeping_up(11 /* latest_version */));
+}
+
 #[test]
 fn test_worker_quit_eagerly() {
     let address = AccountAddress::new([1u8; AccountAddress::LENGTH]);
@@ -162,21 +389,28 @@ fn test_worker_quit_eagerly() {
         let (command_sender, command_receiver) = channel();
         let worker = Worker::new(
             Arc::clone(&db),
-            Arc::clone(&aptos_db.transaction_store),
-            Arc::clone(&aptos_db.ledger_store),
-            Arc::clone(&aptos_db.event_store),
+            vec![Arc::new(StateStorePruner::new(
+                Arc::clone(&db),
+                0,
+                Instant::now(),
+            ))],
             command_receiver,
-            Arc::new(Mutex::new(vec![0, 0])), /* progress */
+            Arc::new(Mutex::new(vec![0])), /* progress */
+            0,                             /* progress_offset */
             100,
+            Arc::new(AtomicBool::new(true)),       /* enabled */
+            Arc::new(Mutex::new(BTreeMap::new())), /* pinned_versions */
+            Arc::new(Mutex::new(vec![0])),         /* batch_target_versions */
+            Arc::new(Mutex::new(None)),            /* last_error */
         );
         command_sender
             .send(Command::Prune {
-                target_db_versions: vec![1, 0, 0, 0, 0],
+                target_db_versions: vec![1],
             })
             .unwrap();
         command_sender
             .send(Command::Prune {
-                target_db_versions: vec![2, 0, 0, 0, 0],
+                target_db_versions: vec![2],
             })
             .unwrap();
         command_sender.send(Command::Quit).unwrap();
@@ -54,6 +54,7 @@ fn verify_write_set_pruner(write_sets: Vec<WriteSet>) {
             state_store_prune_window: Some(0),
             default_prune_window: Some(0),
             max_version_to_prune_per_batch: Some(100),
+            ledger_max_version_to_prune_per_batch: None,
         },
         Arc::clone(transaction_store),
         Arc::clone(&aptos_db.ledger_store),
@@ -105,6 +106,7 @@ fn verify_txn_store_pruner(
             state_store_prune_window: Some(0),
             default_prune_window: Some(0),
             max_version_to_prune_per_batch: Some(100),
+            ledger_max_version_to_prune_per_batch: None,
         },
         Arc::clone(transaction_store),
         Arc::clone(&aptos_db.ledger_store),
@@ -1,8 +1,10 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 use crate::{
-    metrics::DIEM_PRUNER_LEAST_READABLE_VERSION, pruner::db_pruner::DBPruner,
-    transaction::TransactionSchema, TransactionStore,
+    metrics::{DIEM_PRUNER_LEAST_READABLE_VERSION, DIEM_PRUNER_PENDING_VERSIONS},
+    pruner::db_pruner::DBPruner,
+    transaction::TransactionSchema,
+    TransactionStore,
 };
 use aptos_types::transaction::{AtomicVersion, Transaction, Version};
 use schemadb::{ReadOptions, SchemaBatch, DB};
@@ -34,6 +36,11 @@ impl DBPruner for TransactionStorePruner {
             .prune_transaction_by_hash(&candidate_transactions, db_batch)?;
         self.transaction_store
             .prune_transaction_by_account(&candidate_transactions, db_batch)?;
+        self.transaction_store.prune_transaction_by_payload_type(
+            least_readable_version,
+            &candidate_transactions,
+            db_batch,
+        )?;
         self.transaction_store.prune_transaction_schema(
             self.least_readable_version(),
             current_target_version,
@@ -79,6 +86,9 @@ impl DBPruner for TransactionStorePruner {
         DIEM_PRUNER_LEAST_READABLE_VERSION
             .with_label_values(&["transaction_store"])
             .set(least_readable_version as i64);
+        DIEM_PRUNER_PENDING_VERSIONS
+            .with_label_values(&["transaction_store"])
+            .set(self.target_version().saturating_sub(least_readable_version) as i64);
     }
 }
 
@@ -1,8 +1,10 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 use crate::{
-    metrics::DIEM_PRUNER_LEAST_READABLE_VERSION, pruner::db_pruner::DBPruner,
-    write_set::WriteSetSchema, TransactionStore,
+    metrics::{DIEM_PRUNER_LEAST_READABLE_VERSION, DIEM_PRUNER_PENDING_VERSIONS},
+    pruner::db_pruner::DBPruner,
+    write_set::WriteSetSchema,
+    TransactionStore,
 };
 use aptos_types::transaction::{AtomicVersion, Version};
 use schemadb::{ReadOptions, SchemaBatch, DB};
@@ -61,6 +63,9 @@ impl DBPruner for WriteSetPruner {
         DIEM_PRUNER_LEAST_READABLE_VERSION
             .with_label_values(&["write_set"])
             .set(least_readable_version as i64);
+        DIEM_PRUNER_PENDING_VERSIONS
+            .with_label_values(&["write_set"])
+            .set(self.target_version().saturating_sub(least_readable_version) as i64);
     }
 }
 
@@ -6,114 +6,161 @@ use schemadb::{SchemaBatch, DB};
 use crate::pruner::db_pruner::DBPruner;
 use aptos_infallible::Mutex;
 
-use crate::{
-    pruner::{
-        event_store::event_store_pruner::EventStorePruner,
-        ledger_store::ledger_store_pruner::LedgerStorePruner,
-        state_store::StateStorePruner,
-        transaction_store::{
-            transaction_store_pruner::TransactionStorePruner, write_set_pruner::WriteSetPruner,
-        },
-    },
-    EventStore, LedgerStore, TransactionStore,
-};
+use crate::metrics::DIEM_STORAGE_PRUNE_FLOOR;
 use itertools::zip_eq;
 use std::{
-    sync::{mpsc::Receiver, Arc},
-    time::Instant,
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::Receiver,
+        Arc,
+    },
+    thread::sleep,
+    time::Duration,
 };
 
-/// Maintains all the DBPruners and periodically calls the db_pruner's prune method to prune the DB.
-/// This also exposes API to report the progress to the parent thread.
+/// How long a paused worker sleeps before checking again whether it's been resumed.
+const PAUSED_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Maintains a set of DBPruners and periodically calls the db_pruner's prune method to prune the
+/// DB. This also exposes API to report the progress to the parent thread.
 pub struct Worker {
     db: Arc<DB>,
     command_receiver: Receiver<Command>,
-    /// Keeps tracks of all the DB pruners
+    /// Keeps tracks of the DB pruners driven by this worker
     db_pruners: Vec<Mutex<Arc<dyn DBPruner + Send + Sync>>>,
-    /// Keeps a record of the pruning progress. If this equals to version `V`, we know versions
-    /// smaller than `V` are no longer readable.
+    /// Keeps a record of the pruning progress of all the workers. If an entry equals to version
+    /// `V`, we know versions smaller than `V` are no longer readable from the corresponding store.
     /// This being an atomic value is to communicate the info with the Pruner thread (for tests).
     least_readable_versions: Arc<Mutex<Vec<Version>>>,
+    /// Index of the entry in `least_readable_versions` where the progress of the first pruner of
+    /// this worker is recorded.
+    progress_offset: usize,
     /// Indicates if there's NOT any pending work to do currently, to hint
     /// `Self::receive_commands()` to `recv()` blocking-ly.
     blocking_recv: bool,
     max_version_to_prune_per_batch: u64,
+    /// Nothing is pruned while this is false, though the targets are still updated on commands.
+    enabled: Arc<AtomicBool>,
+    /// Versions pinned by outstanding `VersionLease`s, which no batch prunes beyond.
+    pinned_versions: Arc<Mutex<BTreeMap<Version, usize>>>,
+    /// For each store, the version up to which the batch in progress may prune, in the layout of
+    /// `least_readable_versions`. Updated under the lock of `pinned_versions`, against which
+    /// `Pruner::pin_version` checks new pins.
+    batch_target_versions: Arc<Mutex<Vec<Version>>>,
+    /// The error the last batch failed with, `None` if it succeeded. Shared with the `Pruner` so
+    /// that it can surface it to callers waiting on the progress.
+    last_error: Arc<Mutex<Option<String>>>,
 }
 
 impl Worker {
     pub(crate) fn new(
         db: Arc<DB>,
-        transaction_store: Arc<TransactionStore>,
-        ledger_store: Arc<LedgerStore>,
-        event_store: Arc<EventStore>,
+        db_pruners: Vec<Arc<dyn DBPruner + Send + Sync>>,
         command_receiver: Receiver<Command>,
         least_readable_versions: Arc<Mutex<Vec<Version>>>,
+        progress_offset: usize,
         max_version_to_prune_per_batch: u64,
+        enabled: Arc<AtomicBool>,
+        pinned_versions: Arc<Mutex<BTreeMap<Version, usize>>>,
+        batch_target_versions: Arc<Mutex<Vec<Version>>>,
+        last_error: Arc<Mutex<Option<String>>>,
     ) -> Self {
         Self {
-            db: Arc::clone(&db),
-            db_pruners: vec![
-                Mutex::new(Arc::new(StateStorePruner::new(
-                    Arc::clone(&db),
-                    0,
-                    Instant::now(),
-                ))),
-                Mutex::new(Arc::new(TransactionStorePruner::new(
-                    Arc::clone(&db),
-                    Arc::clone(&transaction_store),
-                ))),
-                Mutex::new(Arc::new(LedgerStorePruner::new(
-                    Arc::clone(&db),
-                    Arc::clone(&ledger_store),
-                ))),
-                Mutex::new(Arc::new(EventStorePruner::new(
-                    Arc::clone(&db),
-                    Arc::clone(&event_store),
-                ))),
-                Mutex::new(Arc::new(WriteSetPruner::new(
-                    Arc::clone(&db),
-                    Arc::clone(&transaction_store),
-                ))),
-            ],
+            db,
+            db_pruners: db_pruners.into_iter().map(Mutex::new).collect(),
             command_receiver,
             least_readable_versions,
+            progress_offset,
             blocking_recv: true,
             max_version_to_prune_per_batch,
+            enabled,
+            pinned_versions,
+            batch_target_versions,
+            last_error,
         }
     }
 
     pub(crate) fn work(mut self) {
-        for db_pruner in &self.db_pruners {
-            db_pruner.lock().initialize();
-        }
+        self.initialize();
         while self.receive_commands() {
+            if !self.enabled.load(Ordering::Relaxed) {
+                // Paused, keep receiving the targets without pruning until resumed.
+                sleep(PAUSED_POLL_INTERVAL);
+                continue;
+            }
             // Process a reasonably small batch of work before trying to receive commands again,
             // in case `Command::Quit` is received (that's when we should quit.)
-            let mut error_in_pruning = false;
-            let mut db_batch = SchemaBatch::new();
-            for db_pruner in &self.db_pruners {
-                let result = db_pruner
-                    .lock()
-                    .prune(&mut db_batch, self.max_version_to_prune_per_batch);
-                result.map_err(|_| error_in_pruning = true).ok();
-            }
-            // Commit all the changes to DB atomically
-            let result = self.db.write_schemas(db_batch);
-            result.map_err(|_| error_in_pruning = true).ok();
-            let mut pruning_pending = false;
-            for db_pruner in &self.db_pruners {
-                // if any of the pruner has pending pruning, then we don't block on receive
-                if db_pruner.lock().is_pruning_pending() {
-                    pruning_pending = true;
+            self.prune_batch();
+        }
+    }
+
+    pub(in crate::pruner) fn initialize(&self) {
+        for db_pruner in &self.db_pruners {
+            db_pruner.lock().initialize();
+        }
+    }
+
+    /// Caps the targets of the pruners at the oldest pinned version, in case a target was sent
+    /// before the version got pinned, and records how far the next batch may prune. Both happen
+    /// under the lock of the pinned versions, so a version pinned concurrently is either seen
+    /// here or refused by `Pruner::pin_version`.
+    fn plan_batch(&self) {
+        let pinned_versions = self.pinned_versions.lock();
+        let min_pinned_version = pinned_versions.keys().next().cloned();
+        let mut batch_target_versions = self.batch_target_versions.lock();
+        for (offset, db_pruner) in self.db_pruners.iter().enumerate() {
+            let db_pruner = db_pruner.lock();
+            if let Some(min_pinned_version) = min_pinned_version {
+                let capped_target_version =
+                    min_pinned_version.max(db_pruner.least_readable_version());
+                if db_pruner.target_version() > capped_target_version {
+                    db_pruner.set_target_version(capped_target_version);
                 }
             }
-            if !pruning_pending || error_in_pruning {
-                self.blocking_recv = true;
-            } else {
-                self.blocking_recv = false;
+            let batch_target_version = &mut batch_target_versions[self.progress_offset + offset];
+            *batch_target_version = std::cmp::max(
+                *batch_target_version,
+                db_pruner.get_currrent_batch_target(self.max_version_to_prune_per_batch),
+            );
+        }
+    }
+
+    pub(in crate::pruner) fn prune_batch(&mut self) {
+        self.plan_batch();
+        let mut error_in_pruning = None;
+        let mut db_batch = SchemaBatch::new();
+        for db_pruner in &self.db_pruners {
+            let db_pruner = db_pruner.lock();
+            let result = db_pruner.prune(&mut db_batch, self.max_version_to_prune_per_batch);
+            result
+                .map_err(|e| {
+                    error_in_pruning
+                        .get_or_insert_with(|| format!("{} failed: {:?}", db_pruner.name(), e));
+                })
+                .ok();
+        }
+        // Commit all the changes to DB atomically
+        let result = self.db.write_schemas(db_batch);
+        result
+            .map_err(|e| {
+                error_in_pruning.get_or_insert_with(|| format!("Committing failed: {:?}", e));
+            })
+            .ok();
+        let mut pruning_pending = false;
+        for db_pruner in &self.db_pruners {
+            // if any of the pruner has pending pruning, then we don't block on receive
+            if db_pruner.lock().is_pruning_pending() {
+                pruning_pending = true;
             }
-            self.record_progress();
         }
+        if !pruning_pending || error_in_pruning.is_some() {
+            self.blocking_recv = true;
+        } else {
+            self.blocking_recv = false;
+        }
+        *self.last_error.lock() = error_in_pruning;
+        self.record_progress();
     }
 
     fn record_progress(&mut self) {
@@ -121,7 +168,12 @@ impl Worker {
         for x in &self.db_pruners {
             updated_least_readable_versions.push(x.lock().least_readable_version())
         }
-        *self.least_readable_versions.lock() = updated_least_readable_versions;
+        let mut least_readable_versions = self.least_readable_versions.lock();
+        least_readable_versions
+            [self.progress_offset..self.progress_offset + updated_least_readable_versions.len()]
+            .copy_from_slice(&updated_least_readable_versions);
+        let prune_floor = least_readable_versions.iter().max().cloned().unwrap_or(0);
+        DIEM_STORAGE_PRUNE_FLOOR.set(prune_floor as i64);
     }
 
     /// Tries to receive all pending commands, blocking waits for the next command if no work needs
@@ -130,7 +182,7 @@ impl Worker {
     ///
     /// Returns `false` if `Command::Quit` is received, to break the outer loop and let
     /// `work_loop()` return.
-    fn receive_commands(&mut self) -> bool {
+    pub(in crate::pruner) fn receive_commands(&mut self) -> bool {
         loop {
             let command = if self.blocking_recv {
                 // Worker has nothing to do, blocking wait for the next command.
@@ -153,12 +205,17 @@ impl Worker {
                     for (new_target_version, pruner) in
                         zip_eq(&target_db_versions, &self.db_pruners)
                     {
-                        if *new_target_version > pruner.lock().target_version() {
+                        let pruner = pruner.lock();
+                        // Also retry a target already set if it hasn't been reached, e.g. because
+                        // the last batch failed.
+                        if *new_target_version > pruner.target_version()
+                            || *new_target_version > pruner.least_readable_version()
+                        {
                             // Switch to non-blocking to allow some work to be done after the
                             // channel has drained.
                             self.blocking_recv = false;
                         }
-                        pruner.lock().set_target_version(*new_target_version);
+                        pruner.set_target_version(*new_target_version);
                     }
                 }
             }
@@ -15,10 +15,12 @@ pub(crate) mod jellyfish_merkle_node;
 pub(crate) mod ledger_counters;
 pub(crate) mod ledger_info;
 pub(crate) mod stale_node_index;
+pub(crate) mod state_restore_progress;
 pub(crate) mod transaction;
 pub(crate) mod transaction_accumulator;
 pub(crate) mod transaction_by_account;
 pub(crate) mod transaction_by_hash;
+pub(crate) mod transaction_by_payload_type;
 pub(crate) mod transaction_info;
 pub(crate) mod write_set;
 
@@ -33,10 +35,12 @@ pub const EVENT_CF_NAME: ColumnFamilyName = "event";
 pub const JELLYFISH_MERKLE_NODE_CF_NAME: ColumnFamilyName = "jellyfish_merkle_node";
 pub const LEDGER_COUNTERS_CF_NAME: ColumnFamilyName = "ledger_counters";
 pub const STALE_NODE_INDEX_CF_NAME: ColumnFamilyName = "stale_node_index";
+pub const STATE_RESTORE_PROGRESS_CF_NAME: ColumnFamilyName = "state_restore_progress";
 pub const TRANSACTION_CF_NAME: ColumnFamilyName = "transaction";
 pub const TRANSACTION_ACCUMULATOR_CF_NAME: ColumnFamilyName = "transaction_accumulator";
 pub const TRANSACTION_BY_ACCOUNT_CF_NAME: ColumnFamilyName = "transaction_by_account";
 pub const TRANSACTION_BY_HASH_CF_NAME: ColumnFamilyName = "transaction_by_hash";
+pub const TRANSACTION_BY_PAYLOAD_TYPE_CF_NAME: ColumnFamilyName = "transaction_by_payload_type";
 pub const TRANSACTION_INFO_CF_NAME: ColumnFamilyName = "transaction_info";
 pub const WRITE_SET_CF_NAME: ColumnFamilyName = "write_set";
 
@@ -78,6 +82,9 @@ pub mod fuzzing {
             assert_no_panic_decoding::<super::ledger_counters::LedgerCountersSchema>(data);
             assert_no_panic_decoding::<super::ledger_info::LedgerInfoSchema>(data);
             assert_no_panic_decoding::<super::stale_node_index::StaleNodeIndexSchema>(data);
+            assert_no_panic_decoding::<super::state_restore_progress::StateRestoreProgressSchema>(
+                data,
+            );
             assert_no_panic_decoding::<super::transaction::TransactionSchema>(data);
             assert_no_panic_decoding::<super::transaction_accumulator::TransactionAccumulatorSchema>(
                 data,
@@ -86,6 +93,9 @@ pub mod fuzzing {
                 data,
             );
             assert_no_panic_decoding::<super::transaction_by_hash::TransactionByHashSchema>(data);
+            assert_no_panic_decoding::<
+                super::transaction_by_payload_type::TransactionByPayloadTypeSchema,
+            >(data);
             assert_no_panic_decoding::<super::transaction_info::TransactionInfoSchema>(data);
             assert_no_panic_decoding::<super::write_set::WriteSetSchema>(data);
         }
@@ -0,0 +1,56 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines physical storage schema for the progress of state snapshot restorations,
+//! persisted after each chunk so that a restoration can be resumed after a restart. The progress
+//! is removed once the restoration finishes.
+//! ```text
+//! |<--key-->|<--value->|
+//! | version | progress |
+//! ```
+//!
+//! `Version` is serialized in big endian so that records in RocksDB will be in order of it's
+//! numeric value.
+
+use super::STATE_RESTORE_PROGRESS_CF_NAME;
+use crate::schema::ensure_slice_len_eq;
+use anyhow::Result;
+use aptos_jellyfish_merkle::restore::RestoreProgress;
+use aptos_types::{account_state_blob::AccountStateBlob, transaction::Version};
+use byteorder::{BigEndian, ReadBytesExt};
+use schemadb::{
+    define_schema,
+    schema::{KeyCodec, ValueCodec},
+};
+use std::mem::size_of;
+
+define_schema!(
+    StateRestoreProgressSchema,
+    Version,
+    RestoreProgress<AccountStateBlob>,
+    STATE_RESTORE_PROGRESS_CF_NAME
+);
+
+impl KeyCodec<StateRestoreProgressSchema> for Version {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        Ok(self.to_be_bytes().to_vec())
+    }
+
+    fn decode_key(mut data: &[u8]) -> Result<Self> {
+        ensure_slice_len_eq(data, size_of::<Version>())?;
+        Ok(data.read_u64::<BigEndian>()?)
+    }
+}
+
+impl ValueCodec<StateRestoreProgressSchema> for RestoreProgress<AccountStateBlob> {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        bcs::to_bytes(self).map_err(Into::into)
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        bcs::from_bytes(data).map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,7 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use schemadb::test_no_panic_decoding;
+
+test_no_panic_decoding!(StateRestoreProgressSchema);
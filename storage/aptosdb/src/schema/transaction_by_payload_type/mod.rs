@@ -0,0 +1,71 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines physical storage schema for a transaction index via which the versions of
+//! the user transactions with a given payload type can be found in order. With the version one
+//! can resort to `TransactionSchema` for the transaction content.
+//!
+//! ```text
+//! |<--------key-------->|
+//! | payload_type | txn_ver |
+//! ```
+//!
+//! `payload_type` is the one byte tag of `TransactionPayloadType`, and `txn_ver` is serialized in
+//! big endian so that records in RocksDB will be in order of its numeric value.
+//!
+//! Migration: the column family is created when an existing DB is next opened for writing, and the
+//! index is populated as transactions are committed, so it has no entries for the transactions
+//! already in the DB until `AptosDB::backfill_transaction_by_payload_type_index` is run over it.
+
+use crate::schema::{ensure_slice_len_eq, TRANSACTION_BY_PAYLOAD_TYPE_CF_NAME};
+use anyhow::Result;
+use aptos_types::transaction::{TransactionPayloadType, Version};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use schemadb::{
+    define_schema,
+    schema::{KeyCodec, ValueCodec},
+};
+use std::{convert::TryFrom, mem::size_of};
+
+define_schema!(
+    TransactionByPayloadTypeSchema,
+    Key,
+    (),
+    TRANSACTION_BY_PAYLOAD_TYPE_CF_NAME
+);
+
+type Key = (TransactionPayloadType, Version);
+
+impl KeyCodec<TransactionByPayloadTypeSchema> for Key {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        let (payload_type, version) = *self;
+
+        let mut encoded = vec![payload_type as u8];
+        encoded.write_u64::<BigEndian>(version)?;
+
+        Ok(encoded)
+    }
+
+    fn decode_key(data: &[u8]) -> Result<Self> {
+        ensure_slice_len_eq(data, size_of::<u8>() + size_of::<Version>())?;
+
+        let payload_type = TransactionPayloadType::try_from(data[0])?;
+        let version = (&data[1..]).read_u64::<BigEndian>()?;
+
+        Ok((payload_type, version))
+    }
+}
+
+impl ValueCodec<TransactionByPayloadTypeSchema> for () {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        Ok(Vec::new())
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        ensure_slice_len_eq(data, 0)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,18 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use proptest::prelude::*;
+use schemadb::{schema::fuzzing::assert_encode_decode, test_no_panic_decoding};
+
+proptest! {
+    #[test]
+    fn test_encode_decode(
+        payload_type in any::<TransactionPayloadType>(),
+        version in any::<Version>(),
+    ) {
+        assert_encode_decode::<TransactionByPayloadTypeSchema>(&(payload_type, version), &());
+    }
+}
+
+test_no_panic_decoding!(TransactionByPayloadTypeSchema);
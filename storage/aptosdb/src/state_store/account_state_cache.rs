@@ -0,0 +1,111 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! This file defines `AccountStateCache`, a bounded in-memory LRU cache of account states with
+//! proofs in front of the state Merkle tree.
+
+use crate::metrics::DIEM_STORAGE_ACCOUNT_STATE_CACHE_REQUESTS;
+use aptos_infallible::Mutex;
+use aptos_types::{
+    account_address::AccountAddress, account_state_blob::AccountStateBlob,
+    proof::SparseMerkleProof, transaction::Version,
+};
+use std::collections::{BTreeMap, HashMap};
+
+type Key = (AccountAddress, Version);
+type Value = (
+    Option<AccountStateBlob>,
+    SparseMerkleProof<AccountStateBlob>,
+);
+
+/// Caches up to `capacity` account states keyed by address and version, evicting the least
+/// recently used one when full. Nothing needs to be invalidated on writes, as the state at a
+/// committed version never changes.
+#[derive(Debug)]
+pub(crate) struct AccountStateCache {
+    capacity: usize,
+    inner: Mutex<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    /// The cached values with the tick each was last used at.
+    entries: HashMap<Key, (Value, u64)>,
+    /// The keys by the tick they were last used at, the least recently used first.
+    keys_by_tick: BTreeMap<u64, Key>,
+    next_tick: u64,
+}
+
+impl Inner {
+    fn touch(&mut self, key: Key, last_tick: Option<u64>) -> u64 {
+        if let Some(last_tick) = last_tick {
+            self.keys_by_tick.remove(&last_tick);
+        }
+        let tick = self.next_tick;
+        self.next_tick += 1;
+        self.keys_by_tick.insert(tick, key);
+        tick
+    }
+}
+
+impl AccountStateCache {
+    pub fn new(capacity: usize) -> Self {
+        assert!(
+            capacity > 0,
+            "Account state cache capacity must be positive."
+        );
+        Self {
+            capacity,
+            inner: Mutex::new(Inner::default()),
+        }
+    }
+
+    pub fn get(&self, address: AccountAddress, version: Version) -> Option<Value> {
+        let mut inner = self.inner.lock();
+        let key = (address, version);
+        let last_tick = match inner.entries.get(&key) {
+            Some((_value, last_tick)) => *last_tick,
+            None => {
+                DIEM_STORAGE_ACCOUNT_STATE_CACHE_REQUESTS
+                    .with_label_values(&["miss"])
+                    .inc();
+                return None;
+            }
+        };
+        DIEM_STORAGE_ACCOUNT_STATE_CACHE_REQUESTS
+            .with_label_values(&["hit"])
+            .inc();
+
+        let tick = inner.touch(key, Some(last_tick));
+        let (value, last_tick) = inner.entries.get_mut(&key).expect("Known to exist.");
+        *last_tick = tick;
+        Some(value.clone())
+    }
+
+    pub fn insert(&self, address: AccountAddress, version: Version, value: Value) {
+        let mut inner = self.inner.lock();
+        let key = (address, version);
+        let last_tick = inner
+            .entries
+            .get(&key)
+            .map(|(_value, last_tick)| *last_tick);
+        let tick = inner.touch(key, last_tick);
+        inner.entries.insert(key, (value, tick));
+
+        while inner.entries.len() > self.capacity {
+            let (lru_tick, lru_key) = inner
+                .keys_by_tick
+                .iter()
+                .next()
+                .map(|(tick, key)| (*tick, *key))
+                .expect("Known to be non-empty.");
+            inner.keys_by_tick.remove(&lru_tick);
+            inner.entries.remove(&lru_key);
+        }
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.inner.lock().entries.len()
+    }
+}
@@ -3,6 +3,7 @@
 
 //! This file defines state store APIs that are related account state Merkle tree.
 
+mod account_state_cache;
 #[cfg(test)]
 mod state_store_test;
 
@@ -11,24 +12,33 @@ use crate::{
     ledger_counters::LedgerCounter,
     schema::{
         jellyfish_merkle_node::JellyfishMerkleNodeSchema, stale_node_index::StaleNodeIndexSchema,
+        state_restore_progress::StateRestoreProgressSchema,
     },
+    state_store::account_state_cache::AccountStateCache,
     AptosDbError,
 };
-use anyhow::{ensure, Result};
-use aptos_crypto::HashValue;
+use anyhow::{bail, ensure, format_err, Result};
+use aptos_crypto::{
+    hash::{CryptoHash, SPARSE_MERKLE_PLACEHOLDER_HASH},
+    HashValue,
+};
 use aptos_jellyfish_merkle::{
-    iterator::JellyfishMerkleIterator, node_type::NodeKey, restore::JellyfishMerkleRestore,
+    iterator::JellyfishMerkleIterator,
+    node_type::NodeKey,
+    restore::{JellyfishMerkleRestore, RestoreProgress},
     JellyfishMerkleTree, TreeReader, TreeWriter,
 };
 use aptos_types::{
     account_address::{AccountAddress, HashAccountAddress},
     account_state_blob::{AccountStateBlob, AccountStatesChunkWithProof},
     nibble::{nibble_path::NibblePath, ROOT_NIBBLE_HEIGHT},
-    proof::{SparseMerkleProof, SparseMerkleRangeProof},
+    proof::{
+        SparseMerkleInternalNode, SparseMerkleLeafNode, SparseMerkleProof, SparseMerkleRangeProof,
+    },
     transaction::Version,
 };
 use itertools::process_results;
-use schemadb::{SchemaBatch, DB};
+use schemadb::{ReadOptions, SchemaBatch, DB};
 use std::{collections::HashMap, sync::Arc};
 use storage_interface::StateSnapshotReceiver;
 
@@ -39,11 +49,23 @@ type NodeBatch = aptos_jellyfish_merkle::NodeBatch<AccountStateBlob>;
 #[derive(Debug)]
 pub(crate) struct StateStore {
     db: Arc<DB>,
+    /// Caches the account states read by `get_account_state_with_proof_by_version`, if enabled.
+    account_state_cache: Option<AccountStateCache>,
 }
 
 impl StateStore {
     pub fn new(db: Arc<DB>) -> Self {
-        Self { db }
+        Self {
+            db,
+            account_state_cache: None,
+        }
+    }
+
+    /// Caches up to `capacity` of the account states read, see
+    /// `RocksdbConfig::account_state_cache_capacity`.
+    pub fn with_account_state_cache(mut self, capacity: usize) -> Self {
+        self.account_state_cache = Some(AccountStateCache::new(capacity));
+        self
     }
 
     /// Get the account state blob given account address and root hash of state Merkle tree
@@ -55,7 +77,31 @@ impl StateStore {
         Option<AccountStateBlob>,
         SparseMerkleProof<AccountStateBlob>,
     )> {
-        JellyfishMerkleTree::new(self).get_with_proof(address.hash(), version)
+        if let Some(cache) = self.account_state_cache.as_ref() {
+            if let Some(value) = cache.get(address, version) {
+                return Ok(value);
+            }
+        }
+        let value = JellyfishMerkleTree::new(self).get_with_proof(address.hash(), version)?;
+        if let Some(cache) = self.account_state_cache.as_ref() {
+            cache.insert(address, version, value.clone());
+        }
+        Ok(value)
+    }
+
+    /// Get the account state blob given account address and version, without the proof, e.g. to
+    /// inspect the account state within the DB.
+    pub fn get_account_state_by_version(
+        &self,
+        address: AccountAddress,
+        version: Version,
+    ) -> Result<Option<AccountStateBlob>> {
+        if let Some(cache) = self.account_state_cache.as_ref() {
+            if let Some((blob, _proof)) = cache.get(address, version) {
+                return Ok(blob);
+            }
+        }
+        JellyfishMerkleTree::new(self).get(address.hash(), version)
     }
 
     /// Gets the proof that proves a range of accounts.
@@ -148,10 +194,105 @@ impl StateStore {
         Ok(ret)
     }
 
+    /// Returns the versions in `[start_version, end_version]` at which the account state of
+    /// `address` changed, with the new states, the first entry being the state at `start_version`.
+    ///
+    /// Every tree node is written at the version its subtree last changed and stays current until
+    /// the version it's marked stale since, so the state is read only once per such span of the
+    /// deepest node on the path to the account, instead of at every version.
+    pub fn get_account_state_history(
+        &self,
+        address: AccountAddress,
+        start_version: Version,
+        end_version: Version,
+    ) -> Result<Vec<(Version, Option<AccountStateBlob>)>> {
+        let key = address.hash();
+        let mut history: Vec<(Version, Option<AccountStateBlob>)> = Vec::new();
+        let mut version = end_version;
+        loop {
+            let (blob, written_version) =
+                self.get_account_state_with_written_version(key, version)?;
+            let since_version = std::cmp::max(written_version, start_version);
+            match history.last_mut() {
+                // The node got rewritten without the account state changing, e.g. a leaf moved.
+                Some((later_version, later_blob)) if *later_blob == blob => {
+                    *later_version = since_version
+                }
+                _ => history.push((since_version, blob)),
+            }
+            if since_version == start_version {
+                break;
+            }
+            version = since_version - 1;
+        }
+        history.reverse();
+        Ok(history)
+    }
+
+    /// Returns the account state under `key` at `version`, along with the version the deepest node
+    /// on the path to it was written at, since which the account state has stayed the same.
+    fn get_account_state_with_written_version(
+        &self,
+        key: HashValue,
+        version: Version,
+    ) -> Result<(Option<AccountStateBlob>, Version)> {
+        let mut node_key = NodeKey::new_empty_path(version);
+        let nibble_path = NibblePath::new(key.to_vec());
+        let mut nibble_iter = nibble_path.nibbles();
+
+        for _nibble_depth in 0..=ROOT_NIBBLE_HEIGHT {
+            match self.get_node(&node_key)? {
+                Node::Internal(internal_node) => {
+                    let nibble = nibble_iter
+                        .next()
+                        .ok_or_else(|| format_err!("ran out of nibbles"))?;
+                    match internal_node.child(nibble) {
+                        Some(child) => {
+                            node_key = node_key.gen_child_node_key(child.version, nibble)
+                        }
+                        None => return Ok((None, node_key.version())),
+                    }
+                }
+                Node::Leaf(leaf_node) => {
+                    let blob = if leaf_node.account_key() == key {
+                        Some(leaf_node.value().clone())
+                    } else {
+                        None
+                    };
+                    return Ok((blob, node_key.version()));
+                }
+                Node::Null => return Ok((None, node_key.version())),
+            }
+        }
+        bail!("Jellyfish Merkle tree has cyclic graph inside.");
+    }
+
     pub fn get_account_count(&self, version: Version) -> Result<usize> {
         JellyfishMerkleTree::new(self).get_leaf_count(version)
     }
 
+    /// Returns the number of nodes recorded in the stale node index as stale since a version in
+    /// `[first_version, last_version]`.
+    pub fn get_num_stale_nodes(
+        &self,
+        first_version: Version,
+        last_version: Version,
+    ) -> Result<u64> {
+        let mut iter = self
+            .db
+            .iter::<StaleNodeIndexSchema>(ReadOptions::default())?;
+        iter.seek(&first_version)?;
+        let mut num_stale_nodes = 0;
+        for res in iter {
+            let (index, _) = res?;
+            if index.stale_since_version > last_version {
+                break;
+            }
+            num_stale_nodes += 1;
+        }
+        Ok(num_stale_nodes)
+    }
+
     pub fn get_account_chunk_with_proof(
         self: &Arc<Self>,
         version: Version,
@@ -184,6 +325,58 @@ impl StateStore {
         })
     }
 
+    /// Like `get_account_chunk_with_proof`, but for the up to `chunk_size` leaves ending at
+    /// `last_index`, for walking the account states from the highest index downward. The account
+    /// blobs are still in ascending key order.
+    pub fn get_account_chunk_with_proof_rev(
+        self: &Arc<Self>,
+        version: Version,
+        last_index: usize,
+        chunk_size: usize,
+    ) -> Result<AccountStatesChunkWithProof> {
+        ensure!(chunk_size > 0, "Chunk size must be positive.");
+        let end_index = last_index
+            .checked_add(1)
+            .ok_or_else(|| format_err!("Last index {} overflows.", last_index))?;
+        let first_index = end_index.saturating_sub(chunk_size);
+        let chunk =
+            self.get_account_chunk_with_proof(version, first_index, end_index - first_index)?;
+        ensure!(
+            chunk.last_index == last_index as u64,
+            AptosDbError::NotFound(format!("State chunk ending at {}", last_index)),
+        );
+        Ok(chunk)
+    }
+
+    /// Gets the range proof for the leaf at `after_index`, whose right siblings are all
+    /// placeholders if and only if there are no leaves with greater indices.
+    pub fn get_state_tail_proof(
+        self: &Arc<Self>,
+        version: Version,
+        after_index: usize,
+    ) -> Result<SparseMerkleRangeProof> {
+        let (key, _blob) =
+            JellyfishMerkleIterator::new_by_index(Arc::clone(self), version, after_index)?
+                .next()
+                .ok_or_else(|| {
+                    AptosDbError::NotFound(format!("State leaf at index {}", after_index))
+                })??;
+        self.get_account_state_range_proof(key, version)
+    }
+
+    /// Recomputes the root hash of the state tree at `version` from the values of all its leaves,
+    /// without relying on any hashes stored in the tree nodes.
+    pub fn recompute_root_hash(self: &Arc<Self>, version: Version) -> Result<HashValue> {
+        let leaf_hashes: Vec<(HashValue, HashValue)> = process_results(
+            JellyfishMerkleIterator::new(Arc::clone(self), version, HashValue::zero())?,
+            |iter| {
+                iter.map(|(key, blob)| (key, SparseMerkleLeafNode::new(key, blob.hash()).hash()))
+                    .collect()
+            },
+        )?;
+        Ok(compute_root_hash(&leaf_hashes, 0))
+    }
+
     pub fn get_snapshot_receiver(
         self: &Arc<Self>,
         version: Version,
@@ -195,6 +388,43 @@ impl StateStore {
             expected_root_hash,
         )?))
     }
+
+    /// Like `get_snapshot_receiver`, but resumes the restoration in progress at `version`, if any.
+    pub fn resume_snapshot_receiver(
+        self: &Arc<Self>,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<Box<dyn StateSnapshotReceiver<AccountStateBlob>>> {
+        Ok(Box::new(JellyfishMerkleRestore::resume(
+            Arc::clone(self),
+            version,
+            expected_root_hash,
+        )?))
+    }
+
+    /// Puts to `cs` the tree at `version` made of the tree at `base_version` updated with
+    /// `account_states`, e.g. the ones of an incremental state snapshot, returning its root hash.
+    /// Unlike `put_account_state_sets`, the tree at `version - 1` doesn't need to exist.
+    pub fn put_account_states_on_base(
+        &self,
+        account_states: Vec<(HashValue, AccountStateBlob)>,
+        base_version: Version,
+        version: Version,
+        cs: &mut ChangeSet,
+    ) -> Result<HashValue> {
+        let (root_hash, tree_update_batch) = JellyfishMerkleTree::new(self).put_value_set_on_base(
+            account_states,
+            base_version,
+            version,
+        )?;
+        add_node_batch(&mut cs.batch, &tree_update_batch.node_batch)?;
+        tree_update_batch
+            .stale_node_index_batch
+            .iter()
+            .map(|row| cs.batch.put::<StaleNodeIndexSchema>(row, &()))
+            .collect::<Result<Vec<()>>>()?;
+        Ok(root_hash)
+    }
 }
 
 impl TreeReader<AccountStateBlob> for StateStore {
@@ -202,6 +432,13 @@ impl TreeReader<AccountStateBlob> for StateStore {
         self.db.get::<JellyfishMerkleNodeSchema>(node_key)
     }
 
+    fn get_restore_progress(
+        &self,
+        version: Version,
+    ) -> Result<Option<RestoreProgress<AccountStateBlob>>> {
+        self.db.get::<StateRestoreProgressSchema>(&version)
+    }
+
     fn get_rightmost_leaf(&self) -> Result<Option<(NodeKey, LeafNode)>> {
         // Since everything has the same version during restore, we seek to the first node and get
         // its version.
@@ -270,6 +507,19 @@ impl TreeWriter<AccountStateBlob> for StateStore {
         add_node_batch(&mut batch, node_batch)?;
         self.db.write_schemas(batch)
     }
+
+    fn write_restore_progress(
+        &self,
+        version: Version,
+        progress: Option<&RestoreProgress<AccountStateBlob>>,
+    ) -> Result<()> {
+        let mut batch = SchemaBatch::new();
+        match progress {
+            Some(progress) => batch.put::<StateRestoreProgressSchema>(&version, progress)?,
+            None => batch.delete::<StateRestoreProgressSchema>(&version)?,
+        }
+        self.db.write_schemas(batch)
+    }
 }
 
 fn add_node_batch(batch: &mut SchemaBatch, node_batch: &NodeBatch) -> Result<()> {
@@ -279,3 +529,20 @@ fn add_node_batch(batch: &mut SchemaBatch, node_batch: &NodeBatch) -> Result<()>
         .collect::<Result<Vec<_>>>()?;
     Ok(())
 }
+
+/// Computes the root hash of the sparse Merkle subtree at `depth` holding `leaf_hashes`, which are
+/// `(key, leaf_hash)` pairs sorted by key and sharing the first `depth` bits.
+fn compute_root_hash(leaf_hashes: &[(HashValue, HashValue)], depth: usize) -> HashValue {
+    match leaf_hashes {
+        [] => *SPARSE_MERKLE_PLACEHOLDER_HASH,
+        [(_key, leaf_hash)] => *leaf_hash,
+        _ => {
+            let split = leaf_hashes.partition_point(|(key, _)| !key.bit(depth));
+            SparseMerkleInternalNode::new(
+                compute_root_hash(&leaf_hashes[..split], depth + 1),
+                compute_root_hash(&leaf_hashes[split..], depth + 1),
+            )
+            .hash()
+        }
+    }
+}
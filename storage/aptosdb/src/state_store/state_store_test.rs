@@ -12,7 +12,7 @@ use aptos_jellyfish_merkle::restore::JellyfishMerkleRestore;
 use aptos_temppath::TempPath;
 use aptos_types::{
     account_address::{AccountAddress, HashAccountAddress},
-    account_state_blob::AccountStateBlob,
+    account_state_blob::{AccountStateBlob, AccountStatesChunkWithProof},
 };
 use storage_interface::StateSnapshotReceiver;
 
@@ -142,6 +142,49 @@ fn test_state_store_reader_writer() {
     verify_state_in_store(store, address3, Some(&value3), 1, root);
 }
 
+#[test]
+fn test_account_state_cache() {
+    use crate::metrics::DIEM_STORAGE_ACCOUNT_STATE_CACHE_REQUESTS;
+
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+    let store = &StateStore::new(Arc::clone(&db.db)).with_account_state_cache(2);
+    let address1 = AccountAddress::new([1u8; AccountAddress::LENGTH]);
+    let address2 = AccountAddress::new([2u8; AccountAddress::LENGTH]);
+    let value1 = AccountStateBlob::from(vec![0x01]);
+    let value2 = AccountStateBlob::from(vec![0x02]);
+
+    let root = put_account_state_set(
+        store,
+        vec![(address1, value1.clone()), (address2, value2.clone())],
+        0, /* version */
+        3, /* expected_nodes_created */
+        0, /* expected_nodes_retired */
+        0, /* expected_blobs_retired */
+    );
+    let cache = store.account_state_cache.as_ref().unwrap();
+    let hits = || {
+        DIEM_STORAGE_ACCOUNT_STATE_CACHE_REQUESTS
+            .with_label_values(&["hit"])
+            .get()
+    };
+
+    // The first read populates the cache, and the second is served from it.
+    verify_state_in_store(store, address1, Some(&value1), 0, root);
+    let hits_before = hits();
+    verify_state_in_store(store, address1, Some(&value1), 0, root);
+    assert!(hits() > hits_before);
+
+    // Absent accounts are cached as well, and the least recently used entry is evicted when full.
+    verify_state_in_store(store, address2, Some(&value2), 0, root);
+    verify_state_in_store(store, address1, Some(&value1), 0, root);
+    verify_state_in_store(store, AccountAddress::ZERO, None, 0, root);
+    assert_eq!(cache.len(), 2);
+    assert!(cache.get(address2, 0).is_none());
+    assert!(cache.get(address1, 0).is_some());
+    assert!(cache.get(AccountAddress::ZERO, 0).is_some());
+}
+
 #[test]
 fn test_retired_records() {
     let address1 = AccountAddress::new([1u8; AccountAddress::LENGTH]);
@@ -367,6 +410,108 @@ proptest! {
         );
     }
 
+    #[test]
+    fn test_account_chunks_tile_state(
+        (input, chunk_sizes) in hash_map(any::<AccountAddress>(), any::<AccountStateBlob>(), 2..1000)
+            .prop_flat_map(|input| {
+                let len = input.len();
+                (Just(input), vec(1..len + 1, 1..10))
+            })
+    ) {
+        let tmp_dir1 = TempPath::new();
+        let db1 = AptosDB::new_for_test(&tmp_dir1);
+        let store1 = &db1.state_store;
+        init_store(store1, input.clone().into_iter());
+
+        let version = (input.len() - 1) as Version;
+        let expected_root_hash = store1.get_root_hash(version).unwrap();
+
+        let tmp_dir2 = TempPath::new();
+        let db2 = AptosDB::new_for_test(&tmp_dir2);
+        let store2 = &db2.state_store;
+        let mut restore = store2.get_snapshot_receiver(version, expected_root_hash).unwrap();
+
+        let mut visited_keys = Vec::new();
+        let mut previous_chunk: Option<AccountStatesChunkWithProof> = None;
+        let mut current_idx = 0;
+        for chunk_size in chunk_sizes.iter().cycle() {
+            if current_idx >= input.len() {
+                break;
+            }
+            let chunk = store1.get_account_chunk_with_proof(version, current_idx, *chunk_size).unwrap();
+            prop_assert_eq!(chunk.root_hash, expected_root_hash);
+            if let Some(previous_chunk) = previous_chunk.as_ref() {
+                chunk.ensure_follows(previous_chunk).unwrap();
+                prop_assert!(previous_chunk.ensure_follows(&chunk).is_err());
+            }
+            visited_keys.extend(chunk.account_blobs.iter().map(|(key, _)| *key));
+            current_idx = chunk.last_index as usize + 1;
+            // Every chunk's proof is verified against the shared root hash.
+            restore.add_chunk(chunk.account_blobs.clone(), chunk.proof.clone()).unwrap();
+            previous_chunk = Some(chunk);
+        }
+        restore.finish_box().unwrap();
+
+        let mut expected_keys: Vec<_> = input.keys().map(|addr| addr.hash()).collect();
+        expected_keys.sort_unstable();
+        prop_assert_eq!(visited_keys, expected_keys);
+    }
+
+    #[test]
+    fn test_account_chunks_rev_tile_state(
+        (input, chunk_sizes) in hash_map(any::<AccountAddress>(), any::<AccountStateBlob>(), 2..1000)
+            .prop_flat_map(|input| {
+                let len = input.len();
+                (Just(input), vec(1..len + 1, 1..10))
+            })
+    ) {
+        let tmp_dir = TempPath::new();
+        let db = AptosDB::new_for_test(&tmp_dir);
+        let store = &db.state_store;
+        init_store(store, input.clone().into_iter());
+
+        let version = (input.len() - 1) as Version;
+        let expected_root_hash = store.get_root_hash(version).unwrap();
+
+        let mut chunks = Vec::new();
+        let mut next_last_idx = Some(input.len() - 1);
+        for chunk_size in chunk_sizes.iter().cycle() {
+            let last_idx = match next_last_idx {
+                Some(last_idx) => last_idx,
+                None => break,
+            };
+            let chunk = store.get_account_chunk_with_proof_rev(version, last_idx, *chunk_size).unwrap();
+            prop_assert_eq!(chunk.last_index, last_idx as u64);
+            // The same chunk as when walking upward, including the proof.
+            prop_assert_eq!(
+                &chunk,
+                &store.get_account_chunk_with_proof(
+                    version,
+                    chunk.first_index as usize,
+                    chunk.account_blobs.len(),
+                ).unwrap()
+            );
+            if let Some(next_chunk) = chunks.last() {
+                next_chunk.ensure_follows(&chunk).unwrap();
+            }
+            next_last_idx = (chunk.first_index as usize).checked_sub(1);
+            chunks.push(chunk);
+        }
+        // The chunk starting from the first account can be verified on its own.
+        chunks.last().unwrap().verify(expected_root_hash).unwrap();
+        prop_assert!(store.get_account_chunk_with_proof_rev(version, input.len(), 1).is_err());
+        prop_assert!(store.get_account_chunk_with_proof_rev(version, usize::MAX, 1).is_err());
+
+        let visited_keys: Vec<_> = chunks
+            .iter()
+            .rev()
+            .flat_map(|chunk| chunk.account_blobs.iter().map(|(key, _)| *key))
+            .collect();
+        let mut expected_keys: Vec<_> = input.keys().map(|addr| addr.hash()).collect();
+        expected_keys.sort_unstable();
+        prop_assert_eq!(visited_keys, expected_keys);
+    }
+
     #[test]
     fn test_get_rightmost_leaf(
         (input, batch1_size) in hash_map(any::<AccountAddress>(), any::<AccountStateBlob>(), 2..1000)
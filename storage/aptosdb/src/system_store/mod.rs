@@ -8,7 +8,7 @@ use crate::{
     change_set::ChangeSet, ledger_counters::LedgerCounters,
     schema::ledger_counters::LedgerCountersSchema,
 };
-use anyhow::Result;
+use anyhow::{ensure, Result};
 use aptos_logger::prelude::*;
 use aptos_types::transaction::Version;
 use schemadb::DB;
@@ -61,6 +61,32 @@ impl SystemStore {
 
         Ok(counters)
     }
+
+    /// Gets the ledger counters as of `version`, if recorded.
+    pub fn get_ledger_counters(&self, version: Version) -> Result<Option<LedgerCounters>> {
+        self.db.get::<LedgerCountersSchema>(&version)
+    }
+
+    /// Saves `counters` as the ledger counters as of `version`, so that the next
+    /// `bump_ledger_counters` starting at `version + 1` builds on them. If counters are already
+    /// recorded for `version`, they must be equal to `counters`.
+    pub fn confirm_or_save_ledger_counters(
+        &self,
+        version: Version,
+        counters: &LedgerCounters,
+    ) -> Result<()> {
+        if let Some(existing) = self.get_ledger_counters(version)? {
+            ensure!(
+                &existing == counters,
+                "Ledger counters at version {} do not match those already in DB. Provided: {:?}, in db: {:?}.",
+                version,
+                counters,
+                existing,
+            );
+            return Ok(());
+        }
+        self.db.put::<LedgerCountersSchema>(&version, counters)
+    }
 }
 
 #[cfg(test)]
@@ -6,11 +6,17 @@ use super::*;
 use aptos_crypto::hash::{CryptoHash, EventAccumulatorHasher, TransactionAccumulatorHasher};
 use aptos_types::{
     account_address::HashAccountAddress,
-    ledger_info::LedgerInfoWithSignatures,
+    account_config::NewBlockEvent,
+    block_metadata::new_block_event_key,
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    on_chain_config::{access_path_for_config, OnChainConfig},
     proof::accumulator::InMemoryAccumulator,
     proptest_types::{AccountInfoUniverse, BlockGen},
 };
 use executor_types::ProofReader;
+use move_core_types::{
+    identifier::Identifier, language_storage::TypeTag, move_resource::MoveStructType,
+};
 use proptest::{collection::vec, prelude::*};
 use scratchpad::SparseMerkleTree;
 
@@ -25,6 +31,7 @@ prop_compose! {
         num_accounts: usize,
         max_user_txns_per_block: usize,
         max_blocks: usize,
+        update_block: fn(&mut Vec<TransactionToCommit>, &LedgerInfo, usize),
     )(
         mut universe in any_with::<AccountInfoUniverse>(num_accounts).no_shrink(),
         block_gens in vec(any_with::<BlockGen>(max_user_txns_per_block), 1..=max_blocks),
@@ -36,8 +43,9 @@ prop_compose! {
         let mut txn_accumulator = TxnAccumulator::new_empty();
         let mut result = Vec::new();
 
-        for block_gen in block_gens {
+        for (block_index, block_gen) in block_gens.into_iter().enumerate() {
             let (mut txns_to_commit, mut ledger_info) = block_gen.materialize(&mut universe);
+            update_block(&mut txns_to_commit, &ledger_info, block_index);
 
             // make real txn_info's
             for txn in txns_to_commit.iter_mut() {
@@ -92,5 +100,113 @@ pub fn arb_blocks_to_commit(
         5,  /* num_accounts */
         2,  /* max_user_txn_per_block */
         10, /* max_blocks */
+        |_txns_to_commit, _ledger_info, _block_index| {},
+    )
+}
+
+/// Rebuilds `txn` with `account_states` and `events` in place of its own, for the strategies below
+/// to amend the generated transactions with.
+fn with_account_states_and_events(
+    txn: &TransactionToCommit,
+    account_states: HashMap<AccountAddress, AccountStateBlob>,
+    events: Vec<ContractEvent>,
+) -> TransactionToCommit {
+    TransactionToCommit::new(
+        txn.transaction().clone(),
+        txn.transaction_info().clone(),
+        account_states,
+        txn.jf_node_hashes().cloned(),
+        txn.write_set().clone(),
+        events,
+    )
+}
+
+/// Like [`arb_blocks_to_commit`], but every epoch ending block also sets the on-chain protocol
+/// `Version` config to `major: epoch / 2`, so that it changes at every other reconfiguration.
+pub fn arb_blocks_to_commit_with_version_bumps(
+) -> impl Strategy<Value = Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>> {
+    arb_blocks_to_commit_impl(
+        5,  /* num_accounts */
+        2,  /* max_user_txn_per_block */
+        10, /* max_blocks */
+        |txns_to_commit, ledger_info, _block_index| {
+            if !ledger_info.ends_epoch() {
+                return;
+            }
+            let mut config_account = AccountState::default();
+            config_account.insert(
+                access_path_for_config(OnChainVersion::CONFIG_ID).path,
+                bcs::to_bytes(&OnChainVersion {
+                    major: ledger_info.epoch() / 2,
+                })
+                .unwrap(),
+            );
+            let txn = txns_to_commit.last_mut().unwrap();
+            let mut account_states = txn.account_states().clone();
+            account_states.insert(
+                config_address(),
+                AccountStateBlob::try_from(&config_account).unwrap(),
+            );
+            *txn = with_account_states_and_events(txn, account_states, txn.events().to_vec());
+        },
+    )
+}
+
+/// The account [`arb_blocks_to_commit_with_modules`] publishes its modules under.
+pub fn module_publisher_address() -> AccountAddress {
+    AccountAddress::from_hex_literal("0xcafe").unwrap()
+}
+
+/// Like [`arb_blocks_to_commit`], but the last transaction of the `i`-th block (re)publishes
+/// modules `a` and `b` under [`module_publisher_address`], with code `[i, 0]` and `[i, 1]`.
+pub fn arb_blocks_to_commit_with_modules(
+) -> impl Strategy<Value = Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>> {
+    arb_blocks_to_commit_impl(
+        5,  /* num_accounts */
+        2,  /* max_user_txn_per_block */
+        10, /* max_blocks */
+        |txns_to_commit, _ledger_info, block_index| {
+            let mut publisher = AccountState::default();
+            for (i, name) in ["a", "b"].iter().enumerate() {
+                let module_id =
+                    ModuleId::new(module_publisher_address(), Identifier::new(*name).unwrap());
+                publisher.insert(module_id.access_vector(), vec![block_index as u8, i as u8]);
+            }
+            let txn = txns_to_commit.last_mut().unwrap();
+            let mut account_states = txn.account_states().clone();
+            account_states.insert(
+                module_publisher_address(),
+                AccountStateBlob::try_from(&publisher).unwrap(),
+            );
+            *txn = with_account_states_and_events(txn, account_states, txn.events().to_vec());
+        },
+    )
+}
+
+/// Like [`arb_blocks_to_commit`], but the first transaction of the `i`-th block emits the
+/// `NewBlockEvent` of round `i`, proposed at timestamp `i + 1`.
+pub fn arb_blocks_to_commit_with_block_events(
+) -> impl Strategy<Value = Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>> {
+    arb_blocks_to_commit_impl(
+        5,  /* num_accounts */
+        2,  /* max_user_txn_per_block */
+        10, /* max_blocks */
+        |txns_to_commit, _ledger_info, block_index| {
+            let new_block_event = NewBlockEvent::new(
+                block_index as u64, /* round */
+                AccountAddress::ZERO,
+                Vec::new(), /* prev block voters */
+                block_index as u64 + 1,
+            );
+            let txn = txns_to_commit.first_mut().unwrap();
+            let mut events = vec![ContractEvent::new(
+                new_block_event_key(),
+                block_index as u64,
+                TypeTag::Struct(NewBlockEvent::struct_tag()),
+                bcs::to_bytes(&new_block_event).unwrap(),
+            )];
+            events.extend_from_slice(txn.events());
+            *txn = with_account_states_and_events(txn, txn.account_states().clone(), events);
+        },
     )
 }
@@ -8,7 +8,8 @@ use crate::{
     errors::AptosDbError,
     schema::{
         transaction::TransactionSchema, transaction_by_account::TransactionByAccountSchema,
-        transaction_by_hash::TransactionByHashSchema, write_set::WriteSetSchema,
+        transaction_by_hash::TransactionByHashSchema,
+        transaction_by_payload_type::TransactionByPayloadTypeSchema, write_set::WriteSetSchema,
     },
     transaction_accumulator::TransactionAccumulatorSchema,
     transaction_info::TransactionInfoSchema,
@@ -19,7 +20,7 @@ use aptos_types::{
     account_address::AccountAddress,
     block_metadata::BlockMetadata,
     proof::position::Position,
-    transaction::{Transaction, Version},
+    transaction::{Transaction, TransactionPayloadType, Version},
     write_set::WriteSet,
 };
 use schemadb::{ReadOptions, SchemaBatch, SchemaIterator, DB};
@@ -54,6 +55,33 @@ impl TransactionStore {
         Ok(None)
     }
 
+    /// Gets the sequence number of the latest transaction sent by `address` with
+    /// `version <= ledger_version`, or `None` if there is none.
+    pub fn get_latest_account_sequence_number(
+        &self,
+        address: AccountAddress,
+        ledger_version: Version,
+    ) -> Result<Option<u64>> {
+        let mut iter = self
+            .db
+            .rev_iter::<TransactionByAccountSchema>(ReadOptions::default())?;
+        iter.seek_for_prev(&(address, u64::max_value()))?;
+
+        // Versions grow with sequence numbers, so walk back past the transactions committed
+        // after `ledger_version`, which are expected to be few.
+        for result in iter {
+            let ((txn_address, seq_num), version) = result?;
+            if txn_address != address {
+                break;
+            }
+            if version <= ledger_version {
+                return Ok(Some(seq_num));
+            }
+        }
+
+        Ok(None)
+    }
+
     /// Gets the version of a transaction by its hash.
     pub fn get_transaction_version_by_hash(
         &self,
@@ -95,6 +123,36 @@ impl TransactionStore {
         })
     }
 
+    /// Gets the versions of the user transactions with `payload_type`, starting at
+    /// `start_version` and returning at most `limit` results with `version <= ledger_version`, in
+    /// ascending order.
+    pub fn get_transaction_versions_by_payload_type(
+        &self,
+        payload_type: TransactionPayloadType,
+        start_version: Version,
+        limit: u64,
+        ledger_version: Version,
+    ) -> Result<Vec<Version>> {
+        let mut iter = self
+            .db
+            .iter::<TransactionByPayloadTypeSchema>(ReadOptions::default())?;
+        iter.seek(&(payload_type, start_version))?;
+
+        let mut versions = Vec::new();
+        for result in iter {
+            let ((txn_payload_type, version), ()) = result?;
+            if txn_payload_type != payload_type
+                || version > ledger_version
+                || versions.len() as u64 >= limit
+            {
+                break;
+            }
+            versions.push(version);
+        }
+
+        Ok(versions)
+    }
+
     /// Get signed transaction given `version`
     pub fn get_transaction(&self, version: Version) -> Result<Transaction> {
         self.db
@@ -151,6 +209,39 @@ impl TransactionStore {
         Err(AptosDbError::NotFound(format!("BlockMetadata preceding version {}", version)).into())
     }
 
+    /// Returns the number of transactions in the block that starts at `block_start_version`, i.e.
+    /// the block metadata transaction (if any) and the transactions following it, up to the next
+    /// block metadata transaction or `ledger_version`, whichever comes first.
+    pub fn get_num_transactions_in_block(
+        &self,
+        block_start_version: Version,
+        ledger_version: Version,
+    ) -> Result<u64> {
+        ensure!(
+            block_start_version <= ledger_version,
+            "block_start_version {} is greater than ledger_version {}.",
+            block_start_version,
+            ledger_version,
+        );
+        let mut iter = self.db.iter::<TransactionSchema>(Default::default())?;
+        iter.seek(&(block_start_version + 1))?;
+        let mut next_block_start_version = block_start_version + 1;
+        for res in iter {
+            let (v, txn) = res?;
+            if v > ledger_version || matches!(txn, Transaction::BlockMetadata(_)) {
+                break;
+            }
+            ensure!(
+                v == next_block_start_version,
+                "DB corruption: missing transaction at version {}.",
+                next_block_start_version,
+            );
+            next_block_start_version = v + 1;
+        }
+
+        Ok(next_block_start_version - block_start_version)
+    }
+
     /// Save signed transaction at `version`
     pub fn put_transaction(
         &self,
@@ -163,6 +254,10 @@ impl TransactionStore {
                 &(txn.sender(), txn.sequence_number()),
                 &version,
             )?;
+            cs.batch.put::<TransactionByPayloadTypeSchema>(
+                &(txn.payload().payload_type(), version),
+                &(),
+            )?;
         }
         cs.batch
             .put::<TransactionByHashSchema>(&transaction.hash(), &version)?;
@@ -178,6 +273,33 @@ impl TransactionStore {
         })
     }
 
+    /// Returns the versions in `[first_version, last_version]` whose write set touches the state
+    /// of `address`, regardless of who sent the transaction.
+    pub fn get_account_state_change_versions(
+        &self,
+        address: AccountAddress,
+        first_version: Version,
+        last_version: Version,
+    ) -> Result<Vec<Version>> {
+        let mut iter = self.db.iter::<WriteSetSchema>(Default::default())?;
+        iter.seek(&first_version)?;
+        let mut versions = Vec::new();
+        for res in iter {
+            let (version, write_set) = res?;
+            if version > last_version {
+                break;
+            }
+            if write_set
+                .iter()
+                .any(|(access_path, _)| access_path.address == address)
+            {
+                versions.push(version);
+            }
+        }
+
+        Ok(versions)
+    }
+
     /// Get the first version that write set starts existent.
     pub fn get_first_write_set_version(&self) -> Result<Option<Version>> {
         let mut iter = self.db.iter::<WriteSetSchema>(Default::default())?;
@@ -195,6 +317,35 @@ impl TransactionStore {
         cs.batch.put::<WriteSetSchema>(&version, write_set)
     }
 
+    /// Populates the transaction by payload type index from all the transactions in the DB, in
+    /// batches of `batch_size` transactions. Returns the number of user transactions indexed.
+    pub fn backfill_transaction_by_payload_type(&self, batch_size: usize) -> Result<u64> {
+        let mut iter = self.db.iter::<TransactionSchema>(ReadOptions::default())?;
+        iter.seek_to_first();
+
+        let mut num_indexed = 0;
+        let mut batch = SchemaBatch::new();
+        let mut num_in_batch = 0;
+        for result in iter {
+            let (version, transaction) = result?;
+            if let Transaction::UserTransaction(txn) = transaction {
+                batch.put::<TransactionByPayloadTypeSchema>(
+                    &(txn.payload().payload_type(), version),
+                    &(),
+                )?;
+                num_indexed += 1;
+            }
+            num_in_batch += 1;
+            if num_in_batch >= batch_size {
+                self.db.write_schemas(std::mem::take(&mut batch))?;
+                num_in_batch = 0;
+            }
+        }
+        self.db.write_schemas(batch)?;
+
+        Ok(num_indexed)
+    }
+
     /// Prune the transaction by hash store given a list of transaction
     pub fn prune_transaction_by_hash(
         &self,
@@ -222,6 +373,25 @@ impl TransactionStore {
         Ok(())
     }
 
+    /// Prune the transaction by payload type store given a list of transaction starting at
+    /// `first_version`
+    pub fn prune_transaction_by_payload_type(
+        &self,
+        first_version: Version,
+        transactions: &[Transaction],
+        db_batch: &mut SchemaBatch,
+    ) -> anyhow::Result<()> {
+        for (version, transaction) in (first_version..).zip(transactions) {
+            if let Transaction::UserTransaction(txn) = transaction {
+                db_batch.delete::<TransactionByPayloadTypeSchema>(&(
+                    txn.payload().payload_type(),
+                    version,
+                ))?;
+            }
+        }
+        Ok(())
+    }
+
     /// Prune the transaction schema store between a range of version in [begin, end)
     pub fn prune_transaction_schema(
         &self,
@@ -2,13 +2,15 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::*;
-use crate::AptosDB;
+use crate::{AptosDB, MAX_LIMIT};
 use aptos_proptest_helpers::Index;
 use aptos_temppath::TempPath;
 use aptos_types::{
+    access_path::AccessPath,
     block_metadata::BlockMetadata,
     proptest_types::{AccountInfoUniverse, SignatureCheckedTransactionGen},
     transaction::{SignedTransaction, Transaction},
+    write_set::{WriteOp, WriteSetMut},
 };
 use proptest::{collection::vec, prelude::*};
 use std::collections::BTreeMap;
@@ -246,9 +248,83 @@ proptest! {
             .collect::<BTreeMap<_, _>>();
 
         prop_assert_eq!(&actual_scan, &expected_scan);
+
+        // the latest sequence number of each account as of the ledger version
+        let mut expected_latest = expected_scan
+            .keys()
+            .map(|address| (*address, None))
+            .collect::<BTreeMap<_, _>>();
+        for (version, txn) in &txns {
+            if *version <= ledger_version {
+                expected_latest.insert(txn.sender(), Some(txn.sequence_number()));
+            }
+        }
+        for (address, expected) in expected_latest {
+            prop_assert_eq!(
+                store.get_latest_account_sequence_number(address, ledger_version).unwrap(),
+                expected
+            );
+        }
     }
 }
 
+#[test]
+fn test_get_account_state_change_versions() {
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+    let store = &db.transaction_store;
+
+    let account_a = AccountAddress::random();
+    let account_b = AccountAddress::random();
+    let write_set = |addresses: &[AccountAddress]| {
+        WriteSetMut::new(
+            addresses
+                .iter()
+                .map(|address| {
+                    (
+                        AccessPath::new(*address, b"resource".to_vec()),
+                        WriteOp::Value(vec![1]),
+                    )
+                })
+                .collect(),
+        )
+        .freeze()
+        .unwrap()
+    };
+
+    // All transactions are sent by B, but those at versions 1 and 3 also modify A.
+    let write_sets = vec![
+        write_set(&[account_b]),
+        write_set(&[account_b, account_a]),
+        write_set(&[account_b]),
+        write_set(&[account_a]),
+    ];
+    let mut cs = ChangeSet::new();
+    for (ver, ws) in write_sets.iter().enumerate() {
+        store.put_write_set(ver as Version, ws, &mut cs).unwrap();
+    }
+    store.db.write_schemas(cs.batch).unwrap();
+
+    assert_eq!(
+        db.get_account_state_change_versions(account_a, 0, 3)
+            .unwrap(),
+        vec![1, 3]
+    );
+    assert_eq!(
+        db.get_account_state_change_versions(account_a, 2, 2)
+            .unwrap(),
+        Vec::<Version>::new()
+    );
+    assert_eq!(
+        db.get_account_state_change_versions(account_b, 1, 10)
+            .unwrap(),
+        vec![1, 2]
+    );
+    assert!(db
+        .get_account_state_change_versions(account_a, 0, MAX_LIMIT)
+        .is_err());
+}
+
 fn init_store(
     mut universe: AccountInfoUniverse,
     gens: Vec<(Index, SignatureCheckedTransactionGen)>,
@@ -0,0 +1,133 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! This file defines the optional write buffer which coalesces several consecutive
+//! `save_transactions` calls into a single DB write, amortizing the cost of syncing the WAL when
+//! many tiny blocks are committed.
+//!
+//! Buffered blocks are NOT durable and NOT visible to readers until the buffer is flushed, which
+//! happens when `max_buffered_txns` is reached, when the oldest buffered block has been waiting
+//! for `max_buffered_duration` (checked on the next `save_transactions` or `wait_for_commit`), or
+//! on `wait_for_commit`. Everything buffered is lost if the process crashes before that, so
+//! callers must call `wait_for_commit` before acknowledging a block as persisted.
+
+use crate::AptosDbError;
+use anyhow::{ensure, Result};
+use aptos_crypto::{
+    hash::{CryptoHash, TransactionAccumulatorHasher},
+    HashValue,
+};
+use aptos_types::{
+    ledger_info::LedgerInfoWithSignatures,
+    proof::accumulator::InMemoryAccumulator,
+    transaction::{TransactionToCommit, Version},
+};
+use std::time::{Duration, Instant};
+
+/// Thresholds at which the write buffer is flushed to the DB.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct WriteBufferConfig {
+    /// Flush once at least this many transactions are buffered.
+    pub max_buffered_txns: usize,
+    /// Flush once the oldest buffered block has been waiting at least this long.
+    pub max_buffered_duration: Duration,
+}
+
+/// Blocks saved but not yet written to the DB, in version order.
+#[derive(Debug)]
+pub(crate) struct WriteBuffer {
+    config: WriteBufferConfig,
+    txns_to_commit: Vec<TransactionToCommit>,
+    ledger_infos_with_sigs: Vec<LedgerInfoWithSignatures>,
+    /// The transaction accumulator after all buffered transactions, `None` if nothing is buffered.
+    accumulator: Option<InMemoryAccumulator<TransactionAccumulatorHasher>>,
+    oldest_block_time: Option<Instant>,
+}
+
+impl WriteBuffer {
+    pub fn new(config: WriteBufferConfig) -> Self {
+        Self {
+            config,
+            txns_to_commit: Vec::new(),
+            ledger_infos_with_sigs: Vec::new(),
+            accumulator: None,
+            oldest_block_time: None,
+        }
+    }
+
+    /// The version of the first buffered transaction, if anything is buffered.
+    pub fn first_version(&self) -> Option<Version> {
+        self.accumulator
+            .as_ref()
+            .map(|acc| acc.num_leaves() - self.txns_to_commit.len() as Version)
+    }
+
+    /// Buffers a block, verifying it against the ledger info if provided. `get_accumulator` is
+    /// only called to get the committed accumulator at `first_version` when the buffer is empty.
+    /// Returns the root hash of the transaction accumulator after the block.
+    pub fn push(
+        &mut self,
+        txns_to_commit: &[TransactionToCommit],
+        first_version: Version,
+        ledger_info_with_sigs: Option<&LedgerInfoWithSignatures>,
+        get_accumulator: impl FnOnce() -> Result<InMemoryAccumulator<TransactionAccumulatorHasher>>,
+    ) -> Result<HashValue> {
+        let accumulator = match self.accumulator.as_ref() {
+            Some(accumulator) => accumulator.clone(),
+            None => get_accumulator()?,
+        };
+        ensure!(
+            first_version == accumulator.num_leaves(),
+            "Transaction batch not applicable: first_version {}, expected {}",
+            first_version,
+            accumulator.num_leaves(),
+        );
+        let txn_info_hashes: Vec<_> = txns_to_commit
+            .iter()
+            .map(|t| t.transaction_info().hash())
+            .collect();
+        let new_accumulator = accumulator.append(&txn_info_hashes);
+        let new_root_hash = new_accumulator.root_hash();
+
+        if let Some(x) = ledger_info_with_sigs {
+            let expected_root_hash = x.ledger_info().transaction_accumulator_hash();
+            ensure!(
+                new_root_hash == expected_root_hash,
+                AptosDbError::RootHashMismatch(expected_root_hash, new_root_hash),
+            );
+            self.ledger_infos_with_sigs.push(x.clone());
+        }
+        self.accumulator = Some(new_accumulator);
+        self.txns_to_commit.extend_from_slice(txns_to_commit);
+        self.oldest_block_time.get_or_insert_with(Instant::now);
+
+        Ok(new_root_hash)
+    }
+
+    /// Whether a size or time threshold has been reached.
+    pub fn should_flush(&self) -> bool {
+        self.txns_to_commit.len() >= self.config.max_buffered_txns
+            || self
+                .oldest_block_time
+                .map_or(false, |t| t.elapsed() >= self.config.max_buffered_duration)
+    }
+
+    /// Empties the buffer, returning the first version and all buffered transactions and ledger
+    /// infos.
+    pub fn take(
+        &mut self,
+    ) -> Option<(
+        Version,
+        Vec<TransactionToCommit>,
+        Vec<LedgerInfoWithSignatures>,
+    )> {
+        let first_version = self.first_version()?;
+        self.accumulator = None;
+        self.oldest_block_time = None;
+        Some((
+            first_version,
+            std::mem::take(&mut self.txns_to_commit),
+            std::mem::take(&mut self.ledger_infos_with_sigs),
+        ))
+    }
+}
@@ -25,7 +25,7 @@ use aptosdb::AptosDB;
 use executor_test_helpers::integration_test_impl::test_execution_with_storage_impl;
 use proptest::prelude::*;
 use std::{convert::TryInto, sync::Arc};
-use storage_interface::DbReader;
+use storage_interface::{DbReader, Order};
 use tokio::time::Duration;
 
 #[derive(Debug)]
@@ -152,6 +152,7 @@ fn test_end_to_end_impl(d: TestData) {
     assert_eq!(
         d.db.get_transactions(
             d.txn_start_ver,
+            Order::Ascending,
             num_txns_to_backup,
             d.target_ver,
             true /* fetch_events */
@@ -160,6 +161,7 @@ fn test_end_to_end_impl(d: TestData) {
         tgt_db
             .get_transactions(
                 d.txn_start_ver,
+                Order::Ascending,
                 num_txns_to_backup,
                 d.target_ver,
                 true /* fetch_events */
@@ -171,10 +173,22 @@ fn test_end_to_end_impl(d: TestData) {
         let num_replayed = d.target_ver - state_snapshot_ver;
         // Events recreated:
         assert_eq!(
-            d.db.get_transactions(first_replayed, num_replayed, d.target_ver, true)
-                .unwrap(),
+            d.db.get_transactions(
+                first_replayed,
+                Order::Ascending,
+                num_replayed,
+                d.target_ver,
+                true
+            )
+            .unwrap(),
             tgt_db
-                .get_transactions(first_replayed, num_replayed, d.target_ver, true)
+                .get_transactions(
+                    first_replayed,
+                    Order::Ascending,
+                    num_replayed,
+                    d.target_ver,
+                    true
+                )
                 .unwrap()
         );
     };
@@ -17,7 +17,7 @@ use aptos_temppath::TempPath;
 use aptos_types::transaction::Version;
 use aptosdb::AptosDB;
 use std::{convert::TryInto, mem::size_of, sync::Arc};
-use storage_interface::DbReader;
+use storage_interface::{DbReader, Order};
 use tokio::time::Duration;
 
 #[test]
@@ -109,6 +109,7 @@ fn end_to_end() {
     let recovered_transactions = tgt_db
         .get_transactions(
             first_ver_to_backup,
+            Order::Ascending,
             num_txns_to_restore as u64,
             target_version,
             true, /* fetch_events */
@@ -54,6 +54,7 @@ impl From<RocksdbOpt> for RocksdbConfig {
         Self {
             max_open_files: opt.max_open_files,
             max_total_wal_size: opt.max_total_wal_size,
+            ..Default::default()
         }
     }
 }
@@ -8,7 +8,7 @@ use aptos_config::config::{RocksdbConfig, NO_OP_STORAGE_PRUNER_CONFIG};
 use aptos_logger::info;
 use aptosdb::AptosDB;
 use std::path::PathBuf;
-use storage_interface::DbReader;
+use storage_interface::{DbReader, Order};
 
 use aptos_types::{
     account_address::AccountAddress, account_config::AccountResource, account_state::AccountState,
@@ -71,7 +71,7 @@ fn print_head(db: &AptosDB) -> Result<()> {
 
 fn print_txn(db: &AptosDB, version: u64) {
     let tx_list = db
-        .get_transactions(version, 1, version, false)
+        .get_transactions(version, Order::Ascending, 1, version, false)
         .expect("Unable to load latest TXN");
     let tx = tx_list.transactions.first().expect("Got empty txn list.");
     println!(
@@ -11,7 +11,10 @@ use anyhow::Result;
 use aptos_crypto::HashValue;
 use aptos_types::transaction::Version;
 use rand::{rngs::StdRng, SeedableRng};
-use std::{collections::BTreeMap, sync::Arc};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
 
 #[test]
 fn test_iterator_same_version() {
@@ -30,6 +33,59 @@ fn test_long_path() {
     test_n_consecutive_addresses(50);
 }
 
+#[test]
+fn test_iterator_changed_since() {
+    let db = Arc::new(MockTreeStore::default());
+    let tree = JellyfishMerkleTree::new(&*db);
+    let mut rng = StdRng::from_seed([1; 32]);
+
+    // Version `i` inserts a new key and updates the value of the key inserted at `i / 2`.
+    let mut keys = Vec::new();
+    let mut btree = BTreeMap::new();
+    for i in 0..50usize {
+        let key = HashValue::random_with_rng(&mut rng);
+        keys.push(key);
+        let mut value_set = vec![(key, ValueBlob::from(i.to_be_bytes().to_vec()))];
+        if i > 0 {
+            value_set.push((keys[i / 2], ValueBlob::from(vec![i as u8])));
+        }
+        btree.extend(value_set.iter().cloned());
+        let (_root_hash, batch) = tree.put_value_set(value_set, i as Version).unwrap();
+        db.write_tree_update_batch(batch).unwrap();
+    }
+    let version = 49;
+
+    for base_version in [0, 10, 25, 48] {
+        let changed = JellyfishMerkleIterator::new(Arc::clone(&db), version, HashValue::zero())
+            .unwrap()
+            .changed_since(base_version)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        // Yielded in key order with the latest values, including all the keys written after
+        // `base_version`, along with the unchanged ones whose leaves were moved to make room
+        // for the keys inserted, at most one per insertion.
+        assert!(changed.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        for (key, value) in &changed {
+            assert_eq!(&btree[key], value);
+        }
+        let written: BTreeSet<_> = (base_version as usize + 1..=version as usize)
+            .flat_map(|i| vec![keys[i], keys[i / 2]])
+            .collect();
+        let changed_keys: BTreeSet<_> = changed.iter().map(|(key, _value)| *key).collect();
+        assert!(changed_keys.is_superset(&written));
+        assert!(changed_keys.len() <= written.len() + (version - base_version) as usize);
+    }
+
+    // Nothing changed since the latest version.
+    assert!(
+        JellyfishMerkleIterator::new(Arc::clone(&db), version, HashValue::zero())
+            .unwrap()
+            .changed_since(version)
+            .next()
+            .is_none()
+    );
+}
+
 fn test_n_leaves_same_version(n: usize) {
     let db = Arc::new(MockTreeStore::default());
     let tree = JellyfishMerkleTree::new(&*db);
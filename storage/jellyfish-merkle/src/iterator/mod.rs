@@ -109,6 +109,9 @@ pub struct JellyfishMerkleIterator<R, V> {
     /// additional bit.
     done: bool,
 
+    /// If set, the subtrees unchanged since this version are skipped, see `changed_since`.
+    base_version: Option<Version>,
+
     phantom_value: PhantomData<V>,
 }
 
@@ -161,6 +164,7 @@ where
                         version,
                         parent_stack,
                         done,
+                        base_version: None,
                         phantom_value: PhantomData,
                     });
                 }
@@ -185,10 +189,21 @@ where
             version,
             parent_stack,
             done,
+            base_version: None,
             phantom_value: PhantomData,
         })
     }
 
+    /// Makes the iterator skip the subtrees unchanged since `base_version`, so that it only
+    /// yields the leaves written after it, i.e. the key-value pairs updated after it along with
+    /// the unchanged ones whose leaves were moved down to make room for inserted keys. A node is
+    /// never older than its children, so a subtree whose root is at `base_version` or older can
+    /// be skipped as a whole.
+    pub fn changed_since(mut self, base_version: Version) -> Self {
+        self.base_version = Some(base_version);
+        self
+    }
+
     fn cleanup_stack(parent_stack: &mut Vec<NodeVisitInfo>) {
         while let Some(info) = parent_stack.last_mut() {
             if info.is_rightmost() {
@@ -213,6 +228,7 @@ where
                 version,
                 parent_stack,
                 done: true,
+                base_version: None,
                 phantom_value: PhantomData,
             });
         }
@@ -233,6 +249,7 @@ where
                         version,
                         parent_stack,
                         done: false,
+                        base_version: None,
                         phantom_value: PhantomData,
                     });
                 }
@@ -313,14 +330,22 @@ where
                 .expect("We have checked that self.parent_stack is not empty.");
             let child_index =
                 Nibble::from(last_visited_node_info.next_child_to_visit.trailing_zeros() as u8);
-            let node_key = last_visited_node_info.node_key.gen_child_node_key(
-                last_visited_node_info
-                    .node
-                    .child(child_index)
-                    .expect("Child should exist.")
-                    .version,
-                child_index,
-            );
+            let child_version = last_visited_node_info
+                .node
+                .child(child_index)
+                .expect("Child should exist.")
+                .version;
+            if matches!(self.base_version, Some(base_version) if child_version <= base_version) {
+                Self::cleanup_stack(&mut self.parent_stack);
+                if self.parent_stack.is_empty() {
+                    self.done = true;
+                    return None;
+                }
+                continue;
+            }
+            let node_key = last_visited_node_info
+                .node_key
+                .gen_child_node_key(child_version, child_index);
             match self.reader.get_node(&node_key) {
                 Ok(Node::Internal(internal_node)) => {
                     let visit_info = NodeVisitInfo::new(node_key, internal_node);
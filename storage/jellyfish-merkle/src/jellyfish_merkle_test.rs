@@ -46,6 +46,47 @@ fn test_insert_to_empty_tree() {
     assert_eq!(tree.get(key, 0).unwrap().unwrap(), value);
 }
 
+#[test]
+fn test_put_value_set_on_base() {
+    let key1 = HashValue::random();
+    let value1 = ValueBlob::from(vec![1u8]);
+    let key2 = HashValue::random();
+    let value2 = ValueBlob::from(vec![2u8]);
+
+    // The reference tree, built version by version.
+    let db = MockTreeStore::default();
+    let tree = JellyfishMerkleTree::new(&db);
+    let (_root_hash, batch) = tree
+        .put_value_set(vec![(key1, value1.clone())], 0 /* version */)
+        .unwrap();
+    db.write_tree_update_batch(batch).unwrap();
+    let (root_hash, batch) = tree
+        .put_value_set(vec![(key2, value2.clone())], 1 /* version */)
+        .unwrap();
+    db.write_tree_update_batch(batch).unwrap();
+
+    // A tree only having version 0, jumping to version 5 directly.
+    let db2 = MockTreeStore::default();
+    let tree2 = JellyfishMerkleTree::new(&db2);
+    let (_root_hash, batch) = tree2
+        .put_value_set(vec![(key1, value1.clone())], 0 /* version */)
+        .unwrap();
+    db2.write_tree_update_batch(batch).unwrap();
+    let (root_hash2, batch) = tree2
+        .put_value_set_on_base(vec![(key2, value2.clone())], 0, 5)
+        .unwrap();
+    assert_eq!(root_hash2, root_hash);
+    db2.write_tree_update_batch(batch).unwrap();
+    assert_eq!(tree2.get_root_hash(5).unwrap(), root_hash);
+    assert_eq!(tree2.get(key1, 5).unwrap().unwrap(), value1);
+    assert_eq!(tree2.get(key2, 5).unwrap().unwrap(), value2);
+
+    assert!(tree2
+        .put_value_set_on_base(vec![(key2, value2.clone())], 5, 5)
+        .is_err());
+    assert!(tree2.put_value_set_on_base(vec![], 5, 6).is_err());
+}
+
 #[test]
 fn test_insert_to_pre_genesis() {
     // Set up DB with pre-genesis state (one single leaf node).
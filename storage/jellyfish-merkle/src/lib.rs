@@ -95,6 +95,7 @@ use node_type::{Child, Children, InternalNode, LeafNode, Node, NodeKey, NodeType
 use proptest::arbitrary::Arbitrary;
 #[cfg(any(test, feature = "fuzzing"))]
 use proptest_derive::Arbitrary;
+use restore::RestoreProgress;
 use serde::{de::DeserializeOwned, Serialize};
 use std::{
     collections::{BTreeMap, BTreeSet, HashMap},
@@ -125,11 +126,26 @@ pub trait TreeReader<V> {
     /// Gets the rightmost leaf. Note that this assumes we are in the process of restoring the tree
     /// and all nodes are at the same version.
     fn get_rightmost_leaf(&self) -> Result<Option<(NodeKey, LeafNode<V>)>>;
+
+    /// Gets the progress of the restoration of the tree at `version` persisted via
+    /// `TreeWriter::write_restore_progress`, if any.
+    fn get_restore_progress(&self, version: Version) -> Result<Option<RestoreProgress<V>>>;
 }
 
 pub trait TreeWriter<V>: Send + Sync {
     /// Writes a node batch into storage.
     fn write_node_batch(&self, node_batch: &NodeBatch<V>) -> Result<()>;
+
+    /// Persists the progress of the restoration of the tree at `version` after each chunk, or
+    /// clears it with `None` once the restoration finishes. Storage that doesn't support resuming
+    /// restorations can ignore it.
+    fn write_restore_progress(
+        &self,
+        _version: Version,
+        _progress: Option<&RestoreProgress<V>>,
+    ) -> Result<()> {
+        Ok(())
+    }
 }
 
 /// `Value` defines the types of data that can be stored in a Jellyfish Merkle tree.
@@ -543,6 +559,45 @@ where
         Ok((root_hashes[0], tree_update_batch))
     }
 
+    /// Like [`put_value_set`](struct.JellyfishMerkleTree.html#method.put_value_set), but builds
+    /// the tree at `version` on top of the one at `base_version` rather than `version - 1`, which
+    /// doesn't need to exist, e.g. when applying the account states changed between two versions
+    /// on top of a state snapshot restored at the earlier one.
+    pub fn put_value_set_on_base(
+        &self,
+        value_set: Vec<(HashValue, V)>,
+        base_version: Version,
+        version: Version,
+    ) -> Result<(HashValue, TreeUpdateBatch<V>)> {
+        ensure!(
+            base_version < version,
+            "Base version {} is not older than version {}.",
+            base_version,
+            version,
+        );
+        ensure!(!value_set.is_empty(), "No values to put.");
+        let mut tree_cache = TreeCache::new_with_base(self.reader, base_version, version);
+        let deduped_and_sorted_kvs = value_set
+            .into_iter()
+            .collect::<BTreeMap<_, _>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        let root_node_key = tree_cache.get_root_node_key().clone();
+        let (new_root_node_key, _) = self.batch_insert_at(
+            root_node_key,
+            version,
+            deduped_and_sorted_kvs.as_slice(),
+            0,
+            &None,
+            &mut tree_cache,
+        )?;
+        tree_cache.set_root_node_key(new_root_node_key);
+        tree_cache.freeze();
+
+        let (root_hashes, tree_update_batch) = tree_cache.into();
+        Ok((root_hashes[0], tree_update_batch))
+    }
+
     /// Returns the new nodes and values in a batch after applying `value_set`. For
     /// example, if after transaction `T_i` the committed state of tree in the persistent storage
     /// looks like the following structure:
@@ -3,6 +3,7 @@
 
 use crate::{
     node_type::{LeafNode, Node, NodeKey},
+    restore::RestoreProgress,
     NodeBatch, StaleNodeIndex, TreeReader, TreeUpdateBatch, TreeWriter,
 };
 use anyhow::{bail, ensure, Result};
@@ -12,6 +13,7 @@ use std::collections::{hash_map::Entry, BTreeSet, HashMap};
 
 pub struct MockTreeStore<V> {
     data: RwLock<(HashMap<NodeKey, Node<V>>, BTreeSet<StaleNodeIndex>)>,
+    restore_progress: RwLock<HashMap<Version, RestoreProgress<V>>>,
     allow_overwrite: bool,
 }
 
@@ -19,6 +21,7 @@ impl<V> Default for MockTreeStore<V> {
     fn default() -> Self {
         Self {
             data: RwLock::new((HashMap::new(), BTreeSet::new())),
+            restore_progress: RwLock::new(HashMap::new()),
             allow_overwrite: false,
         }
     }
@@ -48,6 +51,10 @@ where
 
         Ok(node_key_and_node)
     }
+
+    fn get_restore_progress(&self, version: Version) -> Result<Option<RestoreProgress<V>>> {
+        Ok(self.restore_progress.read().get(&version).cloned())
+    }
 }
 
 impl<V> TreeWriter<V> for MockTreeStore<V>
@@ -64,6 +71,19 @@ where
         }
         Ok(())
     }
+
+    fn write_restore_progress(
+        &self,
+        version: Version,
+        progress: Option<&RestoreProgress<V>>,
+    ) -> Result<()> {
+        let mut locked = self.restore_progress.write();
+        match progress {
+            Some(progress) => locked.insert(version, progress.clone()),
+            None => locked.remove(&version),
+        };
+        Ok(())
+    }
 }
 
 impl<V> MockTreeStore<V>
@@ -28,10 +28,11 @@ use aptos_types::{
     transaction::Version,
 };
 use mirai_annotations::*;
-use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::Arc};
 use storage_interface::StateSnapshotReceiver;
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 enum ChildInfo<V> {
     /// This child is an internal node. The hash of the internal node is stored here if it is
     /// known, otherwise it is `None`. In the process of restoring a tree, we will only know the
@@ -108,6 +109,22 @@ where
     }
 }
 
+/// The state of a restoration persisted after each chunk, from which
+/// [`JellyfishMerkleRestore::resume`] picks up after the process restarts.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RestoreProgress<V> {
+    expected_root_hash: HashValue,
+    /// The number of keys restored so far.
+    num_keys_received: u64,
+    /// The children of the partial nodes, from the root down the path to `previous_leaf`. The
+    /// ones left of the path are frozen, i.e. written to storage already.
+    partial_nodes: Vec<[Option<ChildInfo<V>>; 16]>,
+    previous_leaf: LeafNode<V>,
+    /// The proof of the last chunk, proving the keys restored so far against
+    /// `expected_root_hash`.
+    proof: SparseMerkleRangeProof,
+}
+
 pub struct JellyfishMerkleRestore<V> {
     /// The underlying storage.
     store: Arc<dyn TreeWriter<V>>,
@@ -154,11 +171,17 @@ pub struct JellyfishMerkleRestore<V> {
     /// do proof verification.
     previous_leaf: Option<LeafNode<V>>,
 
-    /// The number of keys we have received since the most recent restart.
+    /// The number of keys we have received since the most recent restart, or since the start of
+    /// the restoration if resumed via `resume`.
     num_keys_received: u64,
 
     /// When the restoration process finishes, we expect the tree to have this root hash.
     expected_root_hash: HashValue,
+
+    /// Known hashes of some subtrees, keyed by the nibble path of the subtree root. Whenever one
+    /// of these subtrees gets frozen, its hash is compared against the checkpoint so a divergence
+    /// is detected as soon as the subtree is complete.
+    checkpoints: BTreeMap<NibblePath, HashValue>,
 }
 
 impl<V> JellyfishMerkleRestore<V>
@@ -195,6 +218,7 @@ where
             previous_leaf,
             num_keys_received: 0,
             expected_root_hash,
+            checkpoints: BTreeMap::new(),
         })
     }
 
@@ -211,9 +235,91 @@ where
             previous_leaf: None,
             num_keys_received: 0,
             expected_root_hash,
+            checkpoints: BTreeMap::new(),
         })
     }
 
+    /// Resumes the restoration of the tree at `version` from the progress persisted by a previous
+    /// instance after each chunk, or starts over like `new_overwrite` if there is none. The keys
+    /// restored so far are validated against `expected_root_hash` with the proof of the last
+    /// chunk, and the frozen nodes against the storage. The next chunk must start right after the
+    /// first `num_keys_received()` keys.
+    pub fn resume<D: 'static + TreeReader<V> + TreeWriter<V>>(
+        store: Arc<D>,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<Self> {
+        let progress = match store.get_restore_progress(version)? {
+            Some(progress) => progress,
+            None => return Self::new_overwrite(store, version, expected_root_hash),
+        };
+        ensure!(
+            progress.expected_root_hash == expected_root_hash,
+            "Restoration in progress at version {} expects root hash {}, not {}.",
+            version,
+            progress.expected_root_hash,
+            expected_root_hash,
+        );
+
+        let previous_key = progress.previous_leaf.account_key();
+        let previous_key_path = NibblePath::new(previous_key.to_vec());
+        let mut nibbles = previous_key_path.nibbles();
+        let mut node_key = NodeKey::new_empty_path(version);
+        let mut partial_nodes = Vec::with_capacity(progress.partial_nodes.len());
+        for children in progress.partial_nodes {
+            let child_node_key = match nibbles.next() {
+                Some(nibble) => node_key.gen_child_node_key(version, nibble),
+                None => bail!("Too many partial nodes in restore progress."),
+            };
+            partial_nodes.push(InternalInfo { node_key, children });
+            node_key = child_node_key;
+        }
+
+        // All the children of the partial nodes but the ones on the path to the previous leaf and
+        // the previous leaf itself must have been frozen and written to storage.
+        for partial_node in &partial_nodes {
+            for (index, child_info) in partial_node.children.iter().enumerate() {
+                let expected_hash = match child_info {
+                    Some(ChildInfo::Internal {
+                        hash: Some(hash), ..
+                    }) => *hash,
+                    Some(ChildInfo::Leaf { node }) if node.account_key() != previous_key => {
+                        node.hash()
+                    }
+                    _ => continue,
+                };
+                let child_node_key = partial_node
+                    .node_key
+                    .gen_child_node_key(version, (index as u8).into());
+                ensure!(
+                    store.get_node(&child_node_key)?.hash() == expected_hash,
+                    "Node at {:?} doesn't match the restore progress.",
+                    child_node_key,
+                );
+            }
+        }
+
+        let restore = Self {
+            store,
+            version,
+            partial_nodes,
+            frozen_nodes: NodeBatch::new(),
+            previous_leaf: Some(progress.previous_leaf),
+            num_keys_received: progress.num_keys_received,
+            expected_root_hash,
+            checkpoints: BTreeMap::new(),
+        };
+        restore.verify(&progress.proof)?;
+        Ok(restore)
+    }
+
+    /// Sets the subtree checkpoints to verify against during the restoration. Each checkpoint maps
+    /// the nibble path of a subtree root to the expected hash of that subtree.
+    pub fn with_checkpoints(mut self, checkpoints: BTreeMap<NibblePath, HashValue>) -> Self {
+        self.checkpoints = checkpoints;
+        self
+    }
+
     /// Recovers partial nodes from storage. We do this by looking at all the ancestors of the
     /// rightmost leaf. The ones do not exist in storage are the partial nodes.
     fn recover_partial_nodes(
@@ -310,14 +416,37 @@ where
             self.num_keys_received += 1;
         }
 
+        // Fail fast if any subtree completed by this chunk diverges from its checkpoint.
+        self.verify_checkpoints()?;
+
         // Verify what we have added so far is all correct.
-        self.verify(proof)?;
+        self.verify(&proof)?;
 
         // Write the frozen nodes to storage.
         self.store.write_node_batch(&self.frozen_nodes)?;
         self.frozen_nodes.clear();
 
-        Ok(())
+        // Persist the progress after the nodes, so that resuming from it at most writes some of
+        // the same nodes again.
+        self.store
+            .write_restore_progress(self.version, Some(&self.progress(proof)))
+    }
+
+    fn progress(&self, proof: SparseMerkleRangeProof) -> RestoreProgress<V> {
+        RestoreProgress {
+            expected_root_hash: self.expected_root_hash,
+            num_keys_received: self.num_keys_received,
+            partial_nodes: self
+                .partial_nodes
+                .iter()
+                .map(|partial_node| partial_node.children.clone())
+                .collect(),
+            previous_leaf: self
+                .previous_leaf
+                .clone()
+                .expect("The previous leaf must exist."),
+            proof,
+        }
     }
 
     /// Restores one account.
@@ -532,7 +661,7 @@ where
     /// `self.previous_leaf`) are correct, i.e., we are able to construct `self.expected_root_hash`
     /// by combining all existing accounts and `proof`.
     #[allow(clippy::collapsible_if)]
-    fn verify(&self, proof: SparseMerkleRangeProof) -> Result<()> {
+    fn verify(&self, proof: &SparseMerkleRangeProof) -> Result<()> {
         let previous_leaf = self
             .previous_leaf
             .as_ref()
@@ -601,6 +730,26 @@ where
         )
     }
 
+    /// Verifies the hashes of all the nodes frozen so far against `self.checkpoints`.
+    fn verify_checkpoints(&self) -> Result<()> {
+        if self.checkpoints.is_empty() {
+            return Ok(());
+        }
+        for (node_key, node) in &self.frozen_nodes {
+            if let Some(expected_hash) = self.checkpoints.get(node_key.nibble_path()) {
+                let actual_hash = node.hash();
+                ensure!(
+                    actual_hash == *expected_hash,
+                    "Subtree at {:?} diverges from checkpoint. Expected hash: {}, actual: {}.",
+                    node_key.nibble_path(),
+                    expected_hash,
+                    actual_hash,
+                );
+            }
+        }
+        Ok(())
+    }
+
     /// Computes the sibling on the left for the `n`-th child.
     fn compute_left_sibling(partial_node: &InternalInfo<V>, n: Nibble, height: u8) -> HashValue {
         assert!(height < 4);
@@ -664,14 +813,17 @@ where
                     let node_key = NodeKey::new_empty_path(self.version);
                     assert!(self.frozen_nodes.is_empty());
                     self.frozen_nodes.insert(node_key, node.into());
+                    self.verify_checkpoints()?;
                     self.store.write_node_batch(&self.frozen_nodes)?;
-                    return Ok(());
+                    return self.store.write_restore_progress(self.version, None);
                 }
             }
         }
 
         self.freeze(0);
-        self.store.write_node_batch(&self.frozen_nodes)
+        self.verify_checkpoints()?;
+        self.store.write_node_batch(&self.frozen_nodes)?;
+        self.store.write_restore_progress(self.version, None)
     }
 }
 
@@ -684,6 +836,10 @@ impl<V: crate::Value> StateSnapshotReceiver<V> for JellyfishMerkleRestore<V> {
         self.add_chunk_impl(chunk, proof)
     }
 
+    fn num_keys_received(&self) -> usize {
+        self.num_keys_received as usize
+    }
+
     fn finish(self) -> Result<()> {
         self.finish_impl()
     }
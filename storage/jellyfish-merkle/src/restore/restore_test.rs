@@ -3,12 +3,13 @@
 
 use crate::{
     mock_tree_store::MockTreeStore,
+    node_type::NodeKey,
     restore::JellyfishMerkleRestore,
     test_helper::{init_mock_db, ValueBlob},
     JellyfishMerkleTree, TreeReader,
 };
 use aptos_crypto::HashValue;
-use aptos_types::transaction::Version;
+use aptos_types::{nibble::nibble_path::NibblePath, transaction::Version};
 use proptest::{collection::btree_map, prelude::*};
 use std::{collections::BTreeMap, sync::Arc};
 use storage_interface::StateSnapshotReceiver;
@@ -79,6 +80,57 @@ proptest! {
         assert_success(&restore_db, expected_root_hash, &all, version);
     }
 
+    #[test]
+    fn test_restore_resume(
+        (all, batch1_size) in btree_map(any::<HashValue>(), any::<ValueBlob>(), 2..1000)
+            .prop_flat_map(|btree| {
+                let len = btree.len();
+                (Just(btree), 1..len)
+            })
+    ) {
+        let (db, version) = init_mock_db(&all.clone().into_iter().collect());
+        let tree = JellyfishMerkleTree::new(&db);
+        let expected_root_hash = tree.get_root_hash(version).unwrap();
+        let batch1: Vec<_> = all.clone().into_iter().take(batch1_size).collect();
+        let batch2: Vec<_> = all.clone().into_iter().skip(batch1_size).collect();
+
+        let restore_db = Arc::new(MockTreeStore::default());
+        {
+            let mut restore = JellyfishMerkleRestore::new_overwrite(
+                Arc::clone(&restore_db),
+                version,
+                expected_root_hash,
+            )
+            .unwrap();
+            let proof = tree
+                .get_range_proof(batch1.last().map(|(key, _value)| *key).unwrap(), version)
+                .unwrap();
+            restore.add_chunk(batch1, proof).unwrap();
+            // Do not call `finish`.
+        }
+
+        // The progress is bound to the root hash it was made towards.
+        prop_assert!(JellyfishMerkleRestore::resume(
+            Arc::clone(&restore_db),
+            version,
+            HashValue::zero(),
+        )
+        .is_err());
+
+        let mut restore =
+            JellyfishMerkleRestore::resume(Arc::clone(&restore_db), version, expected_root_hash)
+                .unwrap();
+        prop_assert_eq!(restore.num_keys_received(), batch1_size);
+        let proof = tree
+            .get_range_proof(batch2.last().map(|(key, _value)| *key).unwrap(), version)
+            .unwrap();
+        restore.add_chunk(batch2, proof).unwrap();
+        restore.finish().unwrap();
+
+        assert_success(&restore_db, expected_root_hash, &all, version);
+        prop_assert!(restore_db.get_restore_progress(version).unwrap().is_none());
+    }
+
     #[test]
     fn test_overwrite(
         btree1 in btree_map(any::<HashValue>(), any::<ValueBlob>(), 1..1000),
@@ -92,6 +144,53 @@ proptest! {
     }
 }
 
+#[test]
+fn test_restore_fails_at_checkpoint() {
+    // 16 keys under each of the 16 subtrees right below the root.
+    let btree: BTreeMap<HashValue, ValueBlob> = (0..=255u8)
+        .map(|i| {
+            let mut bytes = [0u8; HashValue::LENGTH];
+            bytes[0] = i;
+            (HashValue::new(bytes), ValueBlob::from(vec![i]))
+        })
+        .collect();
+    let target_version = 0;
+
+    // Restore the correct snapshot once to collect the subtree checkpoints.
+    let good_db = Arc::new(MockTreeStore::default());
+    restore_without_interruption(&btree, target_version, &good_db, true);
+    let checkpoints: BTreeMap<_, _> = (0..16u8)
+        .map(|n| {
+            let path = NibblePath::new_odd(vec![n << 4]);
+            let node = good_db
+                .get_node(&NodeKey::new(target_version, path.clone()))
+                .unwrap();
+            (path, node.hash())
+        })
+        .collect();
+
+    let (db, source_version) = init_mock_db(&btree.iter().map(|(k, v)| (*k, v.clone())).collect());
+    let tree = JellyfishMerkleTree::new(&db);
+    let expected_root_hash = tree.get_root_hash(source_version).unwrap();
+
+    // Corrupt the very first account, then send the first two subtrees as one chunk.
+    let mut chunk: Vec<_> = btree.into_iter().take(32).collect();
+    chunk[0].1 = ValueBlob::from(vec![0xff]);
+    let proof = tree.get_range_proof(chunk[31].0, source_version).unwrap();
+
+    let restore_db = Arc::new(MockTreeStore::default());
+    let mut restore =
+        JellyfishMerkleRestore::new(Arc::clone(&restore_db), target_version, expected_root_hash)
+            .unwrap()
+            .with_checkpoints(checkpoints);
+    let err = restore.add_chunk(chunk, proof).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Subtree at 0 diverges from checkpoint"));
+    // Nothing should have been written.
+    assert_eq!(restore_db.num_nodes(), 0);
+}
+
 fn assert_success<V>(
     db: &MockTreeStore<V>,
     expected_root_hash: HashValue,
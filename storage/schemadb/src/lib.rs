@@ -42,6 +42,18 @@ pub type ReadOptions = rocksdb::ReadOptions;
 /// Type alias to `rocksdb::Options`.
 pub type Options = rocksdb::Options;
 
+/// Type alias to `rocksdb::DBCompactionStyle`.
+pub type DBCompactionStyle = rocksdb::DBCompactionStyle;
+
+/// Type alias to `rocksdb::DBCompressionType`.
+pub type DBCompressionType = rocksdb::DBCompressionType;
+
+/// Type alias to `rocksdb::BlockBasedOptions`.
+pub type BlockBasedOptions = rocksdb::BlockBasedOptions;
+
+/// Type alias to `rocksdb::Cache`.
+pub type Cache = rocksdb::Cache;
+
 /// Type alias to improve readability.
 pub type ColumnFamilyName = &'static str;
 
@@ -121,6 +133,17 @@ impl SchemaBatch {
             .push(WriteOp::DeletionRangeInclusive { begin, end });
         Ok(())
     }
+
+    /// (For tests only.) Moves the updates to column family `cf_name` out of this batch into a
+    /// new one, e.g. to simulate a write torn between column families.
+    #[cfg(any(test, feature = "fuzzing"))]
+    pub fn split_off_cf(&mut self, cf_name: ColumnFamilyName) -> SchemaBatch {
+        let mut batch = SchemaBatch::new();
+        if let Some(rows) = self.rows.remove(cf_name) {
+            batch.rows.insert(cf_name, rows);
+        }
+        batch
+    }
 }
 
 pub enum ScanDirection {
@@ -238,6 +261,24 @@ impl DB {
         name: &'static str,
         column_families: Vec<ColumnFamilyName>,
         db_opts: &rocksdb::Options,
+    ) -> Result<Self> {
+        Self::open_with_cf_opts(
+            path,
+            name,
+            column_families,
+            db_opts,
+            |_cf_name, _cf_opts| {},
+        )
+    }
+
+    /// Same as [`DB::open`], but `customize_cf_opts` is called with the options of each column
+    /// family before it's opened, so they can be tuned per column family.
+    pub fn open_with_cf_opts(
+        path: impl AsRef<Path>,
+        name: &'static str,
+        column_families: Vec<ColumnFamilyName>,
+        db_opts: &rocksdb::Options,
+        customize_cf_opts: impl Fn(ColumnFamilyName, &mut rocksdb::Options),
     ) -> Result<Self> {
         {
             let cfs_set: HashSet<_> = column_families.iter().collect();
@@ -251,7 +292,7 @@ impl DB {
             );
         }
 
-        let db = DB::open_cf(db_opts, path, name, column_families)?;
+        let db = DB::open_cf(db_opts, path, name, column_families, customize_cf_opts)?;
         Ok(db)
     }
 
@@ -281,11 +322,19 @@ impl DB {
         DB::open_cf_as_secondary(db_opts, primary_path, secondary_path, name, column_families)
     }
 
+    /// Catches up with the primary, making its writes since the last catch-up visible. Only
+    /// applicable to a DB opened with `open_as_secondary`.
+    pub fn try_catch_up_with_primary(&self) -> Result<()> {
+        self.inner.try_catch_up_with_primary()?;
+        Ok(())
+    }
+
     fn open_cf(
         db_opts: &rocksdb::Options,
         path: impl AsRef<Path>,
         name: &'static str,
         column_families: Vec<ColumnFamilyName>,
+        customize_cf_opts: impl Fn(ColumnFamilyName, &mut rocksdb::Options),
     ) -> Result<DB> {
         let inner = rocksdb::DB::open_cf_descriptors(
             db_opts,
@@ -293,6 +342,7 @@ impl DB {
             column_families.iter().map(|cf_name| {
                 let mut cf_opts = rocksdb::Options::default();
                 cf_opts.set_compression_type(rocksdb::DBCompressionType::Lz4);
+                customize_cf_opts(*cf_name, &mut cf_opts);
                 rocksdb::ColumnFamilyDescriptor::new((*cf_name).to_string(), cf_opts)
             }),
         )?;
@@ -365,6 +415,13 @@ impl DB {
             .transpose()
     }
 
+    /// Reads single record by its encoded key, returning the encoded value, i.e. bypassing the
+    /// schema codecs. Meant for debugging, e.g. when a value can't be decoded.
+    pub fn get_raw(&self, cf_name: ColumnFamilyName, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let cf_handle = self.get_cf_handle(cf_name)?;
+        Ok(self.inner.get_cf(cf_handle, key)?)
+    }
+
     /// Writes single record.
     pub fn put<S: Schema>(&self, key: &S::Key, value: &S::Value) -> Result<()> {
         // Not necessary to use a batch, but we'd like a central place to bump counters.
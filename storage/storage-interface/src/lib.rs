@@ -1,7 +1,7 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use anyhow::{format_err, Result};
+use anyhow::{ensure, format_err, Result};
 use aptos_crypto::{hash::SPARSE_MERKLE_PLACEHOLDER_HASH, HashValue};
 use aptos_types::{
     access_path::AccessPath,
@@ -9,28 +9,39 @@ use aptos_types::{
     account_config::aptos_root_address,
     account_state::AccountState,
     account_state_blob::{AccountStateBlob, AccountStateWithProof, AccountStatesChunkWithProof},
-    contract_event::{ContractEvent, EventByVersionWithProof, EventWithProof},
+    block_metadata::BlockMetadata,
+    contract_event::{
+        ContractEvent, EventByVersionWithProof, EventWithProof, EventWithTransactionInfoProof,
+    },
     epoch_change::EpochChangeProof,
     epoch_state::EpochState,
     event::EventKey,
     ledger_info::LedgerInfoWithSignatures,
     move_resource::MoveStorage,
-    on_chain_config::{access_path_for_config, dpn_access_path_for_config, ConfigID},
+    on_chain_config::{
+        access_path_for_config, config_address, dpn_access_path_for_config, ConfigID,
+        ParallelExecutionConfig, ValidatorSet,
+    },
     proof::{
         definition::LeafCount, AccumulatorConsistencyProof, SparseMerkleProof,
         SparseMerkleRangeProof, TransactionAccumulatorSummary,
     },
     state_proof::StateProof,
     transaction::{
-        AccountTransactionsWithProof, TransactionInfo, TransactionListWithProof,
+        AccountTransactionsWithProof, TransactionInfo, TransactionListWithProof, TransactionOutput,
         TransactionOutputListWithProof, TransactionToCommit, TransactionWithProof, Version,
     },
+    vm_status::KeptVMStatus,
+};
+use move_core_types::{
+    language_storage::{StructTag, TypeTag},
+    resolver::{ModuleResolver, ResourceResolver},
 };
-use move_core_types::resolver::{ModuleResolver, ResourceResolver};
 use serde::{Deserialize, Serialize};
-use std::{convert::TryFrom, sync::Arc};
+use std::{collections::HashSet, convert::TryFrom, sync::Arc};
 use thiserror::Error;
 
+pub mod metrics;
 #[cfg(any(feature = "testing", feature = "fuzzing"))]
 pub mod mock;
 pub mod state_view;
@@ -130,6 +141,55 @@ impl TreeState {
     }
 }
 
+/// A version that has been checked to be committed, i.e. no larger than the latest version in the
+/// DB it was checked against.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct CommittedVersion(Version);
+
+impl CommittedVersion {
+    pub fn new<R: DbReader + ?Sized>(reader: &R, version: Version) -> Result<Self> {
+        let committed_version = Self(version);
+        committed_version.ensure_committed(reader)?;
+        Ok(committed_version)
+    }
+
+    pub fn version(&self) -> Version {
+        self.0
+    }
+
+    /// Checks that this version is still committed in `reader`, returning the latest version.
+    fn ensure_committed<R: DbReader + ?Sized>(&self, reader: &R) -> Result<Version> {
+        let latest_version = reader.get_latest_version()?;
+        ensure!(
+            self.0 <= latest_version,
+            "Version {} is not committed. Latest version: {}.",
+            self.0,
+            latest_version,
+        );
+        Ok(latest_version)
+    }
+}
+
+/// Selects the events to keep in transaction outputs. An event is kept if it matches every
+/// criterion that is set; an empty filter keeps all events.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct EventFilter {
+    pub event_keys: Option<HashSet<EventKey>>,
+    pub type_tags: Option<HashSet<TypeTag>>,
+}
+
+impl EventFilter {
+    pub fn matches(&self, event: &ContractEvent) -> bool {
+        self.event_keys
+            .as_ref()
+            .map_or(true, |keys| keys.contains(event.key()))
+            && self
+                .type_tags
+                .as_ref()
+                .map_or(true, |type_tags| type_tags.contains(event.type_tag()))
+    }
+}
+
 pub trait StateSnapshotReceiver<V>: Send {
     fn add_chunk(
         &mut self,
@@ -137,6 +197,11 @@ pub trait StateSnapshotReceiver<V>: Send {
         proof: SparseMerkleRangeProof,
     ) -> Result<()>;
 
+    /// Returns the number of accounts received so far, including the ones received before the
+    /// receiver was resumed via `DbWriter::resume_state_snapshot_receiver`, i.e. the index of the
+    /// first account of the next chunk.
+    fn num_keys_received(&self) -> usize;
+
     fn finish(self) -> Result<()>;
 
     fn finish_box(self: Box<Self>) -> Result<()>;
@@ -195,12 +260,26 @@ pub trait DbReader: Send + Sync {
         unimplemented!()
     }
 
+    /// Streams the epoch ending ledger infos of epochs `[start_epoch, end_epoch)` straight off the
+    /// DB, without the page size cap of [`DbReader::get_epoch_ending_ledger_infos`] and without
+    /// buffering. Nothing is verified: the caller must verify the chain incrementally, checking
+    /// each ledger info against the validator set carried by the previous one, before trusting
+    /// it. Errors if a ledger info is missing, after which the iterator ends.
+    fn get_epoch_ending_ledger_info_iter(
+        &self,
+        start_epoch: u64,
+        end_epoch: u64,
+    ) -> Result<Box<dyn Iterator<Item = Result<LedgerInfoWithSignatures>> + '_>> {
+        unimplemented!()
+    }
+
     /// See [`AptosDB::get_transactions`].
     ///
     /// [`AptosDB::get_transactions`]: ../aptosdb/struct.AptosDB.html#method.get_transactions
     fn get_transactions(
         &self,
         start_version: Version,
+        order: Order,
         batch_size: u64,
         ledger_version: Version,
         fetch_events: bool,
@@ -208,6 +287,43 @@ pub trait DbReader: Send + Sync {
         unimplemented!()
     }
 
+    /// Same as [`Self::get_transactions`], but verifies the result against the transaction
+    /// accumulator root hash at `ledger_version` before returning it, to catch storage corruption
+    /// at read time, e.g. on archival read paths. Costs the verification on every read.
+    fn get_transactions_verified(
+        &self,
+        start_version: Version,
+        batch_size: u64,
+        ledger_version: Version,
+        fetch_events: bool,
+    ) -> Result<TransactionListWithProof> {
+        let txn_list_with_proof = self.get_transactions(
+            start_version,
+            Order::Ascending,
+            batch_size,
+            ledger_version,
+            fetch_events,
+        )?;
+        if txn_list_with_proof.transactions.is_empty() {
+            return Ok(txn_list_with_proof);
+        }
+        txn_list_with_proof
+            .verify_against_root_hash(
+                self.get_accumulator_root_hash(ledger_version)?,
+                Some(start_version),
+            )
+            .map_err(|err| {
+                format_err!(
+                    "Transactions [{}, {}) read from storage failed verification against ledger version {}: {}",
+                    start_version,
+                    start_version + txn_list_with_proof.transactions.len() as u64,
+                    ledger_version,
+                    err,
+                )
+            })?;
+        Ok(txn_list_with_proof)
+    }
+
     /// See [`AptosDB::get_transaction_by_hash`].
     ///
     /// [`AptosDB::get_transaction_by_hash`]: ../aptosdb/struct.AptosDB.html#method.get_transaction_by_hash
@@ -232,6 +348,36 @@ pub trait DbReader: Send + Sync {
         unimplemented!()
     }
 
+    /// Fetches each of `versions` like [`DbReader::get_transaction_by_version`], all against
+    /// `ledger_version`. Results are returned in the same order as `versions`; repeated versions
+    /// are only read once.
+    fn get_transactions_by_versions(
+        &self,
+        versions: &[Version],
+        ledger_version: Version,
+        fetch_events: bool,
+    ) -> Result<Vec<TransactionWithProof>> {
+        unimplemented!()
+    }
+
+    /// Same as [`DbReader::get_transaction_by_version`], against the latest ledger version, but
+    /// only accepts a [`CommittedVersion`].
+    fn get_transaction_by_committed_version(
+        &self,
+        version: CommittedVersion,
+        fetch_events: bool,
+    ) -> Result<TransactionWithProof> {
+        let ledger_version = version.ensure_committed(self)?;
+        self.get_transaction_by_version(version.version(), ledger_version, fetch_events)
+    }
+
+    /// Returns the genesis transaction (version 0), together with its events and a proof against
+    /// the latest ledger version.
+    fn get_genesis_transaction_with_proof(&self) -> Result<TransactionWithProof> {
+        let ledger_version = self.get_latest_version()?;
+        self.get_transaction_by_version(0, ledger_version, true)
+    }
+
     /// See [`AptosDB::get_txn_set_version`].
     ///
     /// [`AptosDB::get_first_txn_version`]: ../aptosdb/struct.AptosDB.html#method.get_first_txn_version
@@ -246,6 +392,12 @@ pub trait DbReader: Send + Sync {
         unimplemented!()
     }
 
+    /// Returns the smallest version whose transaction, events etc. haven't been pruned. Requests
+    /// for anything older fail with `AptosDbError::Pruned` and should be served from a backup.
+    fn get_first_readable_version(&self) -> Result<Version> {
+        unimplemented!()
+    }
+
     /// See [`AptosDB::get_transaction_outputs`].
     ///
     /// [`AptosDB::get_transaction_outputs`]: ../aptosdb/struct.AptosDB.html#method.get_transaction_outputs
@@ -258,6 +410,57 @@ pub trait DbReader: Send + Sync {
         unimplemented!()
     }
 
+    /// Returns `(version, gas_used, status)` of up to `limit` transactions starting at
+    /// `start_version`, read from the transaction infos only, without the rest of the outputs.
+    fn get_transaction_execution_summaries(
+        &self,
+        start_version: Version,
+        limit: u64,
+        ledger_version: Version,
+    ) -> Result<Vec<(Version, u64, KeptVMStatus)>> {
+        unimplemented!()
+    }
+
+    /// Same as [`DbReader::get_transaction_outputs`], but only keeps the events in each output
+    /// that match `filter`. The transaction infos are left untouched so the range proof still
+    /// verifies, while the trimmed events no longer match the event root hashes.
+    fn get_transaction_outputs_filtered(
+        &self,
+        start_version: Version,
+        limit: u64,
+        ledger_version: Version,
+        filter: &EventFilter,
+    ) -> Result<TransactionOutputListWithProof> {
+        let mut txn_output_list_with_proof =
+            self.get_transaction_outputs(start_version, limit, ledger_version)?;
+        txn_output_list_with_proof.transactions_and_outputs = txn_output_list_with_proof
+            .transactions_and_outputs
+            .into_iter()
+            .map(|(txn, txn_output)| {
+                let (write_set, events, gas_used, status) = txn_output.unpack();
+                let events = events
+                    .into_iter()
+                    .filter(|event| filter.matches(event))
+                    .collect();
+                (
+                    txn,
+                    TransactionOutput::new(write_set, events, gas_used, status),
+                )
+            })
+            .collect();
+        Ok(txn_output_list_with_proof)
+    }
+
+    /// Returns the events emitted by each transaction in `[start_version, end_version]` that
+    /// emitted any, grouped by version in ascending order.
+    fn get_events_by_version_range(
+        &self,
+        start_version: Version,
+        end_version: Version,
+    ) -> Result<Vec<(Version, Vec<ContractEvent>)>> {
+        unimplemented!()
+    }
+
     /// Returns events by given event key
     fn get_events(
         &self,
@@ -281,6 +484,44 @@ pub trait DbReader: Send + Sync {
         unimplemented!()
     }
 
+    /// Serves each `(event_key, start, order, limit)` request like [`Self::get_events_with_proofs`],
+    /// all against `ledger_version`. Results are returned in the same order as `requests`. The sum
+    /// of the limits is subject to the same bound as the limit of a single request.
+    fn get_events_multi_key(
+        &self,
+        requests: &[(EventKey, u64, Order, u64)],
+        ledger_version: Version,
+    ) -> Result<Vec<Vec<EventWithProof>>> {
+        unimplemented!()
+    }
+
+    /// Same as [`Self::get_events_with_proofs`], but without the event accumulator proofs. See
+    /// [`EventWithTransactionInfoProof`] for why this is reduced-trust.
+    fn get_events_with_transaction_info_proofs(
+        &self,
+        event_key: &EventKey,
+        start: u64,
+        order: Order,
+        limit: u64,
+        known_version: Option<u64>,
+    ) -> Result<Vec<EventWithTransactionInfoProof>> {
+        unimplemented!()
+    }
+
+    /// Like [`Self::get_events_with_proofs`] against the latest version, but returns as many events
+    /// as fit in `max_bytes` when BCS serialized with their proofs, and at least one if any, but
+    /// never more than the configured request limit. Also returns the sequence number to continue
+    /// from if there are more events in `order`.
+    fn get_events_with_proofs_up_to_bytes(
+        &self,
+        event_key: &EventKey,
+        start: u64,
+        order: Order,
+        max_bytes: usize,
+    ) -> Result<(Vec<EventWithProof>, Option<u64>)> {
+        unimplemented!()
+    }
+
     /// See [`AptosDB::get_block_timestamp`].
     ///
     /// [`AptosDB::get_block_timestamp`]:
@@ -289,6 +530,13 @@ pub trait DbReader: Send + Sync {
         unimplemented!()
     }
 
+    /// Returns the `BlockMetadata` of the block containing `version`, together with the version
+    /// of the block metadata transaction. Returns `None` if `version` precedes the first block
+    /// metadata transaction, i.e. it belongs to genesis.
+    fn get_block_metadata(&self, version: Version) -> Result<Option<(Version, BlockMetadata)>> {
+        unimplemented!()
+    }
+
     /// Returns the [`NewBlockEvent`] for the block containing the requested
     /// `version` and proof that the block actually contains the `version`.
     fn get_event_by_version_with_proof(
@@ -300,6 +548,27 @@ pub trait DbReader: Send + Sync {
         unimplemented!()
     }
 
+    /// Same as [`DbReader::get_event_by_version_with_proof`] for each of `event_keys`, all at the
+    /// same `event_version` and `proof_version`.
+    fn get_events_by_version_with_proof_multi(
+        &self,
+        event_keys: &[EventKey],
+        event_version: u64,
+        proof_version: u64,
+    ) -> Result<Vec<EventByVersionWithProof>> {
+        unimplemented!()
+    }
+
+    /// Returns the sequence number of the latest event as of `ledger_version` for each of
+    /// `event_keys`, or `None` for keys without any events by then.
+    fn get_latest_event_sequence_numbers(
+        &self,
+        event_keys: &[EventKey],
+        ledger_version: Version,
+    ) -> Result<Vec<Option<u64>>> {
+        unimplemented!()
+    }
+
     /// Gets the version of the last transaction committed before timestamp,
     /// a commited block at or after the required timestamp must exist (otherwise it's possible
     /// the next block committed as a timestamp smaller than the one in the request).
@@ -322,6 +591,26 @@ pub trait DbReader: Send + Sync {
         unimplemented!()
     }
 
+    /// Same as [`DbReader::get_latest_account_state`], but also returns the version the state was
+    /// read at, for follow-up requests with proofs at exactly that version.
+    fn get_latest_account_state_and_version(
+        &self,
+        address: AccountAddress,
+    ) -> Result<(Option<AccountStateBlob>, Version)> {
+        unimplemented!()
+    }
+
+    /// See [`AptosDB::get_latest_account_state_with_proof`].
+    ///
+    /// [`AptosDB::get_latest_account_state_with_proof`]:
+    /// ../aptosdb/struct.AptosDB.html#method.get_latest_account_state_with_proof
+    fn get_latest_account_state_with_proof(
+        &self,
+        address: AccountAddress,
+    ) -> Result<AccountStateWithProof> {
+        unimplemented!()
+    }
+
     /// Returns the latest ledger info.
     fn get_latest_ledger_info(&self) -> Result<LedgerInfoWithSignatures> {
         unimplemented!()
@@ -360,14 +649,48 @@ pub trait DbReader: Send + Sync {
         unimplemented!()
     }
 
+    /// Resolves each `(address, seq_num)` pair in `requests` like
+    /// [`DbReader::get_account_transaction`], all against `ledger_version`. Results are returned in
+    /// the same order as `requests`, with `None` for transactions that don't exist.
+    fn get_account_transactions_multi(
+        &self,
+        requests: &[(AccountAddress, u64)],
+        ledger_version: Version,
+        include_events: bool,
+    ) -> Result<Vec<Option<TransactionWithProof>>> {
+        unimplemented!()
+    }
+
+    /// Returns the number of transactions sent by the account with `address` as of
+    /// `ledger_version`, i.e. its latest sequence number plus one, or 0 if it never sent any.
+    ///
+    /// This relies on sequence numbers being contiguous from zero, which doesn't hold for
+    /// accounts using conflict-resistant sequence numbers (enabled from `DIEM_VERSION_4`),
+    /// whose transactions carry nonces out of a sliding window. Fails for such accounts, unless
+    /// the account state at `ledger_version` has been pruned, in which case it can't be told
+    /// whether the account uses them and the count is by sequence number regardless.
+    fn get_account_transaction_count(
+        &self,
+        address: AccountAddress,
+        ledger_version: Version,
+    ) -> Result<u64> {
+        unimplemented!()
+    }
+
     /// Returns the list of transactions sent by an account with `address` starting
     /// at sequence number `seq_num`. Will return no more than `limit` transactions.
     /// Will ignore transactions with `txn.version > ledger_version`. Optionally
     /// fetch events for each transaction when `fetch_events` is `true`.
+    ///
+    /// With `Order::Descending`, returns the `limit` transactions up to and including
+    /// `seq_num`, or the latest ones if `seq_num` is `u64::max_value()`. Either way the
+    /// transactions are returned in ascending order of sequence number, so the result
+    /// verifies against the sequence number of its first transaction.
     fn get_account_transactions(
         &self,
         address: AccountAddress,
         seq_num: u64,
+        order: Order,
         limit: u64,
         include_events: bool,
         ledger_version: Version,
@@ -375,6 +698,23 @@ pub trait DbReader: Send + Sync {
         unimplemented!()
     }
 
+    /// Same as [`DbReader::get_account_transactions`], but fetches events only for the
+    /// transactions whose versions satisfy `include_events`, e.g. so that only the most recent
+    /// entries of a page carry events. The result doesn't pass
+    /// `AccountTransactionsWithProof::verify` unless the predicate is constant; verify each
+    /// `TransactionWithProof` instead.
+    fn get_account_transactions_with_events_filter(
+        &self,
+        address: AccountAddress,
+        seq_num: u64,
+        order: Order,
+        limit: u64,
+        include_events: &dyn Fn(Version) -> bool,
+        ledger_version: Version,
+    ) -> Result<AccountTransactionsWithProof> {
+        unimplemented!()
+    }
+
     /// Returns proof of new state for a given ledger info with signatures relative to version known
     /// to client
     fn get_state_proof_with_ledger_info(
@@ -401,6 +741,20 @@ pub trait DbReader: Send + Sync {
         unimplemented!()
     }
 
+    /// Returns the resource `tag` under `address` at `version`, along with the state of the
+    /// account with proof based on `ledger_version`. Note that the proof is over the whole account
+    /// state rather than the resource alone: the returned resource is merely extracted from it for
+    /// convenience, and clients must re-derive it from the blob once the proof is verified.
+    fn get_resource_with_proof(
+        &self,
+        address: AccountAddress,
+        tag: &StructTag,
+        version: Version,
+        ledger_version: Version,
+    ) -> Result<(Option<Vec<u8>>, AccountStateWithProof)> {
+        unimplemented!()
+    }
+
     // Gets an account state by account address, out of the ledger state indicated by the state
     // Merkle tree root with a sparse merkle proof proving state tree root.
     // See [`AptosDB::get_account_state_with_proof_by_version`].
@@ -420,12 +774,87 @@ pub trait DbReader: Send + Sync {
         unimplemented!()
     }
 
+    /// Returns the versions in `[start_version, end_version]` at which the account state of
+    /// `address` changed, with the new states, the first entry being the state at `start_version`.
+    fn get_account_state_history(
+        &self,
+        address: AccountAddress,
+        start_version: Version,
+        end_version: Version,
+    ) -> Result<Vec<(Version, Option<AccountStateBlob>)>> {
+        unimplemented!()
+    }
+
+    /// Returns the on-chain `ValidatorSet` config in the state at `version`, or `None` if it's not
+    /// set.
+    fn get_validator_set(&self, version: Version) -> Result<Option<ValidatorSet>> {
+        match self
+            .get_account_state_with_proof_by_version(config_address(), version)?
+            .0
+        {
+            Some(blob) => AccountState::try_from(&blob)?.get_validator_set(),
+            None => Ok(None),
+        }
+    }
+
+    /// Same as [`DbReader::get_account_state_with_proof_by_version`], but only accepts a
+    /// [`CommittedVersion`].
+    fn get_account_state_with_proof_by_committed_version(
+        &self,
+        address: AccountAddress,
+        version: CommittedVersion,
+    ) -> Result<(
+        Option<AccountStateBlob>,
+        SparseMerkleProof<AccountStateBlob>,
+    )> {
+        version.ensure_committed(self)?;
+        self.get_account_state_with_proof_by_version(address, version.version())
+    }
+
+    /// Returns the sequence number of the account at `address` as of `version`, or `None` if the
+    /// account doesn't exist. Only the `AccountResource` is deserialized from the account state.
+    fn get_account_sequence_number(
+        &self,
+        address: AccountAddress,
+        version: Version,
+    ) -> Result<Option<u64>> {
+        match self
+            .get_account_state_with_proof_by_version(address, version)?
+            .0
+        {
+            Some(blob) => Ok(AccountState::try_from(&blob)?
+                .get_account_resource()?
+                .map(|account_resource| account_resource.sequence_number())),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the on-chain [`ParallelExecutionConfig`] as of `version`, or `None` if it's not
+    /// published.
+    fn get_parallel_execution_config(
+        &self,
+        version: Version,
+    ) -> Result<Option<ParallelExecutionConfig>> {
+        match self
+            .get_account_state_with_proof_by_version(config_address(), version)?
+            .0
+        {
+            Some(blob) => AccountState::try_from(&blob)?.get_parallel_execution_config(),
+            None => Ok(None),
+        }
+    }
+
     /// Gets the latest TreeState no matter if db has been bootstrapped.
     /// Used by the Db-bootstrapper.
     fn get_latest_tree_state(&self) -> Result<TreeState> {
         unimplemented!()
     }
 
+    /// Gets the TreeState right after the transaction at `version` is committed.
+    fn get_tree_state(&self, version: Version) -> Result<TreeState> {
+        unimplemented!()
+    }
+
     /// Get the ledger info of the epoch that `known_version` belongs to.
     fn get_epoch_ending_ledger_info(&self, known_version: u64) -> Result<LedgerInfoWithSignatures> {
         unimplemented!()
@@ -497,10 +926,27 @@ pub trait DbReader: Send + Sync {
         unimplemented!()
     }
 
+    /// Get a chunk of account data ending at the account at `last_idx`, for walking the accounts
+    /// from the highest index downward. The accounts in the chunk are still in ascending order.
+    fn get_account_chunk_with_proof_rev(
+        &self,
+        version: Version,
+        last_idx: usize,
+        chunk_size: usize,
+    ) -> Result<AccountStatesChunkWithProof> {
+        unimplemented!()
+    }
+
     /// Get the state prune window config value.
     fn get_state_prune_window(&self) -> Option<usize> {
         unimplemented!()
     }
+
+    /// Get the ledger prune window config value, i.e. how many versions of transactions, events
+    /// etc. are kept.
+    fn get_ledger_prune_window(&self) -> Option<usize> {
+        unimplemented!()
+    }
 }
 
 impl MoveStorage for &dyn DbReader {
@@ -584,6 +1030,32 @@ pub trait DbWriter: Send + Sync {
         unimplemented!()
     }
 
+    /// Same as [`DbWriter::save_transactions`], but returns the root hash of the transaction
+    /// accumulator after the transactions are committed.
+    fn save_transactions_and_get_root(
+        &self,
+        txns_to_commit: &[TransactionToCommit],
+        first_version: Version,
+        ledger_info_with_sigs: Option<&LedgerInfoWithSignatures>,
+    ) -> Result<HashValue> {
+        unimplemented!()
+    }
+
+    /// Makes sure everything saved up to `version` is persisted and visible to readers, for
+    /// writers which don't commit synchronously in `save_transactions`.
+    fn wait_for_commit(&self, version: Version) -> Result<()> {
+        unimplemented!()
+    }
+
+    /// Persists a ledger info without any new transactions, e.g. a reconfiguration waypoint. The
+    /// ledger info must be at the latest committed version and match its accumulator root hash.
+    fn commit_ledger_info_only(
+        &self,
+        ledger_info_with_sigs: &LedgerInfoWithSignatures,
+    ) -> Result<()> {
+        unimplemented!()
+    }
+
     /// Get a (stateful) state snapshot receiver.
     ///
     /// Chunk of accounts need to be added via `add_chunk()` before finishing up with `finish_box()`
@@ -594,6 +1066,18 @@ pub trait DbWriter: Send + Sync {
     ) -> Result<Box<dyn StateSnapshotReceiver<AccountStateBlob>>> {
         unimplemented!()
     }
+
+    /// Get a state snapshot receiver picking up where the last one at `version` left off if it
+    /// was interrupted, e.g. by a crash, after the accounts restored so far are validated
+    /// against `expected_root_hash`. Chunks need to be added from the account at
+    /// `num_keys_received()` on. Starts over if there is no restoration in progress.
+    fn resume_state_snapshot_receiver(
+        &self,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<Box<dyn StateSnapshotReceiver<AccountStateBlob>>> {
+        unimplemented!()
+    }
 }
 
 pub trait MoveDbReader:
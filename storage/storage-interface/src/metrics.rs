@@ -0,0 +1,29 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_metrics::{register_int_counter_vec, IntCounterVec};
+use aptos_state_view::StateViewId;
+use once_cell::sync::Lazy;
+
+/// Account state reads served by a `VerifiedStateView`, by where the account state came from:
+/// "cache" for the view's own account state cache, "scratchpad" for the speculative state and
+/// "db" for the storage reader.
+pub static VERIFIED_STATE_VIEW_READS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_storage_verified_state_view_reads",
+        "Account state reads served by a VerifiedStateView, by state view id and source.",
+        &["state_view_id", "source"]
+    )
+    .unwrap()
+});
+
+/// The label of a `StateViewId`, leaving out the version or block id to keep the number of time
+/// series bounded.
+pub(crate) fn state_view_id_label(id: StateViewId) -> &'static str {
+    match id {
+        StateViewId::ChunkExecution { .. } => "chunk_execution",
+        StateViewId::BlockExecution { .. } => "block_execution",
+        StateViewId::TransactionValidation { .. } => "transaction_validation",
+        StateViewId::Miscellaneous => "miscellaneous",
+    }
+}
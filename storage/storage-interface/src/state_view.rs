@@ -1,7 +1,10 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::DbReader;
+use crate::{
+    metrics::{state_view_id_label, VERIFIED_STATE_VIEW_READS},
+    DbReader,
+};
 use anyhow::{format_err, Result};
 use aptos_crypto::{hash::SPARSE_MERKLE_PLACEHOLDER_HASH, HashValue};
 use aptos_state_view::{StateView, StateViewId};
@@ -12,11 +15,14 @@ use aptos_types::{
     account_state_blob::AccountStateBlob,
     proof::SparseMerkleProof,
     transaction::{Version, PRE_GENESIS_VERSION},
+    write_set::{WriteOp, WriteSet},
 };
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
+use rayon::prelude::*;
 use scratchpad::{AccountStatus, FrozenSparseMerkleTree, SparseMerkleTree};
+use serde::{Deserialize, Serialize};
 use std::{
-    collections::{hash_map::Entry, HashMap},
+    collections::{hash_map::Entry, HashMap, HashSet},
     convert::TryInto,
     sync::Arc,
 };
@@ -78,6 +84,15 @@ pub struct VerifiedStateView {
     /// ```
     account_to_state_cache: RwLock<HashMap<AccountAddress, AccountState>>,
     account_to_proof_cache: RwLock<HashMap<HashValue, SparseMerkleProof<AccountStateBlob>>>,
+
+    /// The account states resulting from the write sets applied via `apply_write_set`, overlaid
+    /// on `account_to_state_cache`. Kept apart so that `account_to_state_cache` always matches the
+    /// proofs in `account_to_proof_cache`.
+    account_to_speculative_state: RwLock<HashMap<AccountAddress, AccountState>>,
+
+    /// Whether the proofs of account states from `reader` are verified against
+    /// `latest_persistent_state_root`. Only turned off when `reader` is trusted, e.g. a local DB.
+    verify_proofs: bool,
 }
 
 impl VerifiedStateView {
@@ -90,6 +105,44 @@ impl VerifiedStateView {
         latest_persistent_version: Option<Version>,
         latest_persistent_state_root: HashValue,
         speculative_state: SparseMerkleTree<AccountStateBlob>,
+    ) -> Self {
+        Self::new_impl(
+            id,
+            reader,
+            latest_persistent_version,
+            latest_persistent_state_root,
+            speculative_state,
+            true, /* verify_proofs */
+        )
+    }
+
+    /// Like [`VerifiedStateView::new`], but the proofs of account states read from `reader` are
+    /// not verified, only cached. Must only be used when `reader` is trusted, e.g. it accesses the
+    /// local DB directly rather than over RPC.
+    pub fn new_trusted(
+        id: StateViewId,
+        reader: Arc<dyn DbReader>,
+        latest_persistent_version: Option<Version>,
+        latest_persistent_state_root: HashValue,
+        speculative_state: SparseMerkleTree<AccountStateBlob>,
+    ) -> Self {
+        Self::new_impl(
+            id,
+            reader,
+            latest_persistent_version,
+            latest_persistent_state_root,
+            speculative_state,
+            false, /* verify_proofs */
+        )
+    }
+
+    fn new_impl(
+        id: StateViewId,
+        reader: Arc<dyn DbReader>,
+        latest_persistent_version: Option<Version>,
+        latest_persistent_state_root: HashValue,
+        speculative_state: SparseMerkleTree<AccountStateBlob>,
+        verify_proofs: bool,
     ) -> Self {
         // Hack: When there's no transaction in the db but state tree root hash is not the
         // placeholder hash, it implies that there's pre-genesis state present.
@@ -108,66 +161,89 @@ impl VerifiedStateView {
             speculative_state: speculative_state.freeze(),
             account_to_state_cache: RwLock::new(HashMap::new()),
             account_to_proof_cache: RwLock::new(HashMap::new()),
+            account_to_speculative_state: RwLock::new(HashMap::new()),
+            verify_proofs,
         }
     }
 
-    pub fn into_state_cache(self) -> StateCache {
-        StateCache {
-            frozen_base: self.speculative_state,
-            accounts: self.account_to_state_cache.into_inner(),
-            proofs: self.account_to_proof_cache.into_inner(),
+    /// Applies `write_set` on top of the cached account states so that subsequent `get`s within
+    /// this view observe the writes, allowing further speculation on top of them. Nothing is
+    /// written to the DB. Each touched account is loaded (and its proof verified) first, so
+    /// applying the same write set more than once yields the same state. The resulting states are
+    /// kept in an overlay and left out of [`VerifiedStateView::into_state_cache`].
+    pub fn apply_write_set(&self, write_set: &WriteSet) -> Result<()> {
+        let mut account_to_speculative_state = self.account_to_speculative_state.write();
+        for (access_path, write_op) in write_set {
+            let account_state = match account_to_speculative_state.entry(access_path.address) {
+                Entry::Occupied(occupied) => occupied.into_mut(),
+                Entry::Vacant(vacant) => {
+                    vacant.insert(self.load_account_state(access_path.address)?)
+                }
+            };
+            match write_op {
+                WriteOp::Value(value) => {
+                    account_state.insert(access_path.path.clone(), value.clone())
+                }
+                WriteOp::Deletion => account_state.remove(&access_path.path),
+            };
         }
+        Ok(())
     }
-}
-
-pub struct StateCache {
-    pub frozen_base: FrozenSparseMerkleTree<AccountStateBlob>,
-    pub accounts: HashMap<AccountAddress, AccountState>,
-    pub proofs: HashMap<HashValue, SparseMerkleProof<AccountStateBlob>>,
-}
-
-impl StateView for VerifiedStateView {
-    fn id(&self) -> StateViewId {
-        self.id
-    }
-
-    fn get(&self, access_path: &AccessPath) -> Result<Option<Vec<u8>>> {
-        let address = access_path.address;
-        let path = &access_path.path;
 
-        // Lock for read first:
-        if let Some(contents) = self.account_to_state_cache.read().get(&address) {
-            return Ok(contents.get(path).cloned());
+    /// Returns the cached state of `address`, loading it into the cache first if needed. Unlike
+    /// `get`, this doesn't count as a read.
+    fn load_account_state(&self, address: AccountAddress) -> Result<AccountState> {
+        if let Some(account_state) = self.account_to_state_cache.read().get(&address) {
+            return Ok(account_state.clone());
         }
+        let account_state = self.fetch_account_state(address)?;
+        Ok(self
+            .account_to_state_cache
+            .write()
+            .entry(address)
+            .or_insert(account_state)
+            .clone())
+    }
 
-        // Do most of the work outside the write lock.
+    /// Reads the state of `address` from the speculative state, or from `reader` with its proof
+    /// verified (unless the view is trusted) and cached.
+    fn fetch_account_state(&self, address: AccountAddress) -> Result<AccountState> {
         let address_hash = address.hash();
         let account_blob_option = match self.speculative_state.get(address_hash) {
-            AccountStatus::ExistsInScratchPad(blob) => Some(blob),
-            AccountStatus::DoesNotExist => None,
+            AccountStatus::ExistsInScratchPad(blob) => {
+                self.inc_reads("scratchpad");
+                Some(blob)
+            }
+            AccountStatus::DoesNotExist => {
+                self.inc_reads("scratchpad");
+                None
+            }
             // No matter it is in db or unknown, we have to query from db since even the
             // former case, we don't have the blob data but only its hash.
             AccountStatus::ExistsInDB | AccountStatus::Unknown => {
+                self.inc_reads("db");
                 let (blob, proof) = match self.latest_persistent_version {
                     Some(version) => self
                         .reader
                         .get_account_state_with_proof_by_version(address, version)?,
                     None => (None, SparseMerkleProof::new(None, vec![])),
                 };
-                proof
-                    .verify(
-                        self.latest_persistent_state_root,
-                        address.hash(),
-                        blob.as_ref(),
-                    )
-                    .map_err(|err| {
-                        format_err!(
-                            "Proof is invalid for address {:?} with state root hash {:?}: {}",
-                            address,
+                if self.verify_proofs {
+                    proof
+                        .verify(
                             self.latest_persistent_state_root,
-                            err
+                            address.hash(),
+                            blob.as_ref(),
                         )
-                    })?;
+                        .map_err(|err| {
+                            format_err!(
+                                "Proof is invalid for address {:?} with state root hash {:?}: {}",
+                                address,
+                                self.latest_persistent_state_root,
+                                err
+                            )
+                        })?;
+                }
 
                 // multiple threads may enter this code, and another thread might add
                 // an address before this one. Thus the insertion might return a None here.
@@ -179,13 +255,165 @@ impl StateView for VerifiedStateView {
             }
         };
 
-        // Now enter the locked region, and write if still empty.
-        let new_account_blob = account_blob_option
+        Ok(account_blob_option
             .as_ref()
             .map(TryInto::try_into)
             .transpose()?
-            .unwrap_or_default();
+            .unwrap_or_default())
+    }
+
+    fn inc_reads(&self, source: &str) {
+        VERIFIED_STATE_VIEW_READS
+            .with_label_values(&[state_view_id_label(self.id), source])
+            .inc();
+    }
+
+    /// Concurrently loads into the cache the states of all accounts touched by `reads`, e.g. those
+    /// recorded while executing the previous block, when similar accesses are expected.
+    pub fn prewarm_from_reads(&self, reads: &RecordedReads) -> Result<()> {
+        reads
+            .addresses()
+            .into_par_iter()
+            .filter(|address| !self.account_to_state_cache.read().contains_key(address))
+            .try_for_each(|address| {
+                let account_state = self.fetch_account_state(address)?;
+                self.account_to_state_cache
+                    .write()
+                    .entry(address)
+                    .or_insert(account_state);
+                Ok(())
+            })
+    }
+
+    /// The states in the returned cache are the ones read, without the writes applied via
+    /// [`VerifiedStateView::apply_write_set`], so that they match the returned proofs.
+    pub fn into_state_cache(self) -> StateCache {
+        StateCache {
+            frozen_base: self.speculative_state,
+            accounts: self.account_to_state_cache.into_inner(),
+            proofs: self.account_to_proof_cache.into_inner(),
+        }
+    }
+}
+
+/// A [`StateView`] recording all the access paths read through it.
+pub struct RecordingStateView<'a, S> {
+    inner: &'a S,
+    reads: Mutex<HashSet<AccessPath>>,
+}
+
+impl<'a, S: StateView> RecordingStateView<'a, S> {
+    pub fn new(inner: &'a S) -> Self {
+        Self {
+            inner,
+            reads: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub fn into_reads(self) -> RecordedReads {
+        RecordedReads(self.reads.into_inner())
+    }
+}
+
+impl<'a, S: StateView> StateView for RecordingStateView<'a, S> {
+    fn id(&self) -> StateViewId {
+        self.inner.id()
+    }
 
+    fn get(&self, access_path: &AccessPath) -> Result<Option<Vec<u8>>> {
+        self.reads.lock().insert(access_path.clone());
+        self.inner.get(access_path)
+    }
+
+    fn is_genesis(&self) -> bool {
+        self.inner.is_genesis()
+    }
+}
+
+/// The access paths read through a [`RecordingStateView`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RecordedReads(HashSet<AccessPath>);
+
+impl RecordedReads {
+    pub fn access_paths(&self) -> &HashSet<AccessPath> {
+        &self.0
+    }
+
+    /// The distinct accounts read.
+    pub fn addresses(&self) -> HashSet<AccountAddress> {
+        self.0
+            .iter()
+            .map(|access_path| access_path.address)
+            .collect()
+    }
+}
+
+pub struct StateCache {
+    pub frozen_base: FrozenSparseMerkleTree<AccountStateBlob>,
+    pub accounts: HashMap<AccountAddress, AccountState>,
+    pub proofs: HashMap<HashValue, SparseMerkleProof<AccountStateBlob>>,
+}
+
+/// The serializable part of a [`StateCache`]. The in-memory `frozen_base` can't be persisted, so
+/// only its root hash is kept, which is enough to verify the included proofs against.
+#[derive(Deserialize, Serialize)]
+struct SerializableStateCache {
+    base_root_hash: HashValue,
+    accounts: HashMap<AccountAddress, AccountState>,
+    proofs: HashMap<HashValue, SparseMerkleProof<AccountStateBlob>>,
+}
+
+impl StateCache {
+    /// Serializes the cache with BCS, so that caches produced by different processes or nodes for
+    /// the same block can be compared when investigating a divergence.
+    pub fn serialize(&self) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(&SerializableStateCache {
+            base_root_hash: self.frozen_base.root_hash(),
+            accounts: self.accounts.clone(),
+            proofs: self.proofs.clone(),
+        })?)
+    }
+
+    /// Reverse of [`StateCache::serialize`]. The `frozen_base` is reconstructed as a tree with
+    /// only the root hash known, all of its content needs to be resolved via the proofs.
+    pub fn deserialize(bytes: &[u8]) -> Result<Self> {
+        let SerializableStateCache {
+            base_root_hash,
+            accounts,
+            proofs,
+        } = bcs::from_bytes(bytes)?;
+        Ok(Self {
+            frozen_base: SparseMerkleTree::new(base_root_hash).freeze(),
+            accounts,
+            proofs,
+        })
+    }
+}
+
+impl StateView for VerifiedStateView {
+    fn id(&self) -> StateViewId {
+        self.id
+    }
+
+    fn get(&self, access_path: &AccessPath) -> Result<Option<Vec<u8>>> {
+        let address = access_path.address;
+        let path = &access_path.path;
+
+        if let Some(contents) = self.account_to_speculative_state.read().get(&address) {
+            self.inc_reads("cache");
+            return Ok(contents.get(path).cloned());
+        }
+
+        // Lock for read first:
+        if let Some(contents) = self.account_to_state_cache.read().get(&address) {
+            self.inc_reads("cache");
+            return Ok(contents.get(path).cloned());
+        }
+
+        // Do most of the work outside the write lock.
+        let new_account_blob = self.fetch_account_state(address)?;
+
+        // Now enter the locked region, and write if still empty.
         match self.account_to_state_cache.write().entry(address) {
             Entry::Occupied(occupied) => Ok(occupied.get().get(path).cloned()),
             Entry::Vacant(vacant) => Ok(vacant.insert(new_account_blob).get(path).cloned()),
@@ -196,3 +424,212 @@ impl StateView for VerifiedStateView {
         self.latest_persistent_version.is_none()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_types::write_set::WriteSetMut;
+
+    #[test]
+    fn test_state_cache_serialization_round_trip() {
+        let address = AccountAddress::random();
+        let mut account_state = AccountState::default();
+        account_state.insert(b"path".to_vec(), b"value".to_vec());
+        let proof = SparseMerkleProof::new(None, vec![HashValue::random()]);
+
+        let root_hash = HashValue::random();
+        let state_cache = StateCache {
+            frozen_base: SparseMerkleTree::new(root_hash).freeze(),
+            accounts: vec![(address, account_state)].into_iter().collect(),
+            proofs: vec![(address.hash(), proof)].into_iter().collect(),
+        };
+
+        let bytes = state_cache.serialize().unwrap();
+        let deserialized = StateCache::deserialize(&bytes).unwrap();
+        assert_eq!(deserialized.frozen_base.root_hash(), root_hash);
+        assert_eq!(deserialized.accounts, state_cache.accounts);
+        assert_eq!(deserialized.proofs, state_cache.proofs);
+    }
+
+    struct EmptyDb;
+
+    impl DbReader for EmptyDb {}
+
+    #[test]
+    fn test_apply_write_set() {
+        let view = VerifiedStateView::new(
+            StateViewId::Miscellaneous,
+            Arc::new(EmptyDb),
+            None,
+            *SPARSE_MERKLE_PLACEHOLDER_HASH,
+            SparseMerkleTree::new(*SPARSE_MERKLE_PLACEHOLDER_HASH),
+        );
+        let address = AccountAddress::random();
+        let modified = AccessPath::new(address, b"modified".to_vec());
+        let deleted = AccessPath::new(address, b"deleted".to_vec());
+        assert_eq!(view.get(&modified).unwrap(), None);
+
+        let write_set = WriteSetMut::new(vec![
+            (modified.clone(), WriteOp::Value(b"value".to_vec())),
+            (deleted.clone(), WriteOp::Value(b"value".to_vec())),
+        ])
+        .freeze()
+        .unwrap();
+        view.apply_write_set(&write_set).unwrap();
+        assert_eq!(view.get(&modified).unwrap(), Some(b"value".to_vec()));
+        assert_eq!(view.get(&deleted).unwrap(), Some(b"value".to_vec()));
+
+        let write_set = WriteSetMut::new(vec![
+            (modified.clone(), WriteOp::Value(b"new_value".to_vec())),
+            (deleted.clone(), WriteOp::Deletion),
+        ])
+        .freeze()
+        .unwrap();
+        // Replaying the same write set gives the same result.
+        view.apply_write_set(&write_set).unwrap();
+        view.apply_write_set(&write_set).unwrap();
+        assert_eq!(view.get(&modified).unwrap(), Some(b"new_value".to_vec()));
+        assert_eq!(view.get(&deleted).unwrap(), None);
+
+        // The writes don't leak into the state cache, whose states have to match the proofs.
+        let state_cache = view.into_state_cache();
+        assert_eq!(state_cache.accounts[&address], AccountState::default());
+    }
+
+    #[test]
+    fn test_apply_write_set_keeps_proofs_consistent() {
+        // Use a label no other test reads through.
+        let id = StateViewId::ChunkExecution { first_version: 0 };
+        let view = VerifiedStateView::new_trusted(
+            id,
+            Arc::new(EmptyDb),
+            None,
+            HashValue::random(),
+            SparseMerkleTree::new(HashValue::random()),
+        );
+        let address = AccountAddress::random();
+        let access_path = AccessPath::new(address, b"path".to_vec());
+        let reads = || {
+            VERIFIED_STATE_VIEW_READS
+                .with_label_values(&[state_view_id_label(id), "cache"])
+                .get()
+        };
+
+        let cache_reads = reads();
+        let write_set = WriteSetMut::new(vec![(access_path.clone(), WriteOp::Deletion)])
+            .freeze()
+            .unwrap();
+        view.apply_write_set(&write_set).unwrap();
+        assert_eq!(reads(), cache_reads);
+        assert_eq!(view.get(&access_path).unwrap(), None);
+
+        let state_cache = view.into_state_cache();
+        assert_eq!(state_cache.accounts[&address], AccountState::default());
+        assert!(state_cache.proofs.contains_key(&address.hash()));
+    }
+
+    #[test]
+    fn test_prewarm_from_reads() {
+        let new_view = || {
+            VerifiedStateView::new(
+                StateViewId::Miscellaneous,
+                Arc::new(EmptyDb),
+                None,
+                *SPARSE_MERKLE_PLACEHOLDER_HASH,
+                SparseMerkleTree::new(*SPARSE_MERKLE_PLACEHOLDER_HASH),
+            )
+        };
+        let addresses: HashSet<_> = (0..10).map(|_| AccountAddress::random()).collect();
+
+        let view = new_view();
+        let recording_view = RecordingStateView::new(&view);
+        for address in &addresses {
+            recording_view
+                .get(&AccessPath::new(*address, b"path1".to_vec()))
+                .unwrap();
+            recording_view
+                .get(&AccessPath::new(*address, b"path2".to_vec()))
+                .unwrap();
+        }
+        let reads = recording_view.into_reads();
+        assert_eq!(reads.access_paths().len(), addresses.len() * 2);
+        assert_eq!(reads.addresses(), addresses);
+
+        let next_view = new_view();
+        next_view.prewarm_from_reads(&reads).unwrap();
+        let cached_addresses: HashSet<_> = next_view
+            .account_to_state_cache
+            .read()
+            .keys()
+            .cloned()
+            .collect();
+        assert_eq!(cached_addresses, addresses);
+    }
+
+    #[test]
+    fn test_reads_counted_by_source() {
+        let id = StateViewId::TransactionValidation { base_version: 0 };
+        let reads = |source| {
+            VERIFIED_STATE_VIEW_READS
+                .with_label_values(&[state_view_id_label(id), source])
+                .get()
+        };
+        let (scratchpad, cache, db) = (reads("scratchpad"), reads("cache"), reads("db"));
+
+        // An empty speculative state knows every account doesn't exist.
+        let view = VerifiedStateView::new(
+            id,
+            Arc::new(EmptyDb),
+            None,
+            *SPARSE_MERKLE_PLACEHOLDER_HASH,
+            SparseMerkleTree::new(*SPARSE_MERKLE_PLACEHOLDER_HASH),
+        );
+        let access_path = AccessPath::new(AccountAddress::random(), b"path".to_vec());
+        view.get(&access_path).unwrap();
+        view.get(&access_path).unwrap();
+        assert!(reads("scratchpad") > scratchpad);
+        assert!(reads("cache") > cache);
+
+        // A speculative state with only the root hash known has to defer to the reader.
+        let view = VerifiedStateView::new(
+            id,
+            Arc::new(EmptyDb),
+            None,
+            *SPARSE_MERKLE_PLACEHOLDER_HASH,
+            SparseMerkleTree::new(HashValue::random()),
+        );
+        view.get(&access_path).unwrap();
+        assert!(reads("db") > db);
+    }
+
+    #[test]
+    fn test_trusted_view_skips_proof_verification() {
+        type Constructor = fn(
+            StateViewId,
+            Arc<dyn DbReader>,
+            Option<Version>,
+            HashValue,
+            SparseMerkleTree<AccountStateBlob>,
+        ) -> VerifiedStateView;
+        let new_view = |new: Constructor| {
+            // The empty proof returned for a DB without any version doesn't prove anything
+            // against a random root hash.
+            new(
+                StateViewId::Miscellaneous,
+                Arc::new(EmptyDb),
+                None,
+                HashValue::random(),
+                SparseMerkleTree::new(HashValue::random()),
+            )
+        };
+        let address = AccountAddress::random();
+        let access_path = AccessPath::new(address, b"path".to_vec());
+
+        assert!(new_view(VerifiedStateView::new).get(&access_path).is_err());
+
+        let view = new_view(VerifiedStateView::new_trusted);
+        assert_eq!(view.get(&access_path).unwrap(), None);
+        // The proof is still cached.
+        assert!(view.into_state_cache().proofs.contains_key(&address.hash()));
+    }
+}
@@ -13,7 +13,7 @@ use crate::{
     block_metadata::BlockResource,
     on_chain_config::{
         access_path_for_config, dpn_access_path_for_config, ConfigurationResource, OnChainConfig,
-        RegisteredCurrencies, VMPublishingOption, ValidatorSet, Version,
+        ParallelExecutionConfig, RegisteredCurrencies, VMPublishingOption, ValidatorSet, Version,
     },
     timestamp::TimestampResource,
     validator_config::{ValidatorConfigResource, ValidatorOperatorConfigResource},
@@ -187,6 +187,17 @@ impl AccountState {
             .map_err(Into::into)
     }
 
+    pub fn get_parallel_execution_config(&self) -> Result<Option<ParallelExecutionConfig>> {
+        self.0
+            .get(&access_path_for_config(ParallelExecutionConfig::CONFIG_ID).path)
+            .or_else(|| {
+                self.0
+                    .get(&dpn_access_path_for_config(ParallelExecutionConfig::CONFIG_ID).path)
+            })
+            .map(|bytes| ParallelExecutionConfig::deserialize_into_config(bytes))
+            .transpose()
+    }
+
     pub fn get_registered_currency_info_resources(&self) -> Result<Vec<CurrencyInfoResource>> {
         let currencies: Option<RegisteredCurrencies> = self.get_config()?;
         match currencies {
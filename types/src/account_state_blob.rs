@@ -6,12 +6,15 @@ use crate::{
     account_config::{AccountResource, BalanceResource, DiemAccountResource},
     account_state::AccountState,
     ledger_info::LedgerInfo,
-    proof::{AccountStateProof, SparseMerkleRangeProof},
+    proof::{
+        AccountStateProof, AccumulatorConsistencyProof, SparseMerkleInternalNode,
+        SparseMerkleLeafNode, SparseMerkleRangeProof, TransactionAccumulatorSummary,
+    },
     transaction::Version,
 };
 use anyhow::{anyhow, ensure, Error, Result};
 use aptos_crypto::{
-    hash::{CryptoHash, CryptoHasher},
+    hash::{CryptoHash, CryptoHasher, SPARSE_MERKLE_PLACEHOLDER_HASH},
     HashValue,
 };
 use aptos_crypto_derive::CryptoHasher;
@@ -220,11 +223,38 @@ impl AccountStateWithProof {
         self.proof
             .verify(ledger_info, version, address.hash(), self.blob.as_ref())
     }
+
+    /// Verifies this account state against `ledger_info` and `earlier` against
+    /// `earlier_ledger_info`, and that the former ledger extends the latter, via
+    /// `earlier_accumulator` (consistent with `earlier_ledger_info`) and `consistency_proof`.
+    /// Both ledger infos are expected to be trusted already, e.g. their signatures checked.
+    ///
+    /// Returns whether the account state changed between the two versions.
+    pub fn verify_change_since(
+        &self,
+        ledger_info: &LedgerInfo,
+        earlier: &AccountStateWithProof,
+        earlier_ledger_info: &LedgerInfo,
+        earlier_accumulator: &TransactionAccumulatorSummary,
+        consistency_proof: &AccumulatorConsistencyProof,
+        address: AccountAddress,
+    ) -> Result<bool> {
+        ensure!(
+            earlier.version <= self.version,
+            "Earlier state version ({}) is newer than the state version ({}).",
+            earlier.version,
+            self.version,
+        );
+        earlier.verify(earlier_ledger_info, earlier.version, address)?;
+        self.verify(ledger_info, self.version, address)?;
+
+        earlier_accumulator.verify_consistency(earlier_ledger_info)?;
+        earlier_accumulator.try_extend_with_proof(consistency_proof, ledger_info)?;
+
+        Ok(earlier.blob != self.blob)
+    }
 }
 
-/// TODO(joshlind): add a proof implementation (e.g., verify()) and unit tests
-/// for these once we start supporting them.
-///
 /// A single chunk of all account states at a specific version.
 /// Note: this is similar to `StateSnapshotChunk` but all data is included
 /// in the struct itself and not behind pointers/handles to file locations.
@@ -239,6 +269,148 @@ pub struct AccountStatesChunkWithProof {
     pub root_hash: HashValue, // The root hash of the sparse merkle tree for this chunk
 }
 
+impl AccountStatesChunkWithProof {
+    /// Ensures this chunk directly follows `previous` when walking the account states at the same
+    /// version in chunks, i.e. there's neither a gap nor an overlap between the two.
+    pub fn ensure_follows(&self, previous: &AccountStatesChunkWithProof) -> Result<()> {
+        ensure!(
+            self.root_hash == previous.root_hash,
+            "Chunk root hash {} differs from the previous chunk root hash {}.",
+            self.root_hash,
+            previous.root_hash,
+        );
+        ensure!(
+            self.first_index == previous.last_index + 1,
+            "Chunk starts at index {} while the previous chunk ends at index {}.",
+            self.first_index,
+            previous.last_index,
+        );
+        ensure!(
+            self.first_key > previous.last_key,
+            "Chunk first key {} is not after the previous chunk last key {}.",
+            self.first_key,
+            previous.last_key,
+        );
+        Ok(())
+    }
+
+    /// Verifies the chunk against `expected_root_hash`: the keys are sorted and span exactly
+    /// `[first_key, last_key]`, and together with the range proof they hash to the root.
+    ///
+    /// The range proof only carries the siblings to the right of the chunk, so the siblings to the
+    /// left are recomputed from the chunk itself. Hence only a chunk starting from the first
+    /// account can be verified on its own.
+    pub fn verify(&self, expected_root_hash: HashValue) -> Result<()> {
+        ensure!(
+            self.root_hash == expected_root_hash,
+            "Chunk root hash {} differs from the expected root hash {}.",
+            self.root_hash,
+            expected_root_hash,
+        );
+        ensure!(
+            self.first_index == 0,
+            "Chunk starts at index {}, only chunks starting at index 0 can be verified.",
+            self.first_index,
+        );
+        let num_indices = self
+            .last_index
+            .checked_add(1)
+            .ok_or_else(|| anyhow!("Chunk last index {} overflows.", self.last_index))?;
+        ensure!(
+            num_indices == self.account_blobs.len() as u64,
+            "Chunk spans indices [{}, {}] but has {} account blobs.",
+            self.first_index,
+            self.last_index,
+            self.account_blobs.len(),
+        );
+        let (first_key, last_key) = match (self.account_blobs.first(), self.account_blobs.last()) {
+            (Some((first_key, _)), Some((last_key, _))) => (*first_key, *last_key),
+            _ => return Err(anyhow!("Chunk is empty.")),
+        };
+        ensure!(
+            first_key == self.first_key && last_key == self.last_key,
+            "Chunk keys span [{}, {}], expected [{}, {}].",
+            first_key,
+            last_key,
+            self.first_key,
+            self.last_key,
+        );
+        for ((prev_key, _), (key, _)) in self.account_blobs.iter().zip(&self.account_blobs[1..]) {
+            ensure!(
+                prev_key < key,
+                "Chunk keys are not sorted: {} is followed by {}.",
+                prev_key,
+                key,
+            );
+        }
+
+        let leaves: Vec<SparseMerkleLeafNode> = self
+            .account_blobs
+            .iter()
+            .map(|(key, blob)| SparseMerkleLeafNode::new(*key, blob.hash()))
+            .collect();
+        let (rightmost_leaf, known_leaves) = leaves.split_last().expect("Known to be non-empty.");
+
+        // The rightmost leaf sits right below where its path diverges from both of its neighbors.
+        // Each zero bit on the path has a sibling in the proof, the last one of which is where the
+        // path diverges from the right neighbor, if any.
+        let left_divergence_depth = known_leaves
+            .last()
+            .map_or(0, |leaf| leaf.key().common_prefix_bits_len(last_key) + 1);
+        let num_right_siblings = self.proof.right_siblings().len();
+        let right_divergence_depth = if num_right_siblings == 0 {
+            0
+        } else {
+            last_key
+                .iter_bits()
+                .enumerate()
+                .filter(|(_depth, bit)| !bit)
+                .nth(num_right_siblings - 1)
+                .map(|(depth, _bit)| depth + 1)
+                .ok_or_else(|| anyhow!("Proof has too many right siblings."))?
+        };
+        let leaf_depth = std::cmp::max(left_divergence_depth, right_divergence_depth);
+
+        // The left siblings, from the deepest up, each covering the known leaves under it.
+        let left_siblings = last_key
+            .iter_bits()
+            .take(leaf_depth)
+            .enumerate()
+            .filter(|(_depth, bit)| *bit)
+            .map(|(depth, _bit)| {
+                let subtree_leaves: Vec<_> = known_leaves
+                    .iter()
+                    .filter(|leaf| leaf.key().common_prefix_bits_len(last_key) == depth)
+                    .collect();
+                Self::compute_subtree_hash(&subtree_leaves, depth + 1)
+            })
+            .rev()
+            .collect();
+
+        self.proof
+            .verify(self.root_hash, *rightmost_leaf, left_siblings)
+    }
+
+    /// Computes the hash of the subtree at `depth` consisting of exactly `leaves`.
+    fn compute_subtree_hash(leaves: &[&SparseMerkleLeafNode], depth: usize) -> HashValue {
+        match leaves {
+            [] => *SPARSE_MERKLE_PLACEHOLDER_HASH,
+            [leaf] => leaf.hash(),
+            _ => {
+                let (left, right): (Vec<_>, Vec<_>) = leaves
+                    .iter()
+                    .copied()
+                    .partition(|leaf| !leaf.key().bit(depth));
+                SparseMerkleInternalNode::new(
+                    Self::compute_subtree_hash(&left, depth + 1),
+                    Self::compute_subtree_hash(&right, depth + 1),
+                )
+                .hash()
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{AccountStateWithProof, *};
@@ -268,6 +440,82 @@ mod tests {
         }
     }
 
+    /// Returns a chunk of the first two of the three accounts in the following tree, keyed by
+    /// `00..`, `01..` and `1..` respectively.
+    ///
+    ///          root
+    ///         /    \
+    ///        o      c
+    ///       / \
+    ///      a   b
+    fn three_account_chunk() -> AccountStatesChunkWithProof {
+        let blobs: Vec<_> = [0x00u8, 0x40, 0x80]
+            .iter()
+            .map(|byte| {
+                (
+                    HashValue::new([*byte; HashValue::LENGTH]),
+                    AccountStateBlob::from(vec![*byte]),
+                )
+            })
+            .collect();
+        let leaf_hashes: Vec<_> = blobs
+            .iter()
+            .map(|(key, blob)| SparseMerkleLeafNode::new(*key, blob.hash()).hash())
+            .collect();
+        let root_hash = SparseMerkleInternalNode::new(
+            SparseMerkleInternalNode::new(leaf_hashes[0], leaf_hashes[1]).hash(),
+            leaf_hashes[2],
+        )
+        .hash();
+
+        AccountStatesChunkWithProof {
+            first_index: 0,
+            last_index: 1,
+            first_key: blobs[0].0,
+            last_key: blobs[1].0,
+            account_blobs: blobs[..2].to_vec(),
+            proof: SparseMerkleRangeProof::new(vec![leaf_hashes[2]]),
+            root_hash,
+        }
+    }
+
+    #[test]
+    fn test_account_states_chunk_verify() {
+        let chunk = three_account_chunk();
+        chunk.verify(chunk.root_hash).unwrap();
+        assert!(chunk.verify(HashValue::zero()).is_err());
+    }
+
+    #[test]
+    fn test_account_states_chunk_verify_out_of_order_keys() {
+        let mut chunk = three_account_chunk();
+        chunk.account_blobs.swap(0, 1);
+        chunk.first_key = chunk.account_blobs[0].0;
+        chunk.last_key = chunk.account_blobs[1].0;
+        assert!(chunk.verify(chunk.root_hash).is_err());
+    }
+
+    #[test]
+    fn test_account_states_chunk_verify_mutated_value() {
+        let mut chunk = three_account_chunk();
+        chunk.account_blobs[0].1 = AccountStateBlob::from(vec![0xff]);
+        assert!(chunk.verify(chunk.root_hash).is_err());
+    }
+
+    #[test]
+    fn test_account_states_chunk_verify_truncated_proof() {
+        let mut chunk = three_account_chunk();
+        chunk.proof = SparseMerkleRangeProof::new(vec![]);
+        assert!(chunk.verify(chunk.root_hash).is_err());
+    }
+
+    #[test]
+    fn test_account_states_chunk_verify_overflowing_last_index() {
+        let mut chunk = three_account_chunk();
+        chunk.last_index = u64::MAX;
+        assert!(chunk.verify(chunk.root_hash).is_err());
+    }
+
     #[test]
     fn test_debug_does_not_panic() {
         format!("{:#?}", AccountStateBlob::from(vec![1u8, 2u8, 3u8]));
@@ -10,7 +10,7 @@ use crate::{
     },
     event::EventKey,
     ledger_info::LedgerInfo,
-    proof::EventProof,
+    proof::{EventProof, TransactionInfoWithProof},
     transaction::Version,
 };
 use anyhow::{ensure, Context, Error, Result};
@@ -376,6 +376,79 @@ impl EventWithProof {
     }
 }
 
+/// An event with only the proof of the `TransactionInfo` of the transaction that emitted it, i.e.
+/// an [`EventWithProof`] without the event accumulator proof from the transaction's event root
+/// hash to the event. Meant for constrained clients of transactions with huge numbers of events,
+/// whose event accumulator proofs are deep.
+///
+/// This is a REDUCED-TRUST mode: verifying it authenticates the transaction, but not that the
+/// event is actually among the events the transaction emitted, so the content of the event is
+/// trusted to be what the server claims.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
+pub struct EventWithTransactionInfoProof {
+    pub transaction_version: Version,
+    pub event_index: u64,
+    pub event: ContractEvent,
+    pub transaction_info_with_proof: TransactionInfoWithProof,
+}
+
+impl EventWithTransactionInfoProof {
+    /// Constructor.
+    pub fn new(
+        transaction_version: Version,
+        event_index: u64,
+        event: ContractEvent,
+        transaction_info_with_proof: TransactionInfoWithProof,
+    ) -> Self {
+        Self {
+            transaction_version,
+            event_index,
+            event,
+            transaction_info_with_proof,
+        }
+    }
+
+    /// Same as [`EventWithProof::verify`], except that only the transaction info is verified to
+    /// be in the ledger represented by `ledger_info`, see the type level doc comment.
+    pub fn verify(
+        &self,
+        ledger_info: &LedgerInfo,
+        event_key: &EventKey,
+        sequence_number: u64,
+        transaction_version: Version,
+        event_index: u64,
+    ) -> Result<()> {
+        ensure!(
+            self.event.key() == event_key,
+            "Event key ({}) not expected ({}).",
+            self.event.key(),
+            *event_key,
+        );
+        ensure!(
+            self.event.sequence_number == sequence_number,
+            "Sequence number ({}) not expected ({}).",
+            self.event.sequence_number(),
+            sequence_number,
+        );
+        ensure!(
+            self.transaction_version == transaction_version,
+            "Transaction version ({}) not expected ({}).",
+            self.transaction_version,
+            transaction_version,
+        );
+        ensure!(
+            self.event_index == event_index,
+            "Event index ({}) not expected ({}).",
+            self.event_index,
+            event_index,
+        );
+
+        self.transaction_info_with_proof
+            .verify(ledger_info, transaction_version)
+    }
+}
+
 /// The response type for `get_event_by_version_with_proof`, which contains lower
 /// and upper bound events surrounding the requested version along with proofs
 /// for each event.
@@ -14,6 +14,46 @@ impl OnChainConfig for Version {
     const IDENTIFIER: &'static str = "Version";
 }
 
+impl Version {
+    /// Returns the protocol features enabled at this version, in the order they were introduced.
+    pub fn enabled_features(&self) -> Vec<Feature> {
+        Feature::ALL
+            .iter()
+            .filter(|feature| *self >= feature.first_version())
+            .copied()
+            .collect()
+    }
+}
+
+/// Protocol features gated by the `Version` on chain.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Feature {
+    /// The ScriptFunction payload type.
+    ScriptFunction,
+    /// Multi-agent transactions.
+    MultiAgent,
+    /// Conflict-Resistant Sequence Numbers.
+    ConflictResistantSequenceNumbers,
+}
+
+impl Feature {
+    /// All features, in the order they were introduced.
+    pub const ALL: &'static [Feature] = &[
+        Feature::ScriptFunction,
+        Feature::MultiAgent,
+        Feature::ConflictResistantSequenceNumbers,
+    ];
+
+    /// The first version at which this feature is enabled.
+    pub fn first_version(&self) -> Version {
+        match self {
+            Feature::ScriptFunction => DIEM_VERSION_2,
+            Feature::MultiAgent => DIEM_VERSION_3,
+            Feature::ConflictResistantSequenceNumbers => DIEM_VERSION_4,
+        }
+    }
+}
+
 // NOTE: version number for release 1.2 Diem
 // Items gated by this version number include:
 //  - the ScriptFunction payload type
@@ -28,7 +28,7 @@ mod vm_publishing_option;
 pub use self::{
     consensus_config::{ConsensusConfigV1, ConsensusConfigV2, OnChainConsensusConfig},
     diem_version::{
-        Version, DIEM_MAX_KNOWN_VERSION, DIEM_VERSION_2, DIEM_VERSION_3, DIEM_VERSION_4,
+        Feature, Version, DIEM_MAX_KNOWN_VERSION, DIEM_VERSION_2, DIEM_VERSION_3, DIEM_VERSION_4,
     },
     parallel_execution_config::{ParallelExecutionConfig, ReadWriteSetAnalysis},
     registered_currencies::RegisteredCurrencies,
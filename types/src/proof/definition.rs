@@ -190,50 +190,7 @@ where
             HashValue::LENGTH_IN_BITS,
             self.siblings.len(),
         );
-
-        match (element_value, self.leaf) {
-            (Some(value), Some(leaf)) => {
-                // This is an inclusion proof, so the key and value hash provided in the proof
-                // should match element_key and element_value_hash. `siblings` should prove the
-                // route from the leaf node to the root.
-                ensure!(
-                    element_key == leaf.key,
-                    "Keys do not match. Key in proof: {:x}. Expected key: {:x}.",
-                    leaf.key,
-                    element_key
-                );
-                let hash = value.hash();
-                ensure!(
-                    hash == leaf.value_hash,
-                    "Value hashes do not match. Value hash in proof: {:x}. \
-                     Expected value hash: {:x}",
-                    leaf.value_hash,
-                    hash,
-                );
-            }
-            (Some(_value), None) => bail!("Expected inclusion proof. Found non-inclusion proof."),
-            (None, Some(leaf)) => {
-                // This is a non-inclusion proof. The proof intends to show that if a leaf node
-                // representing `element_key` is inserted, it will break a currently existing leaf
-                // node represented by `proof_key` into a branch. `siblings` should prove the
-                // route from that leaf node to the root.
-                ensure!(
-                    element_key != leaf.key,
-                    "Expected non-inclusion proof, but key exists in proof.",
-                );
-                ensure!(
-                    element_key.common_prefix_bits_len(leaf.key) >= self.siblings.len(),
-                    "Key would not have ended up in the subtree where the provided key in proof \
-                     is the only existing key, if it existed. So this is not a valid \
-                     non-inclusion proof.",
-                );
-            }
-            (None, None) => {
-                // This is a non-inclusion proof. The proof intends to show that if a leaf node
-                // representing `element_key` is inserted, it will show up at a currently empty
-                // position. `sibling` should prove the route from this empty position to the root.
-            }
-        }
+        verify_sparse_merkle_leaf(self.leaf, self.siblings.len(), element_key, element_value)?;
 
         let current_hash = self
             .leaf
@@ -265,6 +222,263 @@ where
     }
 }
 
+/// A proof that can be used to authenticate a set of elements in a Sparse Merkle Tree given trusted
+/// root hash. Unlike a list of `SparseMerkleProof`s, each sibling is included only once, and none of
+/// the siblings that can be computed from the paths of the other elements are included at all.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SparseMerkleMultiProof<V> {
+    /// The leaves found at the ends of the paths of the proven keys (see
+    /// `SparseMerkleProof::leaf`), each with the depth of the end, i.e. the number of siblings in
+    /// the corresponding `SparseMerkleProof`. In ascending order of the keys, with one entry for
+    /// all the keys whose paths end at the same position.
+    leaves: Vec<(Option<SparseMerkleLeafNode>, usize)>,
+
+    /// The siblings of the union of the paths that aren't on any of the paths themselves, in the
+    /// order of a depth first, left to right traversal of the paths.
+    siblings: Vec<HashValue>,
+
+    phantom: PhantomData<V>,
+}
+
+impl<V> SparseMerkleMultiProof<V>
+where
+    V: CryptoHash,
+{
+    /// Constructs a new `SparseMerkleMultiProof` using leaves and a list of siblings.
+    pub fn new(
+        leaves: Vec<(Option<SparseMerkleLeafNode>, usize)>,
+        siblings: Vec<HashValue>,
+    ) -> Self {
+        SparseMerkleMultiProof {
+            leaves,
+            siblings,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Merges the proofs of the given keys against the same root hash. Errors if a key is given
+    /// more than once or the proofs are inconsistent with each other.
+    pub fn from_proofs(proofs: &[(HashValue, SparseMerkleProof<V>)]) -> Result<Self> {
+        let mut proofs: Vec<_> = proofs.iter().map(|(key, proof)| (*key, proof)).collect();
+        proofs.sort_by_key(|(key, _proof)| *key);
+        ensure!(!proofs.is_empty(), "No proofs to merge.");
+        ensure_unique_sorted_keys(proofs.iter().map(|(key, _proof)| key))?;
+        for (_key, proof) in &proofs {
+            ensure!(
+                proof.siblings.len() <= HashValue::LENGTH_IN_BITS,
+                "Sparse Merkle Tree proof has more than {} ({}) siblings.",
+                HashValue::LENGTH_IN_BITS,
+                proof.siblings.len(),
+            );
+        }
+
+        let mut multiproof = Self::new(vec![], vec![]);
+        multiproof.merge_proofs(0, &proofs)?;
+        Ok(multiproof)
+    }
+
+    /// Appends the leaves and siblings of the subtree at `depth` containing the paths of `proofs`.
+    fn merge_proofs(
+        &mut self,
+        depth: usize,
+        proofs: &[(HashValue, &SparseMerkleProof<V>)],
+    ) -> Result<()> {
+        let (_key, first) = proofs[0];
+        if proofs
+            .iter()
+            .any(|(_key, proof)| proof.siblings.len() <= depth)
+        {
+            ensure!(
+                proofs.iter().all(|(_key, proof)| {
+                    proof.siblings.len() == depth && proof.leaf == first.leaf
+                }),
+                "Inconsistent proofs: paths end at depth {} with different leaves.",
+                depth,
+            );
+            self.leaves.push((first.leaf, depth));
+            return Ok(());
+        }
+
+        let num_left = proofs.partition_point(|(key, _proof)| !key.bit(depth));
+        let (left, right) = proofs.split_at(num_left);
+        // The sibling at `depth` of any of the paths of the other side.
+        let sibling_of = |proofs: &[(HashValue, &SparseMerkleProof<V>)]| {
+            let (_key, proof) = proofs[0];
+            proof.siblings[proof.siblings.len() - 1 - depth]
+        };
+        if left.is_empty() {
+            self.siblings.push(sibling_of(right));
+        } else {
+            self.merge_proofs(depth + 1, left)?;
+        }
+        if right.is_empty() {
+            self.siblings.push(sibling_of(left));
+        } else {
+            self.merge_proofs(depth + 1, right)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the list of siblings in this proof.
+    pub fn siblings(&self) -> &[HashValue] {
+        &self.siblings
+    }
+
+    /// Verifies that each of the `elements` exists in the Sparse Merkle Tree with the given value,
+    /// or doesn't exist if the value is `None`, like `SparseMerkleProof::verify`. The `elements`
+    /// must be the ones the proof is constructed for, in ascending order of the keys.
+    pub fn verify(
+        &self,
+        expected_root_hash: HashValue,
+        elements: &[(HashValue, Option<&V>)],
+    ) -> Result<()> {
+        ensure!(!elements.is_empty(), "No elements to verify.");
+        ensure_unique_sorted_keys(elements.iter().map(|(key, _value)| key))?;
+
+        let mut leaves = self.leaves.iter().peekable();
+        let mut siblings = self.siblings.iter();
+        let actual_root_hash = Self::compute_hash(0, elements, &mut leaves, &mut siblings)?;
+        ensure!(
+            leaves.next().is_none() && siblings.next().is_none(),
+            "The proof has more leaves or siblings than needed.",
+        );
+        ensure!(
+            actual_root_hash == expected_root_hash,
+            "Root hashes do not match. Actual root hash: {:x}. Expected root hash: {:x}.",
+            actual_root_hash,
+            expected_root_hash,
+        );
+
+        Ok(())
+    }
+
+    /// Computes the hash of the subtree at `depth` containing the paths of `elements`, consuming
+    /// the leaves and siblings of the subtree.
+    fn compute_hash<'a, L, S>(
+        depth: usize,
+        elements: &[(HashValue, Option<&V>)],
+        leaves: &mut std::iter::Peekable<L>,
+        siblings: &mut S,
+    ) -> Result<HashValue>
+    where
+        L: Iterator<Item = &'a (Option<SparseMerkleLeafNode>, usize)>,
+        S: Iterator<Item = &'a HashValue>,
+    {
+        let (leaf, leaf_depth) = **leaves
+            .peek()
+            .ok_or_else(|| format_err!("The proof has fewer leaves than needed."))?;
+        ensure!(
+            leaf_depth >= depth,
+            "Leaf at depth {} found when at depth {}.",
+            leaf_depth,
+            depth,
+        );
+        if leaf_depth == depth {
+            leaves.next();
+            for (key, value) in elements {
+                verify_sparse_merkle_leaf(leaf, depth, *key, *value)?;
+            }
+            return Ok(leaf.map_or(*SPARSE_MERKLE_PLACEHOLDER_HASH, |leaf| leaf.hash()));
+        }
+        ensure!(
+            depth < HashValue::LENGTH_IN_BITS,
+            "Sparse Merkle Tree proof has a path deeper than {}.",
+            HashValue::LENGTH_IN_BITS,
+        );
+
+        let num_left = elements.partition_point(|(key, _value)| !key.bit(depth));
+        let (left, right) = elements.split_at(num_left);
+        let next_sibling = |siblings: &mut S| {
+            siblings
+                .next()
+                .cloned()
+                .ok_or_else(|| format_err!("The proof has fewer siblings than needed."))
+        };
+        let left_hash = if left.is_empty() {
+            next_sibling(siblings)?
+        } else {
+            Self::compute_hash(depth + 1, left, leaves, siblings)?
+        };
+        let right_hash = if right.is_empty() {
+            next_sibling(siblings)?
+        } else {
+            Self::compute_hash(depth + 1, right, leaves, siblings)?
+        };
+        Ok(SparseMerkleInternalNode::new(left_hash, right_hash).hash())
+    }
+}
+
+fn ensure_unique_sorted_keys<'a>(mut keys: impl Iterator<Item = &'a HashValue>) -> Result<()> {
+    if let Some(mut prev) = keys.next() {
+        for key in keys {
+            ensure!(
+                prev < key,
+                "Keys are not unique and sorted: {:x}, {:x}.",
+                prev,
+                key
+            );
+            prev = key;
+        }
+    }
+    Ok(())
+}
+
+/// Verifies that `leaf`, found at `depth` in a Sparse Merkle Tree on the path of `element_key`,
+/// proves `element_key` to have `element_value`, or not to exist if `element_value` is `None`.
+/// See `SparseMerkleProof::leaf`.
+fn verify_sparse_merkle_leaf<V: CryptoHash>(
+    leaf: Option<SparseMerkleLeafNode>,
+    depth: usize,
+    element_key: HashValue,
+    element_value: Option<&V>,
+) -> Result<()> {
+    match (element_value, leaf) {
+        (Some(value), Some(leaf)) => {
+            // This is an inclusion proof, so the key and value hash provided in the proof
+            // should match element_key and element_value_hash. `siblings` should prove the
+            // route from the leaf node to the root.
+            ensure!(
+                element_key == leaf.key,
+                "Keys do not match. Key in proof: {:x}. Expected key: {:x}.",
+                leaf.key,
+                element_key
+            );
+            let hash = value.hash();
+            ensure!(
+                hash == leaf.value_hash,
+                "Value hashes do not match. Value hash in proof: {:x}. \
+                 Expected value hash: {:x}",
+                leaf.value_hash,
+                hash,
+            );
+        }
+        (Some(_value), None) => bail!("Expected inclusion proof. Found non-inclusion proof."),
+        (None, Some(leaf)) => {
+            // This is a non-inclusion proof. The proof intends to show that if a leaf node
+            // representing `element_key` is inserted, it will break a currently existing leaf
+            // node represented by `proof_key` into a branch. `siblings` should prove the
+            // route from that leaf node to the root.
+            ensure!(
+                element_key != leaf.key,
+                "Expected non-inclusion proof, but key exists in proof.",
+            );
+            ensure!(
+                element_key.common_prefix_bits_len(leaf.key) >= depth,
+                "Key would not have ended up in the subtree where the provided key in proof \
+                 is the only existing key, if it existed. So this is not a valid \
+                 non-inclusion proof.",
+            );
+        }
+        (None, None) => {
+            // This is a non-inclusion proof. The proof intends to show that if a leaf node
+            // representing `element_key` is inserted, it will show up at a currently empty
+            // position. `sibling` should prove the route from this empty position to the root.
+        }
+    }
+
+    Ok(())
+}
+
 /// An in-memory accumulator for storing a summary of the core transaction info
 /// accumulator. It is a summary in the sense that it only stores maximally
 /// frozen subtree nodes rather than storing all leaves and internal nodes.
@@ -850,6 +1064,19 @@ impl TransactionInfoListWithProof {
         &self,
         ledger_info: &LedgerInfo,
         first_transaction_info_version: Option<Version>,
+    ) -> Result<()> {
+        self.verify_against_root_hash(
+            ledger_info.transaction_accumulator_hash(),
+            first_transaction_info_version,
+        )
+    }
+
+    /// Same as [`Self::verify`], but against a trusted transaction accumulator root hash instead
+    /// of a ledger info.
+    pub fn verify_against_root_hash(
+        &self,
+        expected_root_hash: HashValue,
+        first_transaction_info_version: Option<Version>,
     ) -> Result<()> {
         let txn_info_hashes: Vec<_> = self
             .transaction_infos
@@ -857,7 +1084,7 @@ impl TransactionInfoListWithProof {
             .map(CryptoHash::hash)
             .collect();
         self.ledger_info_to_transaction_infos_proof.verify(
-            ledger_info.transaction_accumulator_hash(),
+            expected_root_hash,
             first_transaction_info_version,
             &txn_info_hashes,
         )
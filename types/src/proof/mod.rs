@@ -30,9 +30,10 @@ use std::marker::PhantomData;
 
 pub use self::definition::{
     AccountStateProof, AccumulatorConsistencyProof, AccumulatorExtensionProof, AccumulatorProof,
-    AccumulatorRangeProof, EventAccumulatorProof, EventProof, SparseMerkleProof,
-    SparseMerkleRangeProof, TransactionAccumulatorProof, TransactionAccumulatorRangeProof,
-    TransactionAccumulatorSummary, TransactionInfoListWithProof, TransactionInfoWithProof,
+    AccumulatorRangeProof, EventAccumulatorProof, EventProof, SparseMerkleMultiProof,
+    SparseMerkleProof, SparseMerkleRangeProof, TransactionAccumulatorProof,
+    TransactionAccumulatorRangeProof, TransactionAccumulatorSummary, TransactionInfoListWithProof,
+    TransactionInfoWithProof,
 };
 
 #[cfg(any(test, feature = "fuzzing"))]
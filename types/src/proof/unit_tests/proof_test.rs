@@ -36,6 +36,7 @@ use aptos_crypto::{
 use move_core_types::language_storage::TypeTag;
 
 type SparseMerkleProof = crate::proof::SparseMerkleProof<AccountStateBlob>;
+type SparseMerkleMultiProof = crate::proof::SparseMerkleMultiProof<AccountStateBlob>;
 
 #[test]
 fn test_verify_empty_accumulator() {
@@ -256,6 +257,107 @@ fn test_verify_three_element_sparse_merkle() {
     }
 }
 
+#[test]
+fn test_verify_sparse_merkle_multi_proof() {
+    // Same tree as in `test_verify_three_element_sparse_merkle`.
+    //            root
+    //           /    \
+    //          a      default
+    //         / \
+    //     key1   b
+    //           / \
+    //       key2   key3
+    let key1 = b"hello".test_only_hash();
+    let key2 = b"world".test_only_hash();
+    let key3 = b"!".test_only_hash();
+    let non_existing_key1 = b"abc".test_only_hash();
+    let non_existing_key2 = b"def".test_only_hash();
+
+    let blob1 = AccountStateBlob::from(b"1".to_vec());
+    let blob2 = AccountStateBlob::from(b"2".to_vec());
+    let blob3 = AccountStateBlob::from(b"3".to_vec());
+
+    let leaf1 = SparseMerkleLeafNode::new(key1, blob1.hash());
+    let leaf2 = SparseMerkleLeafNode::new(key2, blob2.hash());
+    let leaf3_hash = SparseMerkleLeafNode::new(key3, blob3.hash()).hash();
+    let internal_b_hash = SparseMerkleInternalNode::new(leaf2.hash(), leaf3_hash).hash();
+    let internal_a_hash = SparseMerkleInternalNode::new(leaf1.hash(), internal_b_hash).hash();
+    let root_hash =
+        SparseMerkleInternalNode::new(internal_a_hash, *SPARSE_MERKLE_PLACEHOLDER_HASH).hash();
+
+    let proofs = vec![
+        (
+            key2,
+            SparseMerkleProof::new(
+                Some(leaf2),
+                vec![leaf3_hash, leaf1.hash(), *SPARSE_MERKLE_PLACEHOLDER_HASH],
+            ),
+        ),
+        (
+            key1,
+            SparseMerkleProof::new(
+                Some(leaf1),
+                vec![internal_b_hash, *SPARSE_MERKLE_PLACEHOLDER_HASH],
+            ),
+        ),
+        (
+            non_existing_key1,
+            SparseMerkleProof::new(
+                Some(leaf1),
+                vec![internal_b_hash, *SPARSE_MERKLE_PLACEHOLDER_HASH],
+            ),
+        ),
+        (
+            non_existing_key2,
+            SparseMerkleProof::new(None, vec![internal_a_hash]),
+        ),
+    ];
+    let proof = SparseMerkleMultiProof::from_proofs(&proofs).unwrap();
+    // All the other siblings can be computed from the paths.
+    assert_eq!(proof.siblings(), &[leaf3_hash]);
+
+    let mut elements = vec![
+        (key1, Some(&blob1)),
+        (non_existing_key1, None),
+        (key2, Some(&blob2)),
+        (non_existing_key2, None),
+    ];
+    elements.sort_by_key(|(key, _value)| *key);
+    assert!(proof.verify(root_hash, &elements).is_ok());
+    assert!(proof.verify(HashValue::zero(), &elements).is_err());
+    // Trying to show that a key has another value.
+    let mut bad_elements = elements.clone();
+    bad_elements
+        .iter_mut()
+        .find(|(key, _)| *key == key2)
+        .unwrap()
+        .1 = Some(&blob3);
+    assert!(proof.verify(root_hash, &bad_elements).is_err());
+    // Trying to show that a key doesn't exist.
+    let mut bad_elements = elements.clone();
+    bad_elements
+        .iter_mut()
+        .find(|(key, _)| *key == key1)
+        .unwrap()
+        .1 = None;
+    assert!(proof.verify(root_hash, &bad_elements).is_err());
+    // Elements must match the proof.
+    assert!(proof.verify(root_hash, &elements[..3]).is_err());
+    elements.reverse();
+    assert!(proof.verify(root_hash, &elements).is_err());
+
+    // Inconsistent proofs can't be merged.
+    assert!(SparseMerkleMultiProof::from_proofs(&[proofs[0].clone(), proofs[0].clone()]).is_err());
+    assert!(SparseMerkleMultiProof::from_proofs(&[
+        proofs[1].clone(),
+        (
+            non_existing_key1,
+            SparseMerkleProof::new(None, vec![internal_b_hash, *SPARSE_MERKLE_PLACEHOLDER_HASH]),
+        ),
+    ])
+    .is_err());
+}
+
 #[test]
 fn test_verify_transaction() {
     //            root
@@ -461,6 +461,40 @@ impl TransactionPayload {
             payload => panic!("Expected ScriptFunction(_) payload, found: {:#?}", payload),
         }
     }
+
+    pub fn payload_type(&self) -> TransactionPayloadType {
+        match self {
+            Self::WriteSet(_) => TransactionPayloadType::WriteSet,
+            Self::Script(_) => TransactionPayloadType::Script,
+            Self::ModuleBundle(_) => TransactionPayloadType::ModuleBundle,
+            Self::ScriptFunction(_) => TransactionPayloadType::ScriptFunction,
+        }
+    }
+}
+
+/// The kind of a [`TransactionPayload`], tagged with the index of its variant.
+#[derive(Clone, Copy, Debug, Hash, Eq, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
+#[repr(u8)]
+pub enum TransactionPayloadType {
+    WriteSet = 0,
+    Script = 1,
+    ModuleBundle = 2,
+    ScriptFunction = 3,
+}
+
+impl TryFrom<u8> for TransactionPayloadType {
+    type Error = Error;
+
+    fn try_from(tag: u8) -> Result<Self> {
+        Ok(match tag {
+            0 => Self::WriteSet,
+            1 => Self::Script,
+            2 => Self::ModuleBundle,
+            3 => Self::ScriptFunction,
+            _ => return Err(format_err!("Unknown transaction payload type tag: {}", tag)),
+        })
+    }
 }
 
 /// Two different kinds of WriteSet transactions.
@@ -1162,6 +1196,19 @@ impl TransactionListWithProof {
         &self,
         ledger_info: &LedgerInfo,
         first_transaction_version: Option<Version>,
+    ) -> Result<()> {
+        self.verify_against_root_hash(
+            ledger_info.transaction_accumulator_hash(),
+            first_transaction_version,
+        )
+    }
+
+    /// Same as [`Self::verify`], but against a trusted transaction accumulator root hash instead
+    /// of a ledger info.
+    pub fn verify_against_root_hash(
+        &self,
+        expected_root_hash: HashValue,
+        first_transaction_version: Option<Version>,
     ) -> Result<()> {
         // Verify the first transaction versions match
         ensure!(
@@ -1195,9 +1242,9 @@ impl TransactionListWithProof {
             })
             .collect::<Result<Vec<_>>>()?;
 
-        // Verify the transaction infos are proven by the ledger info.
+        // Verify the transaction infos are proven by the root hash.
         self.proof
-            .verify(ledger_info, self.first_transaction_version)?;
+            .verify_against_root_hash(expected_root_hash, self.first_transaction_version)?;
 
         // Verify the events if they exist.
         if let Some(event_lists) = &self.events {
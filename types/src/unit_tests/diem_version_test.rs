@@ -0,0 +1,31 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::on_chain_config::{
+    Feature, Version, DIEM_MAX_KNOWN_VERSION, DIEM_VERSION_2, DIEM_VERSION_3, DIEM_VERSION_4,
+};
+
+#[test]
+fn test_enabled_features() {
+    assert!(Version { major: 1 }.enabled_features().is_empty());
+    assert_eq!(
+        DIEM_VERSION_2.enabled_features(),
+        vec![Feature::ScriptFunction]
+    );
+    assert_eq!(
+        DIEM_VERSION_3.enabled_features(),
+        vec![Feature::ScriptFunction, Feature::MultiAgent]
+    );
+    assert_eq!(
+        DIEM_VERSION_4.enabled_features(),
+        vec![
+            Feature::ScriptFunction,
+            Feature::MultiAgent,
+            Feature::ConflictResistantSequenceNumbers,
+        ]
+    );
+    assert_eq!(
+        DIEM_MAX_KNOWN_VERSION.enabled_features(),
+        Feature::ALL.to_vec()
+    );
+}